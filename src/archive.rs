@@ -0,0 +1,315 @@
+//! Icons delivered as a zip archive, as design tools export them.
+
+use crate::svg_parser::{read_limited, MAX_DECOMPRESSED_SIZE};
+use anyhow::{Context, Result};
+use flate2::read::DeflateDecoder;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::io::ErrorKind;
+use std::path::{Component, Path, PathBuf};
+
+const LOCAL_HEADER: u32 = 0x0403_4b50;
+const CENTRAL_HEADER: u32 = 0x0201_4b50;
+const END_OF_CENTRAL_DIRECTORY: u32 = 0x0605_4b50;
+
+/// Attempts at a fresh temporary directory name before giving up
+const TEMP_DIR_ATTEMPTS: usize = 16;
+
+/// Whether `path` is a zip archive rather than an icon directory
+pub fn is_zip(path: &Path) -> bool {
+    path.is_file()
+        && path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("zip"))
+}
+
+/// The icons of an archive unpacked into a temporary directory, which is
+/// removed again on drop
+#[derive(Debug)]
+pub struct UnpackedArchive {
+    dir: PathBuf,
+}
+
+impl UnpackedArchive {
+    /// Unpack the SVGs, their sidecar files and the ignore file of an archive.
+    ///
+    /// Folders inside the archive are flattened, so icons exported into
+    /// subfolders end up side by side as in an icon directory.
+    pub fn unpack(archive: &Path) -> Result<Self> {
+        let data = std::fs::read(archive)
+            .with_context(|| format!("Failed to read {}", archive.display()))?;
+        Self::from_bytes(&data, &archive.display().to_string())
+    }
+
+    /// Download a zip archive from `url` and unpack it
+    pub fn download(url: &str) -> Result<Self> {
+        Self::from_bytes(&crate::remote::fetch(url, &[])?, url)
+    }
+
+    fn from_bytes(data: &[u8], source: &str) -> Result<Self> {
+        let entries = read_zip(data).with_context(|| format!("Invalid zip archive {}", source))?;
+        Self::from_files(entries, source)
+    }
+
+    /// Write files read from `source` into a temporary icon directory, as if
+    /// they had been unpacked from an archive
+    pub fn from_files(entries: Vec<(String, Vec<u8>)>, source: &str) -> Result<Self> {
+        let unpacked = Self {
+            dir: create_temp_dir()?,
+        };
+
+        for (name, contents) in entries {
+            let Some(file_name) = unpacked_name(&name) else {
+                continue;
+            };
+            let path = unpacked.dir.join(file_name);
+            if path.exists() {
+                anyhow::bail!("`{}` appears twice in {}", file_name, source);
+            }
+            std::fs::write(&path, contents)
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+        }
+        Ok(unpacked)
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+}
+
+impl Drop for UnpackedArchive {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// Create a new, empty directory in the temp dir. Its name is random and an
+/// existing directory is never reused, so concurrent builds and anything
+/// left in the temp dir cannot get mixed into the icons.
+fn create_temp_dir() -> Result<PathBuf> {
+    let temp = std::env::temp_dir();
+    for _ in 0..TEMP_DIR_ATTEMPTS {
+        let suffix = RandomState::new().build_hasher().finish();
+        let dir = temp.join(format!("svg2font-{}-{:016x}", std::process::id(), suffix));
+        match std::fs::create_dir(&dir) {
+            Ok(()) => return Ok(dir),
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e).with_context(|| format!("Failed to create {}", dir.display())),
+        }
+    }
+    anyhow::bail!(
+        "Failed to create a temporary directory in {}",
+        temp.display()
+    )
+}
+
+/// File name to unpack an entry as, if it is an icon, a sidecar or the
+/// ignore file. macOS resource forks and other hidden files are skipped.
+///
+/// Archives made on Windows may separate folders with backslashes, so both
+/// separators are split on, and only a plain file name is ever returned.
+fn unpacked_name(entry: &str) -> Option<&str> {
+    const SEPARATORS: [char; 2] = ['/', '\\'];
+    if entry.split(SEPARATORS).any(|part| part == "__MACOSX") {
+        return None;
+    }
+    let file_name = entry.rsplit(SEPARATORS).next()?;
+    let mut components = Path::new(file_name).components();
+    if !matches!(
+        (components.next(), components.next()),
+        (Some(Component::Normal(_)), None)
+    ) {
+        return None;
+    }
+    if file_name == crate::svg_parser::IGNORE_FILE_NAME {
+        return Some(file_name);
+    }
+    let extension = Path::new(file_name).extension()?.to_str()?;
+    let wanted = ["svg", "svgz", "toml"]
+        .iter()
+        .any(|e| extension.eq_ignore_ascii_case(e));
+    (wanted && !file_name.starts_with('.')).then_some(file_name)
+}
+
+/// Names and contents of the files in a zip archive.
+///
+/// Only stored and deflated entries are supported, which is what export
+/// tools write; encrypted and ZIP64 archives are rejected, and so are entries
+/// larger than [`MAX_DECOMPRESSED_SIZE`] once inflated.
+pub fn read_zip(data: &[u8]) -> Result<Vec<(String, Vec<u8>)>> {
+    // The end of central directory record is last, followed by a comment of up to 64 KiB
+    let end = (0..=data.len().saturating_sub(22))
+        .rev()
+        .take(u16::MAX as usize + 1)
+        .find(|&offset| u32_at(data, offset) == Some(END_OF_CENTRAL_DIRECTORY))
+        .context("No end of central directory record")?;
+    let count = u16_at(data, end + 10).context("Truncated archive")?;
+    let mut offset = u32_at(data, end + 16).context("Truncated archive")? as usize;
+    if count == u16::MAX || offset == u32::MAX as usize {
+        anyhow::bail!("ZIP64 archives are not supported");
+    }
+
+    let mut files = Vec::new();
+    for _ in 0..count {
+        let field = |at: usize| u16_at(data, offset + at).context("Truncated central directory");
+        if u32_at(data, offset) != Some(CENTRAL_HEADER) {
+            anyhow::bail!("Corrupt central directory");
+        }
+        let flags = field(8)?;
+        let method = field(10)?;
+        let compressed_size = u32_at(data, offset + 20).context("Truncated central directory")?;
+        let size = u32_at(data, offset + 24).context("Truncated central directory")?;
+        let name_len = field(28)? as usize;
+        let extra_len = field(30)? as usize;
+        let comment_len = field(32)? as usize;
+        let local = u32_at(data, offset + 42).context("Truncated central directory")? as usize;
+        let name = data
+            .get(offset + 46..offset + 46 + name_len)
+            .context("Truncated central directory")?;
+        let name = String::from_utf8_lossy(name).into_owned();
+        offset += 46 + name_len + extra_len + comment_len;
+
+        if name.ends_with('/') {
+            continue;
+        }
+        if flags & 1 != 0 {
+            anyhow::bail!("`{}` is encrypted", name);
+        }
+        if u64::from(size) > MAX_DECOMPRESSED_SIZE {
+            anyhow::bail!("`{}` is too large, {} bytes", name, size);
+        }
+
+        // The local header repeats the name, with its own extra field length
+        if u32_at(data, local) != Some(LOCAL_HEADER) {
+            anyhow::bail!("Corrupt local header for `{}`", name);
+        }
+        let start = local
+            + 30
+            + u16_at(data, local + 26).context("Truncated local header")? as usize
+            + u16_at(data, local + 28).context("Truncated local header")? as usize;
+        let compressed = data
+            .get(start..start + compressed_size as usize)
+            .with_context(|| format!("Truncated data for `{}`", name))?;
+        let contents = match method {
+            0 => compressed.to_vec(),
+            // The recorded size may lie, so the inflated data is limited too
+            8 => read_limited(DeflateDecoder::new(compressed), MAX_DECOMPRESSED_SIZE)
+                .with_context(|| format!("Failed to inflate `{}`", name))?,
+            _ => anyhow::bail!("`{}` uses unsupported compression method {}", name, method),
+        };
+        files.push((name, contents));
+    }
+    Ok(files)
+}
+
+fn u16_at(data: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(
+        data.get(offset..offset + 2)?.try_into().ok()?,
+    ))
+}
+
+fn u32_at(data: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(
+        data.get(offset..offset + 4)?.try_into().ok()?,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::{write::DeflateEncoder, Compression};
+    use std::io::Write;
+
+    /// A minimal zip archive, deflating entries when asked
+    fn zip(entries: &[(&str, &[u8], bool)]) -> Vec<u8> {
+        let (mut data, mut central) = (Vec::new(), Vec::new());
+        for &(name, contents, deflate) in entries {
+            let (method, stored) = if deflate {
+                let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+                encoder.write_all(contents).unwrap();
+                (8u16, encoder.finish().unwrap())
+            } else {
+                (0, contents.to_vec())
+            };
+            let header = |signature: u32| {
+                let mut h = signature.to_le_bytes().to_vec();
+                if signature == CENTRAL_HEADER {
+                    h.extend([20, 0]); // version made by
+                }
+                h.extend([20, 0, 0, 0]); // version needed, flags
+                h.extend(method.to_le_bytes());
+                h.extend([0; 8]); // time, date, crc
+                h.extend((stored.len() as u32).to_le_bytes());
+                h.extend((contents.len() as u32).to_le_bytes());
+                h.extend((name.len() as u16).to_le_bytes());
+                h.extend([0, 0]); // extra length
+                h
+            };
+            let mut entry = header(CENTRAL_HEADER);
+            entry.extend([0; 10]); // comment length, disk, attributes
+            entry.extend((data.len() as u32).to_le_bytes());
+            entry.extend(name.as_bytes());
+            central.extend(entry);
+
+            data.extend(header(LOCAL_HEADER));
+            data.extend(name.as_bytes());
+            data.extend(stored);
+        }
+        let offset = data.len() as u32;
+        data.extend(&central);
+        data.extend(END_OF_CENTRAL_DIRECTORY.to_le_bytes());
+        data.extend([0; 4]);
+        data.extend((entries.len() as u16).to_le_bytes());
+        data.extend((entries.len() as u16).to_le_bytes());
+        data.extend((central.len() as u32).to_le_bytes());
+        data.extend(offset.to_le_bytes());
+        data.extend([0, 0]);
+        data
+    }
+
+    #[test]
+    fn test_unpack_archive() {
+        let svg = br#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 24 24"/>"#;
+        let data = zip(&[
+            ("export/", b"", false),
+            ("export/home.svg", svg, true),
+            ("export/home.toml", b"label = \"Home\"\n", false),
+            ("__MACOSX/export/._home.svg", b"junk", false),
+            ("export/readme.txt", b"", false),
+        ]);
+        let entries = read_zip(&data).unwrap();
+        assert_eq!(entries.len(), 4);
+        assert_eq!(entries[0], ("export/home.svg".to_string(), svg.to_vec()));
+
+        // An entry recorded as too large is rejected before inflating it
+        let mut bomb = zip(&[("bomb.svg", svg, true)]);
+        let central = bomb.len() - 22 - (46 + "bomb.svg".len());
+        bomb[central + 24..central + 28].copy_from_slice(&u32::MAX.to_le_bytes());
+        let err = read_zip(&bomb).unwrap_err();
+        assert!(err.to_string().contains("`bomb.svg` is too large"));
+
+        let path = std::env::temp_dir().join(format!("svg2font-zip-{}.zip", std::process::id()));
+        std::fs::write(&path, &data).unwrap();
+        let unpacked = UnpackedArchive::unpack(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let mut names: Vec<_> = std::fs::read_dir(unpacked.dir())
+            .unwrap()
+            .map(|e| e.unwrap().file_name().into_string().unwrap())
+            .collect();
+        names.sort();
+        assert_eq!(names, ["home.svg", "home.toml"]);
+        assert_eq!(unpacked_name("icons\\home.svg"), Some("home.svg"));
+        assert_eq!(unpacked_name("..\\..\\evil.svg"), Some("evil.svg"));
+        assert_eq!(unpacked_name("icons/.."), None);
+        assert_eq!(unpacked_name("icons\\"), None);
+
+        // Unpacking the same archive again gets a directory of its own
+        let again = UnpackedArchive::from_bytes(&data, "again").unwrap();
+        assert_ne!(again.dir(), unpacked.dir());
+
+        let dir = unpacked.dir().to_path_buf();
+        drop(unpacked);
+        assert!(!dir.exists());
+        assert!(again.dir().join("home.svg").exists());
+    }
+}
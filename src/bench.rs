@@ -0,0 +1,108 @@
+use anyhow::Result;
+use std::path::Path;
+use std::time::{Duration, Instant};
+use svg2font::{font_builder, svg_parser};
+
+/// Timings of one pipeline phase across all iterations
+struct Phase {
+    name: &'static str,
+    samples: Vec<Duration>,
+}
+
+impl Phase {
+    fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            samples: Vec::new(),
+        }
+    }
+
+    fn time<T>(&mut self, f: impl FnOnce() -> Result<T>) -> Result<T> {
+        let start = Instant::now();
+        let value = f()?;
+        self.samples.push(start.elapsed());
+        Ok(value)
+    }
+
+    fn print(&self) {
+        let mut sorted = self.samples.clone();
+        sorted.sort();
+
+        let total: Duration = sorted.iter().sum();
+        let mean = total / sorted.len() as u32;
+        let median = sorted[sorted.len() / 2];
+
+        println!(
+            "{:<10} {:>10.3} {:>10.3} {:>10.3} {:>10.3}",
+            self.name,
+            millis(sorted[0]),
+            millis(median),
+            millis(mean),
+            millis(sorted[sorted.len() - 1]),
+        );
+    }
+}
+
+fn millis(d: Duration) -> f64 {
+    d.as_secs_f64() * 1000.0
+}
+
+/// Time the parse, convert and build phases over an icon directory
+pub fn run(input: &Path, iterations: u32, warmup: u32) -> Result<()> {
+    let iterations = iterations.max(1);
+
+    let mut parse = Phase::new("parse");
+    let mut convert = Phase::new("convert");
+    let mut build = Phase::new("build");
+    let mut total = Phase::new("total");
+    let mut icon_count = 0;
+    let mut font_size = 0;
+
+    for i in 0..warmup + iterations {
+        // Warmup runs fill OS file caches and are not recorded
+        let measured = i >= warmup;
+        let start = Instant::now();
+
+        let icons = time_if(measured, &mut parse, || {
+            svg_parser::parse_svg_directory(input, None, false, false)
+        })?;
+        if icons.is_empty() {
+            anyhow::bail!("No SVG files found in {}", input.display());
+        }
+
+        let glyphs = time_if(measured, &mut convert, || {
+            font_builder::convert_glyphs(&icons, &Default::default(), None, false)
+        })?;
+        let font_data = time_if(measured, &mut build, || {
+            font_builder::assemble_font(&icons, &glyphs, "Bench", &Default::default())
+        })?;
+
+        if measured {
+            total.samples.push(start.elapsed());
+        }
+        icon_count = icons.len();
+        font_size = font_data.len();
+    }
+
+    println!(
+        "{} icons, {} byte font, {} iterations ({} warmup)\n",
+        icon_count, font_size, iterations, warmup
+    );
+    println!(
+        "{:<10} {:>10} {:>10} {:>10} {:>10}",
+        "phase", "min ms", "median ms", "mean ms", "max ms"
+    );
+    for phase in [&parse, &convert, &build, &total] {
+        phase.print();
+    }
+
+    Ok(())
+}
+
+fn time_if<T>(measured: bool, phase: &mut Phase, f: impl FnOnce() -> Result<T>) -> Result<T> {
+    if measured {
+        phase.time(f)
+    } else {
+        f()
+    }
+}
@@ -0,0 +1,419 @@
+//! Boolean operations on filled outlines.
+//!
+//! Both operands are flattened to polygons on a shared integer grid, every
+//! edge is split where it meets another, and the pieces that separate the
+//! inside of the result from its outside are chained back into contours.
+//! Curves come back as line segments, so these are only used where the
+//! plain outline would be wrong.
+
+use kurbo::{BezPath, PathEl, Point, Rect, Shape};
+use std::collections::{HashMap, HashSet};
+use usvg::FillRule;
+
+/// Flattening tolerance as a fraction of the operands' size
+const TOLERANCE: f64 = 1.0 / 4000.0;
+
+/// Grid steps across the operands' size, at least
+const GRID: f64 = (1 << 20) as f64;
+
+/// Distance in grid steps from an edge's midpoint at which its sides are sampled
+const SIDE_OFFSET: f64 = 1e-3;
+
+type GridPoint = (i64, i64);
+
+/// An edge of a flattened operand
+#[derive(Debug, Clone, Copy)]
+struct Edge {
+    from: GridPoint,
+    to: GridPoint,
+    /// 0 for the first operand, 1 for the second
+    operand: usize,
+}
+
+/// The area covered by both `a` and `b`
+pub fn intersect(a: &BezPath, a_rule: FillRule, b: &BezPath, b_rule: FillRule) -> BezPath {
+    combine(a, a_rule, b, b_rule, |a, b| a && b)
+}
+
+/// The area covered by `a` or `b`
+pub fn union(a: &BezPath, a_rule: FillRule, b: &BezPath, b_rule: FillRule) -> BezPath {
+    combine(a, a_rule, b, b_rule, |a, b| a || b)
+}
+
+/// The area covered by `a` but not `b`
+pub fn difference(a: &BezPath, a_rule: FillRule, b: &BezPath, b_rule: FillRule) -> BezPath {
+    combine(a, a_rule, b, b_rule, |a, b| a && !b)
+}
+
+/// The area `path` covers under `rule`, as contours that cover the same
+/// area under either fill rule
+pub fn simplify(path: &BezPath, rule: FillRule) -> BezPath {
+    combine(path, rule, &BezPath::new(), FillRule::NonZero, |a, _| a)
+}
+
+/// Whether the contours of `path`, flattened, cross or touch each other
+/// or themselves anywhere but at shared end points
+pub fn has_crossings(path: &BezPath) -> bool {
+    let Some((grid, tolerance)) = Grid::covering(&[path]) else {
+        return false;
+    };
+    let edges = grid.edges(path, tolerance, 0);
+    split_edges(&edges).len() != edges.len()
+}
+
+/// Outline of the points for which `keep(in a, in b)` holds.
+///
+/// Outer contours of the result have a negative signed area and holes a
+/// positive one, the orientation even-odd conversion produces.
+fn combine(
+    a: &BezPath,
+    a_rule: FillRule,
+    b: &BezPath,
+    b_rule: FillRule,
+    keep: impl Fn(bool, bool) -> bool,
+) -> BezPath {
+    let Some((grid, tolerance)) = Grid::covering(&[a, b]) else {
+        return BezPath::new();
+    };
+    let mut edges = grid.edges(a, tolerance, 0);
+    edges.extend(grid.edges(b, tolerance, 1));
+    let edges = split_edges(&edges);
+
+    let rules = [a_rule, b_rule];
+    let inside = |point: (f64, f64)| {
+        let winding = winding_numbers(&edges, point);
+        let filled = |i: usize| match rules[i] {
+            FillRule::NonZero => winding[i] != 0,
+            FillRule::EvenOdd => winding[i] % 2 != 0,
+        };
+        keep(filled(0), filled(1))
+    };
+
+    // Coincident edges of both operands are one boundary piece
+    let mut seen = HashSet::new();
+    let mut boundary = Vec::new();
+    for edge in &edges {
+        let key = (edge.from.min(edge.to), edge.from.max(edge.to));
+        if !seen.insert(key) {
+            continue;
+        }
+        let (dx, dy) = (
+            (edge.to.0 - edge.from.0) as f64,
+            (edge.to.1 - edge.from.1) as f64,
+        );
+        let length = dx.hypot(dy);
+        let (nx, ny) = (-dy / length * SIDE_OFFSET, dx / length * SIDE_OFFSET);
+        let mid = (
+            (edge.from.0 + edge.to.0) as f64 / 2.0,
+            (edge.from.1 + edge.to.1) as f64 / 2.0,
+        );
+        let left = inside((mid.0 + nx, mid.1 + ny));
+        let right = inside((mid.0 - nx, mid.1 - ny));
+        match (left, right) {
+            (true, false) => boundary.push((edge.to, edge.from)),
+            (false, true) => boundary.push((edge.from, edge.to)),
+            _ => {}
+        }
+    }
+
+    let mut result = BezPath::new();
+    for ring in chain(&boundary) {
+        let mut points = ring.into_iter().map(|p| grid.unsnap(p));
+        if let Some(first) = points.next() {
+            result.move_to(first);
+            for point in points {
+                result.line_to(point);
+            }
+            result.close_path();
+        }
+    }
+    result
+}
+
+/// Mapping between user coordinates and the integer grid
+struct Grid {
+    origin: Point,
+    scale: f64,
+}
+
+impl Grid {
+    /// A grid covering `paths`, with the flattening tolerance for their size
+    fn covering(paths: &[&BezPath]) -> Option<(Self, f64)> {
+        let bounds = paths
+            .iter()
+            .filter(|path| !path.elements().is_empty())
+            .map(|path| path.bounding_box())
+            .reduce(|a, b| a.union(b))?;
+        let extent = bounds.width().max(bounds.height());
+        if !extent.is_finite() || extent <= 0.0 {
+            return None;
+        }
+
+        let grid = Grid {
+            origin: bounds.origin(),
+            // A power of two keeps coordinates that are exact in binary exact
+            scale: (GRID / extent).log2().floor().exp2(),
+        };
+        Some((grid, extent * TOLERANCE))
+    }
+
+    /// Edges of a path flattened onto the grid
+    fn edges(&self, path: &BezPath, tolerance: f64, operand: usize) -> Vec<Edge> {
+        let mut edges = Vec::new();
+        for ring in flatten(path, tolerance) {
+            let ring: Vec<GridPoint> = ring.into_iter().map(|p| self.snap(p)).collect();
+            for (i, &from) in ring.iter().enumerate() {
+                let to = ring[(i + 1) % ring.len()];
+                if from != to {
+                    edges.push(Edge { from, to, operand });
+                }
+            }
+        }
+        edges
+    }
+
+    fn snap(&self, p: Point) -> GridPoint {
+        (
+            ((p.x - self.origin.x) * self.scale).round() as i64,
+            ((p.y - self.origin.y) * self.scale).round() as i64,
+        )
+    }
+
+    fn unsnap(&self, p: GridPoint) -> Point {
+        Point::new(
+            p.0 as f64 / self.scale + self.origin.x,
+            p.1 as f64 / self.scale + self.origin.y,
+        )
+    }
+}
+
+/// Closed polygons approximating each subpath, which is filled as if closed
+fn flatten(path: &BezPath, tolerance: f64) -> Vec<Vec<Point>> {
+    let mut rings: Vec<Vec<Point>> = Vec::new();
+    let mut current = Vec::new();
+    kurbo::flatten(path.iter(), tolerance, |el| match el {
+        PathEl::MoveTo(p) => {
+            if current.len() > 2 {
+                rings.push(std::mem::take(&mut current));
+            }
+            current.clear();
+            current.push(p);
+        }
+        PathEl::LineTo(p) => current.push(p),
+        PathEl::ClosePath => {
+            if current.len() > 2 {
+                rings.push(std::mem::take(&mut current));
+            }
+            current.clear();
+        }
+        _ => {}
+    });
+    if current.len() > 2 {
+        rings.push(current);
+    }
+    rings
+}
+
+fn cross(o: GridPoint, a: GridPoint, b: GridPoint) -> i128 {
+    (a.0 - o.0) as i128 * (b.1 - o.1) as i128 - (a.1 - o.1) as i128 * (b.0 - o.0) as i128
+}
+
+fn bounding_rect(edge: &Edge) -> Rect {
+    Rect::from_points(
+        (edge.from.0 as f64, edge.from.1 as f64),
+        (edge.to.0 as f64, edge.to.1 as f64),
+    )
+}
+
+/// Split edges wherever they cross or touch another edge, so that edges
+/// only meet at their end points
+fn split_edges(edges: &[Edge]) -> Vec<Edge> {
+    let mut splits: Vec<Vec<GridPoint>> = vec![Vec::new(); edges.len()];
+    let bounds: Vec<Rect> = edges.iter().map(bounding_rect).collect();
+
+    for i in 0..edges.len() {
+        for j in i + 1..edges.len() {
+            let (a, b) = (&edges[i], &edges[j]);
+            let (ra, rb) = (bounds[i], bounds[j]);
+            if ra.x1 < rb.x0 || rb.x1 < ra.x0 || ra.y1 < rb.y0 || rb.y1 < ra.y0 {
+                continue;
+            }
+
+            let d1 = cross(a.from, a.to, b.from);
+            let d2 = cross(a.from, a.to, b.to);
+            let d3 = cross(b.from, b.to, a.from);
+            let d4 = cross(b.from, b.to, a.to);
+
+            if d1 == 0 && d2 == 0 {
+                // Collinear: each end point inside the other edge splits it
+                for p in [b.from, b.to] {
+                    if strictly_within(a, p) {
+                        splits[i].push(p);
+                    }
+                }
+                for p in [a.from, a.to] {
+                    if strictly_within(b, p) {
+                        splits[j].push(p);
+                    }
+                }
+                continue;
+            }
+
+            if (d1 > 0 && d2 > 0) || (d1 < 0 && d2 < 0) || (d3 > 0 && d4 > 0) || (d3 < 0 && d4 < 0)
+            {
+                continue;
+            }
+
+            // The edges cross or touch; t is the position along `a`
+            let t = d3 as f64 / (d3 - d4) as f64;
+            let point = (
+                (a.from.0 as f64 + t * (a.to.0 - a.from.0) as f64).round() as i64,
+                (a.from.1 as f64 + t * (a.to.1 - a.from.1) as f64).round() as i64,
+            );
+            splits[i].push(point);
+            splits[j].push(point);
+        }
+    }
+
+    let mut result = Vec::with_capacity(edges.len());
+    for (edge, mut points) in edges.iter().zip(splits) {
+        let direction = (edge.to.0 - edge.from.0, edge.to.1 - edge.from.1);
+        let along = |p: &GridPoint| {
+            (p.0 - edge.from.0) as i128 * direction.0 as i128
+                + (p.1 - edge.from.1) as i128 * direction.1 as i128
+        };
+        points.sort_by_key(along);
+
+        let mut from = edge.from;
+        for to in points.into_iter().chain([edge.to]) {
+            if to != from {
+                result.push(Edge {
+                    from,
+                    to,
+                    operand: edge.operand,
+                });
+                from = to;
+            }
+        }
+    }
+    result
+}
+
+/// Whether a point on the line of an edge lies between its end points
+fn strictly_within(edge: &Edge, p: GridPoint) -> bool {
+    let dot = (p.0 - edge.from.0) as i128 * (edge.to.0 - edge.from.0) as i128
+        + (p.1 - edge.from.1) as i128 * (edge.to.1 - edge.from.1) as i128;
+    let length = (edge.to.0 - edge.from.0) as i128 * (edge.to.0 - edge.from.0) as i128
+        + (edge.to.1 - edge.from.1) as i128 * (edge.to.1 - edge.from.1) as i128;
+    dot > 0 && dot < length
+}
+
+/// Winding number of each operand around a point
+fn winding_numbers(edges: &[Edge], (x, y): (f64, f64)) -> [i32; 2] {
+    let mut winding = [0; 2];
+    for edge in edges {
+        let (x0, y0) = (edge.from.0 as f64, edge.from.1 as f64);
+        let (x1, y1) = (edge.to.0 as f64, edge.to.1 as f64);
+        let side = (x1 - x0) * (y - y0) - (x - x0) * (y1 - y0);
+        if y0 <= y {
+            if y1 > y && side > 0.0 {
+                winding[edge.operand] += 1;
+            }
+        } else if y1 <= y && side < 0.0 {
+            winding[edge.operand] -= 1;
+        }
+    }
+    winding
+}
+
+/// Join boundary pieces into closed contours, dropping points in the
+/// middle of straight runs
+fn chain(pieces: &[(GridPoint, GridPoint)]) -> Vec<Vec<GridPoint>> {
+    let mut outgoing: HashMap<GridPoint, Vec<usize>> = HashMap::new();
+    for (i, (from, _)) in pieces.iter().enumerate() {
+        outgoing.entry(*from).or_default().push(i);
+    }
+
+    let mut used = vec![false; pieces.len()];
+    let mut rings = Vec::new();
+    for start in 0..pieces.len() {
+        if used[start] {
+            continue;
+        }
+        let mut ring = vec![pieces[start].0];
+        let mut current = start;
+        loop {
+            used[current] = true;
+            let end = pieces[current].1;
+            if end == pieces[start].0 {
+                break;
+            }
+            ring.push(end);
+            match outgoing[&end].iter().find(|&&next| !used[next]) {
+                Some(&next) => current = next,
+                None => break,
+            }
+        }
+
+        let ring = drop_collinear(ring);
+        if ring.len() > 2 {
+            rings.push(ring);
+        }
+    }
+    rings
+}
+
+fn drop_collinear(ring: Vec<GridPoint>) -> Vec<GridPoint> {
+    let mut points: Vec<GridPoint> = Vec::with_capacity(ring.len());
+    for p in ring {
+        while points.len() >= 2 && cross(points[points.len() - 2], points[points.len() - 1], p) == 0
+        {
+            points.pop();
+        }
+        points.push(p);
+    }
+    // The seam between the last and first point
+    while points.len() > 2
+        && cross(
+            points[points.len() - 2],
+            points[points.len() - 1],
+            points[0],
+        ) == 0
+    {
+        points.pop();
+    }
+    while points.len() > 2 && cross(points[points.len() - 1], points[0], points[1]) == 0 {
+        points.remove(0);
+    }
+    points
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square(x: f64, y: f64, size: f64) -> BezPath {
+        Rect::new(x, y, x + size, y + size).to_path(0.1)
+    }
+
+    #[test]
+    fn test_boolean_operations() {
+        let a = square(0.0, 0.0, 10.0);
+        let b = square(5.0, 5.0, 10.0);
+        let nonzero = FillRule::NonZero;
+
+        let both = intersect(&a, nonzero, &b, nonzero);
+        assert_eq!(both.area(), -25.0);
+        assert_eq!(both.bounding_box(), Rect::new(5.0, 5.0, 10.0, 10.0));
+
+        assert_eq!(union(&a, nonzero, &b, nonzero).area(), -175.0);
+        assert_eq!(difference(&a, nonzero, &b, nonzero).area(), -75.0);
+
+        // A ring drawn as two same-direction squares under even-odd
+        let mut ring = square(0.0, 0.0, 10.0);
+        ring.extend(square(2.0, 2.0, 6.0));
+        let simplified = simplify(&ring, FillRule::EvenOdd);
+        assert_eq!(simplified.area(), -64.0);
+        assert_eq!(simplified.segments().count(), 8);
+    }
+}
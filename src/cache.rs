@@ -0,0 +1,128 @@
+use crate::svg_parser::ShapeFill;
+use anyhow::{Context, Result};
+use kurbo::BezPath;
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+
+/// Entries from other svg2font versions are ignored, since conversion may have changed
+const CACHE_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// An outline stored in the cache, with the size of the SVG it came from
+#[derive(Debug, Clone)]
+pub struct CachedPath {
+    pub width: f64,
+    pub height: f64,
+    /// Fill of each shape of a parsed SVG
+    pub fills: Vec<ShapeFill>,
+    pub path: BezPath,
+}
+
+/// On-disk cache of converted outlines, keyed by SVG content hash.
+///
+/// Each entry is a small text file: the source size and shape fills on the
+/// first line and SVG path data on the second. Unreadable
+/// entries are treated as misses.
+#[derive(Debug, Clone)]
+pub struct PathCache {
+    dir: PathBuf,
+}
+
+impl PathCache {
+    /// Open (and create if needed) a cache directory
+    pub fn open(dir: &Path) -> Result<Self> {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create cache directory {}", dir.display()))?;
+        Ok(Self {
+            dir: dir.to_path_buf(),
+        })
+    }
+
+    /// Look up a cached outline
+    pub fn load(&self, key: &str) -> Option<CachedPath> {
+        let content = std::fs::read_to_string(self.entry_path(key)).ok()?;
+        let (size, data) = content.split_once('\n')?;
+        let mut header = size.split(' ');
+
+        Some(CachedPath {
+            width: header.next()?.parse().ok()?,
+            height: header.next()?.parse().ok()?,
+            fills: header.map(parse_fill).collect::<Option<_>>()?,
+            path: BezPath::from_svg(data.trim_end()).ok()?,
+        })
+    }
+
+    /// Store an outline with its shape fills, replacing any previous entry
+    pub fn store(
+        &self,
+        key: &str,
+        width: f64,
+        height: f64,
+        fills: &[ShapeFill],
+        path: &BezPath,
+    ) -> Result<()> {
+        let mut header = format!("{} {}", width, height);
+        for fill in fills {
+            let [r, g, b, a] = fill.rgba;
+            let range = &fill.elements;
+            header += &format!(
+                " {}-{}:{:02x}{:02x}{:02x}{:02x}{}",
+                range.start,
+                range.end,
+                r,
+                g,
+                b,
+                a,
+                if fill.secondary { "s" } else { "" }
+            );
+        }
+        let content = format!("{}\n{}\n", header, path.to_svg());
+
+        // Write to a temporary file first so concurrent readers never see half an entry
+        let path = self.entry_path(key);
+        let tmp_path = path.with_extension(format!("tmp{}", std::process::id()));
+        std::fs::write(&tmp_path, content)
+            .with_context(|| format!("Failed to write {}", tmp_path.display()))?;
+        std::fs::rename(&tmp_path, &path)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+
+        Ok(())
+    }
+
+    fn entry_path(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{}.path", key))
+    }
+}
+
+/// Parse a shape fill written as `start-end:rrggbbaa`, with an `s` suffix
+/// for secondary layer shapes
+fn parse_fill(field: &str) -> Option<ShapeFill> {
+    let (range, color) = field.split_once(':')?;
+    let (color, secondary) = match color.strip_suffix('s') {
+        Some(color) => (color, true),
+        None => (color, false),
+    };
+    let (start, end) = range.split_once('-')?;
+    let color = u32::from_str_radix(color, 16).ok()?;
+    Some(ShapeFill {
+        elements: start.parse().ok()?..end.parse().ok()?,
+        rgba: color.to_be_bytes(),
+        secondary,
+    })
+}
+
+/// Hex-encoded SHA-256 of `data`
+pub fn content_hash(data: &[u8]) -> String {
+    let digest = Sha256::digest(data);
+    digest.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Cache key for the outline parsed from an SVG with the given content hash
+pub fn parsed_key(source_hash: &str) -> String {
+    format!("{}-v{}-parsed", source_hash, CACHE_VERSION)
+}
+
+/// Cache key for the glyph outline built from an SVG with the given content
+/// hash, with `settings` describing the scaling and curve conversion
+pub fn glyph_key(source_hash: &str, settings: &str) -> String {
+    format!("{}-v{}-glyph{}", source_hash, CACHE_VERSION, settings)
+}
@@ -0,0 +1,305 @@
+use kurbo::{BezPath, PathEl, Point, Shape};
+
+/// First string id after the standard strings
+const FIRST_CUSTOM_SID: usize = 391;
+
+/// Most operands a Type 2 charstring operator may take
+const MAX_STACK: usize = 48;
+
+// Top DICT and Private DICT operators
+const OP_FULL_NAME: &[u8] = &[2];
+const OP_FAMILY_NAME: &[u8] = &[3];
+const OP_FONT_BBOX: &[u8] = &[5];
+const OP_CHARSET: &[u8] = &[15];
+const OP_CHAR_STRINGS: &[u8] = &[17];
+const OP_PRIVATE: &[u8] = &[18];
+const OP_DEFAULT_WIDTH_X: &[u8] = &[20];
+
+// Charstring operators
+const RLINETO: u8 = 5;
+const RRCURVETO: u8 = 8;
+const ENDCHAR: u8 = 14;
+const RMOVETO: u8 = 21;
+
+/// Build a CFF table holding `outlines` as cubic Type 2 charstrings.
+///
+/// Glyph 0 is .notdef drawn with `notdef`, followed by one glyph per outline
+/// named after `glyph_names`. Outlines are in font units, with the widths in
+/// `advances`; .notdef and glyphs without a width have `default_advance`.
+pub fn build_cff(
+    font_name: &str,
+    glyph_names: &[&str],
+    notdef: &BezPath,
+    outlines: &[BezPath],
+    advances: &[u16],
+    default_advance: u16,
+) -> Vec<u8> {
+    let ps_name: String = font_name
+        .chars()
+        .filter(|c| c.is_ascii_graphic() && !"[](){}<>/%".contains(*c))
+        .collect();
+
+    // Strings: the family name, then one per glyph
+    let mut strings = vec![font_name.as_bytes().to_vec()];
+    strings.extend(glyph_names.iter().map(|name| name.as_bytes().to_vec()));
+    let family_sid = FIRST_CUSTOM_SID as i32;
+
+    let mut charset = vec![0u8];
+    for sid in (FIRST_CUSTOM_SID + 1..).take(glyph_names.len()) {
+        charset.extend_from_slice(&(sid as u16).to_be_bytes());
+    }
+
+    let mut charstrings = vec![charstring(notdef, None)];
+    charstrings.extend(outlines.iter().enumerate().map(|(i, outline)| {
+        // nominalWidthX is 0, so a width is stored as it is
+        let width = advances
+            .get(i)
+            .filter(|&&advance| advance != default_advance)
+            .map(|&advance| advance as i32);
+        charstring(outline, width)
+    }));
+
+    let mut private = Vec::new();
+    push_dict_int(&mut private, default_advance as i32);
+    private.extend_from_slice(OP_DEFAULT_WIDTH_X);
+
+    let bbox = std::iter::once(notdef)
+        .chain(outlines)
+        .filter(|outline| !outline.elements().is_empty())
+        .map(|outline| outline.bounding_box())
+        .reduce(|a, b| a.union(b))
+        .unwrap_or_default();
+
+    // Offsets are written as 5-byte integers, so the Top DICT size does not depend on them
+    let top_dict = |charset_offset: i32, charstrings_offset: i32, private_offset: i32| {
+        let mut dict = Vec::new();
+        push_dict_int(&mut dict, family_sid);
+        dict.extend_from_slice(OP_FULL_NAME);
+        push_dict_int(&mut dict, family_sid);
+        dict.extend_from_slice(OP_FAMILY_NAME);
+        for value in [
+            bbox.x0.floor(),
+            bbox.y0.floor(),
+            bbox.x1.ceil(),
+            bbox.y1.ceil(),
+        ] {
+            push_dict_int(&mut dict, value as i32);
+        }
+        dict.extend_from_slice(OP_FONT_BBOX);
+        push_dict_offset(&mut dict, charset_offset);
+        dict.extend_from_slice(OP_CHARSET);
+        push_dict_offset(&mut dict, charstrings_offset);
+        dict.extend_from_slice(OP_CHAR_STRINGS);
+        push_dict_int(&mut dict, private.len() as i32);
+        push_dict_offset(&mut dict, private_offset);
+        dict.extend_from_slice(OP_PRIVATE);
+        dict
+    };
+
+    let header = [1, 0, 4, 4];
+    let name_index = index(&[ps_name.into_bytes()]);
+    let string_index = index(&strings);
+    let global_subrs = index(&[]);
+    let charstrings_index = index(&charstrings);
+
+    let top_dict_len = index(&[top_dict(0, 0, 0)]).len();
+    let charset_offset =
+        header.len() + name_index.len() + top_dict_len + string_index.len() + global_subrs.len();
+    let charstrings_offset = charset_offset + charset.len();
+    let private_offset = charstrings_offset + charstrings_index.len();
+    let top_dict_index = index(&[top_dict(
+        charset_offset as i32,
+        charstrings_offset as i32,
+        private_offset as i32,
+    )]);
+
+    [
+        &header[..],
+        &name_index,
+        &top_dict_index,
+        &string_index,
+        &global_subrs,
+        &charset,
+        &charstrings_index,
+        &private,
+    ]
+    .concat()
+}
+
+/// Encode an outline as a Type 2 charstring with rounded coordinates,
+/// starting with `width` when it is not the default
+fn charstring(outline: &BezPath, width: Option<i32>) -> Vec<u8> {
+    let mut out = Vec::new();
+    // The width goes before the operands of the first moveto or endchar
+    let mut args: Vec<i32> = width.into_iter().collect();
+    let mut pending_op = None;
+    let mut current = (0, 0);
+    let mut start = Point::ZERO;
+    let mut last = Point::ZERO;
+
+    // Deltas between rounded points, so rounding errors do not accumulate
+    let mut delta = |p: Point| {
+        let rounded = (p.x.round() as i32, p.y.round() as i32);
+        let d = [rounded.0 - current.0, rounded.1 - current.1];
+        current = rounded;
+        d
+    };
+
+    for el in outline.elements() {
+        let (op, deltas) = match *el {
+            PathEl::MoveTo(p) => {
+                start = p;
+                last = p;
+                emit(&mut out, &mut args, pending_op.take());
+                let d = delta(p);
+                args.extend(d);
+                emit(&mut out, &mut args, Some(RMOVETO));
+                continue;
+            }
+            PathEl::LineTo(p) => {
+                last = p;
+                (RLINETO, delta(p).to_vec())
+            }
+            PathEl::QuadTo(q, p) => {
+                // Raise to a cubic with the same shape
+                let c1 = last + (q - last) * (2.0 / 3.0);
+                let c2 = p + (q - p) * (2.0 / 3.0);
+                last = p;
+                (RRCURVETO, [delta(c1), delta(c2), delta(p)].concat())
+            }
+            PathEl::CurveTo(c1, c2, p) => {
+                last = p;
+                (RRCURVETO, [delta(c1), delta(c2), delta(p)].concat())
+            }
+            // Charstring contours close implicitly
+            PathEl::ClosePath => {
+                last = start;
+                continue;
+            }
+        };
+        if pending_op != Some(op) || args.len() + deltas.len() > MAX_STACK {
+            let op = pending_op.replace(op);
+            emit(&mut out, &mut args, op);
+        }
+        args.extend(deltas);
+    }
+    emit(&mut out, &mut args, pending_op);
+    out.push(ENDCHAR);
+    out
+}
+
+/// Write the operands collected for `op`, then the operator
+fn emit(out: &mut Vec<u8>, args: &mut Vec<i32>, op: Option<u8>) {
+    for arg in args.drain(..) {
+        push_charstring_int(out, arg);
+    }
+    out.extend(op);
+}
+
+/// Serialize a CFF INDEX
+fn index(items: &[Vec<u8>]) -> Vec<u8> {
+    let mut out = (items.len() as u16).to_be_bytes().to_vec();
+    if items.is_empty() {
+        return out;
+    }
+
+    let data_len: usize = items.iter().map(Vec::len).sum();
+    let off_size = match data_len + 1 {
+        0..=0xFF => 1,
+        0x100..=0xFFFF => 2,
+        0x1_0000..=0xFF_FFFF => 3,
+        _ => 4,
+    };
+    out.push(off_size as u8);
+    let mut offset = 1;
+    for item in std::iter::once(&Vec::new()).chain(items) {
+        offset += item.len();
+        out.extend_from_slice(&(offset as u32).to_be_bytes()[4 - off_size..]);
+    }
+    for item in items {
+        out.extend_from_slice(item);
+    }
+    out
+}
+
+fn push_dict_int(out: &mut Vec<u8>, value: i32) {
+    match value {
+        -107..=107 => out.push((value + 139) as u8),
+        108..=1131 => {
+            let v = value - 108;
+            out.extend_from_slice(&[(v / 256 + 247) as u8, (v % 256) as u8]);
+        }
+        -1131..=-108 => {
+            let v = -value - 108;
+            out.extend_from_slice(&[(v / 256 + 251) as u8, (v % 256) as u8]);
+        }
+        -32768..=32767 => {
+            out.push(28);
+            out.extend_from_slice(&(value as i16).to_be_bytes());
+        }
+        _ => push_dict_offset(out, value),
+    }
+}
+
+/// A DICT integer that always takes 5 bytes
+fn push_dict_offset(out: &mut Vec<u8>, value: i32) {
+    out.push(29);
+    out.extend_from_slice(&value.to_be_bytes());
+}
+
+fn push_charstring_int(out: &mut Vec<u8>, value: i32) {
+    match value {
+        -1131..=1131 => push_dict_int(out, value),
+        // Glyph coordinates are clamped well within an i16
+        _ => {
+            out.push(28);
+            out.extend_from_slice(&(value as i16).to_be_bytes());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use read_fonts::tables::cff::Cff;
+    use read_fonts::tables::postscript::{dict, Index};
+    use read_fonts::{FontData, FontRead};
+
+    #[test]
+    fn test_cff_charstrings() {
+        let mut square = BezPath::new();
+        square.move_to((100.0, 100.0));
+        square.line_to((300.0, 100.0));
+        square.line_to((300.0, 300.0));
+        square.line_to((100.0, 300.0));
+        square.close_path();
+        // A width other than the default comes first
+        assert_eq!(charstring(&square, Some(600))[..4], [248, 236, 239, 239]);
+        let data = build_cff(
+            "My Icons",
+            &["square"],
+            &BezPath::new(),
+            &[square],
+            &[1000],
+            1000,
+        );
+
+        let cff = Cff::read(FontData::new(&data)).unwrap();
+        assert_eq!(cff.name(0).unwrap().to_string(), "MyIcons");
+        let top_dict = cff.top_dicts().get(0).unwrap();
+        let offset = dict::entries(top_dict, None)
+            .find_map(|entry| match entry.unwrap() {
+                dict::Entry::CharstringsOffset(offset) => Some(offset),
+                _ => None,
+            })
+            .unwrap();
+
+        let charstrings = Index::new(&data[offset..], false).unwrap();
+        assert_eq!(charstrings.count(), 2);
+        // rmoveto 100 100, rlineto 200 0 0 200 -200 0, endchar
+        assert_eq!(
+            charstrings.get(1).unwrap(),
+            [239, 239, 21, 247, 92, 139, 139, 247, 92, 251, 92, 139, 5, 14]
+        );
+    }
+}
@@ -0,0 +1,265 @@
+//! Sanity checks on a built font, a lightweight gate in the spirit of FontBakery.
+
+use crate::subset::codepoint_mappings;
+use anyhow::Result;
+use read_fonts::{types::Tag, FontRef, TableProvider};
+use std::fmt;
+
+/// Tables every font needs, whatever its outline format
+const REQUIRED_TABLES: [&[u8; 4]; 7] = [
+    b"cmap", b"head", b"hhea", b"hmtx", b"maxp", b"name", b"post",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+/// One problem found in a font
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Finding {
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self.severity {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        };
+        write!(f, "{}: {}", label, self.message)
+    }
+}
+
+#[derive(Default)]
+struct Findings(Vec<Finding>);
+
+impl Findings {
+    fn error(&mut self, message: String) {
+        self.0.push(Finding {
+            severity: Severity::Error,
+            message,
+        });
+    }
+
+    fn warning(&mut self, message: String) {
+        self.0.push(Finding {
+            severity: Severity::Warning,
+            message,
+        });
+    }
+}
+
+/// Check that a font parses with two independent readers and that its
+/// tables, glyph counts, cmap, loca and metrics are consistent.
+///
+/// A font read-fonts cannot parse gets no further checks.
+pub fn check_font(data: &[u8]) -> Vec<Finding> {
+    let mut findings = Findings::default();
+    let face = match ttf_parser::Face::parse(data, 0) {
+        Ok(face) => Some(face),
+        Err(e) => {
+            findings.error(format!("ttf-parser cannot parse the font: {}", e));
+            None
+        }
+    };
+    let font = match FontRef::new(data) {
+        Ok(font) => font,
+        Err(e) => {
+            findings.error(format!("read-fonts cannot parse the font: {}", e));
+            return findings.0;
+        }
+    };
+    if let Err(e) = check_tables(&font, face.as_ref(), &mut findings) {
+        findings.error(format!("Unreadable table: {}", e));
+    }
+    findings.0
+}
+
+fn check_tables(
+    font: &FontRef,
+    face: Option<&ttf_parser::Face>,
+    findings: &mut Findings,
+) -> Result<()> {
+    let has = |tag: &[u8; 4]| font.table_data(Tag::new(tag)).is_some();
+    for tag in REQUIRED_TABLES {
+        if !has(tag) {
+            findings.error(format!("Missing required table {}", Tag::new(tag)));
+        }
+    }
+    let truetype = has(b"glyf") && has(b"loca");
+    if !truetype && !has(b"CFF ") {
+        findings.error("No outlines: neither glyf and loca nor CFF".to_string());
+    }
+    if !has(b"OS/2") {
+        findings.warning("Missing OS/2 table, which Windows requires".to_string());
+    }
+
+    let head = font.head()?;
+    let units_per_em = head.units_per_em();
+    if !(16..=16384).contains(&units_per_em) {
+        findings.error(format!(
+            "Units per em {} is outside 16..16384",
+            units_per_em
+        ));
+    }
+
+    // Glyph counts
+    let num_glyphs = font.maxp()?.num_glyphs();
+    if let Some(face) = face {
+        if face.number_of_glyphs() != num_glyphs {
+            findings.error(format!(
+                "maxp says {} glyphs, ttf-parser counts {}",
+                num_glyphs,
+                face.number_of_glyphs()
+            ));
+        }
+    }
+    let hhea = font.hhea()?;
+    let long_metrics = hhea.number_of_h_metrics();
+    if long_metrics == 0 || long_metrics > num_glyphs {
+        findings.error(format!(
+            "hhea has {} long metrics for {} glyphs",
+            long_metrics, num_glyphs
+        ));
+    }
+
+    // loca format and offsets
+    if truetype {
+        let format = head.index_to_loc_format();
+        let loca_len = font.table_data(Tag::new(b"loca")).map_or(0, |t| t.len());
+        let glyf_len = font.table_data(Tag::new(b"glyf")).map_or(0, |t| t.len());
+        let entry_size = match format {
+            0 => Some(2),
+            1 => Some(4),
+            _ => {
+                findings.error(format!("head has an invalid loca format {}", format));
+                None
+            }
+        };
+        if let Some(entry_size) = entry_size {
+            let expected = (num_glyphs as usize + 1) * entry_size;
+            if loca_len != expected {
+                findings.error(format!(
+                    "loca is {} bytes, {} glyphs in the {} format need {}",
+                    loca_len,
+                    num_glyphs,
+                    if entry_size == 2 { "short" } else { "long" },
+                    expected
+                ));
+            } else {
+                let loca = font.loca(None)?;
+                let offsets: Vec<u32> = (0..=num_glyphs as usize)
+                    .filter_map(|i| loca.get_raw(i))
+                    .collect();
+                if offsets.windows(2).any(|pair| pair[0] > pair[1]) {
+                    findings.error("loca offsets are not in ascending order".to_string());
+                }
+                if offsets.last().is_some_and(|&end| end as usize > glyf_len) {
+                    findings.error("loca points past the end of glyf".to_string());
+                }
+            }
+        }
+    }
+
+    // cmap
+    let mappings = codepoint_mappings(font)?;
+    if mappings.is_empty() {
+        findings.error("cmap maps no characters".to_string());
+    }
+    for (&codepoint, &gid) in &mappings {
+        if gid >= num_glyphs {
+            findings.error(format!(
+                "U+{:04X} maps to glyph {}, past the last of {} glyphs",
+                codepoint, gid, num_glyphs
+            ));
+            continue;
+        }
+        if gid == 0 {
+            findings.warning(format!("U+{:04X} maps to .notdef", codepoint));
+        }
+        // Every subtable, as symbol cmaps are no Unicode encoding
+        let independent = face.and_then(|face| face.tables().cmap).map(|cmap| {
+            cmap.subtables
+                .into_iter()
+                .find_map(|subtable| subtable.glyph_index(codepoint))
+                .map(|g| g.0)
+        });
+        if let Some(independent) = independent {
+            if independent != Some(gid) {
+                findings.error(format!(
+                    "U+{:04X} maps to glyph {} but ttf-parser reads {:?}",
+                    codepoint, gid, independent
+                ));
+            }
+        }
+    }
+
+    // Metrics
+    let hmtx = font.hmtx()?;
+    for (&codepoint, &gid) in &mappings {
+        let advance = hmtx.advance(read_fonts::types::GlyphId::new(gid as u32));
+        if advance == Some(0) && codepoint != 0x20 {
+            findings.warning(format!("U+{:04X} has a zero advance width", codepoint));
+        }
+    }
+    let (ascender, descender) = (hhea.ascender().to_i16(), hhea.descender().to_i16());
+    if ascender <= 0 || descender > 0 {
+        findings.error(format!(
+            "hhea ascender {} and descender {} should be above and below the baseline",
+            ascender, descender
+        ));
+    }
+    if let Ok(os2) = font.os2() {
+        let (win_ascent, win_descent) = (os2.us_win_ascent() as i32, os2.us_win_descent() as i32);
+        if head.y_max() as i32 > win_ascent || -(head.y_min() as i32) > win_descent {
+            findings.warning(format!(
+                "Glyphs reach from {} to {}, beyond usWinAscent {} and usWinDescent {}, and will be clipped on Windows",
+                head.y_min(),
+                head.y_max(),
+                win_ascent,
+                win_descent
+            ));
+        }
+        if os2.s_typo_ascender() - os2.s_typo_descender() <= 0 {
+            findings.error("OS/2 typo ascender is not above the typo descender".to_string());
+        }
+    }
+    if head.x_min() > head.x_max() || head.y_min() > head.y_max() {
+        findings.error("head bounding box is inverted".to_string());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::font_builder::{build_font_bytes, FontOptions};
+    use crate::svg_parser::parse_svg_str;
+
+    #[test]
+    fn test_check_font() {
+        let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 24 24"><rect x="4" y="4" width="16" height="16"/></svg>"#;
+        let icon = parse_svg_str(svg, "square", 0xE000).unwrap();
+        let data = build_font_bytes(&[icon], "Test", &FontOptions::default(), None, false).unwrap();
+        assert_eq!(check_font(&data), []);
+
+        // A glyph count that disagrees with loca
+        let mut broken = data.clone();
+        let font = FontRef::new(&data).unwrap();
+        let maxp = font
+            .table_directory
+            .table_records()
+            .iter()
+            .find(|r| r.tag() == Tag::new(b"maxp"))
+            .unwrap();
+        let offset = maxp.offset() as usize + 4;
+        broken[offset..offset + 2].copy_from_slice(&9u16.to_be_bytes());
+        let findings = check_font(&broken);
+        assert!(findings.iter().any(|f| f.severity == Severity::Error));
+
+        assert_eq!(check_font(b"not a font").len(), 2);
+    }
+}
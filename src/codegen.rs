@@ -0,0 +1,370 @@
+use crate::identifier::camel_case;
+use crate::svg_parser::Icon;
+use anyhow::{Context, Result};
+use std::fmt::Write as _;
+use std::path::Path;
+
+/// Write a TypeScript module mapping icon names to their characters
+pub fn generate_typescript(icons: &[Icon], font_name: &str, output_path: &Path) -> Result<()> {
+    std::fs::write(output_path, typescript_source(icons, font_name))
+        .with_context(|| format!("Failed to write {}", output_path.display()))
+}
+
+/// Write a React component rendering icons by name, importing the
+/// TypeScript module `module` written by [`generate_typescript`]
+pub fn generate_react(font_name: &str, module: &str, output_path: &Path) -> Result<()> {
+    std::fs::write(output_path, react_source(font_name, module))
+        .with_context(|| format!("Failed to write {}", output_path.display()))
+}
+
+/// Write a Swift enum of the icons with their characters as raw values
+pub fn generate_swift(icons: &[Icon], font_name: &str, output_path: &Path) -> Result<()> {
+    std::fs::write(output_path, swift_source(icons, font_name))
+        .with_context(|| format!("Failed to write {}", output_path.display()))
+}
+
+/// Write a Kotlin object holding each icon's character as a constant
+pub fn generate_kotlin(
+    icons: &[Icon],
+    font_name: &str,
+    package: Option<&str>,
+    output_path: &Path,
+) -> Result<()> {
+    std::fs::write(output_path, kotlin_source(icons, font_name, package))
+        .with_context(|| format!("Failed to write {}", output_path.display()))
+}
+
+/// Write a Dart class with an `IconData` constant per icon, for a font
+/// bundled in the Flutter package `package` if given
+pub fn generate_dart(
+    icons: &[Icon],
+    font_name: &str,
+    package: Option<&str>,
+    output_path: &Path,
+) -> Result<()> {
+    std::fs::write(output_path, dart_source(icons, font_name, package))
+        .with_context(|| format!("Failed to write {}", output_path.display()))
+}
+
+/// The font family name as a type name, e.g. `MyAppIcons` for "My App Icons"
+fn type_name(font_name: &str) -> String {
+    let name: String = font_name
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || *c == '_')
+        .collect();
+    match name.chars().next() {
+        Some(c) if c.is_ascii_digit() => format!("_{}", name),
+        Some(_) => name,
+        None => "Icons".to_string(),
+    }
+}
+
+/// Name of the Swift and Kotlin source files, e.g. `MyAppIcons` for "My App Icons"
+pub fn file_name(font_name: &str) -> String {
+    type_name(font_name)
+}
+
+/// Name of the React component, e.g. `MyAppIcon` for "My App Icons"
+pub fn component_name(font_name: &str) -> String {
+    let type_name = type_name(font_name);
+    match type_name.strip_suffix("Icons") {
+        Some(base) if !base.is_empty() => format!("{}Icon", base),
+        _ => format!("{}Icon", type_name),
+    }
+}
+
+/// Details of a generated Flutter package or Rust crate beyond its icons
+#[derive(Debug, Clone, Default)]
+pub struct PackageInfo<'a> {
+    /// Font version as MAJOR.MINOR, published as MAJOR.MINOR.0 [default: 1.0]
+    pub version: Option<&'a str>,
+    pub description: Option<&'a str>,
+    /// License text, written to `LICENSE`
+    pub license: Option<&'a str>,
+}
+
+/// Name of the Flutter package and Rust crate, e.g. `my_app_icons` for "My App Icons"
+pub fn package_name(font_name: &str) -> String {
+    let name = font_name
+        .to_lowercase()
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join("_");
+    // Package names start with a letter
+    match name.chars().next() {
+        Some(c) if c.is_ascii_alphabetic() => name,
+        Some(_) => format!("icons_{}", name),
+        None => "icons".to_string(),
+    }
+}
+
+/// Prefix of the icons' CSS classes, e.g. `my-app-icons` for "My App Icons"
+pub fn css_prefix(font_name: &str) -> String {
+    font_name.to_lowercase().replace(' ', "-")
+}
+
+/// Ways to refer to one icon in code, named as in the generated sources
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IconSnippets {
+    /// CSS class, as in the duotone stylesheet
+    pub css_class: String,
+    pub html_entity: String,
+    /// Flutter `IconData` constructor
+    pub dart: String,
+    /// Unicode escape, as written in TypeScript, Swift and Dart
+    pub escape: String,
+}
+
+/// Snippets referring to `icon` in the font named `font_name`
+pub fn icon_snippets(icon: &Icon, font_name: &str) -> IconSnippets {
+    let family = dart_string(font_name);
+    IconSnippets {
+        css_class: format!("{}-{}", css_prefix(font_name), icon.name),
+        html_entity: format!("&#x{:04X};", icon.codepoint),
+        dart: format!("IconData(0x{:04X}, fontFamily: {})", icon.codepoint, family),
+        escape: format!("\\u{{{:04X}}}", icon.codepoint),
+    }
+}
+
+/// A single-quoted Dart string literal
+fn dart_string(text: &str) -> String {
+    format!(
+        "'{}'",
+        text.replace('\\', "\\\\")
+            .replace('\'', "\\'")
+            .replace('$', "\\$")
+    )
+}
+
+fn typescript_source(icons: &[Icon], font_name: &str) -> String {
+    let type_name = type_name(font_name);
+    let mut ts = format!(
+        "// Generated by svg2font; do not edit.\n\n\
+         export const {type_name}FontFamily = \"{font_name}\";\n\n\
+         /** Character of each icon in the {font_name} font */\n\
+         export const {type_name} = {{\n",
+        font_name = font_name.replace('"', "\\\""),
+    );
+    for icon in icons {
+        if let Some(label) = &icon.label {
+            let _ = writeln!(ts, "  /** {} */", label.replace("*/", "*\\/"));
+        }
+        let _ = writeln!(
+            ts,
+            "  {}: \"\\u{{{:04X}}}\",",
+            camel_case(&icon.name),
+            icon.codepoint
+        );
+    }
+    let _ = write!(
+        ts,
+        "}} as const;\n\n\
+         /** Name of an icon in the {font_name} font */\n\
+         export type {type_name}Name = keyof typeof {type_name};\n",
+        font_name = font_name.replace("*/", "*\\/"),
+    );
+    ts
+}
+
+fn react_source(font_name: &str, module: &str) -> String {
+    let type_name = type_name(font_name);
+    let component = component_name(font_name);
+    let font_name = font_name.replace("*/", "*\\/");
+    format!(
+        r#"// Generated by svg2font; do not edit.
+import type {{ CSSProperties, HTMLAttributes }} from "react";
+import {{ {type_name}, {type_name}FontFamily, type {type_name}Name }} from "./{module}";
+
+export interface {component}Props extends HTMLAttributes<HTMLSpanElement> {{
+  /** Icon to show */
+  name: {type_name}Name;
+  /** Font size, e.g. 24 or "1.5rem" */
+  size?: number | string;
+  /** Accessible label; without one the icon is hidden from screen readers */
+  label?: string;
+}}
+
+/** An icon from the {font_name} font */
+export function {component}({{ name, size, label, style, ...props }}: {component}Props) {{
+  const iconStyle: CSSProperties = {{
+    display: "inline-block",
+    fontFamily: {type_name}FontFamily,
+    fontStyle: "normal",
+    fontWeight: "normal",
+    lineHeight: 1,
+    fontSize: size,
+    ...style,
+  }};
+  return (
+    <span
+      {{...props}}
+      style={{iconStyle}}
+      role={{label ? "img" : undefined}}
+      aria-label={{label}}
+      aria-hidden={{label ? undefined : true}}
+    >
+      {{{type_name}[name]}}
+    </span>
+  );
+}}
+"#
+    )
+}
+
+fn swift_source(icons: &[Icon], font_name: &str) -> String {
+    let mut swift = format!(
+        "// Generated by svg2font; do not edit.\n\n\
+         /// Icons of the {} font\n\
+         public enum {}: Character, CaseIterable {{\n\
+         \x20   /// Font family to render the icons with\n\
+         \x20   public static let fontFamily = \"{}\"\n",
+        font_name,
+        type_name(font_name),
+        font_name.replace('"', "\\\""),
+    );
+    swift.push('\n');
+    for icon in icons {
+        if let Some(label) = &icon.label {
+            let _ = writeln!(swift, "    /// {}", label);
+        }
+        let _ = writeln!(
+            swift,
+            "    case {} = \"\\u{{{:04X}}}\"",
+            camel_case(&icon.name),
+            icon.codepoint
+        );
+    }
+    swift.push_str("}\n");
+    swift
+}
+
+fn dart_source(icons: &[Icon], font_name: &str, package: Option<&str>) -> String {
+    let mut dart = format!(
+        "// Generated by svg2font; do not edit.\n\
+         // ignore_for_file: constant_identifier_names\n\n\
+         import 'package:flutter/widgets.dart';\n\n\
+         /// Icons of the {} font\n\
+         class {} {{\n\
+         \x20 {}._();\n\n\
+         \x20 /// Font family to render the icons with\n\
+         \x20 static const String fontFamily = {};\n\n\
+         \x20 /// Package bundling the font\n\
+         \x20 static const String? fontPackage = {};\n",
+        font_name,
+        type_name(font_name),
+        type_name(font_name),
+        dart_string(font_name),
+        package.map_or("null".to_string(), dart_string),
+    );
+    for icon in icons {
+        dart.push('\n');
+        if let Some(label) = &icon.label {
+            let _ = writeln!(dart, "  /// {}", label);
+        }
+        // Names are already checked to be valid Dart identifiers
+        let _ = writeln!(
+            dart,
+            "  static const IconData {} = IconData(0x{:04X}, fontFamily: fontFamily, fontPackage: fontPackage);",
+            icon.name, icon.codepoint
+        );
+    }
+    dart.push_str("}\n");
+    dart
+}
+
+fn kotlin_source(icons: &[Icon], font_name: &str, package: Option<&str>) -> String {
+    let mut kotlin = String::from("// Generated by svg2font; do not edit.\n\n");
+    if let Some(package) = package {
+        let _ = writeln!(kotlin, "package {}\n", package);
+    }
+    let _ = write!(
+        kotlin,
+        "/** Icons of the {} font */\n\
+         object {} {{\n\
+         \x20   /** Font family to render the icons with */\n\
+         \x20   const val FONT_FAMILY = \"{}\"\n",
+        font_name.replace("*/", "*\\/"),
+        type_name(font_name),
+        font_name.replace('"', "\\\"").replace('$', "\\$"),
+    );
+    kotlin.push('\n');
+    for icon in icons {
+        if let Some(label) = &icon.label {
+            let _ = writeln!(kotlin, "    /** {} */", label.replace("*/", "*\\/"));
+        }
+        // Kotlin escapes are UTF-16 code units
+        let escaped: String = char::from_u32(icon.codepoint)
+            .map(|c| {
+                c.encode_utf16(&mut [0; 2])
+                    .iter()
+                    .map(|u| format!("\\u{:04X}", u))
+                    .collect()
+            })
+            .unwrap_or_default();
+        let _ = writeln!(
+            kotlin,
+            "    const val {} = \"{}\"",
+            camel_case(&icon.name),
+            escaped
+        );
+    }
+    kotlin.push_str("}\n");
+    kotlin
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::svg_parser::parse_svg_str;
+
+    #[test]
+    fn test_generated_sources() {
+        let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 24 24"><title>Go back</title><rect width="24" height="24"/></svg>"#;
+        let icon = parse_svg_str(svg, "arrow-left", 0xE001).unwrap();
+
+        let icons = [icon];
+        let ts = typescript_source(&icons, "My Icons");
+        assert!(ts.contains("export const MyIconsFontFamily = \"My Icons\";"));
+        assert!(ts.contains("  /** Go back */\n  arrowLeft: \"\\u{E001}\",\n"));
+        assert!(ts.contains("export type MyIconsName = keyof typeof MyIcons;"));
+
+        let tsx = react_source("My Icons", "my_icons");
+        assert!(tsx.contains(
+            r#"import { MyIcons, MyIconsFontFamily, type MyIconsName } from "./my_icons";"#
+        ));
+        assert!(tsx.contains(
+            "export function MyIcon({ name, size, label, style, ...props }: MyIconProps) {"
+        ));
+        assert!(tsx.contains("      {MyIcons[name]}\n"));
+
+        let swift = swift_source(&icons, "My Icons");
+        assert!(swift.contains("public enum MyIcons: Character, CaseIterable {"));
+        assert!(swift.contains("    /// Go back\n    case arrowLeft = \"\\u{E001}\"\n"));
+
+        let kotlin = kotlin_source(&icons, "My Icons", Some("com.example"));
+        assert!(kotlin.contains(
+            "package com.example\n\n/** Icons of the My Icons font */\nobject MyIcons {"
+        ));
+        assert!(kotlin.contains("    /** Go back */\n    const val arrowLeft = \"\\uE001\"\n"));
+
+        let dart = dart_source(&icons, "My Icons", Some("my_icons"));
+        assert!(dart.contains("class MyIcons {\n  MyIcons._();\n"));
+        assert!(dart.contains("  static const String? fontPackage = 'my_icons';\n"));
+        assert!(dart.contains(
+            "  /// Go back\n  static const IconData arrow_left = IconData(0xE001, fontFamily: fontFamily, fontPackage: fontPackage);\n"
+        ));
+
+        assert_eq!(package_name("My App Icons"), "my_app_icons");
+        assert_eq!(package_name("3D Icons"), "icons_3d_icons");
+
+        let snippets = icon_snippets(&icons[0], "Bob's Icons");
+        assert_eq!(snippets.css_class, "bob's-icons-arrow_left");
+        assert_eq!(snippets.html_entity, "&#xE001;");
+        assert_eq!(
+            snippets.dart,
+            r"IconData(0xE001, fontFamily: 'Bob\'s Icons')"
+        );
+        assert_eq!(snippets.escape, r"\u{E001}");
+    }
+}
@@ -0,0 +1,176 @@
+use crate::svg_parser::Icon;
+use anyhow::{Context, Result};
+use std::collections::{BTreeMap, HashMap};
+use std::ops::RangeInclusive;
+use std::path::Path;
+
+/// Private Use Areas that icons get codepoints from, in the order they are
+/// filled: the BMP area holds 6,400 icons, then supplementary planes 15 and 16
+pub const PRIVATE_USE_AREAS: [RangeInclusive<u32>; 3] =
+    [0xE000..=0xF8FF, 0xF0000..=0xFFFFD, 0x100000..=0x10FFFD];
+
+/// First codepoint handed out
+pub const FIRST_PRIVATE_USE: u32 = 0xE000;
+
+/// Explicit icon codepoints, read from a JSON object mapping icon names to
+/// codepoints like `"U+E001"`
+#[derive(Debug, Clone, Default)]
+pub struct CodepointMap {
+    codepoints: HashMap<String, u32>,
+}
+
+impl CodepointMap {
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        Self::parse(&content).with_context(|| format!("Invalid codepoints file {}", path.display()))
+    }
+
+    pub fn parse(content: &str) -> Result<Self> {
+        let entries: BTreeMap<String, String> = serde_json::from_str(content)?;
+
+        let mut owners: BTreeMap<u32, &str> = BTreeMap::new();
+        for (name, value) in &entries {
+            let codepoint =
+                parse_codepoint(value).map_err(|e| anyhow::anyhow!("`{}`: {}", name, e))?;
+            if char::from_u32(codepoint).is_none() {
+                anyhow::bail!("`{}`: U+{:04X} is not a valid character", name, codepoint);
+            }
+            if let Some(other) = owners.insert(codepoint, name) {
+                anyhow::bail!(
+                    "`{}` and `{}` are both mapped to U+{:04X}",
+                    other,
+                    name,
+                    codepoint
+                );
+            }
+        }
+
+        let codepoints = owners
+            .into_iter()
+            .map(|(codepoint, name)| (name.to_string(), codepoint))
+            .collect();
+        Ok(Self { codepoints })
+    }
+
+    pub fn get(&self, name: &str) -> Option<u32> {
+        self.codepoints.get(name).copied()
+    }
+
+    /// Whether some icon is explicitly mapped to `codepoint`
+    pub fn is_taken(&self, codepoint: u32) -> bool {
+        self.codepoints.values().any(|&c| c == codepoint)
+    }
+
+    /// Add the codepoints icons were given in their metadata, for icons the
+    /// map does not name itself
+    pub fn with_icon_codepoints(&self, icons: &[Icon]) -> Result<Self> {
+        let mut map = self.clone();
+        for icon in icons {
+            let Some(codepoint) = icon.overrides.codepoint else {
+                continue;
+            };
+            if map.codepoints.contains_key(&icon.name) {
+                continue;
+            }
+            if let Some((other, _)) = map.codepoints.iter().find(|(_, &c)| c == codepoint) {
+                anyhow::bail!(
+                    "`{}` and `{}` are both mapped to U+{:04X}",
+                    other,
+                    icon.name,
+                    codepoint
+                );
+            }
+            map.codepoints.insert(icon.name.clone(), codepoint);
+        }
+        Ok(map)
+    }
+
+    /// Names in the map that no icon has, sorted
+    pub fn unknown_names(&self, icons: &[Icon]) -> Vec<&str> {
+        let mut names: Vec<&str> = self
+            .codepoints
+            .keys()
+            .map(String::as_str)
+            .filter(|name| !icons.iter().any(|icon| icon.name == *name))
+            .collect();
+        names.sort_unstable();
+        names
+    }
+}
+
+/// Write the icons' codepoints as `name hex` lines, the format Material
+/// Symbols ships its `.codepoints` files in
+pub fn generate_codepoints_list(icons: &[Icon], output_path: &Path) -> Result<()> {
+    std::fs::write(output_path, codepoints_list(icons))
+        .with_context(|| format!("Failed to write {}", output_path.display()))
+}
+
+fn codepoints_list(icons: &[Icon]) -> String {
+    icons
+        .iter()
+        .map(|icon| format!("{} {:x}\n", icon.name, icon.codepoint))
+        .collect()
+}
+
+/// The codepoint handed out after `codepoint`: the next one in its Private
+/// Use Area, or the start of the next area once one is full. Fails once the
+/// last area is full.
+pub fn next_private_use(codepoint: u32) -> Result<u32> {
+    let next = codepoint + 1;
+    PRIVATE_USE_AREAS
+        .iter()
+        .find(|area| next <= *area.end())
+        .map(|area| next.max(*area.start()))
+        .with_context(|| {
+            format!(
+                "Ran out of Private Use codepoints after U+{:04X}",
+                codepoint
+            )
+        })
+}
+
+/// Parse a hex codepoint, with or without a `U+` prefix
+pub fn parse_codepoint(s: &str) -> Result<u32, String> {
+    let hex = s.trim_start_matches("U+").trim_start_matches("u+");
+    u32::from_str_radix(hex, 16).map_err(|_| format!("`{}` is not a hex codepoint", s))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_codepoint_map() {
+        let map = CodepointMap::parse(r#"{"home": "U+E900", "search": "e901"}"#).unwrap();
+        assert_eq!(map.get("home"), Some(0xE900));
+        assert_eq!(map.get("search"), Some(0xE901));
+        assert!(map.is_taken(0xE901));
+
+        let err = CodepointMap::parse(r#"{"a": "U+E900", "b": "E900"}"#).unwrap_err();
+        assert!(err.to_string().contains("both mapped to U+E900"));
+        assert!(CodepointMap::parse(r#"{"a": "D800"}"#).is_err());
+    }
+
+    #[test]
+    fn test_codepoints_list() {
+        let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 24 24"><rect width="24" height="24"/></svg>"#;
+        let icons = [
+            crate::svg_parser::parse_svg_str(svg, "arrow-left", 0xE000).unwrap(),
+            crate::svg_parser::parse_svg_str(svg, "home", 0xF0000).unwrap(),
+        ];
+        assert_eq!(codepoints_list(&icons), "arrow_left e000\nhome f0000\n");
+    }
+
+    #[test]
+    fn test_private_use_spills_into_supplementary_planes() {
+        assert_eq!(next_private_use(0xE000).unwrap(), 0xE001);
+        assert_eq!(next_private_use(0xF8FF).unwrap(), 0xF0000);
+        assert_eq!(next_private_use(0xFFFFD).unwrap(), 0x100000);
+        assert_eq!(next_private_use(0x41).unwrap(), 0xE000);
+        assert_eq!(next_private_use(0x10FFFC).unwrap(), 0x10FFFD);
+        let err = next_private_use(0x10FFFD).unwrap_err();
+        assert!(err.to_string().contains("out of Private Use codepoints"));
+        assert!(next_private_use(0x10FFFF).is_err());
+    }
+}
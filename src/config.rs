@@ -0,0 +1,278 @@
+use crate::codepoints::parse_codepoint;
+use crate::svg_parser::{Icon, IconAlias};
+use crate::variants::Derivation;
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// File name looked up in the input directory when no config is given
+pub const CONFIG_FILE_NAME: &str = "svg2font.toml";
+
+/// Project configuration read from `svg2font.toml`
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub rtl: RtlConfig,
+    /// Icons generated by transforming another icon, keyed by the new icon's name
+    pub derived: BTreeMap<String, Derivation>,
+    /// Icons built by stacking other icons, keyed by the new icon's name
+    pub composed: BTreeMap<String, Vec<String>>,
+    /// Per-icon metadata, keyed by icon name
+    pub icons: BTreeMap<String, IconMetadata>,
+    /// Former names of renamed icons, keyed by the old name
+    pub aliases: BTreeMap<String, AliasConfig>,
+    pub font: FontConfig,
+}
+
+/// Font metrics from `[font]`, overridden by the matching command-line flags
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FontConfig {
+    pub units_per_em: Option<u16>,
+    pub ascender: Option<i16>,
+    pub descender: Option<i16>,
+}
+
+/// Metadata and overrides for one icon, from `[icons.<name>]` or a sidecar `<name>.toml`
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct IconMetadata {
+    /// Short label for screen readers
+    pub label: Option<String>,
+    /// Longer accessibility description
+    pub description: Option<String>,
+    /// Categories the icon is listed under in the manifest
+    pub categories: Vec<String>,
+    /// Search keywords, e.g. `["trash", "bin"]` for `delete`
+    pub tags: Vec<String>,
+    /// Identifier to use instead of the one derived from the file name
+    pub name: Option<String>,
+    /// Codepoint like `"U+E900"`, taking precedence over the lockfile
+    pub codepoint: Option<String>,
+    /// Factor the outline is scaled by, around the middle of the glyph
+    pub scale: Option<f64>,
+    /// Font units to move the glyph up by, or down if negative
+    pub baseline_offset: Option<i16>,
+    /// Advance width in font units
+    pub advance: Option<u16>,
+    /// Leave the icon out of the font
+    pub skip: bool,
+}
+
+impl IconMetadata {
+    /// Read a sidecar metadata file
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        toml::from_str(&content).with_context(|| format!("Invalid metadata {}", path.display()))
+    }
+
+    /// Override the icon's metadata with every field set here
+    pub fn apply(&self, icon: &mut Icon) -> Result<()> {
+        if let Some(label) = &self.label {
+            icon.label = Some(label.clone());
+        }
+        if let Some(description) = &self.description {
+            icon.description = Some(description.clone());
+        }
+        if !self.categories.is_empty() {
+            icon.categories = self.categories.clone();
+        }
+        if !self.tags.is_empty() {
+            icon.tags = self.tags.clone();
+        }
+        if let Some(name) = &self.name {
+            icon.name = name.clone();
+        }
+        if let Some(value) = &self.codepoint {
+            let codepoint = parse_codepoint(value).map_err(anyhow::Error::msg)?;
+            if char::from_u32(codepoint).is_none() {
+                anyhow::bail!("U+{:04X} is not a valid character", codepoint);
+            }
+            icon.overrides.codepoint = Some(codepoint);
+        }
+        if let Some(scale) = self.scale {
+            if !scale.is_finite() || scale <= 0.0 {
+                anyhow::bail!("Scale must be a positive number, got {}", scale);
+            }
+            icon.overrides.scale = Some(scale);
+        }
+        if let Some(offset) = self.baseline_offset {
+            icon.overrides.baseline_offset = Some(offset);
+        }
+        if let Some(advance) = self.advance {
+            icon.overrides.advance = Some(advance);
+        }
+        Ok(())
+    }
+}
+
+/// An old name kept working for an icon, from `[aliases]`
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AliasConfig {
+    /// Name of the icon the alias draws
+    pub icon: String,
+    /// Codepoint like `"U+E905"` also mapped to the icon, e.g. the old icon's
+    pub codepoint: Option<String>,
+}
+
+/// Which icons get a mirrored right-to-left counterpart
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RtlConfig {
+    /// Icon names to mirror, in addition to names ending in `_ltr`
+    pub mirror: Vec<NamePattern>,
+}
+
+/// A glob pattern matched against icon names, e.g. `arrow_*`
+#[derive(Debug, Clone, Deserialize)]
+#[serde(try_from = "String")]
+pub struct NamePattern(glob::Pattern);
+
+impl NamePattern {
+    pub fn matches(&self, name: &str) -> bool {
+        self.0.matches(name)
+    }
+}
+
+impl TryFrom<String> for NamePattern {
+    type Error = glob::PatternError;
+
+    fn try_from(pattern: String) -> Result<Self, Self::Error> {
+        glob::Pattern::new(&pattern).map(Self)
+    }
+}
+
+impl Config {
+    /// Load the config at `path`, or `svg2font.toml` in `input_dir` if present.
+    ///
+    /// A missing default config is not an error; an explicitly given one is.
+    pub fn load(path: Option<&Path>, input_dir: &Path) -> Result<Self> {
+        let path: PathBuf = match path {
+            Some(path) => path.to_path_buf(),
+            None => {
+                let default = input_dir.join(CONFIG_FILE_NAME);
+                if !default.is_file() {
+                    return Ok(Self::default());
+                }
+                default
+            }
+        };
+
+        let content = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        toml::from_str(&content).with_context(|| format!("Invalid config {}", path.display()))
+    }
+
+    /// Apply `[icons.<name>]` metadata to matching icons
+    pub fn apply_icon_metadata(&self, icons: &mut [Icon]) -> Result<()> {
+        for icon in icons {
+            if let Some(metadata) = self.icons.get(&icon.name) {
+                metadata
+                    .apply(icon)
+                    .with_context(|| format!("Invalid metadata for icon `{}`", icon.name))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Attach the `[aliases]` to the icons they name.
+    ///
+    /// Runs once codepoints are assigned, so alias codepoints can be checked
+    /// against them.
+    pub fn apply_aliases(&self, icons: &mut [Icon]) -> Result<()> {
+        let mut taken: BTreeMap<u32, String> = icons
+            .iter()
+            .map(|icon| (icon.codepoint, icon.name.clone()))
+            .collect();
+        for (alias, target) in &self.aliases {
+            if icons.iter().any(|icon| icon.name == *alias) {
+                anyhow::bail!("Alias `{}` is also the name of an icon", alias);
+            }
+            let codepoint = match &target.codepoint {
+                Some(value) => {
+                    let codepoint = parse_codepoint(value)
+                        .map_err(anyhow::Error::msg)
+                        .with_context(|| format!("Invalid codepoint for alias `{}`", alias))?;
+                    if char::from_u32(codepoint).is_none() {
+                        anyhow::bail!("U+{:04X} is not a valid character", codepoint);
+                    }
+                    if let Some(owner) = taken.insert(codepoint, alias.clone()) {
+                        anyhow::bail!(
+                            "Alias `{}` wants U+{:04X}, which `{}` already uses",
+                            alias,
+                            codepoint,
+                            owner
+                        );
+                    }
+                    Some(codepoint)
+                }
+                None => None,
+            };
+            let icon = icons
+                .iter_mut()
+                .find(|icon| icon.name == target.icon)
+                .with_context(|| {
+                    format!("Alias `{}` refers to unknown icon `{}`", alias, target.icon)
+                })?;
+            icon.aliases.push(IconAlias {
+                name: alias.clone(),
+                codepoint,
+            });
+        }
+        Ok(())
+    }
+
+    /// Whether `[icons.<name>]` says to leave the icon out
+    pub fn skips(&self, name: &str) -> bool {
+        self.icons.get(name).is_some_and(|metadata| metadata.skip)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_config() {
+        let config: Config = toml::from_str("[rtl]\nmirror = [\"arrow_*\", \"reply\"]\n").unwrap();
+        assert!(config.rtl.mirror[0].matches("arrow_left"));
+        assert!(!config.rtl.mirror[1].matches("reply_all"));
+
+        assert!(toml::from_str::<Config>("[rtl]\nmirorr = []\n").is_err());
+
+        let config: Config = toml::from_str("[font]\nunits_per_em = 2048\n").unwrap();
+        assert_eq!(config.font.units_per_em, Some(2048));
+        assert_eq!(config.font.ascender, None);
+        assert!(toml::from_str::<Config>("[derived]\nup = \"spin(arrow, 90)\"\n").is_err());
+
+        let config: Config = toml::from_str(
+            "[icons.logo]\ncodepoint = \"U+E900\"\nscale = 0.8\ntags = [\"brand\"]\n[icons.draft]\nskip = true\n",
+        )
+        .unwrap();
+        assert!(config.skips("draft"));
+        assert!(!config.skips("logo"));
+        let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 24 24"/>"#;
+        let mut icons = [crate::svg_parser::parse_svg_str(svg, "logo", 0xE000).unwrap()];
+        config.apply_icon_metadata(&mut icons).unwrap();
+        assert_eq!(icons[0].overrides.codepoint, Some(0xE900));
+        assert_eq!(icons[0].overrides.scale, Some(0.8));
+        assert_eq!(icons[0].tags, ["brand"]);
+
+        let config: Config = toml::from_str(
+            "[aliases]\ncompany = { icon = \"logo\", codepoint = \"U+E901\" }\nbrand = { icon = \"logo\" }\n",
+        )
+        .unwrap();
+        icons[0].name = "logo".to_string();
+        icons[0].codepoint = 0xE900;
+        config.apply_aliases(&mut icons).unwrap();
+        assert_eq!(icons[0].aliases.len(), 2);
+        assert_eq!(icons[0].aliases[1].codepoint, Some(0xE901));
+        let clash: Config =
+            toml::from_str("[aliases]\nold = { icon = \"logo\", codepoint = \"U+E900\" }\n")
+                .unwrap();
+        assert!(clash.apply_aliases(&mut icons).is_err());
+    }
+}
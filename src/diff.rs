@@ -0,0 +1,205 @@
+//! Comparing two manifests of an icon font for changes that break its consumers.
+
+use anyhow::{Context, Result};
+use serde_json::Value;
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+use std::path::Path;
+
+/// What consumers rely on about one icon of a manifest
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiffIcon {
+    pub name: String,
+    pub codepoint: u32,
+    /// Source hash, missing from manifests of older svg2font versions
+    pub hash: Option<String>,
+    /// Alias names and their codepoints
+    pub aliases: Vec<(String, Option<u32>)>,
+}
+
+/// Changes from an old manifest to a new one
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ManifestDiff {
+    pub added: Vec<String>,
+    /// Icons gone without a trace, as `name (U+E000)`
+    pub removed: Vec<String>,
+    /// Icons found under a new name without an alias for the old one, as `old -> new`
+    pub renamed: Vec<String>,
+    /// Icons whose old codepoint no longer draws them, as `name: U+E000 -> U+E001`
+    pub moved: Vec<String>,
+}
+
+impl ManifestDiff {
+    /// Whether any change breaks code or text written against the old font
+    pub fn is_breaking(&self) -> bool {
+        !self.removed.is_empty() || !self.renamed.is_empty() || !self.moved.is_empty()
+    }
+}
+
+impl fmt::Display for ManifestDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (label, entries) in [
+            ("Removed", &self.removed),
+            ("Renamed", &self.renamed),
+            ("Changed codepoints", &self.moved),
+            ("Added", &self.added),
+        ] {
+            if !entries.is_empty() {
+                writeln!(f, "{} ({}):", label, entries.len())?;
+                for entry in entries {
+                    writeln!(f, "  {}", entry)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Read the icons of an svg2font JSON manifest
+pub fn read_icons(path: &Path) -> Result<Vec<DiffIcon>> {
+    let json = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let manifest: Value = serde_json::from_str(&json)
+        .with_context(|| format!("Invalid manifest {}", path.display()))?;
+    icons_of(&manifest)
+        .with_context(|| format!("Invalid icon entry in manifest {}", path.display()))
+}
+
+fn icons_of(manifest: &Value) -> Option<Vec<DiffIcon>> {
+    let hex = |value: &Value| u32::from_str_radix(value.as_str()?, 16).ok();
+    manifest["icons"]
+        .as_array()?
+        .iter()
+        .map(|icon| {
+            let aliases = match icon["aliases"].as_array() {
+                Some(aliases) => aliases
+                    .iter()
+                    .map(|alias| {
+                        Some((
+                            alias["name"].as_str()?.to_string(),
+                            hex(&alias["codepoint"]),
+                        ))
+                    })
+                    .collect::<Option<_>>()?,
+                None => Vec::new(),
+            };
+            Some(DiffIcon {
+                name: icon["name"].as_str()?.to_string(),
+                codepoint: hex(&icon["codepoint"])?,
+                hash: icon["hash"].as_str().map(str::to_string),
+                aliases,
+            })
+        })
+        .collect()
+}
+
+/// Compare the icons of an old and a new manifest.
+///
+/// An old icon is looked up in the new manifest by name, then by alias, then
+/// by codepoint and finally by source hash. Icons found under another name
+/// count as renamed unless the new icon keeps the old name as an alias, and
+/// their old codepoint must still draw them, directly or through an alias.
+pub fn diff(old: &[DiffIcon], new: &[DiffIcon]) -> ManifestDiff {
+    let by_name: HashMap<&str, usize> = new
+        .iter()
+        .enumerate()
+        .map(|(i, icon)| (icon.name.as_str(), i))
+        .collect();
+    let by_alias: HashMap<&str, usize> = new
+        .iter()
+        .enumerate()
+        .flat_map(|(i, icon)| icon.aliases.iter().map(move |(name, _)| (name.as_str(), i)))
+        .collect();
+    let by_codepoint: HashMap<u32, usize> = new
+        .iter()
+        .enumerate()
+        .flat_map(|(i, icon)| {
+            let aliases = icon.aliases.iter().filter_map(|(_, codepoint)| *codepoint);
+            std::iter::once(icon.codepoint)
+                .chain(aliases)
+                .map(move |c| (c, i))
+        })
+        .collect();
+    let by_hash: HashMap<&str, usize> = new
+        .iter()
+        .enumerate()
+        .filter_map(|(i, icon)| Some((icon.hash.as_deref()?, i)))
+        .collect();
+
+    let mut result = ManifestDiff::default();
+    let mut matched = BTreeSet::new();
+    for icon in old {
+        let found = by_name
+            .get(icon.name.as_str())
+            .or_else(|| by_alias.get(icon.name.as_str()))
+            .or_else(|| by_codepoint.get(&icon.codepoint))
+            .or_else(|| by_hash.get(icon.hash.as_deref()?));
+        let Some(&index) = found else {
+            result
+                .removed
+                .push(format!("{} (U+{:04X})", icon.name, icon.codepoint));
+            continue;
+        };
+        matched.insert(index);
+        let current = &new[index];
+
+        if current.name != icon.name && !by_alias.contains_key(icon.name.as_str()) {
+            result
+                .renamed
+                .push(format!("{} -> {}", icon.name, current.name));
+        }
+        if by_codepoint.get(&icon.codepoint) != Some(&index) {
+            result.moved.push(format!(
+                "{}: U+{:04X} -> U+{:04X}",
+                current.name, icon.codepoint, current.codepoint
+            ));
+        }
+    }
+
+    let old_names: BTreeSet<&str> = old.iter().map(|icon| icon.name.as_str()).collect();
+    result.added = new
+        .iter()
+        .enumerate()
+        .filter(|(i, icon)| !matched.contains(i) && !old_names.contains(icon.name.as_str()))
+        .map(|(_, icon)| format!("{} (U+{:04X})", icon.name, icon.codepoint))
+        .collect();
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff() {
+        let old = icons_of(&serde_json::json!({"icons": [
+            {"name": "home", "codepoint": "E000"},
+            {"name": "trash", "codepoint": "E001", "hash": "aa"},
+            {"name": "star", "codepoint": "E002"},
+            {"name": "gone", "codepoint": "E003"},
+            {"name": "bin", "codepoint": "E004", "hash": "bb"},
+            {"name": "menu", "codepoint": "E005"},
+        ]}))
+        .unwrap();
+        let new = icons_of(&serde_json::json!({"icons": [
+            {"name": "home", "codepoint": "E000"},
+            // Renamed, with the old name kept as an alias
+            {"name": "delete", "codepoint": "E001", "hash": "aa", "aliases": [{"name": "trash"}]},
+            {"name": "star", "codepoint": "E010"},
+            // Renamed without an alias, at the same codepoint
+            {"name": "basket", "codepoint": "E004", "hash": "bb"},
+            // Moved, but the old codepoint is kept as an alias
+            {"name": "menu", "codepoint": "E011", "aliases": [{"name": "hamburger", "codepoint": "E005"}]},
+            {"name": "new", "codepoint": "E012"},
+        ]}))
+        .unwrap();
+
+        let diff = diff(&old, &new);
+        assert_eq!(diff.removed, ["gone (U+E003)"]);
+        assert_eq!(diff.renamed, ["bin -> basket"]);
+        assert_eq!(diff.moved, ["star: U+E002 -> U+E010"]);
+        assert_eq!(diff.added, ["new (U+E012)"]);
+        assert!(diff.is_breaking());
+        assert!(!super::diff(&old, &old).is_breaking());
+    }
+}
@@ -0,0 +1,372 @@
+//! A small static documentation site for an icon font: an overview, a page
+//! per category and per icon with usage snippets, and a downloads page.
+
+use crate::codegen;
+use crate::identifier::camel_case;
+use crate::preview::escape_html;
+use crate::svg_parser::Icon;
+use anyhow::{Context, Result};
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+/// Write the site for `icons` to `dir`, inside the output directory `output`
+/// holding the font at `ttf_path` and every file in `downloads`
+pub fn generate_docs(
+    icons: &[Icon],
+    font_name: &str,
+    ttf_path: &Path,
+    downloads: &[(&str, PathBuf)],
+    output: &Path,
+    dir: &Path,
+) -> Result<()> {
+    let font_file = ttf_path
+        .file_name()
+        .context("Font path has no file name")?
+        .to_string_lossy();
+    let site = Site {
+        font_name,
+        icons,
+        categories: categories(icons),
+    };
+
+    let mut pages = vec![
+        (
+            PathBuf::from("style.css"),
+            stylesheet(font_name, &font_file),
+        ),
+        (PathBuf::from("index.html"), site.index_page()),
+        (
+            PathBuf::from("downloads.html"),
+            site.downloads_page(&download_list(downloads, output)?),
+        ),
+    ];
+    for (category, members) in &site.categories {
+        pages.push((
+            Path::new("categories").join(format!("{}.html", slug(category))),
+            site.category_page(category, members),
+        ));
+    }
+    for icon in icons {
+        pages.push((
+            Path::new("icons").join(format!("{}.html", icon.name)),
+            site.icon_page(icon),
+        ));
+    }
+
+    for (file, content) in pages {
+        let path = dir.join(file);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        std::fs::write(&path, content)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+    }
+    Ok(())
+}
+
+/// Icons of each category, by category name
+fn categories(icons: &[Icon]) -> BTreeMap<&str, Vec<&Icon>> {
+    let mut categories: BTreeMap<&str, Vec<&Icon>> = BTreeMap::new();
+    for icon in icons {
+        for category in &icon.categories {
+            categories.entry(category).or_default().push(icon);
+        }
+    }
+    categories
+}
+
+/// File name for a category page, e.g. `file-types` for "File types"
+fn slug(category: &str) -> String {
+    let slug = category
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join("-");
+    if slug.is_empty() {
+        "category".to_string()
+    } else {
+        slug
+    }
+}
+
+/// Every file to offer for download with its kind, path under `output` and size
+fn download_list(
+    downloads: &[(&str, PathBuf)],
+    output: &Path,
+) -> Result<Vec<(String, String, u64)>> {
+    let mut list = Vec::new();
+    for (kind, path) in downloads {
+        // Directories, such as PNG exports, are listed file by file
+        for entry in walkdir::WalkDir::new(path).sort_by_file_name() {
+            let entry = entry?;
+            if !entry.file_type().is_file() {
+                continue;
+            }
+            // Files outside the output directory, like the lockfile, are not published
+            let Ok(relative) = entry.path().strip_prefix(output) else {
+                continue;
+            };
+            let relative = relative
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            list.push((kind.to_string(), relative, entry.metadata()?.len()));
+        }
+    }
+    Ok(list)
+}
+
+/// Sizes in bytes as shown to people
+fn human_size(bytes: u64) -> String {
+    match bytes {
+        0..1024 => format!("{} B", bytes),
+        1024..1_048_576 => format!("{:.1} KB", bytes as f64 / 1024.0),
+        _ => format!("{:.1} MB", bytes as f64 / 1_048_576.0),
+    }
+}
+
+fn stylesheet(font_name: &str, font_file: &str) -> String {
+    format!(
+        r#"@font-face {{
+  font-family: "{font_name}";
+  src: url("../{font_file}") format("truetype");
+}}
+
+body {{ font-family: -apple-system, BlinkMacSystemFont, "Segoe UI", Roboto, sans-serif; margin: 0; color: #1a1a1a; }}
+nav {{ background: #f5f5f5; border-bottom: 1px solid #e0e0e0; padding: 1rem 2rem; display: flex; gap: 1.5rem; flex-wrap: wrap; }}
+nav a {{ color: inherit; text-decoration: none; }}
+nav a:first-child {{ font-weight: 600; }}
+main {{ max-width: 1100px; margin: 0 auto; padding: 2rem; }}
+.glyph {{ font-family: "{font_name}"; font-style: normal; line-height: 1; }}
+.grid {{ display: grid; grid-template-columns: repeat(auto-fill, minmax(110px, 1fr)); gap: 0.75rem; }}
+.grid a {{ border: 1px solid #e0e0e0; border-radius: 8px; padding: 1rem 0.5rem; text-align: center; color: inherit; text-decoration: none; font-size: 0.75rem; word-break: break-word; }}
+.grid a:hover {{ border-color: #999; }}
+.grid .glyph {{ display: block; font-size: 32px; margin-bottom: 0.5rem; }}
+.hero {{ font-size: 96px; }}
+pre {{ background: #f5f5f5; border-radius: 6px; padding: 0.75rem 1rem; overflow-x: auto; }}
+table {{ border-collapse: collapse; }}
+th, td {{ padding: 0.4rem 1rem 0.4rem 0; border-bottom: 1px solid #e0e0e0; text-align: left; }}
+"#,
+        font_name = font_name.replace('"', "\\\""),
+    )
+}
+
+/// What every page is built from
+struct Site<'a> {
+    font_name: &'a str,
+    icons: &'a [Icon],
+    categories: BTreeMap<&'a str, Vec<&'a Icon>>,
+}
+
+impl Site<'_> {
+    /// A complete page, `depth` directories below the site root
+    fn page(&self, title: &str, depth: usize, body: &str) -> String {
+        let root = "../".repeat(depth);
+        let mut nav = format!(
+            r#"<a href="{root}index.html">{name}</a>"#,
+            name = escape_html(self.font_name)
+        );
+        for category in self.categories.keys() {
+            let _ = write!(
+                nav,
+                r#"<a href="{root}categories/{}.html">{}</a>"#,
+                slug(category),
+                escape_html(category)
+            );
+        }
+        let _ = write!(nav, r#"<a href="{root}downloads.html">Downloads</a>"#);
+        format!(
+            r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="UTF-8">
+<meta name="viewport" content="width=device-width, initial-scale=1.0">
+<title>{title} - {name}</title>
+<link rel="stylesheet" href="{root}style.css">
+</head>
+<body>
+<nav>{nav}</nav>
+<main>
+{body}
+</main>
+</body>
+</html>
+"#,
+            title = escape_html(title),
+            name = escape_html(self.font_name),
+        )
+    }
+
+    /// Links to the pages of `icons`, from a page `depth` directories down
+    fn grid<'b>(icons: impl IntoIterator<Item = &'b Icon>, depth: usize) -> String {
+        let root = "../".repeat(depth);
+        let mut grid = String::from(r#"<div class="grid">"#);
+        for icon in icons {
+            let _ = write!(
+                grid,
+                r#"<a href="{root}icons/{name}.html"><span class="glyph">&#x{codepoint:04X};</span>{name}</a>"#,
+                name = icon.name,
+                codepoint = icon.codepoint
+            );
+        }
+        grid.push_str("</div>");
+        grid
+    }
+
+    fn index_page(&self) -> String {
+        let mut body = format!(
+            "<h1>{}</h1>\n<p>{} icons",
+            escape_html(self.font_name),
+            self.icons.len()
+        );
+        if !self.categories.is_empty() {
+            let _ = write!(body, " in {} categories", self.categories.len());
+        }
+        body.push_str(
+            r#". Pick one for its codepoint and usage, or get the font from <a href="downloads.html">Downloads</a>.</p>"#,
+        );
+        body.push('\n');
+        body.push_str(&Self::grid(self.icons, 0));
+        self.page("Overview", 0, &body)
+    }
+
+    fn category_page(&self, category: &str, icons: &[&Icon]) -> String {
+        let body = format!(
+            "<h1>{}</h1>\n<p>{} icons</p>\n{}",
+            escape_html(category),
+            icons.len(),
+            Self::grid(icons.iter().copied(), 1)
+        );
+        self.page(category, 1, &body)
+    }
+
+    fn icon_page(&self, icon: &Icon) -> String {
+        let snippets = codegen::icon_snippets(icon, self.font_name);
+        let family = self.font_name.replace('"', "\\\"");
+        let mut body = format!(
+            r#"<h1>{name}</h1>
+<p class="glyph hero">&#x{codepoint:04X};</p>
+<table>
+<tr><th>Codepoint</th><td>U+{codepoint:04X}</td></tr>
+<tr><th>HTML entity</th><td><code>{entity}</code></td></tr>"#,
+            name = icon.name,
+            codepoint = icon.codepoint,
+            entity = escape_html(&snippets.html_entity),
+        );
+        for (label, value) in [
+            ("Label", icon.label.clone()),
+            ("Description", icon.description.clone()),
+            ("Tags", Some(icon.tags.join(", ")).filter(|t| !t.is_empty())),
+        ] {
+            if let Some(value) = value {
+                let _ = write!(
+                    body,
+                    "\n<tr><th>{}</th><td>{}</td></tr>",
+                    label,
+                    escape_html(&value)
+                );
+            }
+        }
+        if !icon.categories.is_empty() {
+            let links: Vec<String> = icon
+                .categories
+                .iter()
+                .map(|category| {
+                    format!(
+                        r#"<a href="../categories/{}.html">{}</a>"#,
+                        slug(category),
+                        escape_html(category)
+                    )
+                })
+                .collect();
+            let _ = write!(
+                body,
+                "\n<tr><th>Categories</th><td>{}</td></tr>",
+                links.join(", ")
+            );
+        }
+        body.push_str("\n</table>\n");
+
+        let component = codegen::component_name(self.font_name);
+        for (language, code) in [
+            (
+                "CSS",
+                format!(
+                    ".{class}::before {{\n  font-family: \"{family}\";\n  content: \"\\{codepoint:04X}\";\n}}",
+                    class = snippets.css_class,
+                    codepoint = icon.codepoint
+                ),
+            ),
+            ("Flutter", format!("Icon({})", snippets.dart)),
+            (
+                "React",
+                format!(
+                    "<{component} name=\"{}\" />  // with --react",
+                    camel_case(&icon.name)
+                ),
+            ),
+        ] {
+            let _ = write!(
+                body,
+                "\n<h2>{}</h2>\n<pre><code>{}</code></pre>",
+                language,
+                escape_html(&code)
+            );
+        }
+        self.page(&icon.name, 1, &body)
+    }
+
+    fn downloads_page(&self, files: &[(String, String, u64)]) -> String {
+        let mut body = String::from(
+            "<h1>Downloads</h1>\n<table>\n<tr><th>File</th><th>Kind</th><th>Size</th></tr>",
+        );
+        for (kind, path, size) in files {
+            let _ = write!(
+                body,
+                "\n<tr><td><a href=\"../{path}\" download>{path}</a></td><td>{kind}</td><td>{size}</td></tr>",
+                path = escape_html(path),
+                size = human_size(*size)
+            );
+        }
+        body.push_str("\n</table>");
+        self.page("Downloads", 0, &body)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::svg_parser::parse_svg_str;
+
+    #[test]
+    fn test_pages() {
+        let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 24 24"><rect width="24" height="24"/></svg>"#;
+        let mut icon = parse_svg_str(svg, "arrow_left", 0xE001).unwrap();
+        icon.categories = vec!["File & types".to_string()];
+        let icons = [icon];
+        let site = Site {
+            font_name: "My Icons",
+            icons: &icons,
+            categories: categories(&icons),
+        };
+
+        let page = site.icon_page(&icons[0]);
+        assert!(page.contains(r#"href="../style.css""#));
+        assert!(page.contains(r#"<a href="../categories/file-types.html">File &amp; types</a>"#));
+        assert!(page.contains("content: &quot;\\E001&quot;;"));
+        assert!(page.contains("Icon(IconData(0xE001, fontFamily: 'My Icons'))"));
+        assert!(page.contains("&lt;MyIcon name=&quot;arrowLeft&quot; /&gt;"));
+        assert!(site
+            .index_page()
+            .contains(r#"<a href="icons/arrow_left.html">"#));
+
+        let downloads =
+            site.downloads_page(&[("font".to_string(), "my_icons.ttf".to_string(), 2048)]);
+        assert!(downloads.contains(r#"<a href="../my_icons.ttf" download>my_icons.ttf</a>"#));
+        assert!(downloads.contains("2.0 KB"));
+    }
+}
@@ -0,0 +1,224 @@
+use crate::cache;
+use crate::codegen;
+use crate::lockfile::SecondaryCodepoints;
+use crate::svg_parser::{Icon, IconOverrides, ShapeFill};
+use anyhow::{Context, Result};
+use kurbo::BezPath;
+use std::fmt::Write as _;
+use std::path::Path;
+
+/// Opacity the CSS helpers give the secondary layer by default
+const SECONDARY_OPACITY: f64 = 0.4;
+
+/// Split the translucent secondary layer of each icon into its own glyph.
+///
+/// Opaque icons drawn in exactly two colors are split by color instead, with
+/// the lighter shapes as the secondary layer. Primary outlines stay on the
+/// icons; the returned secondary layers are glyph-only icons with codepoints
+/// from `codepoints`, which keeps them stable across builds.
+pub fn split_layers(icons: &mut [Icon], codepoints: &mut SecondaryCodepoints) -> Result<Vec<Icon>> {
+    let mut layers = Vec::new();
+
+    for icon in icons.iter_mut() {
+        let (primary, fills, secondary) = match split_by_color(icon) {
+            Some(layers) => layers,
+            None => split_shapes(icon, |fill| fill.secondary),
+        };
+
+        // Both halves differ from the full outline, so they need their own cache keys
+        let source_hash = icon.source_hash.clone();
+        let layer_hash =
+            |layer: &str| cache::content_hash(format!("{}:{}", source_hash, layer).as_bytes());
+
+        if !secondary.elements().is_empty() {
+            let codepoint = codepoints.assign(icon)?;
+            layers.push(Icon {
+                name: format!("{}_secondary", icon.name),
+                filename: icon.filename.clone(),
+                path: secondary,
+                secondary_codepoint: None,
+                symbol_codepoint: None,
+                fills: Vec::new(),
+                width: icon.width,
+                height: icon.height,
+                codepoint,
+                source_hash: layer_hash("secondary"),
+                source: icon.source.clone(),
+                animated: icon.animated,
+                label: None,
+                description: None,
+                categories: Vec::new(),
+                tags: Vec::new(),
+                aliases: Vec::new(),
+                // The layer is drawn over its icon, at its own codepoint
+                overrides: IconOverrides {
+                    codepoint: None,
+                    ..icon.overrides.clone()
+                },
+            });
+            icon.secondary_codepoint = Some(codepoint);
+            icon.source_hash = layer_hash("primary");
+            icon.path = primary;
+            icon.fills = fills;
+        }
+    }
+
+    Ok(layers)
+}
+
+/// Split an opaque icon with two fill colors into its darker shapes, with
+/// their fills, and its lighter shapes
+fn split_by_color(icon: &Icon) -> Option<(BezPath, Vec<ShapeFill>, BezPath)> {
+    if icon.fills.iter().any(|fill| fill.secondary) {
+        return None;
+    }
+    let rgb = |fill: &ShapeFill| [fill.rgba[0], fill.rgba[1], fill.rgba[2]];
+    let mut colors: Vec<_> = icon.fills.iter().map(rgb).collect();
+    colors.sort_unstable();
+    colors.dedup();
+    let [a, b] = colors[..] else {
+        return None;
+    };
+    let luminance = |[r, g, b]: [u8; 3]| 0.2126 * r as f64 + 0.7152 * g as f64 + 0.0722 * b as f64;
+    let lighter = if luminance(a) > luminance(b) { a } else { b };
+    Some(split_shapes(icon, |fill| rgb(fill) == lighter))
+}
+
+/// Split an icon into the shapes not matching `is_secondary`, with their
+/// fills, and the shapes matching it, keeping each layer in document order
+fn split_shapes(
+    icon: &Icon,
+    is_secondary: impl Fn(&ShapeFill) -> bool,
+) -> (BezPath, Vec<ShapeFill>, BezPath) {
+    let (mut primary, mut fills, mut secondary) = (BezPath::new(), Vec::new(), BezPath::new());
+    for fill in &icon.fills {
+        let shape = &icon.path.elements()[fill.elements.clone()];
+        if is_secondary(fill) {
+            secondary.extend(shape.iter().copied());
+        } else {
+            let start = primary.elements().len();
+            primary.extend(shape.iter().copied());
+            fills.push(ShapeFill {
+                elements: start..primary.elements().len(),
+                rgba: fill.rgba,
+                secondary: false,
+            });
+        }
+    }
+    (primary, fills, secondary)
+}
+
+/// Generate a stylesheet that stacks the two layers of each duotone icon
+pub fn generate_css(
+    icons: &[Icon],
+    font_name: &str,
+    font_file: &str,
+    output_path: &Path,
+) -> Result<()> {
+    std::fs::write(
+        output_path,
+        generate_stylesheet(icons, font_name, font_file),
+    )
+    .with_context(|| format!("Failed to write {}", output_path.display()))
+}
+
+fn generate_stylesheet(icons: &[Icon], font_name: &str, font_file: &str) -> String {
+    let prefix = codegen::css_prefix(font_name);
+
+    let mut css = format!(
+        r#"@font-face {{
+  font-family: "{font_name}";
+  src: url("{font_file}") format("truetype");
+}}
+
+.{prefix} {{
+  display: inline-block;
+  position: relative;
+  font-family: "{font_name}";
+  font-style: normal;
+  font-weight: normal;
+  line-height: 1;
+}}
+
+.{prefix}::before {{
+  content: var(--{prefix}-primary);
+  color: var(--{prefix}-primary-color, currentColor);
+}}
+
+/* The secondary layer is drawn on top of the primary one */
+.{prefix}::after {{
+  content: var(--{prefix}-secondary, "");
+  position: absolute;
+  left: 0;
+  top: 0;
+  color: var(--{prefix}-secondary-color, currentColor);
+  opacity: var(--{prefix}-secondary-opacity, {SECONDARY_OPACITY});
+}}
+"#
+    );
+
+    for icon in icons {
+        let _ = write!(
+            css,
+            "\n.{}-{} {{ --{}-primary: \"\\{:x}\";",
+            prefix, icon.name, prefix, icon.codepoint
+        );
+        if let Some(secondary) = icon.secondary_codepoint {
+            let _ = write!(css, " --{}-secondary: \"\\{:x}\";", prefix, secondary);
+        }
+        css.push_str(" }\n");
+    }
+
+    css
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lockfile::Lockfile;
+    use crate::svg_parser::parse_svg_str;
+    use kurbo::Shape;
+
+    #[test]
+    fn test_split_layers() {
+        let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 24 24">
+            <path class="fa-secondary" d="M8 8h8v8H8z"/>
+            <path d="M4 4h16v16H4z"/>
+        </svg>"#;
+        let mut icons = vec![
+            parse_svg_str(svg, "bell", 0xE000).unwrap(),
+            parse_svg_str(r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 24 24"><path d="M4 4h16v16H4z"/></svg>"#, "box", 0xE001).unwrap(),
+        ];
+        // Shapes stay in document order until the layers are split
+        assert!(icons[0].fills[0].secondary);
+        assert!(!icons[0].fills[1].secondary);
+        let mut codepoints = Lockfile::default().secondary_codepoints(&icons);
+        let layers = split_layers(&mut icons, &mut codepoints).unwrap();
+
+        assert_eq!(layers.len(), 1);
+        assert_eq!(layers[0].name, "bell_secondary");
+        assert_eq!(layers[0].codepoint, 0xE002);
+        assert_eq!(icons[0].secondary_codepoint, Some(0xE002));
+        assert_eq!(icons[0].fills.len(), 1);
+        assert_eq!(
+            icons[0].path.bounding_box(),
+            kurbo::Rect::new(4.0, 4.0, 20.0, 20.0)
+        );
+        assert_eq!(icons[1].secondary_codepoint, None);
+
+        // Two solid colors: the lighter one is the secondary layer
+        let svg = r##"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 24 24">
+            <path fill="#1a73e8" d="M4 4h16v16H4z"/>
+            <path fill="#aecbfa" d="M8 8h8v8H8z"/>
+        </svg>"##;
+        let mut icons = vec![parse_svg_str(svg, "card", 0xE000).unwrap()];
+        let mut codepoints = Lockfile::default().secondary_codepoints(&icons);
+        let layers = split_layers(&mut icons, &mut codepoints).unwrap();
+        assert_eq!(layers.len(), 1);
+        assert_eq!(
+            layers[0].path.bounding_box(),
+            kurbo::Rect::new(8.0, 8.0, 16.0, 16.0)
+        );
+        assert_eq!(icons[0].fills.len(), 1);
+    }
+}
@@ -0,0 +1,317 @@
+use crate::subset::codepoint_mappings;
+use crate::svg_parser::filename_to_identifier;
+use anyhow::{Context, Result};
+use kurbo::{Affine, BezPath, Point};
+use read_fonts::{
+    tables::{
+        glyf::{Anchor, Glyf, Glyph},
+        loca::Loca,
+    },
+    types::{GlyphId, GlyphId16},
+    FontRef, TableProvider,
+};
+use std::collections::{BTreeMap, HashSet};
+use std::path::Path;
+
+/// Composite glyphs nested deeper than this are treated as broken
+const MAX_COMPONENT_DEPTH: u32 = 16;
+
+/// A glyph of a font turned back into an SVG icon
+#[derive(Debug, Clone)]
+pub struct ExtractedIcon {
+    /// File stem of the SVG
+    pub filename: String,
+    /// Icon name the SVG gets when built again
+    pub name: String,
+    pub codepoint: u32,
+    /// Outline in SVG coordinates, within a `width` by `height` viewBox
+    pub path: BezPath,
+    pub width: u16,
+    pub height: u16,
+    pub svg: String,
+}
+
+/// File names and icon names by codepoint from an svg2font JSON manifest
+pub fn manifest_names(path: &Path) -> Result<BTreeMap<u32, (String, String)>> {
+    let json = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let manifest: serde_json::Value = serde_json::from_str(&json)
+        .with_context(|| format!("Invalid manifest {}", path.display()))?;
+
+    let icons = manifest["icons"]
+        .as_array()
+        .with_context(|| format!("Manifest {} has no icons array", path.display()))?;
+    icons
+        .iter()
+        .map(|icon| {
+            let codepoint = icon["codepoint"]
+                .as_str()
+                .and_then(|c| u32::from_str_radix(c, 16).ok())
+                .with_context(|| format!("Invalid codepoint in manifest entry {}", icon))?;
+            let name = icon["name"]
+                .as_str()
+                .with_context(|| format!("Missing name in manifest entry {}", icon))?;
+            let filename = icon["filename"].as_str().unwrap_or(name);
+            Ok((codepoint, (filename.to_string(), name.to_string())))
+        })
+        .collect()
+}
+
+/// Turn every mapped, non-empty glyph of a TrueType font into an SVG.
+///
+/// Icons are named from `names` (file name and icon name by codepoint), then
+/// by the font's `post` glyph names, falling back to `uniE000` style names.
+/// A glyph mapped from several codepoints is extracted once, under its
+/// lowest codepoint.
+pub fn extract_icons(
+    data: &[u8],
+    names: &BTreeMap<u32, (String, String)>,
+) -> Result<Vec<ExtractedIcon>> {
+    let font = FontRef::new(data).map_err(|e| anyhow::anyhow!("Font does not parse: {}", e))?;
+    let glyf = font
+        .glyf()
+        .context("Only fonts with TrueType outlines can be extracted")?;
+    let loca = font.loca(None)?;
+    let hmtx = font.hmtx()?;
+    let post = font.post().ok();
+    let units_per_em = font.head()?.units_per_em();
+
+    let mut seen_glyphs = HashSet::new();
+    let mut used_filenames = HashSet::new();
+    let mut icons = Vec::new();
+    for (codepoint, gid) in codepoint_mappings(&font)? {
+        if gid == 0 || !seen_glyphs.insert(gid) {
+            continue;
+        }
+
+        let mut path = BezPath::new();
+        glyph_path(&glyf, &loca, gid, Affine::IDENTITY, 0, &mut path)
+            .with_context(|| format!("Failed to read glyph {} (U+{:04X})", gid, codepoint))?;
+        if path.elements().is_empty() {
+            continue;
+        }
+        // Like generated glyphs, the viewBox spans from the baseline up one em
+        path.apply_affine(Affine::new([1.0, 0.0, 0.0, -1.0, 0.0, units_per_em as f64]));
+
+        let width = match hmtx.advance(GlyphId::from(gid)) {
+            Some(advance) if advance > 0 => advance,
+            _ => units_per_em,
+        };
+
+        let (filename, name) = match names.get(&codepoint) {
+            Some((filename, name)) => (file_safe_name(filename), Some(name.clone())),
+            None => {
+                let glyph_name = post
+                    .as_ref()
+                    .and_then(|post| post.glyph_name(GlyphId16::new(gid)));
+                (glyph_name.map(file_safe_name).unwrap_or_default(), None)
+            }
+        };
+        let filename = if filename.is_empty() || used_filenames.contains(&filename) {
+            glyph_name_of(codepoint)
+        } else {
+            filename
+        };
+        used_filenames.insert(filename.clone());
+
+        icons.push(ExtractedIcon {
+            name: name.unwrap_or_else(|| filename_to_identifier(&filename)),
+            filename,
+            codepoint,
+            svg: format!(
+                "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"0 0 {} {}\"><path d=\"{}\"/></svg>\n",
+                width,
+                units_per_em,
+                path.to_svg()
+            ),
+            path,
+            width,
+            height: units_per_em,
+        });
+    }
+
+    Ok(icons)
+}
+
+/// Write each icon as `<filename>.svg` plus a `codepoints.json` that keeps
+/// their codepoints when the directory is built again with `--codepoints`
+pub fn write_icons(icons: &[ExtractedIcon], output_dir: &Path) -> Result<()> {
+    std::fs::create_dir_all(output_dir)
+        .with_context(|| format!("Failed to create {}", output_dir.display()))?;
+
+    for icon in icons {
+        let path = output_dir.join(format!("{}.svg", icon.filename));
+        std::fs::write(&path, &icon.svg)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+    }
+
+    let codepoints: BTreeMap<&str, String> = icons
+        .iter()
+        .map(|icon| (icon.name.as_str(), format!("U+{:04X}", icon.codepoint)))
+        .collect();
+    let path = output_dir.join("codepoints.json");
+    std::fs::write(&path, serde_json::to_string_pretty(&codepoints)? + "\n")
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// A glyph's outline in font units as SVG path data, with components
+/// flattened, so the same shape reads the same whether it is stored as a
+/// simple or a composite glyph
+pub fn normalized_outline(glyf: &Glyf, loca: &Loca, gid: u16) -> Result<String> {
+    Ok(glyph_outline(glyf, loca, gid)?.to_svg())
+}
+
+/// A glyph's outline in font units, with components flattened
+pub fn glyph_outline(glyf: &Glyf, loca: &Loca, gid: u16) -> Result<BezPath> {
+    let mut path = BezPath::new();
+    glyph_path(glyf, loca, gid, Affine::IDENTITY, 0, &mut path)?;
+    Ok(path)
+}
+
+/// Append the outline of a glyph, with composites flattened, to `path`
+fn glyph_path(
+    glyf: &Glyf,
+    loca: &Loca,
+    gid: u16,
+    transform: Affine,
+    depth: u32,
+    path: &mut BezPath,
+) -> Result<()> {
+    if depth > MAX_COMPONENT_DEPTH {
+        anyhow::bail!(
+            "Components are nested more than {} deep",
+            MAX_COMPONENT_DEPTH
+        );
+    }
+
+    match loca.get_glyf(GlyphId::from(gid), glyf)? {
+        Some(Glyph::Simple(simple)) => {
+            let points: Vec<(Point, bool)> = simple
+                .points()
+                .map(|p| (transform * Point::new(p.x as f64, p.y as f64), p.on_curve))
+                .collect();
+            let mut start = 0;
+            for end in simple.end_pts_of_contours() {
+                let end = end.get() as usize + 1;
+                contour_path(points.get(start..end).unwrap_or_default(), path);
+                start = end;
+            }
+        }
+        Some(Glyph::Composite(composite)) => {
+            for component in composite.components() {
+                let Anchor::Offset { x, y } = component.anchor else {
+                    anyhow::bail!("Components positioned by point numbers are not supported");
+                };
+                let t = component.transform;
+                let component_transform = Affine::new([
+                    t.xx.to_f32() as f64,
+                    t.yx.to_f32() as f64,
+                    t.xy.to_f32() as f64,
+                    t.yy.to_f32() as f64,
+                    x as f64,
+                    y as f64,
+                ]);
+                glyph_path(
+                    glyf,
+                    loca,
+                    component.glyph.to_u16(),
+                    transform * component_transform,
+                    depth + 1,
+                    path,
+                )?;
+            }
+        }
+        None => {}
+    }
+    Ok(())
+}
+
+/// Append a TrueType contour, where consecutive off-curve points imply an
+/// on-curve point halfway between them
+fn contour_path(points: &[(Point, bool)], path: &mut BezPath) {
+    let Some(&(first, _)) = points.first() else {
+        return;
+    };
+
+    // Start on an on-curve point, or at the implied one before the first point
+    let start = points.iter().position(|(_, on_curve)| *on_curve);
+    let (start_point, offset) = match start {
+        Some(i) => (points[i].0, i + 1),
+        None => (points[points.len() - 1].0.midpoint(first), 0),
+    };
+    path.move_to(start_point);
+
+    let mut control: Option<Point> = None;
+    for i in 0..points.len() - usize::from(start.is_some()) {
+        let (point, on_curve) = points[(offset + i) % points.len()];
+        match (on_curve, control.take()) {
+            (true, None) => path.line_to(point),
+            (true, Some(c)) => path.quad_to(c, point),
+            (false, None) => control = Some(point),
+            (false, Some(c)) => {
+                path.quad_to(c, c.midpoint(point));
+                control = Some(point);
+            }
+        }
+    }
+    if let Some(c) = control {
+        path.quad_to(c, start_point);
+    }
+    path.close_path();
+}
+
+/// Standard glyph name for a codepoint: `uniE000`, or `uF0000` beyond the BMP
+fn glyph_name_of(codepoint: u32) -> String {
+    if codepoint > 0xFFFF {
+        format!("u{:05X}", codepoint)
+    } else {
+        format!("uni{:04X}", codepoint)
+    }
+}
+
+/// Keep a glyph name usable as a file name
+fn file_safe_name(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+        .collect::<String>()
+        .trim_start_matches('.')
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::font_builder::{build_font_bytes, FontOptions};
+    use crate::svg_parser::parse_svg_str;
+
+    #[test]
+    fn test_extract_round_trips_outlines() {
+        let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 24 24"><rect x="6" y="6" width="12" height="12"/></svg>"#;
+        let icon = parse_svg_str(svg, "square", 0xE000).unwrap();
+        let data = build_font_bytes(&[icon], "Test", &FontOptions::default(), None, false).unwrap();
+
+        let unnamed = extract_icons(&data, &BTreeMap::new()).unwrap();
+        assert_eq!(unnamed[0].filename, "uniE000");
+
+        let names =
+            BTreeMap::from([(0xE000, ("square-box".to_string(), "square_box".to_string()))]);
+        let icons = extract_icons(&data, &names).unwrap();
+        assert_eq!(icons.len(), 1);
+        assert_eq!(icons[0].filename, "square-box");
+        assert_eq!(icons[0].name, "square_box");
+        assert!(icons[0].svg.contains(r#"viewBox="0 0 1000 1000""#));
+
+        // Building the extracted SVG again gives the same glyph
+        let again = parse_svg_str(&icons[0].svg, "square", 0xE000).unwrap();
+        let rebuilt =
+            build_font_bytes(&[again], "Test", &FontOptions::default(), None, false).unwrap();
+        let glyph_data = |data: &[u8]| {
+            let font = FontRef::new(data).unwrap();
+            font.table_data(read_fonts::types::Tag::new(b"glyf"))
+                .unwrap()
+                .as_bytes()
+                .to_vec()
+        };
+        assert_eq!(glyph_data(&data), glyph_data(&rebuilt));
+    }
+}
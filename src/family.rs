@@ -0,0 +1,310 @@
+use crate::font_builder::{self, create_name_record};
+use crate::subset::codepoint_mappings;
+use anyhow::{Context, Result};
+use read_fonts::{FontRef, TableProvider};
+use std::collections::BTreeMap;
+use write_fonts::{
+    from_obj::ToOwnedTable,
+    tables::{hhea::Hhea, name::Name, os2::Os2},
+    types::{FWord, GlyphId16, NameId},
+    FontBuilder,
+};
+
+/// Name IDs rewritten so every member agrees on the family
+const FAMILY_NAME_IDS: [NameId; 7] = [
+    NameId::FAMILY_NAME,
+    NameId::SUBFAMILY_NAME,
+    NameId::UNIQUE_ID,
+    NameId::FULL_NAME,
+    NameId::POSTSCRIPT_NAME,
+    NameId::TYPOGRAPHIC_FAMILY_NAME,
+    NameId::TYPOGRAPHIC_SUBFAMILY_NAME,
+];
+
+/// One style of a packaged family
+#[derive(Debug, Clone)]
+pub struct FamilyMember {
+    /// Subfamily name, e.g. `Outlined`
+    pub subfamily: String,
+    /// The rewritten font
+    pub data: Vec<u8>,
+}
+
+/// Every codepoint mapped by any member, with the glyph name from the first font that maps it
+pub type FamilyGlyphs = BTreeMap<u32, Option<String>>;
+
+/// Turn fonts built as separate styles into one coherent family.
+///
+/// Each font keeps its subfamily name but gets the family name `family`
+/// (default: the first font's), and all members share the tallest vertical
+/// metrics so lines of mixed styles line up. Codepoints missing from some
+/// styles are reported as warnings.
+pub fn package_family(
+    fonts: &[Vec<u8>],
+    family: Option<&str>,
+) -> Result<(String, Vec<FamilyMember>, FamilyGlyphs)> {
+    let fonts = fonts
+        .iter()
+        .enumerate()
+        .map(|(i, data)| {
+            FontRef::new(data).map_err(|e| anyhow::anyhow!("Font {} does not parse: {}", i + 1, e))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let first = fonts.first().context("No fonts to package")?;
+
+    let family = match family {
+        Some(family) => family.to_string(),
+        None => name_string(first, NameId::FAMILY_NAME).context("First font has no family name")?,
+    };
+
+    // Members must be distinguishable and drawn on the same grid
+    let mut subfamilies: Vec<String> = Vec::new();
+    for font in &fonts {
+        let subfamily = name_string(font, NameId::TYPOGRAPHIC_SUBFAMILY_NAME)
+            .or_else(|| name_string(font, NameId::SUBFAMILY_NAME))
+            .unwrap_or_else(|| "Regular".to_string());
+        if subfamilies.contains(&subfamily) {
+            anyhow::bail!("More than one font has the subfamily {}", subfamily);
+        }
+        subfamilies.push(subfamily);
+    }
+    let units_per_em = first.head()?.units_per_em();
+    if fonts
+        .iter()
+        .any(|f| f.head().map(|h| h.units_per_em()).ok() != Some(units_per_em))
+    {
+        anyhow::bail!("All fonts must use the same units per em");
+    }
+
+    let metrics = SharedMetrics::of(&fonts)?;
+    let glyphs = family_glyphs(&fonts, &subfamilies)?;
+
+    let members = fonts
+        .iter()
+        .zip(subfamilies)
+        .map(|(font, subfamily)| {
+            let data = rewrite_member(font, &family, &subfamily, &metrics)?;
+            Ok(FamilyMember { subfamily, data })
+        })
+        .collect::<Result<_>>()?;
+
+    Ok((family, members, glyphs))
+}
+
+/// Vertical metrics covering every member of the family
+struct SharedMetrics {
+    ascender: i16,
+    descender: i16,
+    line_gap: i16,
+    win_ascent: u16,
+    win_descent: u16,
+}
+
+impl SharedMetrics {
+    fn of(fonts: &[FontRef]) -> Result<Self> {
+        let mut metrics = Self {
+            ascender: i16::MIN,
+            descender: i16::MAX,
+            line_gap: 0,
+            win_ascent: 0,
+            win_descent: 0,
+        };
+        for font in fonts {
+            let hhea = font.hhea()?;
+            metrics.ascender = metrics.ascender.max(hhea.ascender().to_i16());
+            metrics.descender = metrics.descender.min(hhea.descender().to_i16());
+            metrics.line_gap = metrics.line_gap.max(hhea.line_gap().to_i16());
+            if let Ok(os2) = font.os2() {
+                metrics.win_ascent = metrics.win_ascent.max(os2.us_win_ascent());
+                metrics.win_descent = metrics.win_descent.max(os2.us_win_descent());
+            }
+        }
+        Ok(metrics)
+    }
+}
+
+/// Rebuild `font` with the family's names and shared metrics
+fn rewrite_member(
+    font: &FontRef,
+    family: &str,
+    subfamily: &str,
+    metrics: &SharedMetrics,
+) -> Result<Vec<u8>> {
+    let full_name = format!("{} {}", family, subfamily);
+    let mut name: Name = font.name()?.to_owned_table();
+    name.name_record
+        .retain(|record| !FAMILY_NAME_IDS.contains(&record.name_id));
+    for (name_id, value) in [
+        (NameId::FAMILY_NAME, family.to_string()),
+        (NameId::SUBFAMILY_NAME, subfamily.to_string()),
+        (NameId::UNIQUE_ID, format!("svg2font: {}", full_name)),
+        (NameId::FULL_NAME, full_name),
+        (
+            NameId::POSTSCRIPT_NAME,
+            format!("{}-{}", family, subfamily).replace(' ', ""),
+        ),
+    ] {
+        name.name_record.push(create_name_record(name_id, &value));
+    }
+    name.name_record.sort_by_key(|record| {
+        (
+            record.platform_id,
+            record.encoding_id,
+            record.language_id,
+            record.name_id,
+        )
+    });
+
+    let mut hhea: Hhea = font.hhea()?.to_owned_table();
+    hhea.ascender = FWord::new(metrics.ascender);
+    hhea.descender = FWord::new(metrics.descender);
+    hhea.line_gap = FWord::new(metrics.line_gap);
+
+    let mut builder = FontBuilder::new();
+    builder.add_table(&name)?.add_table(&hhea)?;
+    if let Ok(os2) = font.os2() {
+        let mut os2: Os2 = os2.to_owned_table();
+        os2.s_typo_ascender = metrics.ascender;
+        os2.s_typo_descender = metrics.descender;
+        os2.s_typo_line_gap = metrics.line_gap;
+        os2.us_win_ascent = metrics.win_ascent;
+        os2.us_win_descent = metrics.win_descent;
+        builder.add_table(&os2)?;
+    }
+    builder.copy_missing_tables(font.clone());
+
+    font_builder::build_checked(&mut builder)
+}
+
+/// Collect the family's codepoints, warning about styles that lack some of them
+fn family_glyphs(fonts: &[FontRef], subfamilies: &[String]) -> Result<FamilyGlyphs> {
+    let mappings = fonts
+        .iter()
+        .map(codepoint_mappings)
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut glyphs = FamilyGlyphs::new();
+    for (font, mapping) in fonts.iter().zip(&mappings) {
+        let post = font.post().ok();
+        for (&codepoint, &gid) in mapping {
+            glyphs.entry(codepoint).or_insert_with(|| {
+                post.as_ref()?
+                    .glyph_name(GlyphId16::new(gid))
+                    .map(str::to_string)
+            });
+        }
+    }
+
+    for (mapping, subfamily) in mappings.iter().zip(subfamilies) {
+        let missing: Vec<String> = glyphs
+            .keys()
+            .filter(|codepoint| !mapping.contains_key(codepoint))
+            .map(|codepoint| format!("U+{:04X}", codepoint))
+            .collect();
+        if !missing.is_empty() {
+            eprintln!(
+                "Warning: {} has no glyph for {}",
+                subfamily,
+                missing.join(", ")
+            );
+        }
+    }
+
+    Ok(glyphs)
+}
+
+/// Read a Windows English name record
+fn name_string(font: &FontRef, name_id: NameId) -> Option<String> {
+    let name = font.name().ok()?;
+    name.name_record()
+        .iter()
+        .filter(|record| record.name_id() == name_id && record.platform_id() == 3)
+        .find_map(|record| Some(record.string(name.string_data()).ok()?.to_string()))
+}
+
+/// Pack fonts into a TrueType collection.
+///
+/// Each font is stored whole; only its table offsets are moved to account
+/// for its position in the collection.
+pub fn build_collection(fonts: &[Vec<u8>]) -> Result<Vec<u8>> {
+    let header_len = 12 + 4 * fonts.len();
+    let mut offsets = Vec::with_capacity(fonts.len());
+    let mut body = Vec::new();
+    for font in fonts {
+        let offset = header_len + body.len();
+        let num_tables = u16::from_be_bytes(
+            font.get(4..6)
+                .context("Font is too short for a table directory")?
+                .try_into()?,
+        ) as usize;
+        if font.len() < 12 + 16 * num_tables {
+            anyhow::bail!("Font is too short for its table directory");
+        }
+
+        let mut font = font.clone();
+        for record in font[12..12 + 16 * num_tables].chunks_exact_mut(16) {
+            let table_offset = u32::from_be_bytes(record[8..12].try_into()?) as usize;
+            record[8..12].copy_from_slice(&u32::try_from(table_offset + offset)?.to_be_bytes());
+        }
+        offsets.push(u32::try_from(offset)?);
+        body.extend_from_slice(&font);
+        body.resize(body.len().next_multiple_of(4), 0);
+    }
+
+    let mut collection = Vec::with_capacity(header_len + body.len());
+    collection.extend_from_slice(b"ttcf");
+    collection.extend_from_slice(&1u16.to_be_bytes());
+    collection.extend_from_slice(&0u16.to_be_bytes());
+    collection.extend_from_slice(&(fonts.len() as u32).to_be_bytes());
+    for offset in offsets {
+        collection.extend_from_slice(&offset.to_be_bytes());
+    }
+    collection.extend_from_slice(&body);
+
+    Ok(collection)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::font_builder::{build_font_bytes, FontOptions};
+    use crate::svg_parser::parse_svg_str;
+    use read_fonts::{FileRef, FontRef};
+
+    #[test]
+    fn test_package_family_into_collection() {
+        let square = r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 24 24"><rect x="4" y="4" width="16" height="16"/></svg>"#;
+        let icons = vec![parse_svg_str(square, "square", 0xE000).unwrap()];
+        let fonts: Vec<Vec<u8>> = ["Filled", "Outlined"]
+            .iter()
+            .map(|subfamily| {
+                let options = FontOptions {
+                    subfamily: Some(subfamily.to_string()),
+                    ..Default::default()
+                };
+                build_font_bytes(&icons, "Old", &options, None, false).unwrap()
+            })
+            .collect();
+
+        let (family, members, glyphs) = package_family(&fonts, Some("New")).unwrap();
+        assert_eq!(family, "New");
+        assert_eq!(glyphs.keys().collect::<Vec<_>>(), [&0x20, &0xE000]);
+        let font = FontRef::new(&members[1].data).unwrap();
+        assert_eq!(
+            name_string(&font, NameId::FULL_NAME).unwrap(),
+            "New Outlined"
+        );
+
+        let data: Vec<Vec<u8>> = members.into_iter().map(|m| m.data).collect();
+        let collection = build_collection(&data).unwrap();
+        let FileRef::Collection(collection) = FileRef::new(&collection).unwrap() else {
+            panic!("not a collection");
+        };
+        let second = collection.get(1).unwrap();
+        assert_eq!(
+            name_string(&second, NameId::POSTSCRIPT_NAME).unwrap(),
+            "New-Outlined"
+        );
+        assert!(second.glyf().is_ok());
+    }
+}
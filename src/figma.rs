@@ -0,0 +1,145 @@
+//! Importing the components of a Figma file as SVG icons.
+
+use crate::remote;
+use anyhow::{Context, Result};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+const API: &str = "https://api.figma.com/v1";
+
+/// Components rendered per image request, keeping the URL short
+const BATCH_SIZE: usize = 100;
+
+/// Export every component of a Figma file as an SVG into `output`,
+/// returning the files written.
+///
+/// Files are named after the components, so a later import overwrites the
+/// same files and icons keep their codepoints in the lockfile.
+pub fn import(file_key: &str, token: &str, output: &Path) -> Result<Vec<PathBuf>> {
+    let headers = [format!("X-Figma-Token: {}", token)];
+    let get_json = |url: String| -> Result<Value> {
+        let body = remote::fetch(&url, &headers)?;
+        serde_json::from_slice(&body).with_context(|| format!("Invalid response from {}", url))
+    };
+
+    let components = components(&get_json(format!("{}/files/{}/components", API, file_key))?)?;
+    if components.is_empty() {
+        anyhow::bail!("Figma file {} has no components", file_key);
+    }
+    std::fs::create_dir_all(output)
+        .with_context(|| format!("Failed to create {}", output.display()))?;
+
+    let components: Vec<_> = components.into_iter().collect();
+    let mut written = Vec::new();
+    for batch in components.chunks(BATCH_SIZE) {
+        let ids: Vec<&str> = batch.iter().map(|(_, id)| id.as_str()).collect();
+        let urls = image_urls(&get_json(format!(
+            "{}/images/{}?ids={}&format=svg",
+            API,
+            file_key,
+            ids.join(",")
+        ))?)?;
+        for (name, id) in batch {
+            // Empty or hidden components render to nothing, which should not stop the others
+            let Some(url) = urls.get(id) else {
+                eprintln!("Warning: Figma rendered no SVG for `{}`, skipping it", name);
+                continue;
+            };
+            let path = output.join(format!("{}.svg", name));
+            std::fs::write(&path, remote::fetch(url, &[])?)
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+            written.push(path);
+        }
+    }
+    if written.is_empty() {
+        anyhow::bail!("Figma rendered none of the components of {}", file_key);
+    }
+    Ok(written)
+}
+
+/// File names and node ids of the components in a `/components` response
+fn components(response: &Value) -> Result<BTreeMap<String, String>> {
+    let list = response["meta"]["components"]
+        .as_array()
+        .context("Response lists no components")?;
+
+    let mut components = BTreeMap::new();
+    for component in list {
+        let (Some(id), Some(name)) = (component["node_id"].as_str(), component["name"].as_str())
+        else {
+            continue;
+        };
+        let file_name =
+            file_name(name).with_context(|| format!("Component `{}` has no usable name", name))?;
+        if components
+            .insert(file_name.clone(), id.to_string())
+            .is_some()
+        {
+            anyhow::bail!("Several components would be saved as {}.svg", file_name);
+        }
+    }
+    Ok(components)
+}
+
+/// SVG URLs by node id from an `/images` response
+fn image_urls(response: &Value) -> Result<HashMap<String, String>> {
+    if let Some(err) = response["err"].as_str() {
+        anyhow::bail!("Figma failed to render the components: {}", err);
+    }
+    let images = response["images"]
+        .as_object()
+        .context("Response has no images")?;
+    Ok(images
+        .iter()
+        .filter_map(|(id, url)| Some((id.clone(), url.as_str()?.to_string())))
+        .collect())
+}
+
+/// File name for a component: the last part of a `Group/Name` path, with
+/// anything but letters, digits, `-` and `_` turned into dashes
+fn file_name(component: &str) -> Option<String> {
+    let name = component.rsplit('/').next()?.trim();
+    let mut file_name = String::new();
+    for c in name.chars() {
+        if c.is_alphanumeric() || c == '_' || c == '-' {
+            file_name.push(c);
+        } else if !file_name.ends_with('-') {
+            file_name.push('-');
+        }
+    }
+    let file_name = file_name.trim_matches('-');
+    (!file_name.is_empty()).then(|| file_name.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_responses() {
+        let response = serde_json::json!({
+            "meta": {"components": [
+                {"node_id": "1:2", "name": "Navigation/Arrow Right"},
+                {"node_id": "1:3", "name": "Size=24, Style=Filled"},
+            ]}
+        });
+        let parsed = components(&response).unwrap();
+        assert_eq!(parsed["Arrow-Right"], "1:2");
+        assert_eq!(parsed["Size-24-Style-Filled"], "1:3");
+
+        let clash = serde_json::json!({
+            "meta": {"components": [
+                {"node_id": "1:2", "name": "a/close"},
+                {"node_id": "1:3", "name": "b/close"},
+            ]}
+        });
+        assert!(components(&clash).is_err());
+
+        let images =
+            serde_json::json!({"err": null, "images": {"1:2": "https://x/1.svg", "1:3": null}});
+        let urls = image_urls(&images).unwrap();
+        assert_eq!(urls.len(), 1);
+        assert!(image_urls(&serde_json::json!({"err": "Invalid token"})).is_err());
+    }
+}
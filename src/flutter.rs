@@ -0,0 +1,132 @@
+//! A ready-to-publish Flutter package bundling the font with a Dart class of
+//! its icons.
+
+use crate::codegen;
+use crate::svg_parser::Icon;
+use anyhow::{Context, Result};
+use std::path::Path;
+
+/// Write the package for the font at `ttf_path` into `dir`: `pubspec.yaml`
+/// declaring the font, the font under `fonts/`, the Dart class under `lib/`
+/// and the README, changelog and license pub.dev expects
+pub fn generate_package(
+    icons: &[Icon],
+    font_name: &str,
+    ttf_path: &Path,
+    info: &codegen::PackageInfo,
+    dir: &Path,
+) -> Result<()> {
+    let package = codegen::package_name(font_name);
+    let font_file = ttf_path
+        .file_name()
+        .context("Font path has no file name")?
+        .to_string_lossy()
+        .into_owned();
+    for sub in ["fonts", "lib"] {
+        let path = dir.join(sub);
+        std::fs::create_dir_all(&path)
+            .with_context(|| format!("Failed to create {}", path.display()))?;
+    }
+
+    let font_path = dir.join("fonts").join(&font_file);
+    std::fs::copy(ttf_path, &font_path)
+        .with_context(|| format!("Failed to copy the font to {}", font_path.display()))?;
+    codegen::generate_dart(
+        icons,
+        font_name,
+        Some(&package),
+        &dir.join("lib").join(format!("{}.dart", package)),
+    )?;
+
+    let version = format!("{}.0", info.version.unwrap_or("1.0"));
+    let mut files = vec![
+        (
+            "pubspec.yaml",
+            pubspec(&package, font_name, &font_file, &version, info.description),
+        ),
+        ("README.md", readme(icons, font_name, &package)),
+        (
+            "CHANGELOG.md",
+            format!("## {}\n\n- {} icons.\n", version, icons.len()),
+        ),
+    ];
+    if let Some(license) = info.license {
+        files.push(("LICENSE", license.to_string()));
+    }
+    for (file, content) in files {
+        let path = dir.join(file);
+        std::fs::write(&path, content)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+    }
+    Ok(())
+}
+
+fn pubspec(
+    package: &str,
+    font_name: &str,
+    font_file: &str,
+    version: &str,
+    description: Option<&str>,
+) -> String {
+    let description = description.map_or_else(
+        || {
+            format!(
+                "The {} icon font for Flutter, with an IconData constant for every icon.",
+                font_name
+            )
+        },
+        str::to_string,
+    );
+    format!(
+        "name: {package}\n\
+         description: {description}\n\
+         version: {version}\n\n\
+         environment:\n\
+         \x20 sdk: \">=2.17.0 <4.0.0\"\n\
+         \x20 flutter: \">=3.0.0\"\n\n\
+         dependencies:\n\
+         \x20 flutter:\n\
+         \x20   sdk: flutter\n\n\
+         flutter:\n\
+         \x20 fonts:\n\
+         \x20   - family: {family}\n\
+         \x20     fonts:\n\
+         \x20       - asset: fonts/{font_file}\n",
+        description = yaml_string(&description),
+        family = yaml_string(font_name),
+    )
+}
+
+/// A double-quoted YAML string
+fn yaml_string(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn readme(icons: &[Icon], font_name: &str, package: &str) -> String {
+    let class = codegen::file_name(font_name);
+    let example = icons.first().map_or("icon", |icon| icon.name.as_str());
+    format!(
+        "# {package}\n\n\
+         The {font_name} icon font, {count} icons, generated by svg2font.\n\n\
+         ```dart\n\
+         import 'package:{package}/{package}.dart';\n\n\
+         Icon({class}.{example})\n\
+         ```\n",
+        count = icons.len(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pubspec() {
+        let yaml = pubspec("my_icons", "My \"Icons\"", "my_icons.ttf", "2.1.0", None);
+        assert!(yaml.starts_with("name: my_icons\ndescription: \"The My \\\"Icons\\\" icon font"));
+        assert!(yaml.contains("version: 2.1.0\n"));
+        assert!(yaml.contains(
+            "  fonts:\n    - family: \"My \\\"Icons\\\"\"\n      fonts:\n        - asset: fonts/my_icons.ttf\n"
+        ));
+    }
+}
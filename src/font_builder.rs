@@ -1,171 +1,1518 @@
-use crate::svg_parser::Icon;
+use crate::cache::{self, PathCache};
+use crate::cff;
+use crate::license::LicenseText;
+use crate::provenance::Provenance;
+use crate::svg_parser::{Icon, ShapeFill};
+use crate::unicode_ranges;
+use crate::variable::{self, FillDeltas};
 use anyhow::{Context, Result};
-use kurbo::{Affine, BezPath, CubicBez, ParamCurve, PathEl, Point, QuadBez};
+use kurbo::{Affine, BezPath, CubicBez, PathEl, Point, Rect, Shape};
+use rayon::prelude::*;
+use read_fonts::{tables::glyf::CurvePoint, FontRef};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::path::Path;
 use write_fonts::{
     tables::{
-        cmap::Cmap,
-        glyf::{GlyfLocaBuilder, SimpleGlyph},
+        cmap::{Cmap, PlatformId},
+        colr::{BaseGlyph, Colr, Layer},
+        cpal::{ColorRecord, Cpal},
+        glyf::{
+            Anchor, Bbox, Component, ComponentFlags, CompositeGlyph, GlyfLocaBuilder, SimpleGlyph,
+            Transform,
+        },
+        gsub::{
+            Gsub, Ligature, LigatureSet, LigatureSubstFormat1, SubstitutionLookup,
+            SubstitutionLookupList,
+        },
         head::{Head, MacStyle},
         hhea::Hhea,
         hmtx::Hmtx,
+        layout::{
+            CoverageTable, Feature, FeatureList, FeatureRecord, LangSys, Lookup, LookupFlag,
+            Script, ScriptList, ScriptRecord,
+        },
         maxp::Maxp,
         name::{Name, NameRecord},
-        os2::Os2,
+        os2::{Os2, SelectionFlags},
         post::Post,
         vmtx::LongMetric,
     },
-    types::{FWord, Fixed, GlyphId, NameId, UfWord},
+    types::{
+        F2Dot14, FWord, Fixed, GlyphId, GlyphId16, NameId, Tag, UfWord, Version16Dot16,
+        CFF_SFNT_VERSION,
+    },
     FontBuilder,
 };
 
-/// Units per em for the generated font
-const UNITS_PER_EM: u16 = 1000;
+/// The space character, which every font gets a blank glyph for
+const SPACE: u32 = 0x20;
+
+/// Units per em of the generated font unless configured otherwise
+pub const DEFAULT_UNITS_PER_EM: u16 = 1000;
+
+/// Units per em allowed by the OpenType spec
+pub const UNITS_PER_EM_RANGE: std::ops::RangeInclusive<u16> = 16..=16384;
+
+/// COLR palette index meaning the current text color
+const FOREGROUND_PALETTE_INDEX: u16 = 0xFFFF;
+
+/// Largest coordinate magnitude allowed in a glyph, so point deltas fit in an i16
+const MAX_COORD: f64 = 16383.0;
+
+/// Default maximum distance, in font units, between a cubic and its quadratics
+pub const DEFAULT_CURVE_TOLERANCE: f64 = 1.0;
+
+/// Smallest curve tolerance accepted, which keeps the number of quadratics sane
+const MIN_CURVE_TOLERANCE: f64 = 0.01;
+
+/// Options controlling how the font tables are assembled
+#[derive(Debug, Clone, Default)]
+pub struct FontOptions {
+    /// Deduplicate identical glyphs and drop redundant metrics and glyph names
+    pub optimize_size: bool,
+    /// Copyright holder written into the copyright notice
+    pub copyright: Option<String>,
+    /// License description and URL for the name table
+    pub license: Option<LicenseText>,
+    /// License URL for the name table, in place of the license's own
+    pub license_url: Option<String>,
+    /// Font version as `MAJOR.MINOR` [default: 1.0]
+    pub version: Option<String>,
+    /// Designer recorded in the name table
+    pub designer: Option<String>,
+    /// Description recorded in the name table
+    pub description: Option<String>,
+    /// Vendor ID for OS/2 achVendID [default: four spaces]
+    pub vendor_id: Option<Tag>,
+    /// Embedding permissions in OS/2 fsType
+    pub embedding: Embedding,
+    /// Icon drawn for characters the font has no glyph for [default: nothing]
+    pub notdef: Option<Icon>,
+    /// Build provenance appended to the version string
+    pub provenance: Option<Provenance>,
+    /// Subfamily name [default: Regular]
+    pub subfamily: Option<String>,
+    /// Weight class, 100 (Thin) to 900 (Black) [default: 400]
+    pub weight: Option<u16>,
+    /// Width class, 1 (Ultra-condensed) to 9 (Ultra-expanded) [default: 5]
+    pub width: Option<u16>,
+    /// Mark the font italic, with this slant in degrees (negative leans right)
+    pub italic: Option<f64>,
+    /// Table tweaks for applications that need them
+    pub compat: Option<Compatibility>,
+    /// Add COLR/CPAL color layers for icons with colored shapes
+    pub color: bool,
+    /// Add a `liga` feature turning each icon's name into its glyph
+    pub ligatures: bool,
+    /// Add a FILL axis, making this a variable font (see [`build_fill_font_bytes`])
+    pub fill_axis: bool,
+    /// Maximum distance in font units between a cubic curve and its quadratic
+    /// approximation [default: 1.0]
+    pub curve_tolerance: Option<f64>,
+    /// Fit each glyph's advance to its outline, with this side bearing in
+    /// font units on both sides, instead of one em
+    pub proportional: Option<u16>,
+    /// Units per em, which icons are scaled to [default: 1000]
+    pub units_per_em: Option<u16>,
+    /// Ascender in font units [default: 80% of the em]
+    pub ascender: Option<i16>,
+    /// Descender in font units, usually negative [default: -20% of the em]
+    pub descender: Option<i16>,
+    /// How the SVG viewBox is placed between the ascender and descender
+    pub fit: VerticalFit,
+    /// Font units to move every glyph up by, or down if negative
+    pub baseline_offset: i16,
+    /// Crop each icon to a square around its outline instead of its viewBox
+    pub trim: bool,
+    /// Margin around each icon, in percent of its larger side
+    pub padding: f64,
+    /// How each outline is centered horizontally in its advance
+    pub center_x: Centering,
+    /// How each outline is centered vertically in its fitted span
+    pub center_y: Centering,
+}
+
+/// Vertical placement of the viewBox in the glyph
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum VerticalFit {
+    /// The viewBox fills the span from descender to ascender, centered in it
+    Em,
+    /// The viewBox is as tall as capital letters and sits on the baseline
+    #[value(name = "capheight")]
+    CapHeight,
+    /// The viewBox's larger side is one em, with its bottom on the baseline
+    #[default]
+    None,
+}
+
+/// Which center of an outline is aligned with the center of the glyph
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum Centering {
+    /// Keep the outline where the viewBox puts it
+    #[default]
+    None,
+    /// Center the outline's bounding box
+    Geometric,
+    /// Center the outline's area-weighted centroid, which looks balanced for
+    /// lopsided shapes such as arrows and play buttons
+    Centroid,
+}
+
+/// Embedding permissions granted to documents and applications, as OS/2 fsType
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum Embedding {
+    /// The font may be embedded and permanently installed
+    #[default]
+    Installable,
+    /// The font must not be embedded without the legal owner's permission
+    Restricted,
+    /// The font may be embedded to view and print documents, which are read-only
+    Preview,
+    /// The font may be embedded in documents that can be edited
+    Editable,
+}
+
+impl Embedding {
+    /// The OS/2 fsType value
+    pub fn fs_type(self) -> u16 {
+        match self {
+            Embedding::Installable => 0,
+            Embedding::Restricted => 0x0002,
+            Embedding::Preview => 0x0004,
+            Embedding::Editable => 0x0008,
+        }
+    }
+}
+
+/// Compatibility profiles adjusting tables for picky font consumers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Compatibility {
+    /// A pure symbol font as old GDI applications expect: only the Symbol
+    /// cmap, symbol-encoded names and the Symbol code page in OS/2
+    WindowsSymbol,
+}
+
+impl FontOptions {
+    /// Curve tolerance to convert outlines with
+    pub fn curve_tolerance(&self) -> f64 {
+        self.curve_tolerance.unwrap_or(DEFAULT_CURVE_TOLERANCE)
+    }
+
+    pub fn units_per_em(&self) -> u16 {
+        self.units_per_em.unwrap_or(DEFAULT_UNITS_PER_EM)
+    }
+
+    pub fn weight(&self) -> u16 {
+        self.weight.unwrap_or(400)
+    }
+
+    pub fn width(&self) -> u16 {
+        self.width.unwrap_or(5)
+    }
+
+    /// Style name made of the subfamily, width, weight and slope, or `None`
+    /// for Regular
+    pub fn style_name(&self) -> Option<String> {
+        let parts: Vec<&str> = [
+            self.subfamily.as_deref(),
+            width_name(self.width()),
+            weight_name(self.weight()),
+            self.italic.map(|_| "Italic"),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        (!parts.is_empty()).then(|| parts.join(" "))
+    }
+
+    fn is_bold(&self) -> bool {
+        self.weight() >= 700
+    }
+
+    pub fn version(&self) -> &str {
+        self.version.as_deref().unwrap_or("1.0")
+    }
+
+    pub fn ascender(&self) -> i16 {
+        self.ascender.unwrap_or_else(|| self.scale_metric(800))
+    }
+
+    pub fn descender(&self) -> i16 {
+        self.descender.unwrap_or_else(|| self.scale_metric(-200))
+    }
+
+    pub fn cap_height(&self) -> i16 {
+        self.scale_metric(700)
+    }
+
+    /// Everything that changes how an outline becomes a glyph, for cache keys
+    fn glyph_settings(&self) -> String {
+        format!(
+            "{}-tol{}-{:?}{}{}{}-trim{}-pad{}-center{:?}{:?}",
+            self.units_per_em(),
+            self.curve_tolerance(),
+            self.fit,
+            self.ascender(),
+            self.descender(),
+            self.baseline_offset,
+            self.trim,
+            self.padding,
+            self.center_x,
+            self.center_y
+        )
+    }
+
+    /// Scale a metric given for a 1000 unit em to the configured em
+    fn scale_metric(&self, value: i16) -> i16 {
+        (value as i32 * self.units_per_em() as i32 / DEFAULT_UNITS_PER_EM as i32) as i16
+    }
+
+    /// Check that the metrics describe a usable font
+    pub fn validate(&self) -> Result<()> {
+        let units_per_em = self.units_per_em();
+        if !UNITS_PER_EM_RANGE.contains(&units_per_em) {
+            anyhow::bail!(
+                "Units per em must be between {} and {}, not {}",
+                UNITS_PER_EM_RANGE.start(),
+                UNITS_PER_EM_RANGE.end(),
+                units_per_em
+            );
+        }
+        if self.ascender() <= self.descender() {
+            anyhow::bail!(
+                "The ascender ({}) must be above the descender ({})",
+                self.ascender(),
+                self.descender()
+            );
+        }
+        if let Some(angle) = self.italic {
+            if !(-45.0..=45.0).contains(&angle) {
+                anyhow::bail!(
+                    "The italic angle must be between -45 and 45 degrees, not {}",
+                    angle
+                );
+            }
+        }
+        Ok(())
+    }
+
+    fn windows_symbol(&self) -> bool {
+        self.compat == Some(Compatibility::WindowsSymbol)
+    }
+}
 
 /// Build a TTF font from a list of icons
 pub fn build_font(
     icons: &[Icon],
     font_name: &str,
     output_path: &Path,
+    options: &FontOptions,
+    cache: Option<&PathCache>,
     verbose: bool,
 ) -> Result<()> {
-    // Build glyf and loca tables
-    let mut glyf_builder = GlyfLocaBuilder::new();
+    let font_data = build_font_bytes(icons, font_name, options, cache, verbose)?;
+    write_font(&font_data, output_path)
+}
 
-    // Add .notdef glyph (required, empty)
-    glyf_builder.add_glyph(&empty_glyph())?;
+/// Write assembled font data to disk
+pub fn write_font(font_data: &[u8], output_path: &Path) -> Result<()> {
+    std::fs::write(output_path, font_data)
+        .with_context(|| format!("Failed to write {}", output_path.display()))
+}
 
-    // Track metrics for hmtx
-    let mut metrics: Vec<LongMetric> = vec![LongMetric {
-        advance: UNITS_PER_EM,
-        side_bearing: 0,
-    }];
+/// Build a TTF font from a list of icons, returning the font file contents
+pub fn build_font_bytes(
+    icons: &[Icon],
+    font_name: &str,
+    options: &FontOptions,
+    cache: Option<&PathCache>,
+    verbose: bool,
+) -> Result<Vec<u8>> {
+    let glyphs = convert_glyphs(icons, options, cache, verbose)?;
+    assemble_font(icons, &glyphs, font_name, options)
+}
+
+/// Convert every icon's outline to a glyph, in parallel, preserving icon order
+pub fn convert_glyphs(
+    icons: &[Icon],
+    options: &FontOptions,
+    cache: Option<&PathCache>,
+    verbose: bool,
+) -> Result<Vec<SimpleGlyph>> {
+    let glyphs: Vec<_> = icons
+        .par_iter()
+        .map(|icon| icon_to_glyph(icon, options, cache))
+        .collect();
+
+    // Messages follow icon order, as if built one by one
+    icons
+        .iter()
+        .zip(glyphs)
+        .map(|(icon, glyph)| {
+            if verbose {
+                println!("  Building glyph: {} (U+{:04X})", icon.name, icon.codepoint);
+            }
+            glyph
+        })
+        .collect()
+}
+
+/// Assemble converted glyphs (one per icon, in order) into a font
+pub fn assemble_font(
+    icons: &[Icon],
+    glyphs: &[SimpleGlyph],
+    font_name: &str,
+    options: &FontOptions,
+) -> Result<Vec<u8>> {
+    let mut assembler = FontAssembler::new(options.clone())?;
+    for (icon, glyph) in icons.iter().zip(glyphs) {
+        assembler.add_color_layers(icon)?;
+        assembler.add_glyph(glyph, icon.overrides.advance)?;
+    }
+
+    assembler.finish(font_name, icons)
+}
+
+/// Build a variable TTF with a FILL axis from the outlined and filled styles
+/// of the same icons, in the same order, returning the font file contents.
+///
+/// The outlined style is the default; names, codepoints and metrics come
+/// from `outlined`.
+pub fn build_fill_font_bytes(
+    outlined: &[Icon],
+    filled: &[Icon],
+    font_name: &str,
+    options: &FontOptions,
+    cache: Option<&PathCache>,
+    verbose: bool,
+) -> Result<Vec<u8>> {
+    let outlines = convert_glyphs(outlined, options, cache, verbose)?;
+    let fills = convert_glyphs(filled, options, cache, verbose)?;
+    let options = FontOptions {
+        fill_axis: true,
+        ..options.clone()
+    };
+    let mut assembler = FontAssembler::new(options)?;
+    for ((icon, outline), fill) in outlined.iter().zip(&outlines).zip(&fills) {
+        assembler.add_fill_glyph(outline, fill, icon.overrides.advance)?;
+    }
+
+    assembler.finish(font_name, outlined)
+}
+
+/// A color layer glyph and its RGBA color
+type ColorLayer = (SimpleGlyph, [u8; 4]);
+
+/// Glyphs and tables spelling icon names as ligatures
+struct NameLigatures {
+    /// cmap entries of the characters used in the names
+    letters: Vec<(u32, u16)>,
+    gsub: Gsub,
+    /// Length of the longest name, for OS/2 usMaxContext
+    max_context: u16,
+}
+
+/// Incrementally assembles a font one glyph at a time.
+///
+/// Glyph outlines are serialized as they are added, so callers can drop
+/// each icon's path right away and keep memory flat for very large sets.
+/// Codepoints are only read when the font is finished, so they can still
+/// be reassigned after the glyphs are built.
+pub struct FontAssembler {
+    glyf_builder: GlyfLocaBuilder,
+    metrics: Vec<LongMetric>,
+    options: FontOptions,
+    /// Glyph id and bounding box origin of outlines already added, keyed by
+    /// a hash of their glyf data once moved to the origin. A fixed-size key
+    /// keeps memory flat in low-memory mode.
+    glyph_ids: HashMap<String, (u16, (i16, i16))>,
+    /// Color layers added after every icon glyph, keyed by the icon's glyph id
+    color_layers: Vec<(u16, Vec<ColorLayer>)>,
+    /// Horizontal shift applied to each glyph by proportional spacing
+    x_offsets: Vec<i16>,
+    /// Point deltas of the glyphs that change along the fill axis, by glyph id
+    fill_variations: Vec<(u16, FillDeltas)>,
+    bounds: FontBounds,
+}
+
+/// Extents of the glyphs, accumulated for head, hhea and OS/2
+#[derive(Debug, Clone, Copy, Default)]
+struct FontBounds {
+    /// Union of the bounding boxes of all non-empty glyphs
+    bbox: Option<Bbox>,
+    advance_width_max: u16,
+    min_left_side_bearing: i16,
+    min_right_side_bearing: i16,
+    x_max_extent: i16,
+    /// Sum and count of the non-zero advances, for OS/2 xAvgCharWidth
+    advance_sum: u64,
+    advance_count: u64,
+}
 
-    for icon in icons {
+impl FontBounds {
+    /// Account for a glyph, returning its left side bearing
+    fn add(&mut self, advance: u16, bbox: Option<Bbox>) -> i16 {
+        self.advance_width_max = self.advance_width_max.max(advance);
+        if advance > 0 {
+            self.advance_sum += advance as u64;
+            self.advance_count += 1;
+        }
+        let Some(glyph) = bbox else {
+            return 0;
+        };
+
+        let right_side_bearing = (advance as i32 - glyph.x_max as i32) as i16;
+        match &mut self.bbox {
+            Some(font) => {
+                font.x_min = font.x_min.min(glyph.x_min);
+                font.y_min = font.y_min.min(glyph.y_min);
+                font.x_max = font.x_max.max(glyph.x_max);
+                font.y_max = font.y_max.max(glyph.y_max);
+                self.min_left_side_bearing = self.min_left_side_bearing.min(glyph.x_min);
+                self.min_right_side_bearing = self.min_right_side_bearing.min(right_side_bearing);
+                self.x_max_extent = self.x_max_extent.max(glyph.x_max);
+            }
+            None => {
+                self.bbox = Some(glyph);
+                self.min_left_side_bearing = glyph.x_min;
+                self.min_right_side_bearing = right_side_bearing;
+                self.x_max_extent = glyph.x_max;
+            }
+        }
+        glyph.x_min
+    }
+}
+
+impl FontAssembler {
+    /// Create an assembler holding only the .notdef glyph
+    pub fn new(options: FontOptions) -> Result<Self> {
+        let mut glyf_builder = GlyfLocaBuilder::new();
+        options.validate()?;
+
+        // Add .notdef glyph (required, empty unless one is given)
+        let notdef = match &options.notdef {
+            Some(icon) => icon_to_glyph(icon, &options, None)?,
+            None => empty_glyph(),
+        };
+        glyf_builder.add_glyph(&notdef)?;
+        let units_per_em = options.units_per_em();
+        let mut bounds = FontBounds::default();
+        let bbox = (!notdef.contours.is_empty()).then_some(notdef.bbox);
+        let side_bearing = bounds.add(units_per_em, bbox);
+
+        Ok(Self {
+            glyf_builder,
+            // Track metrics for hmtx
+            metrics: vec![LongMetric {
+                advance: units_per_em,
+                side_bearing,
+            }],
+            options,
+            glyph_ids: HashMap::new(),
+            color_layers: Vec::new(),
+            x_offsets: vec![0],
+            fill_variations: Vec::new(),
+            bounds,
+        })
+    }
+
+    /// Convert an icon's outline and add it as the next glyph
+    pub fn add_icon(
+        &mut self,
+        icon: &Icon,
+        cache: Option<&PathCache>,
+        verbose: bool,
+    ) -> Result<()> {
+        // Codepoints may still be reassigned, so only the name is shown
         if verbose {
-            println!("  Building glyph: {} (U+{:04X})", icon.name, icon.codepoint);
+            println!("  Building glyph: {}", icon.name);
         }
+        let glyph = icon_to_glyph(icon, &self.options, cache)?;
+        self.add_color_layers(icon)?;
+        self.add_glyph(&glyph, icon.overrides.advance)
+    }
 
-        // Convert SVG path to font glyph
-        let glyph = svg_path_to_glyph(&icon.path, icon.width, icon.height)?;
-        glyf_builder.add_glyph(&glyph)?;
+    /// Convert the shapes of a colored icon into layer glyphs, if color is
+    /// enabled. Call before adding the icon's own glyph.
+    pub fn add_color_layers(&mut self, icon: &Icon) -> Result<()> {
+        let is_colored = icon.fills.iter().any(|f| f.rgba != ShapeFill::BLACK);
+        if !self.options.color || !is_colored {
+            return Ok(());
+        }
 
-        metrics.push(LongMetric {
-            advance: UNITS_PER_EM,
-            side_bearing: 0,
+        let elements = icon.path.elements();
+        let layers = icon
+            .fills
+            .iter()
+            .map(|fill| {
+                let shape = BezPath::from_vec(elements[fill.elements.clone()].to_vec());
+                let path = svg_path_to_glyph_path(&shape, icon, &self.options)?;
+                Ok((path_to_glyph(&path)?, fill.rgba))
+            })
+            .collect::<Result<Vec<_>>>()
+            .with_context(|| format!("Failed to build color layers for {}", icon.filename))?;
+        self.color_layers.push((self.metrics.len() as u16, layers));
+        Ok(())
+    }
+
+    /// Add an already converted glyph, with `advance` overriding its advance width
+    pub fn add_glyph(&mut self, glyph: &SimpleGlyph, advance: Option<u16>) -> Result<()> {
+        let x_range = (!glyph.contours.is_empty()).then_some((glyph.bbox.x_min, glyph.bbox.x_max));
+        match glyph_advance(&self.options, x_range, advance) {
+            (0, advance) => self.push_glyph(glyph, advance, 0),
+            (x_offset, advance) => {
+                self.push_glyph(&shift_glyph(glyph, x_offset), advance, x_offset)
+            }
+        }
+    }
+
+    /// Add a glyph that morphs from `outlined` to `filled` along the fill axis
+    pub fn add_fill_glyph(
+        &mut self,
+        outlined: &SimpleGlyph,
+        filled: &SimpleGlyph,
+        advance: Option<u16>,
+    ) -> Result<()> {
+        let (default, filled) = variable::fill_masters(outlined, filled);
+        let x_range =
+            (!default.contours.is_empty()).then_some((default.bbox.x_min, default.bbox.x_max));
+        let (x_offset, advance) = glyph_advance(&self.options, x_range, advance);
+        let (default, filled) = (
+            shift_glyph(&default, x_offset),
+            shift_glyph(&filled, x_offset),
+        );
+        if let Some(deltas) = variable::fill_deltas(&default, &filled) {
+            self.fill_variations
+                .push((self.metrics.len() as u16, deltas));
+        }
+        self.push_glyph(&default, advance, x_offset)
+    }
+
+    /// Add a glyph already placed in its advance
+    fn push_glyph(&mut self, glyph: &SimpleGlyph, advance: u16, x_offset: i16) -> Result<()> {
+        // Glyphs with the same default outline may still vary differently
+        let dedup =
+            self.options.optimize_size && !self.options.fill_axis && !glyph.contours.is_empty();
+        let component = if dedup {
+            self.reused_outline(glyph)?
+        } else {
+            None
+        };
+        match component {
+            Some(component) => {
+                self.glyf_builder
+                    .add_glyph(&CompositeGlyph::new(component, glyph.bbox))?;
+            }
+            None => {
+                self.glyf_builder.add_glyph(glyph)?;
+                if let Some((shape, origin)) =
+                    dedup.then(|| placed_outline(glyph, IDENTITY)).flatten()
+                {
+                    let gid = self.metrics.len() as u16;
+                    self.glyph_ids.insert(outline_hash(&shape)?, (gid, origin));
+                }
+            }
+        }
+
+        let bbox = (!glyph.contours.is_empty()).then_some(glyph.bbox);
+        self.metrics.push(LongMetric {
+            advance,
+            side_bearing: self.bounds.add(advance, bbox),
         });
+        self.x_offsets.push(x_offset);
+        Ok(())
     }
 
-    let (glyf, loca, loca_format) = glyf_builder.build();
+    /// A component drawing `glyph` from an outline already added, moved,
+    /// mirrored or turned, so duplicates and derived variants are stored once
+    fn reused_outline(&self, glyph: &SimpleGlyph) -> Result<Option<Component>> {
+        for m in COMPONENT_TRANSFORMS {
+            // The outline that `m` turns into this glyph, as the inverse of
+            // these maps is their transpose
+            let Some((shape, origin)) = placed_outline(glyph, [m[0], m[2], m[1], m[3]]) else {
+                continue;
+            };
+            let Some(&(original, (x, y))) = self.glyph_ids.get(&outline_hash(&shape)?) else {
+                continue;
+            };
+            let (dx, dy) = (
+                i32::from(origin.0) - i32::from(x),
+                i32::from(origin.1) - i32::from(y),
+            );
+            let [xx, yx, xy, yy] = m.map(i32::from);
+            let (Ok(x), Ok(y)) = (
+                i16::try_from(xx * dx + xy * dy),
+                i16::try_from(yx * dx + yy * dy),
+            ) else {
+                continue;
+            };
+            let [xx, yx, xy, yy] = m.map(|v| F2Dot14::from_f32(v as f32));
+            return Ok(Some(Component::new(
+                GlyphId16::new(original),
+                Anchor::Offset { x, y },
+                Transform { xx, yx, xy, yy },
+                // The offset places the transformed outline, it is not transformed itself
+                ComponentFlags {
+                    unscaled_component_offset: true,
+                    ..Default::default()
+                },
+            )));
+        }
+        Ok(None)
+    }
 
-    // Build cmap table (character to glyph mapping)
-    let cmap = build_cmap(icons)?;
+    /// Build the remaining tables and return the font file contents.
+    ///
+    /// `icons` are the icons of the added glyphs, in the same order.
+    pub fn finish(mut self, font_name: &str, icons: &[Icon]) -> Result<Vec<u8>> {
+        if icons.len() + 1 != self.metrics.len() {
+            anyhow::bail!(
+                "{} icons given for {} glyphs",
+                icons.len(),
+                self.metrics.len() - 1
+            );
+        }
+        let color_tables = self.add_pending_layers()?;
+        let ligatures = if self.options.ligatures {
+            Some(self.add_name_ligatures(icons)?)
+        } else {
+            None
+        };
+        let mut extra = ligatures.as_ref().map_or(Vec::new(), |l| l.letters.clone());
+        if let Some(advance) = space_advance(icons, &self.options) {
+            extra.push((SPACE, self.metrics.len() as u16));
+            self.push_glyph(&empty_glyph(), advance, 0)?;
+        }
+        let num_glyphs = self.metrics.len() as u16;
+        let (glyf, loca, loca_format) = self.glyf_builder.build();
 
-    // Build head table
-    let mut head = build_head();
-    head.index_to_loc_format = loca_format as i16;
+        // Build cmap and OS/2 tables from the icons' codepoints
+        let (cmap, mut os2) = build_cmap_and_os2(icons, &extra, &self.options)?;
+        if let Some(ligatures) = &ligatures {
+            os2.us_max_context = Some(ligatures.max_context);
+        }
+        fit_os2_metrics(&mut os2, &self.bounds);
 
-    // Build hhea table
-    let hhea = build_hhea(icons.len() as u16 + 1);
+        // Build head table
+        let mut head = build_head(&self.bounds, &self.options);
+        head.index_to_loc_format = loca_format as i16;
 
-    // Build hmtx table
-    let hmtx = Hmtx::new(metrics, vec![]);
+        // Build hmtx table
+        let hmtx = if self.options.optimize_size {
+            compact_hmtx(self.metrics)
+        } else {
+            Hmtx::new(self.metrics, vec![])
+        };
 
-    // Build maxp table
-    let maxp = Maxp {
-        num_glyphs: icons.len() as u16 + 1, // +1 for .notdef
-        ..Default::default()
-    };
+        // Build hhea table
+        let hhea = build_hhea(hmtx.h_metrics.len() as u16, &self.bounds, &self.options);
+
+        // Build maxp table
+        let maxp = Maxp {
+            num_glyphs, // includes .notdef
+            ..Default::default()
+        };
+
+        // Build name table
+        let mut name = build_name(font_name, &self.options);
+        let variation_tables = if self.options.fill_axis {
+            Some(variable::fill_axis_tables(
+                num_glyphs,
+                &self.fill_variations,
+                &mut name,
+            )?)
+        } else {
+            None
+        };
+
+        // Build post table
+        let post = build_post(self.options.optimize_size, &self.options);
+
+        // Assemble the font
+        let mut builder = FontBuilder::new();
+        builder
+            .add_table(&head)?
+            .add_table(&hhea)?
+            .add_table(&maxp)?
+            .add_table(&os2)?
+            .add_table(&hmtx)?
+            .add_table(&cmap)?
+            .add_table(&name)?
+            .add_table(&post)?
+            .add_table(&loca)?
+            .add_table(&glyf)?;
+        if let Some((colr, cpal)) = &color_tables {
+            builder.add_table(colr)?.add_table(cpal)?;
+        }
+        if let Some(ligatures) = &ligatures {
+            builder.add_table(&ligatures.gsub)?;
+        }
+        if let Some((fvar, gvar, stat)) = &variation_tables {
+            builder.add_table(fvar)?.add_table(gvar)?.add_table(stat)?;
+        }
 
-    // Build name table
-    let name = build_name(font_name);
+        build_checked(&mut builder)
+    }
+
+    /// Add a blank glyph for every character used in the icon names and a
+    /// `liga` lookup spelling each name with them.
+    ///
+    /// Single-character names are skipped so they do not replace every
+    /// occurrence of that letter.
+    fn add_name_ligatures(&mut self, icons: &[Icon]) -> Result<NameLigatures> {
+        let names: Vec<(&str, u16)> = icons
+            .iter()
+            .zip(1..)
+            .flat_map(|(icon, gid)| {
+                let aliases = icon.aliases.iter().map(|alias| alias.name.as_str());
+                std::iter::once(icon.name.as_str())
+                    .chain(aliases)
+                    .map(move |name| (name, gid))
+            })
+            .filter(|(name, _)| name.is_ascii() && name.len() > 1)
+            .collect();
+
+        let chars: BTreeSet<char> = names.iter().flat_map(|(name, _)| name.chars()).collect();
+        let mut letters = Vec::new();
+        for c in chars {
+            letters.push((c as u32, self.metrics.len() as u16));
+            self.add_glyph(&empty_glyph(), None)?;
+        }
+        let letter_gid = |c: char| {
+            let index = letters.binary_search_by_key(&(c as u32), |(c, _)| *c);
+            GlyphId16::new(letters[index.expect("every name character has a glyph")].1)
+        };
+
+        let ligatures = names.iter().map(|(name, gid)| {
+            let glyphs: Vec<GlyphId16> = name.chars().map(letter_gid).collect();
+            (glyphs, GlyphId16::new(*gid))
+        });
+        let max_context = names.iter().map(|(name, _)| name.len()).max().unwrap_or(0);
+        Ok(NameLigatures {
+            gsub: build_liga_gsub(ligatures),
+            letters,
+            max_context: max_context as u16,
+        })
+    }
+
+    /// Add the color layer glyphs after the icon glyphs and build COLR and
+    /// CPAL for them. Black shapes use the text color instead of a palette entry.
+    fn add_pending_layers(&mut self) -> Result<Option<(Colr, Cpal)>> {
+        if self.color_layers.is_empty() {
+            return Ok(None);
+        }
+
+        let mut palette: Vec<[u8; 4]> = Vec::new();
+        let mut base_glyphs = Vec::new();
+        let mut layers = Vec::new();
+        for (base, shapes) in std::mem::take(&mut self.color_layers) {
+            base_glyphs.push(BaseGlyph::new(
+                GlyphId16::new(base),
+                layers.len() as u16,
+                shapes.len() as u16,
+            ));
+            for (glyph, rgba) in shapes {
+                let palette_index = if rgba == ShapeFill::BLACK {
+                    FOREGROUND_PALETTE_INDEX
+                } else {
+                    match palette.iter().position(|c| *c == rgba) {
+                        Some(index) => index as u16,
+                        None => {
+                            palette.push(rgba);
+                            palette.len() as u16 - 1
+                        }
+                    }
+                };
+                layers.push(Layer::new(
+                    GlyphId16::new(self.metrics.len() as u16),
+                    palette_index,
+                ));
+                // Layers are placed like the icon they belong to
+                let x_offset = self.x_offsets[base as usize];
+                let advance = self.metrics[base as usize].advance;
+                self.push_glyph(&shift_glyph(&glyph, x_offset), advance, x_offset)?;
+            }
+        }
 
-    // Build OS/2 table
-    let os2 = build_os2(icons);
+        let colr = Colr::new(
+            base_glyphs.len() as u16,
+            Some(base_glyphs),
+            Some(layers.clone()),
+            layers.len() as u16,
+        );
+        // CPAL needs at least one entry even if every layer uses the text color
+        if palette.is_empty() {
+            palette.push(ShapeFill::BLACK);
+        }
+        let colors: Vec<ColorRecord> = palette
+            .iter()
+            .map(|[r, g, b, a]| ColorRecord::new(*b, *g, *r, *a))
+            .collect();
+        let cpal = Cpal::new(
+            colors.len() as u16,
+            1,
+            colors.len() as u16,
+            Some(colors),
+            vec![0],
+        );
+        Ok(Some((colr, cpal)))
+    }
+}
+
+/// Build an OpenType font with CFF outlines, returning the font file contents.
+///
+/// Unlike the TrueType build, the icons' cubic curves are kept as they are
+/// instead of being approximated with quadratics.
+pub fn build_otf_bytes(icons: &[Icon], font_name: &str, options: &FontOptions) -> Result<Vec<u8>> {
+    options.validate()?;
+    let units_per_em = options.units_per_em();
+    let mut outlines = icons
+        .par_iter()
+        .map(|icon| {
+            let transform = glyph_transform(icon, options)
+                .with_context(|| format!("Failed to build glyph for {}", icon.filename))?;
+            Ok(transform_path(&icon.path, transform))
+        })
+        .collect::<Result<Vec<BezPath>>>()?;
+
+    let notdef = match &options.notdef {
+        Some(icon) => transform_path(&icon.path, glyph_transform(icon, options)?),
+        None => BezPath::new(),
+    };
+    let mut bounds = FontBounds::default();
+    let mut metrics = vec![LongMetric {
+        advance: units_per_em,
+        side_bearing: bounds.add(units_per_em, cff_bbox(&notdef)),
+    }];
+    let mut advances = Vec::with_capacity(outlines.len());
+    for (outline, icon) in outlines.iter_mut().zip(icons) {
+        let mut bbox = cff_bbox(outline);
+        let x_range = bbox.map(|bbox| (bbox.x_min, bbox.x_max));
+        let (x_offset, advance) = glyph_advance(options, x_range, icon.overrides.advance);
+        if let (Some(bbox), true) = (bbox.as_mut(), x_offset != 0) {
+            outline.apply_affine(Affine::translate((x_offset as f64, 0.0)));
+            bbox.x_min += x_offset;
+            bbox.x_max += x_offset;
+        }
+        advances.push(advance);
+        metrics.push(LongMetric {
+            advance,
+            side_bearing: bounds.add(advance, bbox),
+        });
+    }
+    let mut names: Vec<&str> = icons.iter().map(|icon| icon.name.as_str()).collect();
+    let mut extra = Vec::new();
+    if let Some(advance) = space_advance(icons, options) {
+        extra.push((SPACE, names.len() as u16 + 1));
+        names.push("space");
+        outlines.push(BezPath::new());
+        advances.push(advance);
+        metrics.push(LongMetric {
+            advance,
+            side_bearing: bounds.add(advance, None),
+        });
+    }
+    let num_glyphs = names.len() as u16 + 1;
+    let cff = cff::build_cff(
+        font_name,
+        &names,
+        &notdef,
+        &outlines,
+        &advances,
+        units_per_em,
+    );
 
-    // Build post table
-    let post = build_post();
+    let (cmap, mut os2) = build_cmap_and_os2(icons, &extra, options)?;
+    fit_os2_metrics(&mut os2, &bounds);
+    let hmtx = if options.optimize_size {
+        compact_hmtx(metrics)
+    } else {
+        Hmtx::new(metrics, vec![])
+    };
+    let mut head = build_head(&bounds, options);
+    head.index_to_loc_format = 0;
 
-    // Assemble the font
-    let font_data = FontBuilder::new()
+    let mut font_data = FontBuilder::new()
         .add_table(&head)?
-        .add_table(&hhea)?
-        .add_table(&maxp)?
+        .add_table(&build_hhea(hmtx.h_metrics.len() as u16, &bounds, options))?
+        .add_table(&Maxp {
+            num_glyphs,
+            ..Default::default()
+        })?
         .add_table(&os2)?
         .add_table(&hmtx)?
         .add_table(&cmap)?
-        .add_table(&name)?
-        .add_table(&post)?
-        .add_table(&loca)?
-        .add_table(&glyf)?
+        .add_table(&build_name(font_name, options))?
+        // Glyph names live in the CFF charset
+        .add_table(&build_post(true, options))?
+        .add_raw(Tag::new(b"CFF "), cff)
         .build();
+    set_cff_sfnt_version(&mut font_data)?;
+    check_checksums(&font_data)?;
 
-    std::fs::write(output_path, font_data)
-        .with_context(|| format!("Failed to write {}", output_path.display()))?;
+    Ok(font_data)
+}
+
+/// Bounds of a CFF outline, rounded outwards as the CFF FontBBox is
+fn cff_bbox(outline: &BezPath) -> Option<Bbox> {
+    (!outline.elements().is_empty()).then(|| {
+        let rect = outline.bounding_box();
+        Bbox {
+            x_min: rect.x0.floor() as i16,
+            y_min: rect.y0.floor() as i16,
+            x_max: rect.x1.ceil() as i16,
+            y_max: rect.y1.ceil() as i16,
+        }
+    })
+}
+
+/// Mark a font built by `FontBuilder` as having CFF outlines.
+///
+/// The sfnt version is covered by head.checkSumAdjustment, which is
+/// adjusted by the same amount.
+fn set_cff_sfnt_version(font_data: &mut [u8]) -> Result<()> {
+    let font =
+        FontRef::new(font_data).map_err(|e| anyhow::anyhow!("Font does not parse: {}", e))?;
+    let head = font
+        .table_directory
+        .table_records()
+        .iter()
+        .find(|record| record.tag() == Tag::new(b"head"))
+        .context("The font has no head table")?
+        .offset() as usize;
+    let old_version = font.table_directory.sfnt_version();
+
+    let adjustment = head + 8;
+    let checksum = u32::from_be_bytes(font_data[adjustment..adjustment + 4].try_into()?);
+    let checksum = checksum.wrapping_sub(CFF_SFNT_VERSION.wrapping_sub(old_version));
+    font_data[adjustment..adjustment + 4].copy_from_slice(&checksum.to_be_bytes());
+    font_data[..4].copy_from_slice(&CFF_SFNT_VERSION.to_be_bytes());
+    Ok(())
+}
+
+/// Build the cmap and OS/2 tables for the icons' codepoints and any extra
+/// (codepoint, glyph id) mappings
+fn build_cmap_and_os2(
+    icons: &[Icon],
+    extra: &[(u32, u16)],
+    options: &FontOptions,
+) -> Result<(Cmap, Os2)> {
+    let codepoints: Vec<u32> = icons.iter().map(|icon| icon.codepoint).collect();
+    let symbol_codepoints: Vec<(u32, u16)> = (1..)
+        .zip(icons)
+        .filter_map(|(gid, icon)| Some((icon.symbol_codepoint?, gid)))
+        .collect();
+    // Aliases map their codepoints to the glyph of the icon they stand for
+    let extra: Vec<(u32, u16)> = (1..)
+        .zip(icons)
+        .flat_map(|(gid, icon)| {
+            icon.aliases
+                .iter()
+                .filter_map(move |alias| Some((alias.codepoint?, gid)))
+        })
+        .chain(extra.iter().copied())
+        .collect();
+
+    let cmap = build_cmap(
+        &codepoints,
+        &extra,
+        &symbol_codepoints,
+        options.windows_symbol(),
+    )?;
+    let mut os2 = build_os2(options);
+    let mapped: Vec<u32> = codepoints
+        .iter()
+        .chain(extra.iter().map(|(c, _)| c))
+        .chain(symbol_codepoints.iter().map(|(c, _)| c))
+        .copied()
+        .collect();
+    unicode_ranges::set_ranges(&mut os2, &mapped);
+    if options.windows_symbol() {
+        make_symbol_os2(&mut os2, &symbol_codepoints);
+    }
+    Ok((cmap, os2))
+}
+
+/// Store metrics once for the trailing run of glyphs sharing the same advance.
+///
+/// Glyphs after the last long metric reuse its advance and only store a side bearing.
+fn compact_hmtx(mut metrics: Vec<LongMetric>) -> Hmtx {
+    let last_advance = metrics.last().map(|m| m.advance);
+    let mut num_long = metrics.len();
+    while num_long > 1 && Some(metrics[num_long - 2].advance) == last_advance {
+        num_long -= 1;
+    }
 
+    let side_bearings = metrics
+        .split_off(num_long)
+        .into_iter()
+        .map(|m| m.side_bearing)
+        .collect();
+    Hmtx::new(metrics, side_bearings)
+}
+
+/// Serialize a font, making sure its checksums came out right
+pub fn build_checked(builder: &mut FontBuilder) -> Result<Vec<u8>> {
+    let font_data = builder.build();
+    check_checksums(&font_data)?;
+    Ok(font_data)
+}
+
+/// Check every table checksum and head.checkSumAdjustment, which strict
+/// validators and some platforms reject fonts over
+pub fn check_checksums(font_data: &[u8]) -> Result<()> {
+    let font =
+        FontRef::new(font_data).map_err(|e| anyhow::anyhow!("Font does not parse: {}", e))?;
+    for record in font.table_directory.table_records() {
+        let start = record.offset() as usize;
+        let table = font_data
+            .get(start..start + record.length() as usize)
+            .with_context(|| format!("The {} table is truncated", record.tag()))?;
+        let mut checksum = checksum(table);
+        if record.tag() == Tag::new(b"head") {
+            // The head checksum is computed with checkSumAdjustment zeroed
+            let adjustment = table.get(8..12).context("The head table is truncated")?;
+            checksum = checksum.wrapping_sub(checksum_of_word(adjustment));
+        }
+        if checksum != record.checksum() {
+            anyhow::bail!(
+                "The {} table checksum is {:08X}, but its record says {:08X}",
+                record.tag(),
+                checksum,
+                record.checksum()
+            );
+        }
+    }
+    // With the adjustment in place, the whole font sums to a magic number
+    if checksum(font_data) != 0xB1B0_AFBA {
+        anyhow::bail!("head.checkSumAdjustment does not match the font");
+    }
     Ok(())
 }
 
+/// Sum of the big-endian 32-bit words of `data`, zero padded
+fn checksum(data: &[u8]) -> u32 {
+    data.chunks(4)
+        .map(checksum_of_word)
+        .fold(0, u32::wrapping_add)
+}
+
+fn checksum_of_word(word: &[u8]) -> u32 {
+    let mut padded = [0; 4];
+    padded[..word.len()].copy_from_slice(word);
+    u32::from_be_bytes(padded)
+}
+
+/// Size in bytes of every table in a font, in table directory order
+pub fn table_sizes(font_data: &[u8]) -> Result<Vec<(String, u32)>> {
+    let font =
+        FontRef::new(font_data).map_err(|e| anyhow::anyhow!("Font does not parse: {}", e))?;
+    Ok(font
+        .table_directory
+        .table_records()
+        .iter()
+        .map(|record| (record.tag().to_string(), record.length()))
+        .collect())
+}
+
+/// Convert an icon's outline into a glyph, reusing a cached outline if present
+fn icon_to_glyph(
+    icon: &Icon,
+    options: &FontOptions,
+    cache: Option<&PathCache>,
+) -> Result<SimpleGlyph> {
+    let settings = options.glyph_settings() + &icon.overrides.outline_settings();
+    let key = cache::glyph_key(&icon.source_hash, &settings);
+
+    let glyph_path = match cache.and_then(|c| c.load(&key)) {
+        Some(cached) => cached.path,
+        None => {
+            let glyph_path = svg_path_to_glyph_path(&icon.path, icon, options)
+                .with_context(|| format!("Failed to build glyph for {}", icon.filename))?;
+            if let Some(cache) = cache {
+                cache.store(&key, icon.width, icon.height, &[], &glyph_path)?;
+            }
+            glyph_path
+        }
+    };
+
+    path_to_glyph(&glyph_path)
+        .with_context(|| format!("Failed to build glyph for {}", icon.filename))
+}
+
+/// Horizontal shift and advance width of a glyph whose outline spans
+/// `x_range`: one em, or fitted to the outline with `--proportional`.
+///
+/// An explicit `advance` keeps the glyph centered where its default advance
+/// put it.
+fn glyph_advance(
+    options: &FontOptions,
+    x_range: Option<(i16, i16)>,
+    advance: Option<u16>,
+) -> (i16, u16) {
+    let (x_offset, default_advance) = match (options.proportional, x_range) {
+        (Some(side_bearing), Some((x_min, x_max))) => {
+            proportional_metrics(x_min, x_max, side_bearing)
+        }
+        _ => (0, options.units_per_em()),
+    };
+    match advance {
+        Some(advance) => {
+            let shift = (advance as i32 - default_advance as i32) / 2;
+            let x_offset = (x_offset as i32 + shift).clamp(i16::MIN as i32, i16::MAX as i32);
+            (x_offset as i16, advance)
+        }
+        None => (x_offset, default_advance),
+    }
+}
+
+/// Horizontal shift and advance that put `side_bearing` units on either side
+/// of a glyph spanning `x_min..x_max`
+fn proportional_metrics(x_min: i16, x_max: i16, side_bearing: u16) -> (i16, u16) {
+    let x_offset = side_bearing as i32 - x_min as i32;
+    let advance = x_max as i32 - x_min as i32 + 2 * side_bearing as i32;
+    (
+        x_offset.clamp(i16::MIN as i32, i16::MAX as i32) as i16,
+        advance.clamp(0, u16::MAX as i32) as u16,
+    )
+}
+
+/// Move a glyph horizontally by `x_offset` font units
+fn shift_glyph(glyph: &SimpleGlyph, x_offset: i16) -> SimpleGlyph {
+    if x_offset == 0 {
+        return glyph.clone();
+    }
+    let shift = |x: i16| x.saturating_add(x_offset);
+    SimpleGlyph {
+        bbox: Bbox {
+            x_min: shift(glyph.bbox.x_min),
+            x_max: shift(glyph.bbox.x_max),
+            ..glyph.bbox
+        },
+        contours: glyph
+            .contours
+            .iter()
+            .map(|contour| {
+                contour
+                    .iter()
+                    .map(|point| CurvePoint {
+                        x: shift(point.x),
+                        ..*point
+                    })
+                    .collect::<Vec<_>>()
+                    .into()
+            })
+            .collect(),
+        instructions: glyph.instructions.clone(),
+    }
+}
+
+/// Linear map as `[xx, yx, xy, yy]`, leaving outlines unchanged
+const IDENTITY: [i16; 4] = [1, 0, 0, 1];
+
+/// Maps a glyph can reuse another glyph's outline through: none, the mirror
+/// images and quarter turns that derived and right-to-left variants apply
+const COMPONENT_TRANSFORMS: [[i16; 4]; 6] = [
+    IDENTITY,
+    [-1, 0, 0, 1],
+    [1, 0, 0, -1],
+    [-1, 0, 0, -1],
+    [0, 1, -1, 0],
+    [0, -1, 1, 0],
+];
+
+/// `glyph` transformed by the linear map `m` and moved so its bounding box
+/// starts at the origin, with the corner it was moved from. `None` when the
+/// coordinates overflow.
+fn placed_outline(glyph: &SimpleGlyph, m: [i16; 4]) -> Option<(SimpleGlyph, (i16, i16))> {
+    let [xx, yx, xy, yy] = m.map(i32::from);
+    let apply = |x: i16, y: i16| {
+        let (x, y) = (i32::from(x), i32::from(y));
+        (xx * x + xy * y, yx * x + yy * y)
+    };
+    let bbox = &glyph.bbox;
+    let (x0, y0) = apply(bbox.x_min, bbox.y_min);
+    let (x1, y1) = apply(bbox.x_max, bbox.y_max);
+    let (x_min, y_min) = (x0.min(x1), y0.min(y1));
+    let place = |x: i16, y: i16| {
+        let (x, y) = apply(x, y);
+        Some((
+            i16::try_from(x - x_min).ok()?,
+            i16::try_from(y - y_min).ok()?,
+        ))
+    };
+    let contours = glyph
+        .contours
+        .iter()
+        .map(|contour| {
+            contour
+                .iter()
+                .map(|point| {
+                    let (x, y) = place(point.x, point.y)?;
+                    Some(CurvePoint { x, y, ..*point })
+                })
+                .collect::<Option<Vec<_>>>()
+                .map(Into::into)
+        })
+        .collect::<Option<_>>()?;
+    let shape = SimpleGlyph {
+        bbox: Bbox {
+            x_min: 0,
+            y_min: 0,
+            x_max: i16::try_from((x0 - x1).abs()).ok()?,
+            y_max: i16::try_from((y0 - y1).abs()).ok()?,
+        },
+        contours,
+        instructions: glyph.instructions.clone(),
+    };
+    Some((
+        shape,
+        (i16::try_from(x_min).ok()?, i16::try_from(y_min).ok()?),
+    ))
+}
+
+/// Hash of a glyph's serialized glyf data
+fn outline_hash(glyph: &SimpleGlyph) -> Result<String> {
+    Ok(cache::content_hash(&write_fonts::dump_table(glyph)?))
+}
+
 /// Create an empty .notdef glyph
 fn empty_glyph() -> SimpleGlyph {
     SimpleGlyph::default()
 }
 
-/// Convert an SVG BezPath to a font SimpleGlyph
-fn svg_path_to_glyph(path: &BezPath, svg_width: f64, svg_height: f64) -> Result<SimpleGlyph> {
-    // Calculate scale to fit in UNITS_PER_EM
-    let scale = UNITS_PER_EM as f64 / svg_width.max(svg_height);
+/// Advance of the blank glyph added for the space character, one em like
+/// the icons, or `None` when an icon is mapped to the space itself.
+///
+/// Some text stacks refuse to use a font without a space glyph.
+fn space_advance(icons: &[Icon], options: &FontOptions) -> Option<u16> {
+    (!icons.iter().any(|icon| icon.codepoint == SPACE)).then(|| options.units_per_em())
+}
+
+/// Scale an SVG BezPath of `icon` into font units and convert it to quadratic curves
+fn svg_path_to_glyph_path(path: &BezPath, icon: &Icon, options: &FontOptions) -> Result<BezPath> {
+    let transform = glyph_transform(icon, options)?;
+
+    // Convert cubic beziers to quadratic (TTF only supports quadratic)
+    Ok(cubic_to_quadratic(
+        path,
+        transform,
+        options.curve_tolerance(),
+    ))
+}
+
+/// The part of an icon's user space that is fitted into the glyph: its
+/// viewBox, or with `trim` a square around its outline, plus the padding
+fn icon_frame(icon: &Icon, options: &FontOptions) -> Rect {
+    let mut frame = Rect::new(0.0, 0.0, icon.width, icon.height);
+    if options.trim && !icon.path.elements().is_empty() {
+        // Square, so trimmed icons stay centered in the em
+        let bounds = icon.path.bounding_box();
+        let side = bounds.width().max(bounds.height());
+        if side > 0.0 {
+            frame = Rect::from_center_size(bounds.center(), (side, side));
+        }
+    }
+    let margin = frame.width().max(frame.height()) * options.padding / 100.0;
+    frame.inflate(margin, margin)
+}
+
+/// Transform from an icon's SVG user units to font units
+pub(crate) fn glyph_transform(icon: &Icon, options: &FontOptions) -> Result<Affine> {
+    let frame = icon_frame(icon, options);
+    let (svg_width, svg_height) = (frame.width(), frame.height());
+
+    let (bottom, top) = span(options);
+    // Never wider than the em, so the icon stays within its advance
+    let scale = ((top - bottom) / svg_height).min(options.units_per_em() as f64 / svg_width);
+    if !scale.is_finite() || scale <= 0.0 {
+        anyhow::bail!("Invalid icon size {}x{}", svg_width, svg_height);
+    }
+
+    let bottom = match options.fit {
+        // Center in the span when the width limits the scale
+        VerticalFit::Em | VerticalFit::CapHeight => {
+            bottom + (top - bottom - svg_height * scale) / 2.0
+        }
+        VerticalFit::None => bottom,
+    } + options.baseline_offset as f64;
 
     // Transform: scale and flip Y axis (SVG is Y-down, fonts are Y-up)
-    // Also center vertically
-    let transform = Affine::new([
+    let mut transform = Affine::new([
         scale,
         0.0,
         0.0,
         -scale, // Flip Y
         0.0,
-        svg_height * scale, // Move origin
-    ]);
+        bottom + svg_height * scale, // Move origin
+    ]) * Affine::translate((-frame.x0, -frame.y0));
 
-    let transformed = transform * path.clone();
+    let middle = Point::new(
+        options.units_per_em() as f64 / 2.0,
+        (span(options).0 + top) / 2.0 + options.baseline_offset as f64,
+    );
+    if options.center_x != Centering::None || options.center_y != Centering::None {
+        // Move the chosen centers to the middle of the advance and of the span
+        let path = transform * icon.path.clone();
+        let dx = outline_center(&path, options.center_x).map_or(0.0, |c| middle.x - c.x);
+        let dy = outline_center(&path, options.center_y).map_or(0.0, |c| middle.y - c.y);
+        transform = Affine::translate((dx, dy)) * transform;
+    }
 
-    // Convert cubic beziers to quadratic (TTF only supports quadratic)
-    let quadratic_path = cubic_to_quadratic(&transformed);
+    if let Some(factor) = icon.overrides.scale {
+        transform = Affine::scale_about(factor, middle) * transform;
+    }
+    if let Some(offset) = icon.overrides.baseline_offset {
+        transform = Affine::translate((0.0, offset as f64)) * transform;
+    }
+    Ok(transform)
+}
+
+/// The vertical span in font units that the viewBox is fitted into
+fn span(options: &FontOptions) -> (f64, f64) {
+    match options.fit {
+        VerticalFit::Em => (options.descender() as f64, options.ascender() as f64),
+        VerticalFit::CapHeight => (0.0, options.cap_height() as f64),
+        VerticalFit::None => (0.0, options.units_per_em() as f64),
+    }
+}
+
+/// Center of an outline for the given centering mode, if it has one
+fn outline_center(path: &BezPath, centering: Centering) -> Option<Point> {
+    let bounds = path.bounding_box();
+    if path.elements().is_empty() || bounds.area() <= 0.0 {
+        return None;
+    }
+    match centering {
+        Centering::None => None,
+        Centering::Geometric => Some(bounds.center()),
+        // Shapes without area, such as bare lines, fall back to their bounds
+        Centering::Centroid => Some(centroid(path).unwrap_or_else(|| bounds.center())),
+    }
+}
+
+/// Area-weighted centroid of a filled path, with holes subtracted
+fn centroid(path: &BezPath) -> Option<Point> {
+    let (mut area, mut cx, mut cy) = (0.0, 0.0, 0.0);
+    let (mut start, mut last) = (Point::ZERO, Point::ZERO);
+    let mut add_edge = |a: Point, b: Point| {
+        let cross = a.x * b.y - b.x * a.y;
+        area += cross;
+        cx += (a.x + b.x) * cross;
+        cy += (a.y + b.y) * cross;
+    };
+    kurbo::flatten(path, 0.25, |el| match el {
+        PathEl::MoveTo(p) => {
+            // Close the previous subpath implicitly
+            add_edge(last, start);
+            (start, last) = (p, p);
+        }
+        PathEl::LineTo(p) => {
+            add_edge(last, p);
+            last = p;
+        }
+        PathEl::ClosePath => {
+            add_edge(last, start);
+            last = start;
+        }
+        _ => {}
+    });
+    add_edge(last, start);
+    // Contours are wound consistently, so holes have the opposite sign
+    if area.abs() < 1e-9 {
+        return None;
+    }
+    Some(Point::new(cx / (3.0 * area), cy / (3.0 * area)))
+}
+
+/// Transform a path into font units, keeping its curves and dropping empty subpaths
+fn transform_path(path: &BezPath, transform: Affine) -> BezPath {
+    let mut result = BezPath::with_capacity(path.elements().len());
+    let mut pending_move = None;
+    for el in path.elements() {
+        match el {
+            PathEl::MoveTo(p) => {
+                pending_move = Some(clamp_point(transform * *p));
+                continue;
+            }
+            PathEl::ClosePath if pending_move.is_some() => continue,
+            _ => {}
+        }
+        if let Some(p) = pending_move.take() {
+            result.move_to(p);
+        }
+        result.push(match *el {
+            PathEl::LineTo(p) => PathEl::LineTo(clamp_point(transform * p)),
+            PathEl::QuadTo(p1, p2) => {
+                PathEl::QuadTo(clamp_point(transform * p1), clamp_point(transform * p2))
+            }
+            PathEl::CurveTo(p1, p2, p3) => PathEl::CurveTo(
+                clamp_point(transform * p1),
+                clamp_point(transform * p2),
+                clamp_point(transform * p3),
+            ),
+            other => other,
+        });
+    }
+    result
+}
 
-    // Create glyph from path
+/// Create a glyph from a quadratic outline in font units
+fn path_to_glyph(quadratic_path: &BezPath) -> Result<SimpleGlyph> {
     if quadratic_path.elements().is_empty() {
         return Ok(SimpleGlyph::default());
     }
 
-    SimpleGlyph::from_bezpath(&quadratic_path)
+    SimpleGlyph::from_bezpath(quadratic_path)
         .map_err(|e| anyhow::anyhow!("Failed to create glyph: {:?}", e))
 }
 
 /// Convert cubic bezier curves to quadratic approximations
 /// TTF glyphs only support quadratic beziers
-fn cubic_to_quadratic(path: &BezPath) -> BezPath {
-    let mut result = BezPath::new();
+fn cubic_to_quadratic(path: &BezPath, transform: Affine, tolerance: f64) -> BezPath {
+    // Every element yields at least one output element, usually exactly one
+    let mut result = BezPath::with_capacity(path.elements().len());
     let mut current_point = Point::ZERO;
+    // MoveTo is deferred so that empty subpaths are dropped
+    let mut pending_move: Option<Point> = None;
+
+    // Transform points as they are read rather than copying the whole path first
+    let map = |p: Point| clamp_point(transform * p);
 
     for el in path.elements() {
         match el {
             PathEl::MoveTo(p) => {
-                result.move_to(*p);
-                current_point = *p;
+                let p = map(*p);
+                pending_move = Some(p);
+                current_point = p;
+                continue;
             }
+            PathEl::ClosePath if pending_move.is_some() => continue,
+            _ => {}
+        }
+
+        if let Some(p) = pending_move.take() {
+            result.move_to(p);
+        }
+
+        match el {
+            PathEl::MoveTo(_) => {}
             PathEl::LineTo(p) => {
-                result.line_to(*p);
-                current_point = *p;
+                let p = map(*p);
+                result.line_to(p);
+                current_point = p;
             }
             PathEl::QuadTo(p1, p2) => {
-                result.quad_to(*p1, *p2);
-                current_point = *p2;
+                let p2 = map(*p2);
+                result.quad_to(map(*p1), p2);
+                current_point = p2;
             }
             PathEl::CurveTo(p1, p2, p3) => {
                 // Approximate cubic with multiple quadratics
-                let cubic = CubicBez::new(current_point, *p1, *p2, *p3);
-                approximate_cubic_with_quadratics(&cubic, &mut result);
-                current_point = *p3;
+                let p3 = map(*p3);
+                let cubic = CubicBez::new(current_point, map(*p1), map(*p2), p3);
+                approximate_cubic_with_quadratics(&cubic, tolerance, &mut result);
+                current_point = p3;
             }
             PathEl::ClosePath => {
                 result.close_path();
@@ -176,136 +1523,360 @@ fn cubic_to_quadratic(path: &BezPath) -> BezPath {
     result
 }
 
-/// Approximate a cubic bezier with quadratic beziers
-/// Uses subdivision for better accuracy
-fn approximate_cubic_with_quadratics(cubic: &CubicBez, path: &mut BezPath) {
-    // Simple approximation: use the midpoint method
-    // For more accuracy, we could use adaptive subdivision
-
-    let tolerance = 1.0; // Error tolerance in font units
-
-    // Try to fit with a single quadratic first
-    let midpoint = cubic.eval(0.5);
-    let quad_control = Point::new(
-        (cubic.p1.x + cubic.p2.x) / 2.0,
-        (cubic.p1.y + cubic.p2.y) / 2.0,
-    );
+/// Keep a point within the coordinate range a glyph can store
+fn clamp_point(p: Point) -> Point {
+    let clamp = |v: f64| {
+        if v.is_finite() {
+            v.clamp(-MAX_COORD, MAX_COORD)
+        } else {
+            0.0
+        }
+    };
+    Point::new(clamp(p.x), clamp(p.y))
+}
 
-    let quad = QuadBez::new(cubic.p0, quad_control, cubic.p3);
-    let quad_mid = quad.eval(0.5);
+/// Parse a `--curve-tolerance` value
+pub fn parse_curve_tolerance(s: &str) -> Result<f64, String> {
+    let tolerance: f64 = s.parse().map_err(|_| format!("`{}` is not a number", s))?;
+    if !(tolerance >= MIN_CURVE_TOLERANCE && tolerance.is_finite()) {
+        return Err(format!(
+            "Curve tolerance must be at least {} font units",
+            MIN_CURVE_TOLERANCE
+        ));
+    }
+    Ok(tolerance)
+}
 
-    let error = (midpoint.x - quad_mid.x).abs() + (midpoint.y - quad_mid.y).abs();
+/// Style name of a weight class, `None` for Regular
+fn weight_name(weight: u16) -> Option<&'static str> {
+    match weight {
+        ..=149 => Some("Thin"),
+        150..=249 => Some("ExtraLight"),
+        250..=349 => Some("Light"),
+        350..=449 => None,
+        450..=549 => Some("Medium"),
+        550..=649 => Some("SemiBold"),
+        650..=749 => Some("Bold"),
+        750..=849 => Some("ExtraBold"),
+        _ => Some("Black"),
+    }
+}
 
-    if error < tolerance {
-        // Single quadratic is good enough
-        path.quad_to(quad_control, cubic.p3);
-    } else {
-        // Subdivide the cubic and approximate each half
-        let (left, right) = subdivide_cubic(cubic);
-        approximate_cubic_with_quadratics(&left, path);
-        approximate_cubic_with_quadratics(&right, path);
+/// Style name of a width class, `None` for Normal
+fn width_name(width: u16) -> Option<&'static str> {
+    match width {
+        ..=1 => Some("UltraCondensed"),
+        2 => Some("ExtraCondensed"),
+        3 => Some("Condensed"),
+        4 => Some("SemiCondensed"),
+        5 => None,
+        6 => Some("SemiExpanded"),
+        7 => Some("Expanded"),
+        8 => Some("ExtraExpanded"),
+        _ => Some("UltraExpanded"),
     }
 }
 
-/// Subdivide a cubic bezier at t=0.5
-fn subdivide_cubic(cubic: &CubicBez) -> (CubicBez, CubicBez) {
-    let p01 = midpoint(cubic.p0, cubic.p1);
-    let p12 = midpoint(cubic.p1, cubic.p2);
-    let p23 = midpoint(cubic.p2, cubic.p3);
-    let p012 = midpoint(p01, p12);
-    let p123 = midpoint(p12, p23);
-    let p0123 = midpoint(p012, p123);
+/// Parse a `--version` of the form `MAJOR.MINOR`
+pub fn parse_font_version(s: &str) -> Result<String, String> {
+    let error = || format!("`{}` is not a version like 1.0 or 2.13", s);
+    let (major, minor) = s.split_once('.').ok_or_else(error)?;
+    let is_number = |part: &str| !part.is_empty() && part.bytes().all(|b| b.is_ascii_digit());
+    if !is_number(major) || !is_number(minor) || major.parse::<u16>().map_or(true, |m| m > 32767) {
+        return Err(error());
+    }
+    Ok(s.to_string())
+}
 
-    let left = CubicBez::new(cubic.p0, p01, p012, p0123);
-    let right = CubicBez::new(p0123, p123, p23, cubic.p3);
+/// Parse a `--vendor-id` of up to four printable ASCII characters
+pub fn parse_vendor_id(s: &str) -> Result<Tag, String> {
+    let error = || {
+        format!(
+            "`{}` is not a vendor ID of one to four letters or digits",
+            s
+        )
+    };
+    if s.is_empty() || s.len() > 4 || !s.bytes().all(|b| b.is_ascii_graphic()) {
+        return Err(error());
+    }
+    Tag::new_checked(format!("{:<4}", s).as_bytes()).map_err(|_| error())
+}
 
-    (left, right)
+/// Parse a `--padding` percentage
+pub fn parse_padding(s: &str) -> Result<f64, String> {
+    let padding: f64 = s.parse().map_err(|_| format!("`{}` is not a number", s))?;
+    if !(0.0..=100.0).contains(&padding) {
+        return Err("Padding must be between 0 and 100 percent".to_string());
+    }
+    Ok(padding)
 }
 
-fn midpoint(a: Point, b: Point) -> Point {
-    Point::new((a.x + b.x) / 2.0, (a.y + b.y) / 2.0)
+/// Approximate a cubic bezier with quadratic beziers.
+///
+/// The cubic is split into the fewest equal pieces whose quadratic spline
+/// stays within `tolerance` of it. Neighbouring quadratics share their
+/// on-curve point halfway between their control points, so it is implied in
+/// the glyph and costs nothing to store.
+fn approximate_cubic_with_quadratics(cubic: &CubicBez, tolerance: f64, path: &mut BezPath) {
+    let tolerance = tolerance.max(MIN_CURVE_TOLERANCE);
+    match cubic.approx_spline(tolerance) {
+        Some(spline) => {
+            for quad in spline.to_quads() {
+                path.quad_to(quad.p1, quad.p2);
+            }
+        }
+        // Curves needing more pieces than a spline is tried with
+        None => {
+            for (_, _, quad) in cubic.to_quads(tolerance) {
+                path.quad_to(quad.p1, quad.p2);
+            }
+        }
+    }
 }
 
-/// Build the cmap table (character to glyph mapping)
-fn build_cmap(icons: &[Icon]) -> Result<Cmap> {
+/// Build the cmap table (character to glyph mapping).
+///
+/// `codepoints` map to glyphs 1.. in order, `extra` mappings are added as
+/// given. `symbol` mappings go into an extra Windows Symbol (3, 0) subtable, which
+/// becomes the only subtable when `symbol_only` is set.
+fn build_cmap(
+    codepoints: &[u32],
+    extra: &[(u32, u16)],
+    symbol: &[(u32, u16)],
+    symbol_only: bool,
+) -> Result<Cmap> {
     // Build mappings from codepoint to glyph ID
-    let mappings: Vec<(char, GlyphId)> = icons
+    let mut mappings: Vec<(char, GlyphId)> = codepoints
         .iter()
         .enumerate()
-        .filter_map(|(i, icon)| {
+        .filter_map(|(i, codepoint)| {
             // Convert codepoint to char, skip if invalid
-            char::from_u32(icon.codepoint).map(|c| {
+            char::from_u32(*codepoint).map(|c| {
                 let glyph_id = GlyphId::new((i + 1) as u32); // +1 because .notdef is 0
                 (c, glyph_id)
             })
         })
         .collect();
+    // Icons keep their codepoint if an extra mapping collides with it
+    for &(codepoint, gid) in extra {
+        if !codepoints.contains(&codepoint) {
+            if let Some(c) = char::from_u32(codepoint) {
+                mappings.push((c, GlyphId::new(gid as u32)));
+            }
+        }
+    }
 
     // Create cmap from mappings
-    Cmap::from_mappings(mappings).map_err(|e| anyhow::anyhow!("Failed to create cmap: {:?}", e))
+    let mut cmap = Cmap::from_mappings(mappings)
+        .map_err(|e| anyhow::anyhow!("Failed to create cmap: {:?}", e))?;
+
+    if !symbol.is_empty() {
+        // Reuse the format 4 subtable built for the Windows BMP encoding
+        let symbol_mappings = symbol.iter().filter_map(|(codepoint, gid)| {
+            Some((char::from_u32(*codepoint)?, GlyphId::new(*gid as u32)))
+        });
+        let symbol_cmap = Cmap::from_mappings(symbol_mappings)
+            .map_err(|e| anyhow::anyhow!("Failed to create symbol cmap: {:?}", e))?;
+        let mut record = symbol_cmap
+            .encoding_records
+            .into_iter()
+            .find(|r| r.platform_id == PlatformId::Windows)
+            .context("Symbol cmap has no Windows subtable")?;
+        record.encoding_id = 0;
+        if symbol_only {
+            cmap.encoding_records.clear();
+        }
+        cmap.encoding_records.push(record);
+        cmap.encoding_records
+            .sort_by_key(|r| (r.platform_id, r.encoding_id));
+    }
+
+    Ok(cmap)
+}
+
+/// Build a GSUB table with a single `liga` lookup substituting each glyph
+/// sequence, for the default and Latin scripts
+fn build_liga_gsub(ligatures: impl IntoIterator<Item = (Vec<GlyphId16>, GlyphId16)>) -> Gsub {
+    // Ligatures are grouped by their first glyph, longest first so the
+    // longest matching name wins
+    let mut sets: BTreeMap<GlyphId16, Vec<Ligature>> = BTreeMap::new();
+    for (glyphs, ligature) in ligatures {
+        let Some((first, rest)) = glyphs.split_first() else {
+            continue;
+        };
+        sets.entry(*first)
+            .or_default()
+            .push(Ligature::new(ligature, rest.to_vec()));
+    }
+    for set in sets.values_mut() {
+        set.sort_by_key(|l| std::cmp::Reverse(l.component_glyph_ids.len()));
+    }
+
+    let coverage: CoverageTable = sets.keys().copied().collect();
+    let sets = sets.into_values().map(LigatureSet::new).collect();
+    let lookup = SubstitutionLookup::Ligature(Lookup::new(
+        LookupFlag::empty(),
+        vec![LigatureSubstFormat1::new(coverage, sets)],
+    ));
+
+    let script = Script::new(Some(LangSys::new(vec![0])), vec![]);
+    let scripts = ScriptList::new(vec![
+        ScriptRecord::new(Tag::new(b"DFLT"), script.clone()),
+        ScriptRecord::new(Tag::new(b"latn"), script),
+    ]);
+    let features = FeatureList::new(vec![FeatureRecord::new(
+        Tag::new(b"liga"),
+        Feature::new(None, vec![0]),
+    )]);
+    Gsub::new(scripts, features, SubstitutionLookupList::new(vec![lookup]))
+}
+
+/// Codepoint of the icon at `codepoint` in a legacy symbol cmap.
+///
+/// Symbol fonts are addressed with 8-bit character codes offset by 0xF000,
+/// so icons map to U+F021..U+F0FF in codepoint order, leaving space unused.
+pub fn symbol_codepoint(codepoint: u32) -> Result<u32> {
+    let code = codepoint
+        .checked_sub(0xE000)
+        .map(|offset| offset + 0x21)
+        .filter(|code| *code <= 0xFF)
+        .with_context(|| {
+            format!(
+                "U+{:04X} does not fit in a symbol cmap, which holds at most 223 icons from U+E000",
+                codepoint
+            )
+        })?;
+    Ok(0xF000 + code)
 }
 
 /// Build the head table
-fn build_head() -> Head {
+fn build_head(bounds: &FontBounds, options: &FontOptions) -> Head {
+    let bbox = bounds.bbox.unwrap_or_default();
+    let mut mac_style = MacStyle::empty();
+    if options.is_bold() {
+        mac_style |= MacStyle::BOLD;
+    }
+    if options.italic.is_some() {
+        mac_style |= MacStyle::ITALIC;
+    }
     Head {
-        font_revision: Fixed::from_f64(1.0),
-        units_per_em: UNITS_PER_EM,
+        font_revision: Fixed::from_f64(options.version().parse().unwrap_or(1.0)),
+        units_per_em: options.units_per_em(),
         created: Default::default(),
         modified: Default::default(),
-        mac_style: MacStyle::empty(),
+        mac_style,
         lowest_rec_ppem: 8,
         index_to_loc_format: 1, // Long offsets (will be updated)
+        x_min: bbox.x_min,
+        y_min: bbox.y_min,
+        x_max: bbox.x_max,
+        y_max: bbox.y_max,
         ..Default::default()
     }
 }
 
 /// Build the hhea table
-fn build_hhea(num_glyphs: u16) -> Hhea {
+fn build_hhea(num_h_metrics: u16, bounds: &FontBounds, options: &FontOptions) -> Hhea {
+    // The caret leans with the glyphs
+    let slant = options.italic.unwrap_or(0.0).to_radians().tan();
+    let (caret_slope_rise, caret_slope_run) = match slant {
+        0.0 => (1, 0),
+        _ => (
+            options.units_per_em() as i16,
+            (-slant * options.units_per_em() as f64).round() as i16,
+        ),
+    };
     Hhea {
-        ascender: FWord::new(800),
-        descender: FWord::new(-200),
+        ascender: FWord::new(options.ascender()),
+        descender: FWord::new(options.descender()),
         line_gap: FWord::new(0),
-        advance_width_max: UfWord::new(UNITS_PER_EM),
-        min_left_side_bearing: FWord::new(0),
-        min_right_side_bearing: FWord::new(0),
-        x_max_extent: FWord::new(UNITS_PER_EM as i16),
-        caret_slope_rise: 1,
-        caret_slope_run: 0,
+        advance_width_max: UfWord::new(bounds.advance_width_max),
+        min_left_side_bearing: FWord::new(bounds.min_left_side_bearing),
+        min_right_side_bearing: FWord::new(bounds.min_right_side_bearing),
+        x_max_extent: FWord::new(bounds.x_max_extent),
+        caret_slope_rise,
+        caret_slope_run,
         caret_offset: 0,
-        number_of_h_metrics: num_glyphs,
+        number_of_h_metrics: num_h_metrics,
     }
 }
 
 /// Build the name table
-fn build_name(font_name: &str) -> Name {
+fn build_name(font_name: &str, options: &FontOptions) -> Name {
     let mut name = Name::default();
 
     // Add name records for all required name IDs
-    name.name_record.push(create_name_record(
-        NameId::COPYRIGHT_NOTICE,
-        "Generated by svg2font",
-    ));
+    let copyright = match &options.copyright {
+        Some(holder) => format!("Copyright (c) {}", holder),
+        None => "Generated by svg2font".to_string(),
+    };
+    name.name_record
+        .push(create_name_record(NameId::COPYRIGHT_NOTICE, &copyright));
+    // Styles other than Regular are part of the full and PostScript names
+    let style_name = options.style_name();
+    let (subfamily, full_name, postscript_name) = match style_name.as_deref() {
+        Some(subfamily) => (
+            subfamily,
+            format!("{} {}", font_name, subfamily),
+            format!("{}-{}", font_name, subfamily),
+        ),
+        None => ("Regular", font_name.to_string(), font_name.to_string()),
+    };
     name.name_record
         .push(create_name_record(NameId::FAMILY_NAME, font_name));
     name.name_record
-        .push(create_name_record(NameId::SUBFAMILY_NAME, "Regular"));
+        .push(create_name_record(NameId::SUBFAMILY_NAME, subfamily));
     name.name_record.push(create_name_record(
         NameId::UNIQUE_ID,
-        &format!("svg2font: {}", font_name),
+        &format!("svg2font: {}", full_name),
     ));
     name.name_record
-        .push(create_name_record(NameId::FULL_NAME, font_name));
+        .push(create_name_record(NameId::FULL_NAME, &full_name));
+    let version = match &options.provenance {
+        Some(provenance) => format!("Version {}; {}", options.version(), provenance.summary()),
+        None => format!("Version {}", options.version()),
+    };
     name.name_record
-        .push(create_name_record(NameId::VERSION_STRING, "Version 1.0"));
+        .push(create_name_record(NameId::VERSION_STRING, &version));
     name.name_record.push(create_name_record(
         NameId::POSTSCRIPT_NAME,
-        &font_name.replace(' ', ""),
+        &postscript_name.replace(' ', ""),
     ));
 
+    if let Some(designer) = &options.designer {
+        name.name_record
+            .push(create_name_record(NameId::DESIGNER, designer));
+    }
+    if let Some(description) = &options.description {
+        name.name_record
+            .push(create_name_record(NameId::DESCRIPTION, description));
+    }
+    if let Some(license) = &options.license {
+        name.name_record.push(create_name_record(
+            NameId::LICENSE_DESCRIPTION,
+            &license.text,
+        ));
+    }
+    let license_url = options.license_url.as_ref().or(options
+        .license
+        .as_ref()
+        .and_then(|license| license.url.as_ref()));
+    if let Some(url) = license_url {
+        name.name_record
+            .push(create_name_record(NameId::LICENSE_URL, url));
+    }
+
+    // Symbol fonts use the symbol encoding for their names too
+    if options.windows_symbol() {
+        for record in &mut name.name_record {
+            record.encoding_id = 0;
+        }
+    }
+
     name
 }
 
-fn create_name_record(name_id: NameId, value: &str) -> NameRecord {
+pub(crate) fn create_name_record(name_id: NameId, value: &str) -> NameRecord {
     NameRecord {
         platform_id: 3,     // Windows
         encoding_id: 1,     // Unicode BMP
@@ -315,46 +1886,617 @@ fn create_name_record(name_id: NameId, value: &str) -> NameRecord {
     }
 }
 
+/// Mark the OS/2 table as describing a symbol font, as GDI requires for
+/// fonts with only a Symbol cmap
+fn make_symbol_os2(os2: &mut Os2, symbol: &[(u32, u16)]) {
+    os2.s_family_class = 12 << 8; // Symbolic
+    os2.panose_10[0] = 5; // Latin Pictorial
+    os2.ul_code_page_range_1 = Some(1 << 31); // Symbol character set
+    os2.us_first_char_index = symbol.iter().map(|(c, _)| *c).min().unwrap_or(0xF020) as u16;
+    os2.us_last_char_index = symbol.iter().map(|(c, _)| *c).max().unwrap_or(0xF020) as u16;
+}
+
 /// Build the OS/2 table
-fn build_os2(_icons: &[Icon]) -> Os2 {
+fn build_os2(options: &FontOptions) -> Os2 {
+    let scale = |value: i16| options.scale_metric(value);
+    let (ascender, descender) = (options.ascender(), options.descender());
     Os2 {
-        x_avg_char_width: UNITS_PER_EM as i16,
-        us_weight_class: 400, // Normal
-        us_width_class: 5,    // Medium
-        fs_type: 0,           // Installable
-        y_subscript_x_size: 650,
-        y_subscript_y_size: 600,
+        x_avg_char_width: options.units_per_em() as i16,
+        us_weight_class: options.weight(),
+        us_width_class: options.width(),
+        fs_type: options.embedding.fs_type(),
+        y_subscript_x_size: scale(650),
+        y_subscript_y_size: scale(600),
         y_subscript_x_offset: 0,
-        y_subscript_y_offset: 75,
-        y_superscript_x_size: 650,
-        y_superscript_y_size: 600,
+        y_subscript_y_offset: scale(75),
+        y_superscript_x_size: scale(650),
+        y_superscript_y_size: scale(600),
         y_superscript_x_offset: 0,
-        y_superscript_y_offset: 350,
-        y_strikeout_size: 50,
-        y_strikeout_position: 300,
-        s_typo_ascender: 800,
-        s_typo_descender: -200,
+        y_superscript_y_offset: scale(350),
+        y_strikeout_size: scale(50),
+        y_strikeout_position: scale(300),
+        s_typo_ascender: ascender,
+        s_typo_descender: descender,
         s_typo_line_gap: 0,
-        us_win_ascent: 1000,
-        us_win_descent: 200,
-        ul_unicode_range_1: 0,
-        ul_unicode_range_2: 0,
-        ul_unicode_range_3: 0,
-        ul_unicode_range_4: 1 << 28,   // Private Use Area
-        ul_code_page_range_1: Some(1), // Latin 1
-        ul_code_page_range_2: Some(0),
-        sx_height: Some(500),
-        s_cap_height: Some(700),
+        // Icons fill the em above the baseline
+        us_win_ascent: ascender.max(0).max(options.units_per_em() as i16) as u16,
+        us_win_descent: (-(descender as i32)).max(0) as u16,
+        sx_height: Some(scale(500)),
+        s_cap_height: Some(scale(700)),
         us_default_char: Some(0),
         us_break_char: Some(32),
         us_max_context: Some(0),
+        ach_vend_id: options.vendor_id.unwrap_or_default(),
+        fs_selection: selection_flags(options),
         us_lower_optical_point_size: None,
         us_upper_optical_point_size: None,
         ..Default::default()
     }
 }
 
-/// Build the post table
-fn build_post() -> Post {
-    Post::new_v2(std::iter::empty::<&str>())
+/// The OS/2 fsSelection bits for the font's style
+fn selection_flags(options: &FontOptions) -> SelectionFlags {
+    let mut flags = SelectionFlags::empty();
+    if options.is_bold() {
+        flags |= SelectionFlags::BOLD;
+    }
+    if options.italic.is_some() {
+        flags |= SelectionFlags::ITALIC;
+    }
+    flags
+}
+
+/// Set the average advance, and grow the Windows ascent and descent to cover
+/// every glyph, since Windows clips whatever lies outside them
+fn fit_os2_metrics(os2: &mut Os2, bounds: &FontBounds) {
+    if let Some(average) = bounds.advance_sum.checked_div(bounds.advance_count) {
+        os2.x_avg_char_width = average.min(i16::MAX as u64) as i16;
+    }
+    if let Some(bbox) = bounds.bbox {
+        os2.us_win_ascent = os2.us_win_ascent.max(bbox.y_max.max(0) as u16);
+        os2.us_win_descent = os2.us_win_descent.max((-(bbox.y_min as i32)).max(0) as u16);
+    }
+}
+
+/// Build the post table, without a glyph name table when `compact` is set
+fn build_post(compact: bool, options: &FontOptions) -> Post {
+    let mut post = if compact {
+        Post {
+            version: Version16Dot16::VERSION_3_0,
+            ..Default::default()
+        }
+    } else {
+        Post::new_v2(std::iter::empty::<&str>())
+    };
+    post.italic_angle = Fixed::from_f64(options.italic.unwrap_or(0.0));
+    post
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use read_fonts::{tables::glyf::Glyph, TableProvider};
+
+    #[test]
+    fn test_cubic_approximation_within_tolerance() {
+        use kurbo::{ParamCurve, ParamCurveNearest, PathSeg};
+
+        let cubic = CubicBez::new((0.0, 0.0), (0.0, 900.0), (1000.0, -900.0), (1000.0, 0.0));
+        let quads = |tolerance: f64| {
+            let mut path = BezPath::new();
+            path.move_to(cubic.p0);
+            approximate_cubic_with_quadratics(&cubic, tolerance, &mut path);
+            path.segments()
+                .map(|s| match s {
+                    PathSeg::Quad(q) => q,
+                    other => panic!("Expected a quadratic, got {:?}", other),
+                })
+                .collect::<Vec<_>>()
+        };
+
+        for tolerance in [0.1, 1.0, 10.0] {
+            let quads = quads(tolerance);
+            assert_eq!(quads.last().unwrap().p2, cubic.p3);
+            for quad in &quads {
+                for step in 0..=10 {
+                    let point = quad.eval(step as f64 / 10.0);
+                    let distance = cubic.nearest(point, 1e-9).distance_sq.sqrt();
+                    assert!(distance <= tolerance, "{} > {}", distance, tolerance);
+                }
+            }
+        }
+        // A looser tolerance needs fewer pieces
+        assert!(quads(10.0).len() < quads(0.1).len());
+    }
+
+    #[test]
+    fn test_bounds_from_outlines() {
+        let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 24 24"><rect x="4" y="2" width="16" height="30"/></svg>"#;
+        let icon = crate::svg_parser::parse_svg_str(svg, "tall.svg", 0xE000).unwrap();
+        let data = build_font_bytes(&[icon], "Test", &FontOptions::default(), None, false).unwrap();
+
+        let font = FontRef::new(&data).unwrap();
+        let head = font.head().unwrap();
+        assert_eq!(
+            (head.x_min(), head.y_min(), head.x_max(), head.y_max()),
+            (167, -333, 833, 917)
+        );
+        let hhea = font.hhea().unwrap();
+        assert_eq!(hhea.advance_width_max().to_u16(), 1000);
+        assert_eq!(hhea.min_left_side_bearing().to_i16(), 167);
+        assert_eq!(hhea.min_right_side_bearing().to_i16(), 167);
+        assert_eq!(hhea.x_max_extent().to_i16(), 833);
+        assert_eq!(
+            font.hmtx().unwrap().side_bearing(GlyphId::new(1)),
+            Some(167)
+        );
+        // The default Windows descent of 200 would clip the bottom
+        assert_eq!(font.os2().unwrap().us_win_descent(), 333);
+    }
+
+    #[test]
+    fn test_custom_units_per_em() {
+        let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 24 24"><rect x="0" y="0" width="24" height="24"/></svg>"#;
+        let icon = crate::svg_parser::parse_svg_str(svg, "square.svg", 0xE000).unwrap();
+        let options = FontOptions {
+            units_per_em: Some(2048),
+            descender: Some(-512),
+            ..Default::default()
+        };
+        let data = build_font_bytes(&[icon], "Test", &options, None, false).unwrap();
+
+        let font = FontRef::new(&data).unwrap();
+        let head = font.head().unwrap();
+        assert_eq!(head.units_per_em(), 2048);
+        assert_eq!((head.x_max(), head.y_max()), (2048, 2048));
+        let hhea = font.hhea().unwrap();
+        assert_eq!(hhea.ascender().to_i16(), 1638);
+        assert_eq!(hhea.descender().to_i16(), -512);
+        assert_eq!(font.os2().unwrap().us_win_descent(), 512);
+
+        let inverted = FontOptions {
+            ascender: Some(-600),
+            ..Default::default()
+        };
+        assert!(inverted.validate().is_err());
+    }
+
+    #[test]
+    fn test_vertical_fit() {
+        let wide = r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 24 12"><rect width="24" height="12"/></svg>"#;
+        let icons = [crate::svg_parser::parse_svg_str(wide, "wide.svg", 0xE000).unwrap()];
+        let bbox = |fit: VerticalFit, baseline_offset: i16| {
+            let options = FontOptions {
+                fit,
+                baseline_offset,
+                ..Default::default()
+            };
+            let data = build_font_bytes(&icons, "Test", &options, None, false).unwrap();
+            let head = FontRef::new(&data).unwrap().head().unwrap();
+            (head.x_min(), head.y_min(), head.x_max(), head.y_max())
+        };
+
+        assert_eq!(bbox(VerticalFit::None, 0), (0, 0, 1000, 500));
+        assert_eq!(bbox(VerticalFit::None, -100), (0, -100, 1000, 400));
+        // Centered between descender and ascender
+        assert_eq!(bbox(VerticalFit::Em, 0), (0, 50, 1000, 550));
+        assert_eq!(bbox(VerticalFit::CapHeight, 0), (0, 100, 1000, 600));
+    }
+
+    #[test]
+    fn test_trim_and_padding() {
+        let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 24 24"><rect x="2" y="2" width="8" height="4"/></svg>"#;
+        let icons = [crate::svg_parser::parse_svg_str(svg, "small.svg", 0xE000).unwrap()];
+        let bbox = |padding: f64| {
+            let options = FontOptions {
+                trim: true,
+                padding,
+                ..Default::default()
+            };
+            let data = build_font_bytes(&icons, "Test", &options, None, false).unwrap();
+            let head = FontRef::new(&data).unwrap().head().unwrap();
+            (head.x_min(), head.y_min(), head.x_max(), head.y_max())
+        };
+
+        // Trimmed to an 8 unit square, centered vertically
+        assert_eq!(bbox(0.0), (0, 250, 1000, 750));
+        assert_eq!(bbox(25.0), (167, 333, 833, 667));
+    }
+
+    #[test]
+    fn test_centering() {
+        let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 24 24"><path d="M2 4 L2 20 L14 12 Z"/></svg>"#;
+        let icons = [crate::svg_parser::parse_svg_str(svg, "play.svg", 0xE000).unwrap()];
+        let bbox = |center_x: Centering| {
+            let options = FontOptions {
+                center_x,
+                center_y: Centering::Geometric,
+                ..Default::default()
+            };
+            let data = build_font_bytes(&icons, "Test", &options, None, false).unwrap();
+            let head = FontRef::new(&data).unwrap().head().unwrap();
+            (head.x_min(), head.y_min(), head.x_max(), head.y_max())
+        };
+
+        assert_eq!(bbox(Centering::Geometric), (250, 167, 750, 833));
+        // The centroid sits a third of the way into the triangle
+        assert_eq!(bbox(Centering::Centroid), (333, 167, 833, 833));
+    }
+
+    #[test]
+    fn test_icon_overrides() {
+        let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 24 24"><rect width="24" height="24"/></svg>"#;
+        let mut icon = crate::svg_parser::parse_svg_str(svg, "square", 0xE000).unwrap();
+        icon.overrides = crate::svg_parser::IconOverrides {
+            scale: Some(0.5),
+            baseline_offset: Some(100),
+            advance: Some(1200),
+            ..Default::default()
+        };
+        let icons = [icon];
+        let options = FontOptions::default();
+
+        let data = build_font_bytes(&icons, "Test", &options, None, false).unwrap();
+        let font = FontRef::new(&data).unwrap();
+        let head = font.head().unwrap();
+        // Half size around the middle of the em, centered in the wider advance
+        assert_eq!(
+            (head.x_min(), head.y_min(), head.x_max(), head.y_max()),
+            (350, 350, 850, 850)
+        );
+        assert_eq!(font.hmtx().unwrap().advance(GlyphId::new(1)), Some(1200));
+
+        let data = build_otf_bytes(&icons, "Test", &options).unwrap();
+        let font = FontRef::new(&data).unwrap();
+        assert_eq!(font.hmtx().unwrap().advance(GlyphId::new(1)), Some(1200));
+    }
+
+    #[test]
+    fn test_proportional_advances() {
+        let wide = r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 24 24"><rect x="0" y="8" width="24" height="8"/></svg>"#;
+        let narrow = r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 24 24"><rect x="6" y="0" width="6" height="24"/></svg>"#;
+        let icons: Vec<Icon> = [wide, narrow]
+            .iter()
+            .zip(0xE000..)
+            .map(|(svg, codepoint)| {
+                crate::svg_parser::parse_svg_str(svg, "icon.svg", codepoint).unwrap()
+            })
+            .collect();
+        let options = FontOptions {
+            proportional: Some(50),
+            ..Default::default()
+        };
+
+        let data = build_font_bytes(&icons, "Test", &options, None, false).unwrap();
+        let font = FontRef::new(&data).unwrap();
+        let hmtx = font.hmtx().unwrap();
+        let metrics = |gid: u32| {
+            let gid = GlyphId::new(gid);
+            (hmtx.advance(gid).unwrap(), hmtx.side_bearing(gid).unwrap())
+        };
+        assert_eq!(metrics(1), (1100, 50));
+        assert_eq!(metrics(2), (350, 50));
+
+        let data = build_otf_bytes(&icons, "Test", &options).unwrap();
+        let font = FontRef::new(&data).unwrap();
+        let hmtx = font.hmtx().unwrap();
+        assert_eq!(hmtx.advance(GlyphId::new(2)), Some(350));
+    }
+
+    #[test]
+    fn test_optimize_size_dedups_glyphs() {
+        let svg = |d: &str| {
+            format!(
+                r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 24 24"><path d="{}"/></svg>"#,
+                d
+            )
+        };
+        // A square, the same square moved, an arrow and the arrow mirrored and turned
+        let icons: Vec<Icon> = [
+            "M4 4h8v8H4z",
+            "M10 10h8v8h-8z",
+            "M4 12l8-8v5h8v6h-8v5z",
+            "M20 12l-8-8v5h-8v6h8v5z",
+            "M12 4L20 12H15V20H9V12H4z",
+        ]
+        .iter()
+        .enumerate()
+        .map(|(i, d)| crate::svg_parser::parse_svg_str(&svg(d), "icon.svg", 0xE000 + i as u32))
+        .collect::<Result<_>>()
+        .unwrap();
+        let options = FontOptions {
+            optimize_size: true,
+            ..Default::default()
+        };
+        let data = build_font_bytes(&icons, "Test", &options, None, false).unwrap();
+
+        let font = FontRef::new(&data).unwrap();
+        assert_eq!(font.hhea().unwrap().number_of_h_metrics(), 1);
+        let glyf = font.glyf().unwrap();
+        let loca = font.loca(None).unwrap();
+        let glyph = |gid: u32| loca.get_glyf(GlyphId::new(gid), &glyf).unwrap();
+        for (gid, composite) in [(1, false), (2, true), (3, false), (4, true), (5, true)] {
+            assert_eq!(
+                matches!(glyph(gid), Some(Glyph::Composite(_))),
+                composite,
+                "glyph {}",
+                gid
+            );
+        }
+        // Composites draw exactly the outline built on its own
+        for gid in [2, 4, 5] {
+            let alone = &icons[gid as usize - 1..gid as usize];
+            let data =
+                build_font_bytes(alone, "Test", &FontOptions::default(), None, false).unwrap();
+            let font = FontRef::new(&data).unwrap();
+            let (alone_glyf, alone_loca) = (font.glyf().unwrap(), font.loca(None).unwrap());
+            assert_eq!(
+                crate::extract::normalized_outline(&glyf, &loca, gid).unwrap(),
+                crate::extract::normalized_outline(&alone_glyf, &alone_loca, 1).unwrap()
+            );
+        }
+
+        // Without --optimize-size every glyph keeps its own outline
+        let data = build_font_bytes(&icons, "Test", &FontOptions::default(), None, false).unwrap();
+        let font = FontRef::new(&data).unwrap();
+        let (glyf, loca) = (font.glyf().unwrap(), font.loca(None).unwrap());
+        for gid in 1..=5 {
+            let glyph = loca.get_glyf(GlyphId::new(gid), &glyf).unwrap();
+            assert!(matches!(glyph, Some(Glyph::Simple(_))));
+        }
+    }
+
+    #[test]
+    fn test_metadata() {
+        let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 24 24"><rect x="4" y="4" width="16" height="16"/></svg>"#;
+        let icons = [crate::svg_parser::parse_svg_str(svg, "square.svg", 0xE000).unwrap()];
+        let options = FontOptions {
+            version: Some(parse_font_version("2.5").unwrap()),
+            designer: Some("Ada".to_string()),
+            description: Some("Product icons".to_string()),
+            license_url: Some("https://example.com/license".to_string()),
+            vendor_id: Some(parse_vendor_id("EX").unwrap()),
+            embedding: Embedding::Preview,
+            ..Default::default()
+        };
+        let data = build_font_bytes(&icons, "Test", &options, None, false).unwrap();
+        let font = FontRef::new(&data).unwrap();
+        let name = font.name().unwrap();
+        let string = |id: NameId| {
+            name.name_record()
+                .iter()
+                .find(|record| record.name_id() == id)
+                .map(|record| record.string(name.string_data()).unwrap().to_string())
+        };
+        assert_eq!(string(NameId::VERSION_STRING).unwrap(), "Version 2.5");
+        assert_eq!(string(NameId::DESIGNER).unwrap(), "Ada");
+        assert_eq!(string(NameId::DESCRIPTION).unwrap(), "Product icons");
+        assert_eq!(
+            string(NameId::LICENSE_URL).unwrap(),
+            "https://example.com/license"
+        );
+        assert_eq!(font.head().unwrap().font_revision(), Fixed::from_f64(2.5));
+        assert_eq!(font.os2().unwrap().ach_vend_id(), Tag::new(b"EX  "));
+        assert_eq!(font.os2().unwrap().fs_type(), 0x0004);
+
+        assert!(parse_font_version("2").is_err());
+        assert!(parse_font_version("1.x").is_err());
+        assert!(parse_vendor_id("TOOLONG").is_err());
+    }
+
+    #[test]
+    fn test_style() {
+        let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 24 24"><rect x="4" y="4" width="16" height="16"/></svg>"#;
+        let icons = [crate::svg_parser::parse_svg_str(svg, "square.svg", 0xE000).unwrap()];
+        let options = FontOptions {
+            weight: Some(700),
+            width: Some(3),
+            italic: Some(-12.0),
+            ..Default::default()
+        };
+        assert_eq!(options.style_name().unwrap(), "Condensed Bold Italic");
+        assert_eq!(FontOptions::default().style_name(), None);
+
+        let data = build_font_bytes(&icons, "Test", &options, None, false).unwrap();
+        let font = FontRef::new(&data).unwrap();
+        let os2 = font.os2().unwrap();
+        assert_eq!((os2.us_weight_class(), os2.us_width_class()), (700, 3));
+        assert_eq!(
+            os2.fs_selection(),
+            SelectionFlags::BOLD | SelectionFlags::ITALIC
+        );
+        assert_eq!(
+            font.head().unwrap().mac_style(),
+            MacStyle::BOLD | MacStyle::ITALIC
+        );
+        assert_eq!(font.post().unwrap().italic_angle(), Fixed::from_f64(-12.0));
+        assert_eq!(font.hhea().unwrap().caret_slope_run(), 213);
+
+        let slanted = FontOptions {
+            italic: Some(60.0),
+            ..Default::default()
+        };
+        assert!(slanted.validate().is_err());
+    }
+
+    #[test]
+    fn test_notdef_and_space() {
+        let square = r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 24 24"><rect x="4" y="4" width="16" height="16"/></svg>"#;
+        let icons = [crate::svg_parser::parse_svg_str(square, "square.svg", 0xE000).unwrap()];
+        let options = FontOptions {
+            notdef: Some(crate::svg_parser::parse_svg_str(square, "notdef", 0).unwrap()),
+            ..Default::default()
+        };
+        let ttf = build_font_bytes(&icons, "Test", &options, None, false).unwrap();
+        let otf = build_otf_bytes(&icons, "Test", &options).unwrap();
+        for data in [&ttf, &otf] {
+            let font = FontRef::new(data).unwrap();
+            let space = font.cmap().unwrap().map_codepoint(0x20u32);
+            assert_eq!(space, Some(GlyphId::new(2)));
+            // The .notdef outline starts a sixth of the em in
+            let side_bearing = font.hmtx().unwrap().side_bearing(GlyphId::new(0));
+            assert!(
+                matches!(side_bearing, Some(166..=167)),
+                "{:?}",
+                side_bearing
+            );
+        }
+
+        // An icon mapped to the space takes its place
+        let space = [crate::svg_parser::parse_svg_str(square, "space.svg", 0x20).unwrap()];
+        let data = build_font_bytes(&space, "Test", &FontOptions::default(), None, false).unwrap();
+        assert_eq!(FontRef::new(&data).unwrap().maxp().unwrap().num_glyphs(), 2);
+    }
+
+    #[test]
+    fn test_checksums() {
+        let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 24 24"><rect x="4" y="4" width="16" height="16"/></svg>"#;
+        let icons = [crate::svg_parser::parse_svg_str(svg, "square.svg", 0xE000).unwrap()];
+        let mut data =
+            build_font_bytes(&icons, "Test", &FontOptions::default(), None, false).unwrap();
+        check_checksums(&data).unwrap();
+        check_checksums(&build_otf_bytes(&icons, "Test", &FontOptions::default()).unwrap())
+            .unwrap();
+
+        let glyf = FontRef::new(&data)
+            .unwrap()
+            .table_directory
+            .table_records()
+            .iter()
+            .find(|record| record.tag() == Tag::new(b"glyf"))
+            .unwrap()
+            .offset() as usize;
+        data[glyf + 2] ^= 1;
+        let error = check_checksums(&data).unwrap_err().to_string();
+        assert!(error.contains("glyf"), "{}", error);
+    }
+
+    #[test]
+    fn test_symbol_cmap() {
+        let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 24 24"><rect x="4" y="4" width="16" height="16"/></svg>"#;
+        let mut icon = crate::svg_parser::parse_svg_str(svg, "square", 0xE001).unwrap();
+        icon.symbol_codepoint = Some(symbol_codepoint(icon.codepoint).unwrap());
+        let data =
+            build_font_bytes(&[icon.clone()], "Test", &Default::default(), None, false).unwrap();
+
+        let font = FontRef::new(&data).unwrap();
+        let cmap = font.cmap().unwrap();
+        let Ok(read_fonts::tables::cmap::CmapSubtable::Format4(symbol)) = cmap
+            .encoding_records()
+            .iter()
+            .find(|r| r.platform_id() == PlatformId::Windows && r.encoding_id() == 0)
+            .unwrap()
+            .subtable(cmap.offset_data())
+        else {
+            panic!("no format 4 symbol subtable");
+        };
+        assert_eq!(symbol.map_codepoint(0xF022u32), Some(GlyphId::new(1)));
+        assert_eq!(cmap.map_codepoint(0xE001u32), Some(GlyphId::new(1)));
+        assert!(symbol_codepoint(0xE000 + 223).is_err());
+
+        // The Windows symbol profile keeps only the symbol cmap
+        let options = FontOptions {
+            compat: Some(Compatibility::WindowsSymbol),
+            ..Default::default()
+        };
+        let data = build_font_bytes(&[icon], "Test", &options, None, false).unwrap();
+        let font = FontRef::new(&data).unwrap();
+        let records = font.cmap().unwrap().encoding_records();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].encoding_id(), 0);
+        let os2 = font.os2().unwrap();
+        assert_eq!(os2.ul_code_page_range_1(), Some(1 << 31));
+        assert_eq!(os2.us_first_char_index(), 0xF022);
+    }
+
+    #[test]
+    fn test_color_layers() {
+        let svg = r##"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 24 24"><rect x="2" y="2" width="20" height="20" fill="#ff8000"/><circle cx="12" cy="12" r="4"/></svg>"##;
+        let icon = crate::svg_parser::parse_svg_str(svg, "badge", 0xE000).unwrap();
+        let options = FontOptions {
+            color: true,
+            ..Default::default()
+        };
+        let data = build_font_bytes(&[icon], "Test", &options, None, false).unwrap();
+
+        let font = FontRef::new(&data).unwrap();
+        assert_eq!(font.maxp().unwrap().num_glyphs(), 5);
+        let colr = font.colr().unwrap();
+        let base = &colr.base_glyph_records().unwrap().unwrap()[0];
+        assert_eq!((base.glyph_id().to_u32(), base.num_layers()), (1, 2));
+        let layers = colr.layer_records().unwrap().unwrap();
+        // The orange square gets a palette entry, the black dot follows the text color
+        let palette: Vec<_> = layers.iter().map(|l| l.palette_index()).collect();
+        assert_eq!(palette, [0, FOREGROUND_PALETTE_INDEX]);
+        let color = font.cpal().unwrap().color_records_array().unwrap().unwrap()[0];
+        assert_eq!(
+            (color.red(), color.green(), color.blue(), color.alpha()),
+            (255, 128, 0, 255)
+        );
+    }
+
+    #[test]
+    fn test_name_ligatures() {
+        let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 24 24"><rect width="24" height="24"/></svg>"#;
+        let icons: Vec<Icon> = ["home", "home_filled", "x"]
+            .iter()
+            .zip(0xE000..)
+            .map(|(name, cp)| crate::svg_parser::parse_svg_str(svg, name, cp).unwrap())
+            .collect();
+        let options = FontOptions {
+            ligatures: true,
+            ..Default::default()
+        };
+        let data = build_font_bytes(&icons, "Test", &options, None, false).unwrap();
+
+        let font = FontRef::new(&data).unwrap();
+        let cmap = font.cmap().unwrap();
+        // Letters of "home_filled" follow the three icons: _ d e f h i l m o
+        assert_eq!(cmap.map_codepoint('h').map(|g| g.to_u32()), Some(8));
+        assert_eq!(cmap.map_codepoint('x'), None);
+        assert_eq!(font.os2().unwrap().us_max_context(), Some(11));
+
+        let gsub = font.gsub().unwrap();
+        let lookup = gsub.lookup_list().unwrap().lookups().get(0).unwrap();
+        let read_fonts::tables::gsub::SubstitutionLookup::Ligature(lookup) = lookup else {
+            panic!("expected a ligature lookup");
+        };
+        let subtable = lookup.subtables().get(0).unwrap();
+        let set = subtable.ligature_sets().get(0).unwrap();
+        // The longer name is tried first
+        let ligatures: Vec<_> = set
+            .ligatures()
+            .iter()
+            .map(|l| l.unwrap().ligature_glyph().to_u32())
+            .collect();
+        assert_eq!(ligatures, [2, 1]);
+    }
+
+    #[test]
+    fn test_aliases() {
+        let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 24 24"><rect width="24" height="24"/></svg>"#;
+        let mut icon = crate::svg_parser::parse_svg_str(svg, "delete", 0xE000).unwrap();
+        icon.aliases.push(crate::svg_parser::IconAlias {
+            name: "trash".to_string(),
+            codepoint: Some(0xE005),
+        });
+        let options = FontOptions {
+            ligatures: true,
+            ..Default::default()
+        };
+        let data = build_font_bytes(&[icon], "Test", &options, None, false).unwrap();
+
+        let font = FontRef::new(&data).unwrap();
+        let cmap = font.cmap().unwrap();
+        assert_eq!(cmap.map_codepoint(0xE005u32).map(|g| g.to_u32()), Some(1));
+        assert_eq!(cmap.map_codepoint(0xE000u32).map(|g| g.to_u32()), Some(1));
+
+        let gsub = font.gsub().unwrap();
+        let lookup = gsub.lookup_list().unwrap().lookups().get(0).unwrap();
+        let read_fonts::tables::gsub::SubstitutionLookup::Ligature(lookup) = lookup else {
+            panic!("expected a ligature lookup");
+        };
+        // "delete" and "trash" start with different letters
+        assert_eq!(lookup.subtables().get(0).unwrap().ligature_sets().len(), 2);
+    }
 }
@@ -0,0 +1,54 @@
+use crate::{font_builder, svg_parser};
+
+/// Run arbitrary bytes through the whole SVG-to-glyph pipeline:
+/// parse, winding fix, cubic conversion and glyph building.
+///
+/// Malformed input is expected to be rejected with an error; any panic is a bug.
+pub fn fuzz_svg(data: &[u8]) {
+    let Ok(svg) = std::str::from_utf8(data) else {
+        return;
+    };
+
+    let Ok(icon) = svg_parser::parse_svg_str(svg, "fuzz", 0xE000) else {
+        return;
+    };
+
+    let _ = font_builder::build_font_bytes(
+        std::slice::from_ref(&icon),
+        "Fuzz",
+        &Default::default(),
+        None,
+        false,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn svg(body: &str) -> String {
+        format!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 24 24">{}</svg>"#,
+            body
+        )
+    }
+
+    #[test]
+    fn test_malformed_inputs_do_not_panic() {
+        let cases = [
+            String::new(),
+            "<svg".to_string(),
+            svg(r#"<path d="M0 0"/>"#),
+            svg(r#"<path d="M0 0Z M1 1 Z"/>"#),
+            svg(r#"<path fill-rule="evenodd" d="M0 0L0 0L0 0Z M0 0L0 0Z"/>"#),
+            svg(r#"<path d="M0 0C1e30 1e30 -1e30 -1e30 1e30 0Z"/>"#),
+            svg(r#"<path d="M0 0C0 0 0 0 0 0Z"/>"#),
+            svg(r#"<path d="M-1e38 -1e38L1e38 1e38L1e38 -1e38Z"/>"#),
+            svg(r#"<g transform="scale(1e30)"><path d="M0 0L1 0L1 1Z"/></g>"#),
+        ];
+
+        for case in &cases {
+            fuzz_svg(case.as_bytes());
+        }
+    }
+}
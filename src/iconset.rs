@@ -0,0 +1,391 @@
+//! Icon libraries shipped as JSON: Iconify collections and IcoMoon
+//! `selection.json` files.
+
+use crate::extract;
+use crate::svg_parser::Icon;
+use anyhow::{Context, Result};
+use kurbo::{Affine, BezPath, PathEl, Point};
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Iconify's default icon size
+const ICONIFY_SIZE: f64 = 16.0;
+
+/// IcoMoon's default grid height
+const ICOMOON_SIZE: f64 = 1024.0;
+
+/// Aliases may point at other aliases, but not endlessly
+const MAX_ALIAS_DEPTH: usize = 16;
+
+/// Whether `path` is a JSON icon set rather than an icon directory
+pub fn is_icon_set(path: &Path) -> bool {
+    path.is_file()
+        && path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("json"))
+}
+
+/// Convert an Iconify collection or IcoMoon selection into SVG files, named
+/// and laid out as in an icon directory.
+///
+/// IcoMoon codepoints are kept through sidecar files, so a set moved over from
+/// IcoMoon keeps its codepoints.
+pub fn read(path: &Path) -> Result<Vec<(String, Vec<u8>)>> {
+    let data = std::fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let set: Value = serde_json::from_slice(&data)
+        .with_context(|| format!("Invalid JSON in {}", path.display()))?;
+    let files = match &set["icons"] {
+        Value::Object(icons) => iconify(&set, icons),
+        Value::Array(icons) => icomoon(&set, icons),
+        _ => anyhow::bail!("No icons, expected an Iconify or IcoMoon icon set"),
+    };
+    files.with_context(|| format!("Invalid icon set {}", path.display()))
+}
+
+/// The viewBox and transforms of an Iconify icon or alias
+#[derive(Debug, Clone, Copy)]
+struct IconifyBox {
+    left: f64,
+    top: f64,
+    width: f64,
+    height: f64,
+    rotate: i64,
+    h_flip: bool,
+    v_flip: bool,
+}
+
+impl IconifyBox {
+    /// Apply the properties an icon or alias sets over `self`. Sizes replace
+    /// the inherited ones, while flips and rotations add up.
+    fn with(mut self, props: &Value) -> Self {
+        let number = |key: &str, inherited: f64| props[key].as_f64().unwrap_or(inherited);
+        self.left = number("left", self.left);
+        self.top = number("top", self.top);
+        self.width = number("width", self.width);
+        self.height = number("height", self.height);
+        self.rotate = (self.rotate + props["rotate"].as_i64().unwrap_or(0)).rem_euclid(4);
+        self.h_flip ^= props["hFlip"].as_bool().unwrap_or(false);
+        self.v_flip ^= props["vFlip"].as_bool().unwrap_or(false);
+        self
+    }
+
+    fn svg(&self, body: &str) -> String {
+        let (cx, cy) = (self.left + self.width / 2.0, self.top + self.height / 2.0);
+        // Flips apply first, then the rotation
+        let mut transforms = Vec::new();
+        if self.rotate != 0 {
+            transforms.push(format!("rotate({} {} {})", self.rotate * 90, cx, cy));
+        }
+        if self.h_flip {
+            transforms.push(format!("translate({} 0) scale(-1 1)", cx * 2.0));
+        }
+        if self.v_flip {
+            transforms.push(format!("translate(0 {}) scale(1 -1)", cy * 2.0));
+        }
+        // Quarter turns swap the sides of the box around its center
+        let (width, height) = if self.rotate % 2 == 1 {
+            (self.height, self.width)
+        } else {
+            (self.width, self.height)
+        };
+        let body = if transforms.is_empty() {
+            body.to_string()
+        } else {
+            format!(r#"<g transform="{}">{}</g>"#, transforms.join(" "), body)
+        };
+        format!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="{} {} {} {}">{}</svg>"#,
+            cx - width / 2.0,
+            cy - height / 2.0,
+            width,
+            height,
+            body
+        )
+    }
+}
+
+fn iconify(set: &Value, icons: &Map<String, Value>) -> Result<Vec<(String, Vec<u8>)>> {
+    let defaults = IconifyBox {
+        left: 0.0,
+        top: 0.0,
+        width: ICONIFY_SIZE,
+        height: ICONIFY_SIZE,
+        rotate: 0,
+        h_flip: false,
+        v_flip: false,
+    }
+    .with(set);
+
+    let mut files = Vec::new();
+    for (name, icon) in icons {
+        let body = icon["body"]
+            .as_str()
+            .with_context(|| format!("Icon `{}` has no body", name))?;
+        files.push(svg_file(name, defaults.with(icon).svg(body))?);
+    }
+
+    let empty = Map::new();
+    let aliases = set["aliases"].as_object().unwrap_or(&empty);
+    for name in aliases.keys() {
+        // Collect the chain of aliases down to the icon, then apply it from the icon up
+        let mut chain = vec![&aliases[name]];
+        let mut parent = aliases[name]["parent"].as_str();
+        let icon = loop {
+            let parent_name = parent.with_context(|| format!("Alias `{}` has no parent", name))?;
+            if let Some(icon) = icons.get(parent_name) {
+                break icon;
+            }
+            let alias = aliases
+                .get(parent_name)
+                .filter(|_| chain.len() < MAX_ALIAS_DEPTH)
+                .with_context(|| format!("Alias `{}` does not lead to an icon", name))?;
+            chain.push(alias);
+            parent = alias["parent"].as_str();
+        };
+        let props = chain
+            .iter()
+            .rev()
+            .fold(defaults.with(icon), |props, alias| props.with(alias));
+        let body = icon["body"].as_str().unwrap_or_default();
+        files.push(svg_file(name, props.svg(body))?);
+    }
+    Ok(files)
+}
+
+fn icomoon(set: &Value, icons: &[Value]) -> Result<Vec<(String, Vec<u8>)>> {
+    let height = set["height"].as_f64().unwrap_or(ICOMOON_SIZE);
+
+    let mut files = Vec::new();
+    for entry in icons {
+        let (icon, properties) = (&entry["icon"], &entry["properties"]);
+        // IcoMoon names may list several, comma-separated
+        let name = properties["name"]
+            .as_str()
+            .and_then(|names| names.split(',').next())
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .context("Icon without a name")?;
+        let paths = icon["paths"]
+            .as_array()
+            .with_context(|| format!("Icon `{}` has no paths", name))?;
+
+        let mut body = String::new();
+        for (i, d) in paths.iter().enumerate() {
+            let d = d.as_str().unwrap_or_default();
+            match icon["attrs"][i]["fill"].as_str() {
+                Some(fill) => body.push_str(&format!(r#"<path d="{}" fill="{}"/>"#, d, fill)),
+                None => body.push_str(&format!(r#"<path d="{}"/>"#, d)),
+            }
+        }
+        let width = icon["width"].as_f64().unwrap_or(height);
+        files.push(svg_file(
+            name,
+            format!(
+                r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 {} {}">{}</svg>"#,
+                width, height, body
+            ),
+        )?);
+        if let Some(code) = properties["code"].as_u64() {
+            files.push((
+                format!("{}.toml", name),
+                format!("codepoint = \"U+{:04X}\"\n", code).into_bytes(),
+            ));
+        }
+    }
+    Ok(files)
+}
+
+/// Write an IcoMoon `selection.json` of the built font, so the set can be
+/// loaded into IcoMoon and tools that read its format.
+///
+/// Outlines are read back from the font, scaled to IcoMoon's 1024 unit grid.
+/// Color layers are not included.
+pub fn generate_selection(
+    icons: &[Icon],
+    font_name: &str,
+    font_path: &Path,
+    output_path: &Path,
+) -> Result<()> {
+    let data = std::fs::read(font_path)
+        .with_context(|| format!("Failed to read {}", font_path.display()))?;
+    let glyphs: BTreeMap<u32, extract::ExtractedIcon> =
+        extract::extract_icons(&data, &BTreeMap::new())?
+            .into_iter()
+            .map(|glyph| (glyph.codepoint, glyph))
+            .collect();
+
+    let mut entries = Vec::new();
+    for icon in icons {
+        let Some(glyph) = glyphs.get(&icon.codepoint) else {
+            continue;
+        };
+        let scale = ICOMOON_SIZE / glyph.height as f64;
+        let path = rounded(&(Affine::scale(scale) * &glyph.path));
+        let width = (glyph.width as f64 * scale).round();
+
+        let mut svg = json!({
+            "paths": [path.to_svg()],
+            "attrs": [{}],
+            "isMulticolor": false,
+            "grid": 0,
+            "tags": std::iter::once(&icon.name).chain(&icon.tags).collect::<Vec<_>>(),
+        });
+        if width != ICOMOON_SIZE {
+            svg["width"] = json!(width as u32);
+        }
+        let index = entries.len();
+        entries.push(json!({
+            "icon": svg,
+            "attrs": [{}],
+            "properties": {
+                "order": index + 1,
+                "id": index,
+                "name": icon.filename,
+                "code": icon.codepoint,
+            },
+            "setIdx": 0,
+            "setId": 0,
+            "iconIdx": index,
+        }));
+    }
+
+    let selection = json!({
+        "IcoMoonType": "selection",
+        "icons": entries,
+        "height": ICOMOON_SIZE as u32,
+        "metadata": {"name": font_name},
+        "preferences": {
+            "showGlyphs": true,
+            "fontPref": {
+                "prefix": "icon-",
+                "metadata": {"fontFamily": font_name},
+            },
+        },
+    });
+    std::fs::write(
+        output_path,
+        serde_json::to_string_pretty(&selection)? + "\n",
+    )
+    .with_context(|| format!("Failed to write {}", output_path.display()))
+}
+
+/// `path` with its points rounded to two decimals, which is plenty on a
+/// 1024 unit grid
+fn rounded(path: &BezPath) -> BezPath {
+    let round = |p: Point| Point::new((p.x * 100.0).round() / 100.0, (p.y * 100.0).round() / 100.0);
+    path.elements()
+        .iter()
+        .map(|el| match *el {
+            PathEl::MoveTo(p) => PathEl::MoveTo(round(p)),
+            PathEl::LineTo(p) => PathEl::LineTo(round(p)),
+            PathEl::QuadTo(p1, p2) => PathEl::QuadTo(round(p1), round(p2)),
+            PathEl::CurveTo(p1, p2, p3) => PathEl::CurveTo(round(p1), round(p2), round(p3)),
+            PathEl::ClosePath => PathEl::ClosePath,
+        })
+        .collect()
+}
+
+fn svg_file(name: &str, svg: String) -> Result<(String, Vec<u8>)> {
+    if name.contains(['/', '\\']) || name.starts_with('.') {
+        anyhow::bail!("`{}` is not a usable icon name", name);
+    }
+    Ok((format!("{}.svg", name), svg.into_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read_set(json: &str) -> Vec<(String, String)> {
+        let path = std::env::temp_dir().join(format!(
+            "svg2font-iconset-{}-{}.json",
+            std::process::id(),
+            json.len()
+        ));
+        std::fs::write(&path, json).unwrap();
+        let files = read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        files
+            .into_iter()
+            .map(|(name, data)| (name, String::from_utf8(data).unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn test_read_icon_sets() {
+        let files = read_set(
+            r#"{
+                "prefix": "demo",
+                "width": 24, "height": 24,
+                "icons": {
+                    "arrow": {"body": "<path d='M0 0h12v24z'/>"},
+                    "wide": {"body": "<path d='M0 0h32v24z'/>", "width": 32}
+                },
+                "aliases": {
+                    "arrow-back": {"parent": "arrow", "hFlip": true},
+                    "arrow-up": {"parent": "arrow-back", "rotate": 1}
+                }
+            }"#,
+        );
+        assert_eq!(files.len(), 4);
+        assert!(files[0].1.contains(r#"viewBox="0 0 24 24""#));
+        assert!(files[1].1.contains(r#"viewBox="0 0 32 24""#));
+        assert!(files[2]
+            .1
+            .contains(r#"transform="translate(24 0) scale(-1 1)""#));
+        assert_eq!(files[3].0, "arrow-up.svg");
+        assert!(files[3]
+            .1
+            .contains(r#"transform="rotate(90 12 12) translate(24 0) scale(-1 1)""#));
+        let icon = crate::svg_parser::parse_svg(&files[3].1, "arrow-up.svg", 0, false).unwrap();
+        assert!(!icon.path.elements().is_empty());
+
+        let files = read_set(
+            r#"{
+                "IcoMoonType": "selection",
+                "height": 1024,
+                "icons": [{
+                    "icon": {"paths": ["M0 0h1024v1024z"], "attrs": [{"fill": "rgb(0, 0, 0)"}]},
+                    "properties": {"name": "home, house", "code": 59648}
+                }]
+            }"#,
+        );
+        assert_eq!(files[0].0, "home.svg");
+        assert!(files[0].1.contains(r#"fill="rgb(0, 0, 0)""#));
+        assert_eq!(
+            files[1],
+            ("home.toml".into(), "codepoint = \"U+E900\"\n".into())
+        );
+    }
+
+    #[test]
+    fn test_selection_round_trips() {
+        use crate::font_builder::{build_font_bytes, FontOptions};
+        use crate::svg_parser::parse_svg_str;
+
+        let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 24 24"><rect x="6" y="6" width="12" height="12"/></svg>"#;
+        let mut icon = parse_svg_str(svg, "square-box", 0xE005).unwrap();
+        icon.filename = "square-box".into();
+        let data = build_font_bytes(
+            &[icon.clone()],
+            "Test",
+            &FontOptions::default(),
+            None,
+            false,
+        )
+        .unwrap();
+        let dir = std::env::temp_dir().join(format!("svg2font-selection-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("test.ttf"), data).unwrap();
+        let selection = dir.join("selection.json");
+        generate_selection(&[icon], "Test", &dir.join("test.ttf"), &selection).unwrap();
+        let files = read(&selection).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(files[0].0, "square-box.svg");
+        let svg = String::from_utf8(files[0].1.clone()).unwrap();
+        assert!(svg.contains(r#"viewBox="0 0 1024 1024""#));
+        assert_eq!(files[1].1, b"codepoint = \"U+E005\"\n");
+    }
+}
@@ -0,0 +1,320 @@
+use crate::svg_parser::Icon;
+use anyhow::Result;
+use std::collections::HashSet;
+use thiserror::Error;
+
+/// Languages that generated identifiers must compile in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Language {
+    Dart,
+    #[value(name = "typescript", alias = "ts")]
+    TypeScript,
+    Swift,
+    Kotlin,
+    Rust,
+}
+
+impl Language {
+    pub fn display_name(self) -> &'static str {
+        match self {
+            Language::Dart => "Dart",
+            Language::TypeScript => "TypeScript",
+            Language::Swift => "Swift",
+            Language::Kotlin => "Kotlin",
+            Language::Rust => "Rust",
+        }
+    }
+
+    /// Words that cannot be used as a member identifier, space separated
+    fn reserved_words(self) -> &'static str {
+        match self {
+            Language::Dart => {
+                "_ assert await break case catch class const continue default do else enum \
+                 extends false final finally for if in is new null rethrow return super switch \
+                 this throw true try var void while with yield"
+            }
+            Language::TypeScript => {
+                "await break case catch class const continue debugger default delete do else \
+                 enum export extends false finally for function if implements import in \
+                 instanceof interface let new null package private protected public return \
+                 static super switch this throw true try typeof var void while with yield"
+            }
+            Language::Swift => {
+                "_ Any Self as associatedtype await break case catch class continue default \
+                 defer deinit do else enum extension fallthrough false fileprivate for func \
+                 guard if import in init inout internal is let nil open operator \
+                 precedencegroup private protocol public repeat rethrows return self static \
+                 struct subscript super switch throw throws true try typealias var where while"
+            }
+            Language::Kotlin => {
+                "_ as break class continue do else false for fun if in interface is null object \
+                 package return super this throw true try typealias typeof val var when while"
+            }
+            // Icons become PascalCase enum variants, where only `Self` is taken
+            Language::Rust => "_ self",
+        }
+    }
+
+    fn is_reserved(self, name: &str) -> bool {
+        self.reserved_words().split_whitespace().any(|w| w == name)
+    }
+
+    /// Whether `c` may appear in an identifier (after the first character)
+    fn is_identifier_char(self, c: char) -> bool {
+        match self {
+            // Dart identifiers are restricted to ASCII
+            Language::Dart => c.is_ascii_alphanumeric() || c == '_' || c == '$',
+            Language::TypeScript => c.is_alphanumeric() || c == '_' || c == '$',
+            Language::Swift | Language::Kotlin | Language::Rust => c.is_alphanumeric() || c == '_',
+        }
+    }
+}
+
+/// Reasons an identifier is rejected by a target language
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum IdentifierError {
+    #[error("identifier is empty")]
+    Empty,
+    #[error("`{name}` starts with a digit")]
+    StartsWithDigit { name: String },
+    #[error("`{name}` contains '{ch}', which is not allowed in {language}")]
+    InvalidChar {
+        name: String,
+        ch: char,
+        language: &'static str,
+    },
+    #[error("`{name}` is a reserved word in {language}")]
+    Reserved {
+        name: String,
+        language: &'static str,
+    },
+}
+
+/// Check that `name` is a valid member identifier in `language`
+pub fn validate(name: &str, language: Language) -> Result<(), IdentifierError> {
+    let first = name.chars().next().ok_or(IdentifierError::Empty)?;
+
+    if first.is_numeric() {
+        return Err(IdentifierError::StartsWithDigit {
+            name: name.to_string(),
+        });
+    }
+
+    if let Some(ch) = name.chars().find(|c| !language.is_identifier_char(*c)) {
+        return Err(IdentifierError::InvalidChar {
+            name: name.to_string(),
+            ch,
+            language: language.display_name(),
+        });
+    }
+
+    if language.is_reserved(name) {
+        return Err(IdentifierError::Reserved {
+            name: name.to_string(),
+            language: language.display_name(),
+        });
+    }
+
+    Ok(())
+}
+
+/// Rewrite `name` so it is valid in every language in `languages`
+pub fn sanitize(name: &str, languages: &[Language]) -> String {
+    let mut result: String = name
+        .chars()
+        .map(|c| {
+            if languages.iter().all(|l| l.is_identifier_char(c)) {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+
+    if result.is_empty() {
+        result.push_str("icon");
+    }
+
+    if result.chars().next().is_some_and(|c| c.is_numeric()) {
+        result = format!("icon_{}", result);
+    }
+
+    while languages.iter().any(|l| l.is_reserved(&result)) {
+        result.push('_');
+    }
+
+    result
+}
+
+/// Convert a snake_case icon name to lowerCamelCase, e.g. `arrow_down` to `arrowDown`
+pub fn camel_case(name: &str) -> String {
+    let mut result = String::with_capacity(name.len());
+    let mut upper = false;
+    for c in name.chars() {
+        if c == '_' && !result.is_empty() {
+            upper = true;
+        } else if upper {
+            result.extend(c.to_uppercase());
+            upper = false;
+        } else {
+            result.push(c);
+        }
+    }
+    // Keep a trailing underscore, which marks an escaped reserved word
+    if upper {
+        result.push('_');
+    }
+    result
+}
+
+/// Convert a snake_case icon name to UpperCamelCase, e.g. `arrow_down` to `ArrowDown`
+pub fn pascal_case(name: &str) -> String {
+    let camel = camel_case(name);
+    let mut chars = camel.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => camel,
+    }
+}
+
+/// Validate every icon name against the target languages.
+///
+/// Invalid or duplicate names are rewritten with a warning, or reported as
+/// an error when `strict` is set. Names that only differ in case or
+/// underscores, like `arrow_left` and `arrowLeft`, count as duplicates since
+/// they generate the same camelCase and PascalCase identifiers.
+pub fn check_icon_names(icons: &mut [Icon], languages: &[Language], strict: bool) -> Result<()> {
+    let mut problems = Vec::new();
+    let mut seen = HashSet::new();
+
+    for icon in icons.iter_mut() {
+        let errors: Vec<IdentifierError> = languages
+            .iter()
+            .filter_map(|l| validate(&icon.name, *l).err())
+            .collect();
+
+        if !errors.is_empty() {
+            if strict {
+                problems.extend(errors.iter().map(|e| format!("{}: {}", icon.filename, e)));
+            } else {
+                let fixed = sanitize(&icon.name, languages);
+                eprintln!(
+                    "Warning: Renamed `{}` to `{}` ({})",
+                    icon.name, fixed, errors[0]
+                );
+                icon.name = fixed;
+            }
+        }
+
+        if !seen.insert(pascal_case(&icon.name)) {
+            if strict {
+                problems.push(format!(
+                    "{}: identifier `{}` is already used by another icon",
+                    icon.filename,
+                    pascal_case(&icon.name)
+                ));
+                continue;
+            }
+
+            let mut suffix = 2;
+            while seen.contains(&pascal_case(&format!("{}_{}", icon.name, suffix))) {
+                suffix += 1;
+            }
+            let fixed = format!("{}_{}", icon.name, suffix);
+            eprintln!(
+                "Warning: Renamed duplicate `{}` from {} to `{}`",
+                icon.name, icon.filename, fixed
+            );
+            seen.insert(pascal_case(&fixed));
+            icon.name = fixed;
+        }
+    }
+
+    if !problems.is_empty() {
+        anyhow::bail!("Invalid icon identifiers:\n  {}", problems.join("\n  "));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL: [Language; 5] = [
+        Language::Dart,
+        Language::TypeScript,
+        Language::Swift,
+        Language::Kotlin,
+        Language::Rust,
+    ];
+
+    #[test]
+    fn test_validate() {
+        assert!(validate("arrow_down", Language::Dart).is_ok());
+        assert_eq!(
+            validate("class", Language::Kotlin),
+            Err(IdentifierError::Reserved {
+                name: "class".to_string(),
+                language: "Kotlin",
+            })
+        );
+        assert!(validate("let", Language::Dart).is_ok());
+        assert!(validate("let", Language::Swift).is_err());
+        assert!(validate("café", Language::Dart).is_err());
+        assert!(validate("café", Language::Swift).is_ok());
+        assert!(validate("1up", Language::TypeScript).is_err());
+    }
+
+    #[test]
+    fn test_sanitize() {
+        assert_eq!(sanitize("class", &ALL), "class_");
+        assert_eq!(sanitize("arrow.down", &ALL), "arrow_down");
+        assert_eq!(sanitize("café", &[Language::Dart]), "caf_");
+        assert_eq!(sanitize("café", &[Language::Swift]), "café");
+        assert_eq!(sanitize("", &ALL), "icon");
+        assert_eq!(sanitize("self", &[Language::Rust]), "self_");
+    }
+
+    #[test]
+    fn test_camel_case() {
+        assert_eq!(camel_case("arrow_down_left"), "arrowDownLeft");
+        assert_eq!(camel_case("class_"), "class_");
+        assert_eq!(camel_case("icon_1up"), "icon1up");
+        assert_eq!(pascal_case("arrow_down_left"), "ArrowDownLeft");
+        assert_eq!(pascal_case("self_"), "Self_");
+    }
+
+    #[test]
+    fn test_check_icon_names() {
+        let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 24 24"><rect width="24" height="24"/></svg>"#;
+        let icons = || -> Vec<Icon> {
+            ["arrow_left", "arrowLeft", "arrow__left", "arrow_left_2"]
+                .iter()
+                .map(|name| {
+                    let mut icon = crate::svg_parser::parse_svg_str(svg, "icon", 0xE001).unwrap();
+                    icon.name = name.to_string();
+                    icon
+                })
+                .collect()
+        };
+
+        let mut renamed = icons();
+        check_icon_names(&mut renamed, &ALL, false).unwrap();
+        let names: Vec<&str> = renamed.iter().map(|icon| icon.name.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "arrow_left",
+                "arrowLeft_2",
+                "arrow__left_3",
+                "arrow_left_2_2"
+            ]
+        );
+
+        let error = check_icon_names(&mut icons(), &ALL, true).unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("identifier `ArrowLeft` is already used"));
+    }
+}
@@ -0,0 +1,54 @@
+use anyhow::{Context, Result};
+use std::path::Path;
+use svg2font::{config::CONFIG_FILE_NAME, svg_parser::IGNORE_FILE_NAME};
+
+/// Commented config listing every section
+const CONFIG_TEMPLATE: &str = include_str!("../assets/init/svg2font.toml");
+
+/// Files created in the icons directory
+const FILES: &[(&str, &str)] = &[
+    (CONFIG_FILE_NAME, CONFIG_TEMPLATE),
+    (
+        IGNORE_FILE_NAME,
+        include_str!("../assets/init/svgfontignore"),
+    ),
+    ("star.svg", include_str!("../assets/init/star.svg")),
+];
+
+/// Scaffold an icons directory with a config, an ignore file and a sample icon.
+///
+/// Files that already exist are left untouched.
+pub fn run(icons_dir: &Path) -> Result<()> {
+    std::fs::create_dir_all(icons_dir)
+        .with_context(|| format!("Failed to create {}", icons_dir.display()))?;
+
+    for (name, content) in FILES {
+        let path = icons_dir.join(name);
+        if path.exists() {
+            println!("Skipped: {} (already exists)", path.display());
+            continue;
+        }
+        std::fs::write(&path, content)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        println!("Created: {}", path.display());
+    }
+
+    println!(
+        "\nDone! Add your SVG files to {} and run: svg2font generate -i {} --preview",
+        icons_dir.display(),
+        icons_dir.display()
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use svg2font::config::Config;
+
+    #[test]
+    fn test_config_template_parses() {
+        let config: Config = toml::from_str(CONFIG_TEMPLATE).unwrap();
+        assert!(config.icons.contains_key("star"));
+    }
+}
@@ -0,0 +1,264 @@
+use crate::manifest;
+use crate::subset::codepoint_mappings;
+use anyhow::Result;
+use read_fonts::{
+    tables::glyf::Glyph,
+    types::{GlyphId, GlyphId16},
+    FontRef, TableProvider,
+};
+use serde::Serialize;
+use std::fmt;
+
+/// What `svg2font inspect` reports about a font
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FontInfo {
+    pub glyph_count: u16,
+    pub units_per_em: u16,
+    /// Table tags, in file order
+    pub tables: Vec<String>,
+    /// Windows name records
+    pub names: Vec<NameEntry>,
+    pub metrics: Metrics,
+    pub cmap: Vec<CmapEntry>,
+    pub glyphs: Vec<GlyphInfo>,
+    /// Manifest stored in the font by `--embed-manifest`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub manifest: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct NameEntry {
+    pub id: u16,
+    pub value: String,
+}
+
+/// Vertical metrics from hhea and OS/2
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Metrics {
+    pub ascender: i16,
+    pub descender: i16,
+    pub line_gap: i16,
+    pub typo_ascender: Option<i16>,
+    pub typo_descender: Option<i16>,
+    pub win_ascent: Option<u16>,
+    pub win_descent: Option<u16>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CmapEntry {
+    /// Hex codepoint, like the manifest's
+    pub codepoint: String,
+    pub glyph: u16,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GlyphInfo {
+    pub id: u16,
+    pub name: Option<String>,
+    pub advance: Option<u16>,
+    /// Contours of a simple glyph; `None` for composite glyphs and fonts without glyf
+    pub contours: Option<usize>,
+    pub points: Option<usize>,
+    /// Number of components of a composite glyph
+    pub components: Option<usize>,
+}
+
+/// Read the glyphs, mappings, names and metrics of a font
+pub fn inspect(data: &[u8]) -> Result<FontInfo> {
+    let font = FontRef::new(data).map_err(|e| anyhow::anyhow!("Font does not parse: {}", e))?;
+
+    let glyph_count = font.maxp()?.num_glyphs();
+    let tables = font
+        .table_directory
+        .table_records()
+        .iter()
+        .map(|record| record.tag().to_string())
+        .collect();
+
+    let names = match font.name() {
+        Ok(name) => name
+            .name_record()
+            .iter()
+            .filter(|record| record.platform_id() == 3)
+            .filter_map(|record| {
+                Some(NameEntry {
+                    id: record.name_id().to_u16(),
+                    value: record.string(name.string_data()).ok()?.to_string(),
+                })
+            })
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+
+    let hhea = font.hhea()?;
+    let os2 = font.os2().ok();
+    let metrics = Metrics {
+        ascender: hhea.ascender().to_i16(),
+        descender: hhea.descender().to_i16(),
+        line_gap: hhea.line_gap().to_i16(),
+        typo_ascender: os2.as_ref().map(|os2| os2.s_typo_ascender()),
+        typo_descender: os2.as_ref().map(|os2| os2.s_typo_descender()),
+        win_ascent: os2.as_ref().map(|os2| os2.us_win_ascent()),
+        win_descent: os2.as_ref().map(|os2| os2.us_win_descent()),
+    };
+
+    let cmap = codepoint_mappings(&font)?
+        .into_iter()
+        .map(|(codepoint, glyph)| CmapEntry {
+            codepoint: format!("{:04X}", codepoint),
+            glyph,
+        })
+        .collect();
+
+    let post = font.post().ok();
+    let hmtx = font.hmtx().ok();
+    let outlines = font.glyf().ok().zip(font.loca(None).ok());
+    let glyphs = (0..glyph_count)
+        .map(|id| {
+            let gid = GlyphId::new(id as u32);
+            let glyph = outlines
+                .as_ref()
+                .and_then(|(glyf, loca)| loca.get_glyf(gid, glyf).ok().flatten());
+            let (contours, points, components) = match &glyph {
+                Some(Glyph::Simple(simple)) => (
+                    Some(simple.end_pts_of_contours().len()),
+                    Some(simple.num_points()),
+                    None,
+                ),
+                Some(Glyph::Composite(composite)) => {
+                    (None, None, Some(composite.components().count()))
+                }
+                // Empty glyphs have no glyf data at all
+                None if outlines.is_some() => (Some(0), Some(0), None),
+                None => (None, None, None),
+            };
+            GlyphInfo {
+                id,
+                name: post
+                    .as_ref()
+                    .and_then(|post| post.glyph_name(GlyphId16::new(id)))
+                    .map(str::to_string),
+                advance: hmtx.as_ref().and_then(|hmtx| hmtx.advance(gid)),
+                contours,
+                points,
+                components,
+            }
+        })
+        .collect();
+
+    Ok(FontInfo {
+        glyph_count,
+        units_per_em: font.head()?.units_per_em(),
+        tables,
+        names,
+        metrics,
+        cmap,
+        glyphs,
+        manifest: manifest::embedded_manifest(&font)?,
+    })
+}
+
+/// Label of the common name ids
+fn name_label(id: u16) -> &'static str {
+    match id {
+        0 => "copyright",
+        1 => "family",
+        2 => "subfamily",
+        3 => "unique id",
+        4 => "full name",
+        5 => "version",
+        6 => "PostScript name",
+        13 => "license",
+        14 => "license URL",
+        16 => "typographic family",
+        17 => "typographic subfamily",
+        _ => "",
+    }
+}
+
+impl fmt::Display for FontInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Glyphs: {}", self.glyph_count)?;
+        writeln!(f, "Units per em: {}", self.units_per_em)?;
+        writeln!(f, "Tables: {}", self.tables.join(", "))?;
+        if let Some(manifest) = &self.manifest {
+            let icons = manifest["icons"].as_array().map_or(0, Vec::len);
+            writeln!(f, "Embedded manifest: {} icons", icons)?;
+        }
+
+        writeln!(f, "\nNames:")?;
+        for name in &self.names {
+            let label = name_label(name.id);
+            let separator = if label.is_empty() { "" } else { " " };
+            writeln!(f, "  {:>2}{}{}: {}", name.id, separator, label, name.value)?;
+        }
+
+        let m = &self.metrics;
+        writeln!(f, "\nMetrics:")?;
+        writeln!(
+            f,
+            "  hhea  ascender {}, descender {}, line gap {}",
+            m.ascender, m.descender, m.line_gap
+        )?;
+        if let (Some(ascender), Some(descender)) = (m.typo_ascender, m.typo_descender) {
+            writeln!(f, "  typo  ascender {}, descender {}", ascender, descender)?;
+        }
+        if let (Some(ascent), Some(descent)) = (m.win_ascent, m.win_descent) {
+            writeln!(f, "  win   ascent {}, descent {}", ascent, descent)?;
+        }
+
+        writeln!(f, "\nCmap ({} entries):", self.cmap.len())?;
+        for entry in &self.cmap {
+            writeln!(f, "  U+{} -> {}", entry.codepoint, entry.glyph)?;
+        }
+
+        writeln!(f, "\nGlyphs:")?;
+        for glyph in &self.glyphs {
+            write!(
+                f,
+                "  {:>5}  {:<24}",
+                glyph.id,
+                glyph.name.as_deref().unwrap_or("-")
+            )?;
+            if let Some(advance) = glyph.advance {
+                write!(f, "  advance {:>5}", advance)?;
+            }
+            match (glyph.contours, glyph.points, glyph.components) {
+                (Some(contours), Some(points), _) => {
+                    write!(f, "  {} contours, {} points", contours, points)?
+                }
+                (_, _, Some(components)) => write!(f, "  composite of {}", components)?,
+                _ => {}
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::font_builder::{build_font_bytes, FontOptions};
+    use crate::svg_parser::parse_svg_str;
+
+    #[test]
+    fn test_inspect_generated_font() {
+        let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 24 24"><rect x="4" y="4" width="16" height="16"/></svg>"#;
+        let icon = parse_svg_str(svg, "square", 0xE000).unwrap();
+        let data = build_font_bytes(&[icon], "Test", &FontOptions::default(), None, false).unwrap();
+
+        let info = inspect(&data).unwrap();
+        assert_eq!(info.glyph_count, 3);
+        assert_eq!(info.cmap[0].codepoint, "0020");
+        assert_eq!(info.cmap[1].codepoint, "E000");
+        assert_eq!(info.cmap[1].glyph, 1);
+        assert_eq!(info.glyphs[1].contours, Some(1));
+        assert_eq!(info.glyphs[1].points, Some(4));
+        assert!(info.names.iter().any(|n| n.id == 1 && n.value == "Test"));
+        assert!(info.to_string().contains("U+E000 -> 1"));
+        assert!(info.manifest.is_none());
+    }
+}
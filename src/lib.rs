@@ -0,0 +1,49 @@
+//! Convert SVG icons to TTF icon fonts.
+//!
+//! The binary drives these modules; they are exposed as a library so the
+//! pipeline can be embedded in other tools and fuzzed. [`project::FontProject`]
+//! runs the whole `generate` command with the same options as the CLI.
+
+pub mod archive;
+pub mod boolean;
+pub mod cache;
+pub mod cff;
+pub mod check;
+pub mod codegen;
+pub mod codepoints;
+pub mod config;
+pub mod diff;
+pub mod docs;
+pub mod duotone;
+pub mod extract;
+pub mod family;
+pub mod figma;
+pub mod flutter;
+pub mod font_builder;
+pub mod fuzz;
+pub mod iconset;
+pub mod identifier;
+pub mod inspect;
+pub mod license;
+pub mod lockfile;
+pub mod manifest;
+pub mod merge;
+pub mod png;
+pub mod preview;
+pub mod project;
+pub mod provenance;
+pub mod raster;
+pub mod remote;
+pub mod rust_crate;
+pub mod specimen;
+pub mod sprite;
+pub mod styles;
+pub mod subset;
+pub mod svg_parser;
+pub mod svg_table;
+pub mod unicode_ranges;
+pub mod usage;
+pub mod variable;
+pub mod variants;
+pub mod verify;
+pub mod woff;
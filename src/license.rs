@@ -0,0 +1,95 @@
+use anyhow::{Context, Result};
+use std::path::PathBuf;
+use std::str::FromStr;
+
+const MIT_TEXT: &str = include_str!("../assets/licenses/MIT.txt");
+const OFL_TEXT: &str = include_str!("../assets/licenses/OFL-1.1.txt");
+
+/// License presets accepted by `--license`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum License {
+    /// SPDX `MIT`
+    Mit,
+    /// SPDX `OFL-1.1`
+    Ofl,
+    /// All rights reserved
+    Proprietary,
+    /// Custom license text read from a file
+    File(PathBuf),
+}
+
+/// Full license text and URL to embed in a font
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LicenseText {
+    pub text: String,
+    pub url: Option<String>,
+}
+
+impl FromStr for License {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(path) = s.strip_prefix("file:") {
+            return Ok(License::File(PathBuf::from(path)));
+        }
+
+        match s.to_ascii_lowercase().as_str() {
+            "mit" => Ok(License::Mit),
+            "ofl-1.1" => Ok(License::Ofl),
+            "proprietary" => Ok(License::Proprietary),
+            _ => Err(format!(
+                "unknown license `{}`, expected MIT, OFL-1.1, proprietary or file:PATH",
+                s
+            )),
+        }
+    }
+}
+
+impl License {
+    /// Produce the license text, filling in the copyright holder
+    pub fn resolve(&self, copyright: &str) -> Result<LicenseText> {
+        let (text, url) = match self {
+            License::Mit => (
+                MIT_TEXT.replace("{copyright}", copyright),
+                Some("https://opensource.org/licenses/MIT"),
+            ),
+            License::Ofl => (
+                OFL_TEXT.replace("{copyright}", copyright),
+                Some("https://openfontlicense.org"),
+            ),
+            License::Proprietary => (
+                format!("Copyright (c) {}. All rights reserved.\n", copyright),
+                None,
+            ),
+            License::File(path) => (
+                std::fs::read_to_string(path)
+                    .with_context(|| format!("Failed to read license {}", path.display()))?,
+                None,
+            ),
+        };
+
+        Ok(LicenseText {
+            text,
+            url: url.map(str::to_string),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_license() {
+        assert_eq!("MIT".parse(), Ok(License::Mit));
+        assert_eq!("ofl-1.1".parse(), Ok(License::Ofl));
+        assert_eq!(
+            "file:legal/EULA.txt".parse(),
+            Ok(License::File(PathBuf::from("legal/EULA.txt")))
+        );
+        assert!("GPL-3.0".parse::<License>().is_err());
+
+        let mit = License::Mit.resolve("2026 Example Corp").unwrap();
+        assert!(mit.text.contains("Copyright (c) 2026 Example Corp"));
+    }
+}
@@ -0,0 +1,474 @@
+use crate::cache::content_hash;
+use crate::codepoints::{self, CodepointMap};
+use crate::extract;
+use crate::svg_parser::Icon;
+use anyhow::{Context, Result};
+use read_fonts::{FontRef, TableProvider};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+/// File name looked up in the input directory when no lockfile is given
+pub const LOCKFILE_NAME: &str = "svg2font.lock";
+
+const HEADER: &str =
+    "# Generated by svg2font; commit it to keep codepoints stable across builds.\n\n";
+
+/// Codepoints and content hashes of the icons in the last build.
+///
+/// The lockfile is authoritative for codepoints: a locked icon keeps its
+/// codepoint whatever else is added or removed, and codepoints of removed
+/// icons are never handed out again.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Lockfile {
+    /// Lowest codepoint that was never handed out
+    #[serde(with = "hex_codepoint")]
+    pub next_codepoint: u32,
+    #[serde(default, rename = "icon")]
+    pub icons: Vec<LockedIcon>,
+}
+
+/// One icon of the last build
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LockedIcon {
+    pub name: String,
+    #[serde(with = "hex_codepoint")]
+    pub codepoint: u32,
+    /// Codepoint of the icon's duotone secondary layer
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "hex_codepoint::option"
+    )]
+    pub secondary: Option<u32>,
+    /// Source SVG file
+    pub file: String,
+    /// SHA-256 of the source
+    pub source: String,
+    /// SHA-256 of the normalized glyph outline in every built font
+    pub glyph: String,
+}
+
+/// What changed since the lockfile was written
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LockChanges {
+    pub added: Vec<String>,
+    /// Icons whose glyph outline changed; edits to the SVG that leave the
+    /// shape alone do not count
+    pub changed: Vec<String>,
+    pub removed: Vec<String>,
+    /// Icons that kept their codepoint under a new name, as `old -> new`
+    pub renamed: Vec<String>,
+}
+
+impl Lockfile {
+    /// Read a lockfile; a missing file is an empty lock
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        toml::from_str(&content).with_context(|| format!("Invalid lockfile {}", path.display()))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let content = format!("{}{}", HEADER, toml::to_string(self)?);
+        std::fs::write(path, content).with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    /// Give icons their explicit codepoints from `explicit`, locked icons
+    /// their recorded codepoints, and new icons fresh ones in order after
+    /// every codepoint handed out so far.
+    ///
+    /// An icon that is not locked but has the same source as a locked icon
+    /// that is gone counts as renamed and takes over its codepoint. Explicit
+    /// codepoints win: a locked icon whose codepoint was given to another
+    /// icon gets a fresh one.
+    pub fn assign_codepoints(&self, icons: &mut [Icon], explicit: &CodepointMap) -> Result<()> {
+        let locked: HashMap<&str, u32> = self
+            .icons
+            .iter()
+            .map(|icon| (icon.name.as_str(), icon.codepoint))
+            .collect();
+        let mut renamed: HashMap<&str, u32> = self
+            .icons
+            .iter()
+            .filter(|locked| !icons.iter().any(|icon| icon.name == locked.name))
+            .map(|locked| (locked.source.as_str(), locked.codepoint))
+            .collect();
+
+        let mut last = self.last_handed_out();
+        for icon in icons {
+            let recorded = locked
+                .get(icon.name.as_str())
+                .copied()
+                .filter(|&codepoint| !explicit.is_taken(codepoint));
+            let codepoint = explicit.get(&icon.name).or(recorded).or_else(|| {
+                renamed
+                    .remove(icon.source_hash.as_str())
+                    .filter(|&codepoint| !explicit.is_taken(codepoint))
+            });
+            icon.codepoint = match codepoint {
+                Some(codepoint) => codepoint,
+                None => {
+                    last = codepoints::next_private_use(last)?;
+                    while explicit.is_taken(last) {
+                        last = codepoints::next_private_use(last)?;
+                    }
+                    last
+                }
+            };
+        }
+        Ok(())
+    }
+
+    /// The codepoint new ones are handed out after
+    fn last_handed_out(&self) -> u32 {
+        self.next_codepoint.max(codepoints::FIRST_PRIVATE_USE) - 1
+    }
+
+    /// Allocator for the secondary layers of duotone `icons`, whose own
+    /// codepoints are already assigned. Each icon keeps the layer codepoint
+    /// recorded for it; new layers get fresh codepoints after every one
+    /// handed out so far, like new icons.
+    pub fn secondary_codepoints(&self, icons: &[Icon]) -> SecondaryCodepoints {
+        let taken = icons
+            .iter()
+            .flat_map(|icon| {
+                let aliases = icon.aliases.iter().filter_map(|alias| alias.codepoint);
+                std::iter::once(icon.codepoint).chain(aliases)
+            })
+            .collect();
+        SecondaryCodepoints {
+            locked: self
+                .icons
+                .iter()
+                .filter_map(|locked| Some((locked.name.clone(), locked.secondary?)))
+                .collect(),
+            taken,
+            last: self.last_handed_out(),
+        }
+    }
+
+    /// Fail if any locked icon would be remapped: given a different codepoint,
+    /// renamed, or gone without being removed from the lockfile
+    pub fn check_frozen(&self, icons: &[Icon]) -> Result<()> {
+        let mut problems = Vec::new();
+        for locked in &self.icons {
+            let file = |icon: &&Icon| {
+                format!("{}.svg", icon.filename) == locked.file || icon.source_hash == locked.source
+            };
+            if let Some(icon) = icons.iter().find(|icon| icon.name == locked.name) {
+                if icon.codepoint != locked.codepoint {
+                    problems.push(format!(
+                        "`{}` would move from U+{:04X} to U+{:04X}",
+                        locked.name, locked.codepoint, icon.codepoint
+                    ));
+                }
+            } else if let Some(icon) = icons.iter().find(file) {
+                problems.push(format!(
+                    "`{}` ({}) would be renamed to `{}`",
+                    locked.name, locked.file, icon.name
+                ));
+            } else {
+                problems.push(format!(
+                    "`{}` disappeared; run `svg2font remove {}` if this is intended",
+                    locked.name, locked.name
+                ));
+            }
+        }
+
+        if !problems.is_empty() {
+            anyhow::bail!(
+                "The build would remap shipped icons:\n  {}",
+                problems.join("\n  ")
+            );
+        }
+        Ok(())
+    }
+
+    /// Drop icons from the lock; their codepoints stay retired
+    pub fn remove(&mut self, names: &[String]) -> Result<()> {
+        for name in names {
+            let index = self
+                .icons
+                .iter()
+                .position(|icon| &icon.name == name)
+                .with_context(|| format!("`{}` is not in the lockfile", name))?;
+            self.icons.remove(index);
+        }
+        Ok(())
+    }
+
+    /// Record a build, given each icon's glyph hash, and report what changed
+    pub fn update(&mut self, icons: &[Icon], glyph_hashes: &[String]) -> LockChanges {
+        let mut entries: Vec<LockedIcon> = icons
+            .iter()
+            .zip(glyph_hashes)
+            .map(|(icon, glyph)| LockedIcon {
+                name: icon.name.clone(),
+                codepoint: icon.codepoint,
+                secondary: icon.secondary_codepoint,
+                file: format!("{}.svg", icon.filename),
+                source: icon.source_hash.clone(),
+                glyph: glyph.clone(),
+            })
+            .collect();
+        entries.sort_by_key(|entry| entry.codepoint);
+
+        let old: HashMap<&str, &LockedIcon> = self
+            .icons
+            .iter()
+            .map(|icon| (icon.name.as_str(), icon))
+            .collect();
+        let mut changes = LockChanges::default();
+        for entry in &entries {
+            match old.get(entry.name.as_str()) {
+                None => changes.added.push(entry.name.clone()),
+                Some(locked) if locked.glyph != entry.glyph => {
+                    changes.changed.push(entry.name.clone())
+                }
+                Some(_) => {}
+            }
+        }
+        changes.removed = self
+            .icons
+            .iter()
+            .filter(|locked| !entries.iter().any(|e| e.name == locked.name))
+            .map(|locked| locked.name.clone())
+            .collect();
+
+        // A new name on a removed icon's codepoint is that icon, renamed
+        for locked in &self.icons {
+            let Some(entry) = entries
+                .iter()
+                .find(|e| e.codepoint == locked.codepoint && e.name != locked.name)
+            else {
+                continue;
+            };
+            if changes.removed.contains(&locked.name) && changes.added.contains(&entry.name) {
+                changes.removed.retain(|name| *name != locked.name);
+                changes.added.retain(|name| *name != entry.name);
+                changes
+                    .renamed
+                    .push(format!("{} -> {}", locked.name, entry.name));
+            }
+        }
+
+        let highest = entries
+            .iter()
+            .flat_map(|e| std::iter::once(e.codepoint).chain(e.secondary))
+            // Past the last Private Use codepoint once all are handed out
+            .map(|codepoint| codepoints::next_private_use(codepoint).unwrap_or(codepoint + 1))
+            .max();
+        self.next_codepoint = self
+            .next_codepoint
+            .max(highest.unwrap_or(codepoints::FIRST_PRIVATE_USE))
+            .max(codepoints::FIRST_PRIVATE_USE);
+        self.icons = entries;
+        changes
+    }
+}
+
+/// Hands out the codepoints of duotone secondary layers, from
+/// [`Lockfile::secondary_codepoints`]
+#[derive(Debug, Clone)]
+pub struct SecondaryCodepoints {
+    /// Recorded layer codepoint of each icon
+    locked: HashMap<String, u32>,
+    /// Codepoints of the icons, their aliases and the layers so far
+    taken: HashSet<u32>,
+    /// The codepoint new layers are handed out after
+    last: u32,
+}
+
+impl SecondaryCodepoints {
+    /// The codepoint of the secondary layer of `icon`
+    pub fn assign(&mut self, icon: &Icon) -> Result<u32> {
+        let codepoint = match self.locked.get(&icon.name) {
+            Some(&codepoint) if !self.taken.contains(&codepoint) => codepoint,
+            _ => {
+                self.last = codepoints::next_private_use(self.last)?;
+                while self.taken.contains(&self.last) {
+                    self.last = codepoints::next_private_use(self.last)?;
+                }
+                self.last
+            }
+        };
+        self.taken.insert(codepoint);
+        Ok(codepoint)
+    }
+}
+
+/// Hash the normalized outline of each glyph after .notdef, combining the
+/// same glyph id across `fonts`
+pub fn glyph_hashes(fonts: &[Vec<u8>]) -> Result<Vec<String>> {
+    let mut outlines: Vec<String> = Vec::new();
+    for data in fonts {
+        let font = FontRef::new(data).map_err(|e| anyhow::anyhow!("Font does not parse: {}", e))?;
+        let glyf = font.glyf()?;
+        let loca = font.loca(None)?;
+
+        let num_glyphs = font.maxp()?.num_glyphs();
+        outlines.resize(
+            outlines.len().max(num_glyphs.saturating_sub(1) as usize),
+            String::new(),
+        );
+        for gid in 1..num_glyphs {
+            let outline = extract::normalized_outline(&glyf, &loca, gid)?;
+            let combined = &mut outlines[gid as usize - 1];
+            combined.push_str(&outline);
+            combined.push('\n');
+        }
+    }
+
+    Ok(outlines
+        .iter()
+        .map(|outline| content_hash(outline.as_bytes()))
+        .collect())
+}
+
+/// Serialize codepoints as hex strings like `E000`
+mod hex_codepoint {
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(codepoint: &u32, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&format!("{:04X}", codepoint))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u32, D::Error> {
+        let hex = String::deserialize(deserializer)?;
+        u32::from_str_radix(&hex, 16).map_err(D::Error::custom)
+    }
+
+    /// The same for optional codepoints
+    pub mod option {
+        use serde::{Deserialize, Deserializer, Serializer};
+
+        pub fn serialize<S: Serializer>(
+            codepoint: &Option<u32>,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            match codepoint {
+                Some(codepoint) => super::serialize(codepoint, serializer),
+                None => serializer.serialize_none(),
+            }
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(
+            deserializer: D,
+        ) -> Result<Option<u32>, D::Error> {
+            #[derive(Deserialize)]
+            struct Hex(#[serde(with = "super")] u32);
+            Ok(Some(Hex::deserialize(deserializer)?.0))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::svg_parser::parse_svg_str;
+
+    #[test]
+    fn test_locked_codepoints_are_stable() {
+        // The title gives every icon its own source, like distinct SVG files
+        let svg = |title: &str| {
+            format!(
+                r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 24 24"><title>{}</title><rect x="4" y="4" width="16" height="16"/></svg>"#,
+                title
+            )
+        };
+        let icons = |names: &[&str]| -> Vec<Icon> {
+            names
+                .iter()
+                .map(|name| parse_svg_str(&svg(name), name, 0).unwrap())
+                .collect()
+        };
+        let hashes = vec![String::new(); 3];
+
+        let mut lock = Lockfile::default();
+        let mut first = icons(&["b", "c"]);
+        lock.assign_codepoints(&mut first, &CodepointMap::default())
+            .unwrap();
+        lock.update(&first, &hashes);
+
+        // A new icon sorting first and a removed one do not move existing codepoints
+        let mut second = icons(&["a", "c"]);
+        lock.assign_codepoints(&mut second, &CodepointMap::default())
+            .unwrap();
+        let codepoints: Vec<u32> = second.iter().map(|i| i.codepoint).collect();
+        assert_eq!(codepoints, [0xE002, 0xE001]);
+
+        let changes = lock.update(&second, &hashes);
+        assert_eq!(changes.added, ["a"]);
+        assert_eq!(changes.removed, ["b"]);
+        assert_eq!(lock.next_codepoint, 0xE003);
+
+        // Under --frozen, dropping a locked icon needs an explicit remove
+        assert!(lock.check_frozen(&second[..1]).is_err());
+        lock.remove(&["c".to_string()]).unwrap();
+        assert!(lock.check_frozen(&second[..1]).is_ok());
+
+        // A renamed file keeps its codepoint
+        let mut third = vec![parse_svg_str(&svg("a"), "a_renamed", 0).unwrap()];
+        lock.assign_codepoints(&mut third, &CodepointMap::default())
+            .unwrap();
+        assert_eq!(third[0].codepoint, 0xE002);
+        assert!(lock.check_frozen(&third).is_err());
+        let changes = lock.update(&third, &hashes);
+        assert_eq!(changes.renamed, ["a -> a_renamed"]);
+        assert!(changes.added.is_empty() && changes.removed.is_empty());
+
+        // Only a different outline counts as a change, not a different source
+        let mut fourth = vec![parse_svg_str(&svg("edited"), "a_renamed", 0).unwrap()];
+        lock.assign_codepoints(&mut fourth, &CodepointMap::default())
+            .unwrap();
+        assert!(lock.update(&fourth, &hashes).changed.is_empty());
+        let changes = lock.update(&fourth, &["reshaped".to_string()]);
+        assert_eq!(changes.changed, ["a_renamed"]);
+
+        let parsed: Lockfile = toml::from_str(&toml::to_string(&lock).unwrap()).unwrap();
+        assert_eq!(parsed.icons, lock.icons);
+
+        // Duotone layers keep their codepoints, which new icons never take
+        let mut lock = Lockfile::default();
+        let mut first = icons(&["a", "b"]);
+        lock.assign_codepoints(&mut first, &CodepointMap::default())
+            .unwrap();
+        let mut layers = lock.secondary_codepoints(&first);
+        first[0].secondary_codepoint = Some(layers.assign(&first[0]).unwrap());
+        assert_eq!(first[0].secondary_codepoint, Some(0xE002));
+        lock.update(&first, &hashes);
+
+        let mut second = icons(&["a", "b", "c"]);
+        lock.assign_codepoints(&mut second, &CodepointMap::default())
+            .unwrap();
+        assert_eq!(second[2].codepoint, 0xE003);
+        let mut layers = lock.secondary_codepoints(&second);
+        assert_eq!(layers.assign(&second[1]).unwrap(), 0xE004);
+        assert_eq!(layers.assign(&second[0]).unwrap(), 0xE002);
+
+        let parsed: Lockfile = toml::from_str(&toml::to_string(&lock).unwrap()).unwrap();
+        assert_eq!(parsed.icons[0].secondary, Some(0xE002));
+        assert_eq!(parsed.icons[1].secondary, None);
+
+        // The last Private Use codepoint is handed out, after that builds fail
+        let mut lock = Lockfile {
+            next_codepoint: 0x10FFFD,
+            icons: Vec::new(),
+        };
+        let mut last = icons(&["a"]);
+        lock.assign_codepoints(&mut last, &CodepointMap::default())
+            .unwrap();
+        assert_eq!(last[0].codepoint, 0x10FFFD);
+        lock.update(&last, &hashes);
+        let err = lock
+            .assign_codepoints(&mut icons(&["a", "b"]), &CodepointMap::default())
+            .unwrap_err();
+        assert!(err.to_string().contains("out of Private Use codepoints"));
+    }
+}
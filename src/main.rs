@@ -1,11 +1,18 @@
-mod font_builder;
-mod manifest;
-mod preview;
-mod svg_parser;
+mod bench;
+mod init;
+mod selftest;
+mod serve;
 
-use anyhow::Result;
-use clap::{Parser, Subcommand};
+use anyhow::{Context, Result};
+use clap::{Args, Parser, Subcommand};
+use serde::Serialize;
+use std::io::Read;
 use std::path::{Path, PathBuf};
+use svg2font::project::{FontProject, GenerateOptions};
+use svg2font::{
+    cache, check, codepoints, diff, extract, family, figma, font_builder, inspect, lockfile, merge,
+    preview, subset, usage, verify,
+};
 
 #[derive(Parser)]
 #[command(name = "svg2font")]
@@ -18,90 +25,685 @@ struct Cli {
 #[derive(Subcommand)]
 enum Commands {
     /// Generate TTF font from SVG icons
-    Generate {
+    Generate(Box<GenerateOptions>),
+
+    /// Serve the preview over HTTP, rebuilding and reloading it when the icons change
+    Serve {
+        #[command(flatten)]
+        options: Box<GenerateOptions>,
+
+        /// Address to listen on
+        #[arg(long, default_value = "127.0.0.1")]
+        host: String,
+
+        /// Port to listen on
+        #[arg(long, default_value_t = 8000)]
+        port: u16,
+    },
+
+    /// Create an icons directory with a commented config, a sample icon and an ignore file
+    Init {
+        /// Icons directory to create
+        #[arg(default_value = "./icons")]
+        dir: PathBuf,
+    },
+
+    /// Remove icons from the lockfile, retiring their codepoints
+    Remove {
+        /// Names of the icons to remove
+        #[arg(required = true)]
+        names: Vec<String>,
+
+        /// Input directory holding the lockfile
+        #[arg(short, long, default_value = "./icons")]
+        input: PathBuf,
+
+        /// Lockfile to edit [default: svg2font.lock in the input directory]
+        #[arg(long)]
+        lockfile: Option<PathBuf>,
+    },
+
+    /// Build a font from bundled sample icons and validate the output
+    Selftest {
+        /// Keep the generated files instead of deleting them
+        #[arg(long)]
+        keep: bool,
+
+        /// Enable verbose output
+        #[arg(short, long)]
+        verbose: bool,
+    },
+
+    /// Time the parse, convert and build phases over an icon directory
+    Bench {
         /// Input directory containing SVG files
         #[arg(short, long, default_value = "./icons")]
         input: PathBuf,
 
-        /// Output directory for generated files
-        #[arg(short, long, default_value = "./output")]
+        /// Number of measured iterations
+        #[arg(short = 'n', long, default_value_t = 10)]
+        iterations: u32,
+
+        /// Number of unmeasured warmup iterations
+        #[arg(long, default_value_t = 1)]
+        warmup: u32,
+    },
+
+    /// Write the glyphs of a TrueType font back out as SVG icons
+    Extract {
+        /// Font to extract
+        font: PathBuf,
+
+        /// Directory to write the SVGs to
+        #[arg(short, long)]
         output: PathBuf,
 
-        /// Font family name
-        #[arg(short, long, default_value = "Icons")]
-        name: String,
+        /// Name the icons after an svg2font manifest of the font
+        #[arg(long)]
+        manifest: Option<PathBuf>,
+    },
+
+    /// Print the glyphs, cmap, names and metrics of a font
+    Inspect {
+        /// Font to inspect
+        font: PathBuf,
+
+        /// Print JSON instead of text
+        #[arg(long)]
+        json: bool,
+
+        /// Print only the manifest embedded with `generate --embed-manifest`
+        #[arg(long, conflicts_with = "json")]
+        manifest: bool,
+    },
+
+    /// Run sanity checks on fonts, failing on errors
+    Check {
+        /// Fonts to check
+        #[arg(required = true)]
+        fonts: Vec<PathBuf>,
+    },
+
+    /// Render each glyph and its source SVG, failing on icons that visibly diverge
+    Verify {
+        /// Font to verify
+        font: PathBuf,
+
+        /// Directory with the source SVGs
+        #[arg(short, long, default_value = "./icons")]
+        input: PathBuf,
+
+        /// Manifest of the font; defaults to the one embedded with `--embed-manifest`
+        #[arg(long)]
+        manifest: Option<PathBuf>,
+
+        /// Size in pixels of both renderings
+        #[arg(long, default_value_t = 64)]
+        size: usize,
+
+        /// Percentage of differing pixels above which an icon fails
+        #[arg(long, default_value_t = 5.0)]
+        threshold: f64,
+    },
+
+    /// Compare two manifests, failing on removed, renamed or moved icons
+    Diff {
+        /// Manifest of the previous release
+        old: PathBuf,
+
+        /// Manifest of the new build
+        new: PathBuf,
+    },
 
-        /// Generate HTML preview page
+    /// Combine icon fonts into one, failing on codepoint or glyph name collisions
+    Merge {
+        /// Fonts to merge; the first provides names and metrics
+        #[arg(required = true, num_args = 2..)]
+        fonts: Vec<PathBuf>,
+
+        /// Where to write the merged font
         #[arg(short, long)]
-        preview: bool,
+        output: PathBuf,
+    },
 
-        /// Enable verbose output
+    /// Trim an existing TrueType font down to selected glyphs
+    Subset {
+        /// Font to subset
         #[arg(short, long)]
-        verbose: bool,
+        font: PathBuf,
+
+        /// Where to write the subset font
+        #[arg(short, long)]
+        output: PathBuf,
+
+        /// Codepoints to keep, in hex (e.g. E000,U+E001)
+        #[arg(long, value_delimiter = ',', value_parser = codepoints::parse_codepoint)]
+        codepoints: Vec<u32>,
+
+        /// Glyph names to keep, as listed in the font's post table
+        #[arg(long, value_delimiter = ',')]
+        glyphs: Vec<String>,
+
+        /// Keep every codepoint listed in an svg2font manifest
+        #[arg(long)]
+        manifest: Option<PathBuf>,
+    },
+
+    /// Combine fonts of different styles into one family with shared names and metrics
+    PackageFamily(PackageFamilyArgs),
+
+    /// Read a generate spec as JSON from stdin and report the artifacts as JSON on stdout
+    Pipeline,
+
+    /// Find unused icons, and references to icons that do not exist, in a codebase
+    Usage(UsageArgs),
+
+    /// Download icons from a design tool into an icon directory
+    Import {
+        #[command(subcommand)]
+        source: ImportSource,
     },
 }
 
+#[derive(Subcommand)]
+enum ImportSource {
+    /// Export every component of a Figma file as an SVG
+    Figma {
+        /// Key of the Figma file, as in figma.com/design/<KEY>/...
+        #[arg(long)]
+        file_key: String,
+
+        /// Personal access token [default: the FIGMA_TOKEN environment variable]
+        #[arg(long)]
+        token: Option<String>,
+
+        /// Icon directory to write the SVGs to
+        #[arg(short, long, default_value = "./icons")]
+        output: PathBuf,
+    },
+}
+
+#[derive(Args)]
+struct UsageArgs {
+    /// Manifest of the icon font
+    #[arg(short, long)]
+    manifest: PathBuf,
+
+    /// Root of the codebase to scan
+    #[arg(short, long, default_value = ".")]
+    root: PathBuf,
+
+    /// Files to scan, as globs relative to the root
+    #[arg(long, value_delimiter = ',', default_values = [
+        "**/*.dart", "**/*.ts", "**/*.tsx", "**/*.js", "**/*.jsx", "**/*.vue", "**/*.svelte",
+        "**/*.swift", "**/*.kt", "**/*.java", "**/*.html", "**/*.css", "**/*.scss",
+    ])]
+    include: Vec<glob::Pattern>,
+
+    /// Files to skip, as globs relative to the root
+    #[arg(long, value_delimiter = ',', default_values = ["**/node_modules/**", "**/*_preview.html"])]
+    exclude: Vec<glob::Pattern>,
+
+    /// Names that qualify icon references, e.g. AppIcons [default: the font family name]
+    #[arg(long, value_delimiter = ',')]
+    qualifiers: Vec<String>,
+
+    /// Fail when unused icons or undefined references are found
+    #[arg(long)]
+    strict: bool,
+}
+
+#[derive(Args)]
+struct PackageFamilyArgs {
+    /// Fonts to combine, one per style
+    #[arg(required = true)]
+    fonts: Vec<PathBuf>,
+
+    /// Output directory for the family
+    #[arg(short, long, default_value = "./family")]
+    output: PathBuf,
+
+    /// Family name [default: the first font's family name]
+    #[arg(short, long)]
+    name: Option<String>,
+
+    /// Generate an HTML page comparing the styles
+    #[arg(short, long)]
+    preview: bool,
+
+    /// Also write every style into one TrueType collection
+    #[arg(long)]
+    ttc: bool,
+}
+
+/// The `generate` options of a pipeline build spec
+#[derive(Parser)]
+struct PipelineSpec {
+    #[command(flatten)]
+    args: GenerateOptions,
+}
+
+/// An artifact written by a pipeline build
+#[derive(Serialize)]
+struct ArtifactReport {
+    kind: &'static str,
+    path: PathBuf,
+    bytes: u64,
+    sha256: String,
+}
+
 fn main() -> Result<()> {
     let cli = Cli::parse();
 
     match cli.command {
-        Commands::Generate {
+        Commands::Generate(args) => {
+            FontProject::new(*args).generate()?;
+        }
+        Commands::Serve {
+            options,
+            host,
+            port,
+        } => {
+            serve::run(*options, &format!("{}:{}", host, port))?;
+        }
+        Commands::Init { dir } => {
+            init::run(&dir)?;
+        }
+        Commands::Remove {
+            names,
             input,
+            lockfile,
+        } => {
+            let path = lockfile.unwrap_or_else(|| input.join(lockfile::LOCKFILE_NAME));
+            let mut lock = lockfile::Lockfile::load(&path)?;
+            lock.remove(&names)?;
+            lock.save(&path)?;
+            println!("Removed {} icons from {}", names.len(), path.display());
+        }
+        Commands::Selftest { keep, verbose } => {
+            selftest::run(keep, verbose)?;
+        }
+        Commands::Bench {
+            input,
+            iterations,
+            warmup,
+        } => {
+            bench::run(&input, iterations, warmup)?;
+        }
+        Commands::Extract {
+            font,
             output,
-            name,
-            preview,
-            verbose,
+            manifest,
         } => {
-            generate_font(&input, &output, &name, preview, verbose)?;
+            extract_font(&font, &output, manifest.as_deref())?;
+        }
+        Commands::Inspect {
+            font,
+            json,
+            manifest,
+        } => {
+            let data = std::fs::read(&font)
+                .with_context(|| format!("Failed to read {}", font.display()))?;
+            let info = inspect::inspect(&data)
+                .with_context(|| format!("Failed to inspect {}", font.display()))?;
+            if manifest {
+                let manifest = info
+                    .manifest
+                    .with_context(|| format!("{} has no embedded manifest", font.display()))?;
+                println!("{}", serde_json::to_string_pretty(&manifest)?);
+            } else if json {
+                println!("{}", serde_json::to_string_pretty(&info)?);
+            } else {
+                print!("{}", info);
+            }
+        }
+        Commands::Check { fonts } => {
+            check_fonts(&fonts)?;
+        }
+        Commands::Verify {
+            font,
+            input,
+            manifest,
+            size,
+            threshold,
+        } => {
+            verify_font(&font, &input, manifest.as_deref(), size, threshold)?;
+        }
+        Commands::Diff { old, new } => {
+            diff_manifests(&old, &new)?;
+        }
+        Commands::Merge { fonts, output } => {
+            merge_fonts(&fonts, &output)?;
+        }
+        Commands::Subset {
+            font,
+            output,
+            codepoints,
+            glyphs,
+            manifest,
+        } => {
+            let mut request = subset::SubsetRequest {
+                codepoints: codepoints.into_iter().collect(),
+                glyph_names: glyphs,
+            };
+            if let Some(manifest) = manifest {
+                request.add_manifest(&manifest)?;
+            }
+            subset_font(&font, &output, &request)?;
+        }
+        Commands::PackageFamily(args) => {
+            package_family(&args)?;
+        }
+        Commands::Pipeline => {
+            run_pipeline()?;
+        }
+        Commands::Usage(args) => {
+            report_usage(&args)?;
+        }
+        Commands::Import {
+            source:
+                ImportSource::Figma {
+                    file_key,
+                    token,
+                    output,
+                },
+        } => {
+            let token = token
+                .or_else(|| std::env::var("FIGMA_TOKEN").ok())
+                .filter(|token| !token.is_empty())
+                .context("Pass --token or set FIGMA_TOKEN to import from Figma")?;
+            let files = figma::import(&file_key, &token, &output)?;
+            println!("Imported {} icons into {}", files.len(), output.display());
         }
     }
 
     Ok(())
 }
 
-fn generate_font(
-    input: &Path,
-    output: &Path,
-    font_name: &str,
-    generate_preview: bool,
-    verbose: bool,
-) -> Result<()> {
-    // Create output directory if it doesn't exist
-    std::fs::create_dir_all(output)?;
+/// Run a build described by a JSON spec on stdin and print its artifacts as JSON
+fn run_pipeline() -> Result<()> {
+    let mut spec = String::new();
+    std::io::stdin()
+        .read_to_string(&mut spec)
+        .context("Failed to read the build spec from stdin")?;
+    let spec: serde_json::Value = serde_json::from_str(&spec).context("Invalid build spec")?;
+
+    let mut args = PipelineSpec::try_parse_from(spec_to_args(&spec)?)
+        .map_err(|e| anyhow::anyhow!("Invalid build spec: {}", e))?
+        .args;
+    args.pipeline = true;
+    // Verbose output comes from the library and would end up on stdout
+    args.verbose = false;
+
+    let artifacts = FontProject::new(args)
+        .generate()?
+        .into_iter()
+        .map(|(kind, path)| {
+            let data = std::fs::read(&path)
+                .with_context(|| format!("Failed to read {}", path.display()))?;
+            Ok(ArtifactReport {
+                kind,
+                path: std::fs::canonicalize(&path)?,
+                bytes: data.len() as u64,
+                sha256: cache::content_hash(&data),
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let report = serde_json::json!({ "artifacts": artifacts });
+    println!("{}", serde_json::to_string_pretty(&report)?);
+    Ok(())
+}
+
+/// Turn a JSON build spec into `generate` arguments.
+///
+/// Keys are long option names (`cache_dir` or `cache-dir`), `true` sets a
+/// flag, and arrays repeat the option once per element.
+fn spec_to_args(spec: &serde_json::Value) -> Result<Vec<String>> {
+    let spec = spec
+        .as_object()
+        .context("Build spec must be a JSON object")?;
+
+    let mut argv = vec!["svg2font".to_string()];
+    for (key, value) in spec {
+        let flag = format!("--{}", key.replace('_', "-"));
+        let values = match value {
+            serde_json::Value::Array(items) => items.as_slice(),
+            value => std::slice::from_ref(value),
+        };
+        for value in values {
+            match value {
+                serde_json::Value::Bool(true) => argv.push(flag.clone()),
+                serde_json::Value::Bool(false) | serde_json::Value::Null => {}
+                serde_json::Value::String(s) => argv.push(format!("{}={}", flag, s)),
+                serde_json::Value::Number(n) => argv.push(format!("{}={}", flag, n)),
+                _ => anyhow::bail!("Unsupported value for `{}` in the build spec", key),
+            }
+        }
+    }
+    Ok(argv)
+}
+
+fn subset_font(font: &Path, output: &Path, request: &subset::SubsetRequest) -> Result<()> {
+    if request.codepoints.is_empty() && request.glyph_names.is_empty() {
+        anyhow::bail!("Nothing to keep: pass --codepoints, --glyphs or --manifest");
+    }
 
-    if verbose {
-        println!("Scanning SVG files in: {}", input.display());
+    let data = std::fs::read(font).with_context(|| format!("Failed to read {}", font.display()))?;
+    let subset = subset::subset_font(&data, request)
+        .with_context(|| format!("Failed to subset {}", font.display()))?;
+    font_builder::write_font(&subset, output)?;
+
+    println!("Generated: {}", output.display());
+    println!("\nDone! {} -> {} bytes.", data.len(), subset.len());
+    Ok(())
+}
+
+fn extract_font(font: &Path, output: &Path, manifest: Option<&Path>) -> Result<()> {
+    let names = match manifest {
+        Some(manifest) => extract::manifest_names(manifest)?,
+        None => Default::default(),
+    };
+
+    let data = std::fs::read(font).with_context(|| format!("Failed to read {}", font.display()))?;
+    let icons = extract::extract_icons(&data, &names)
+        .with_context(|| format!("Failed to extract {}", font.display()))?;
+    extract::write_icons(&icons, output)?;
+
+    println!(
+        "\nDone! Extracted {} icons to {}",
+        icons.len(),
+        output.display()
+    );
+    Ok(())
+}
+
+fn merge_fonts(fonts: &[PathBuf], output: &Path) -> Result<()> {
+    let fonts = fonts
+        .iter()
+        .map(|path| {
+            let data = std::fs::read(path)
+                .with_context(|| format!("Failed to read {}", path.display()))?;
+            Ok((path.display().to_string(), data))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let merged = merge::merge_fonts(&fonts)?;
+    font_builder::write_font(&merged, output)?;
+
+    println!("Generated: {}", output.display());
+    println!("\nDone! Merged {} fonts.", fonts.len());
+    Ok(())
+}
+
+fn package_family(args: &PackageFamilyArgs) -> Result<()> {
+    let fonts = args
+        .fonts
+        .iter()
+        .map(|path| {
+            std::fs::read(path).with_context(|| format!("Failed to read {}", path.display()))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let (family_name, members, glyphs) = family::package_family(&fonts, args.name.as_deref())?;
+
+    std::fs::create_dir_all(&args.output)?;
+    let base_name = family_name.to_lowercase().replace(' ', "_");
+
+    for member in &members {
+        let path = args.output.join(format!(
+            "{}_{}.ttf",
+            base_name,
+            member.subfamily.to_lowercase().replace(' ', "_")
+        ));
+        font_builder::write_font(&member.data, &path)?;
+        println!("Generated: {}", path.display());
     }
 
-    // Parse all SVG files
-    let icons = svg_parser::parse_svg_directory(input, verbose)?;
+    if args.ttc {
+        let data: Vec<Vec<u8>> = members.iter().map(|m| m.data.clone()).collect();
+        let path = args.output.join(format!("{}.ttc", base_name));
+        font_builder::write_font(&family::build_collection(&data)?, &path)?;
+        println!("Generated: {}", path.display());
+    }
 
-    if icons.is_empty() {
-        anyhow::bail!("No SVG files found in {}", input.display());
+    if args.preview {
+        let path = args.output.join(format!("{}_preview.html", base_name));
+        preview::generate_family_preview(&family_name, &members, &glyphs, &path)?;
+        println!("Generated: {}", path.display());
     }
 
-    println!("Found {} icons", icons.len());
+    println!(
+        "\nDone! {} styles of {} packaged.",
+        members.len(),
+        family_name
+    );
+    Ok(())
+}
 
-    let base_name = font_name.to_lowercase().replace(' ', "_");
+fn check_fonts(fonts: &[PathBuf]) -> Result<()> {
+    let mut errors = 0;
+    for font in fonts {
+        let data =
+            std::fs::read(font).with_context(|| format!("Failed to read {}", font.display()))?;
+        let findings = check::check_font(&data);
+        if findings.is_empty() {
+            println!("{}: OK", font.display());
+        }
+        for finding in &findings {
+            println!("{}: {}", font.display(), finding);
+        }
+        errors += findings
+            .iter()
+            .filter(|f| f.severity == check::Severity::Error)
+            .count();
+    }
+    if errors > 0 {
+        anyhow::bail!("{} errors found", errors);
+    }
+    Ok(())
+}
 
-    // Build the font
-    let ttf_path = output.join(format!("{}.ttf", base_name));
-    font_builder::build_font(&icons, font_name, &ttf_path, verbose)?;
-    println!("Generated: {}", ttf_path.display());
+fn verify_font(
+    font: &Path,
+    input: &Path,
+    manifest: Option<&Path>,
+    size: usize,
+    threshold: f64,
+) -> Result<()> {
+    let data = std::fs::read(font).with_context(|| format!("Failed to read {}", font.display()))?;
+    let manifest = match manifest {
+        Some(path) => {
+            let json = std::fs::read_to_string(path)
+                .with_context(|| format!("Failed to read {}", path.display()))?;
+            serde_json::from_str(&json)
+                .with_context(|| format!("Invalid manifest {}", path.display()))?
+        }
+        None => inspect::inspect(&data)?.manifest.with_context(|| {
+            format!(
+                "{} has no embedded manifest; pass --manifest",
+                font.display()
+            )
+        })?,
+    };
 
-    // Generate manifest (always)
-    let manifest_path = output.join(format!("{}.json", base_name));
-    manifest::generate_manifest(&icons, font_name, &manifest_path)?;
-    println!("Generated: {}", manifest_path.display());
+    let mut scores = verify::verify_font(&data, &manifest, input, size)?;
+    scores.sort_by(|a, b| b.score.total_cmp(&a.score));
+    let failed: Vec<_> = scores.iter().filter(|s| s.score > threshold).collect();
+    for score in &failed {
+        println!(
+            "{} (U+{:04X}): {:.1}% of pixels differ",
+            score.name, score.codepoint, score.score
+        );
+    }
+    if let Some(worst) = scores.first() {
+        println!(
+            "Verified {} icons at {}px, worst: {} ({:.1}%)",
+            scores.len(),
+            size,
+            worst.name,
+            worst.score
+        );
+    }
+    if !failed.is_empty() {
+        anyhow::bail!(
+            "{} icons differ from their sources by more than {}%",
+            failed.len(),
+            threshold
+        );
+    }
+    Ok(())
+}
 
-    // Generate preview if requested
-    if generate_preview {
-        let preview_path = output.join(format!("{}_preview.html", base_name));
-        preview::generate_preview(&icons, font_name, &ttf_path, &preview_path)?;
-        println!("Generated: {}", preview_path.display());
+fn diff_manifests(old: &Path, new: &Path) -> Result<()> {
+    let changes = diff::diff(&diff::read_icons(old)?, &diff::read_icons(new)?);
+    print!("{}", changes);
+    if changes.is_breaking() {
+        anyhow::bail!(
+            "{} breaking changes",
+            changes.removed.len() + changes.renamed.len() + changes.moved.len()
+        );
     }
+    println!("No breaking changes.");
+    Ok(())
+}
+
+fn report_usage(args: &UsageArgs) -> Result<()> {
+    let (family, icons) = usage::read_manifest(&args.manifest)?;
+    let qualifiers = if args.qualifiers.is_empty() {
+        vec![family.replace(' ', "")]
+    } else {
+        args.qualifiers.clone()
+    };
+
+    let files = usage::find_sources(&args.root, &args.include, &args.exclude);
+    let report = usage::scan(&icons, &qualifiers, &files);
 
-    println!("\nDone! {} icons processed.", icons.len());
+    println!("Scanned {} files", report.files);
+    if !report.unused.is_empty() {
+        println!("\nUnused icons ({}):", report.unused.len());
+        for icon in &report.unused {
+            println!("  {}  U+{:04X}", icon.name, icon.codepoint);
+        }
+    }
+    if !report.undefined.is_empty() {
+        println!("\nUndefined references ({}):", report.undefined.len());
+        for reference in &report.undefined {
+            println!(
+                "  {}:{}  {}",
+                reference.path.display(),
+                reference.line,
+                reference.text
+            );
+        }
+    }
 
+    if report.unused.is_empty() && report.undefined.is_empty() {
+        println!("\nDone! All {} icons are used.", icons.len());
+    } else if args.strict {
+        anyhow::bail!(
+            "{} unused icons and {} undefined references",
+            report.unused.len(),
+            report.undefined.len()
+        );
+    }
     Ok(())
 }
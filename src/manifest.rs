@@ -1,45 +1,291 @@
+use crate::cache::content_hash;
+use crate::extract;
+use crate::font_builder;
+use crate::provenance::Provenance;
+use crate::subset::codepoint_mappings;
 use crate::svg_parser::Icon;
 use anyhow::{Context, Result};
-use std::io::Write;
+use read_fonts::{
+    tables::glyf::Glyph,
+    types::{GlyphId, Tag},
+    FontRef, TableProvider,
+};
+use serde::Serialize;
+use std::collections::BTreeMap;
 use std::path::Path;
+use write_fonts::FontBuilder;
 
-/// Generate a JSON manifest with icon metadata
+/// Private table holding the manifest as compact UTF-8 JSON
+pub const MANIFEST_TABLE: Tag = Tag::new(b"SVGM");
+
+/// The JSON manifest written next to the font
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Manifest {
+    pub font_family: String,
+    /// Build time as an RFC 3339 UTC timestamp, omitted in deterministic builds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub generated: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub provenance: Option<ManifestProvenance>,
+    pub icons: Vec<ManifestIcon>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ManifestProvenance {
+    pub svg2font: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub commit: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub built: Option<String>,
+}
+
+/// One icon of the manifest, with the metrics of its glyph in font units
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ManifestIcon {
+    pub name: String,
+    pub filename: String,
+    /// Hex codepoint, like `E000`
+    pub codepoint: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub categories: Vec<String>,
+    /// Search keywords
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Former names that still draw the icon
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub aliases: Vec<ManifestAlias>,
+    /// SVG file the icon was built from, relative to the icon directory
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    /// SHA-256 of the SVG source
+    pub hash: String,
+    /// SHA-256 of the glyph outline in font units, with components flattened
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub outline: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub advance: Option<u16>,
+    /// `[xMin, yMin, xMax, yMax]` of the outline; absent for empty glyphs
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bbox: Option<[i16; 4]>,
+    /// Outline points of a simple glyph
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub points: Option<usize>,
+    /// Animations cannot live in a glyph, so apps should use `source` instead
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub animated: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub symbol_codepoint: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub secondary_codepoint: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ManifestAlias {
+    pub name: String,
+    /// Hex codepoint also mapped to the icon's glyph
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub codepoint: Option<String>,
+}
+
+/// Advance, bounds, point count and outline hash of a glyph
+#[derive(Debug, Clone, Default)]
+struct GlyphMetrics {
+    advance: Option<u16>,
+    bbox: Option<[i16; 4]>,
+    points: Option<usize>,
+    outline: Option<String>,
+}
+
+/// Generate a JSON manifest with icon metadata and the metrics of each
+/// icon's glyph in the built font at `font_path`.
+///
+/// Source paths are given relative to `source_dir` when inside it.
 pub fn generate_manifest(
     icons: &[Icon],
     font_name: &str,
+    provenance: Option<&Provenance>,
+    generated: Option<&str>,
+    font_path: &Path,
+    source_dir: &Path,
     output_path: &Path,
 ) -> Result<()> {
-    let json = generate_json(icons, font_name);
+    let data = std::fs::read(font_path)
+        .with_context(|| format!("Failed to read {}", font_path.display()))?;
+    let manifest = build_manifest(icons, font_name, provenance, generated, &data, source_dir)?;
 
-    let mut file = std::fs::File::create(output_path)
-        .with_context(|| format!("Failed to create {}", output_path.display()))?;
+    std::fs::write(output_path, serde_json::to_string_pretty(&manifest)? + "\n")
+        .with_context(|| format!("Failed to write {}", output_path.display()))
+}
 
-    file.write_all(json.as_bytes())
-        .with_context(|| format!("Failed to write {}", output_path.display()))?;
+/// The manifest of `icons` as built into `font_data`
+pub fn build_manifest(
+    icons: &[Icon],
+    font_name: &str,
+    provenance: Option<&Provenance>,
+    generated: Option<&str>,
+    font_data: &[u8],
+    source_dir: &Path,
+) -> Result<Manifest> {
+    let metrics = glyph_metrics(font_data)?;
+    let icons = icons
+        .iter()
+        .map(|icon| {
+            let glyph = metrics.get(&icon.codepoint).cloned().unwrap_or_default();
+            let source = icon.source.as_ref().map(|path| {
+                path.strip_prefix(source_dir)
+                    .unwrap_or(path)
+                    .to_string_lossy()
+                    .replace('\\', "/")
+            });
+            ManifestIcon {
+                name: icon.name.clone(),
+                filename: icon.filename.clone(),
+                codepoint: format!("{:04X}", icon.codepoint),
+                label: icon.label.clone(),
+                description: icon.description.clone(),
+                categories: icon.categories.clone(),
+                tags: icon.tags.clone(),
+                aliases: icon
+                    .aliases
+                    .iter()
+                    .map(|alias| ManifestAlias {
+                        name: alias.name.clone(),
+                        codepoint: alias.codepoint.map(|c| format!("{:04X}", c)),
+                    })
+                    .collect(),
+                source,
+                hash: icon.source_hash.clone(),
+                outline: glyph.outline,
+                advance: glyph.advance,
+                bbox: glyph.bbox,
+                points: glyph.points,
+                animated: icon.animated,
+                symbol_codepoint: icon.symbol_codepoint.map(|c| format!("{:04X}", c)),
+                secondary_codepoint: icon.secondary_codepoint.map(|c| format!("{:04X}", c)),
+            }
+        })
+        .collect();
+
+    Ok(Manifest {
+        font_family: font_name.to_string(),
+        generated: generated.map(str::to_string),
+        provenance: provenance.map(|provenance| ManifestProvenance {
+            svg2font: provenance.version.to_string(),
+            commit: provenance.commit.clone(),
+            built: provenance.timestamp.clone(),
+        }),
+        icons,
+    })
+}
 
-    Ok(())
+/// Store `manifest` in the font's `SVGM` table, replacing any earlier one
+pub fn embed_manifest(font_data: &[u8], manifest: &Manifest) -> Result<Vec<u8>> {
+    let font =
+        FontRef::new(font_data).map_err(|e| anyhow::anyhow!("Font does not parse: {}", e))?;
+    let mut builder = FontBuilder::new();
+    builder.add_raw(MANIFEST_TABLE, serde_json::to_vec(manifest)?);
+    builder.copy_missing_tables(font);
+    font_builder::build_checked(&mut builder)
 }
 
-fn generate_json(icons: &[Icon], font_name: &str) -> String {
-    let mut icons_json = String::new();
+/// The manifest embedded in a font with `--embed-manifest`, if any
+pub fn embedded_manifest(font: &FontRef) -> Result<Option<serde_json::Value>> {
+    let Some(table) = font.table_data(MANIFEST_TABLE) else {
+        return Ok(None);
+    };
+    let manifest = serde_json::from_slice(table.as_bytes())
+        .with_context(|| format!("Invalid JSON in the {} table", MANIFEST_TABLE))?;
+    Ok(Some(manifest))
+}
 
-    for (i, icon) in icons.iter().enumerate() {
-        if i > 0 {
-            icons_json.push_str(",\n");
-        }
-        icons_json.push_str(&format!(
-            r#"    {{ "name": "{}", "filename": "{}", "codepoint": "{:04X}" }}"#,
-            icon.name, icon.filename, icon.codepoint
-        ));
-    }
+/// Metrics of the glyph each codepoint of a font maps to
+fn glyph_metrics(data: &[u8]) -> Result<BTreeMap<u32, GlyphMetrics>> {
+    let font = FontRef::new(data).map_err(|e| anyhow::anyhow!("Font does not parse: {}", e))?;
+    let hmtx = font.hmtx().ok();
+    let outlines = font.glyf().ok().zip(font.loca(None).ok());
 
-    format!(
-        r#"{{
-  "fontFamily": "{}",
-  "icons": [
-{}
-  ]
-}}"#,
-        font_name, icons_json
-    )
+    codepoint_mappings(&font)?
+        .into_iter()
+        .map(|(codepoint, id)| {
+            let gid = GlyphId::new(id as u32);
+            let glyph = outlines
+                .as_ref()
+                .and_then(|(glyf, loca)| loca.get_glyf(gid, glyf).ok().flatten());
+            let bbox = glyph
+                .as_ref()
+                .map(|glyph| [glyph.x_min(), glyph.y_min(), glyph.x_max(), glyph.y_max()]);
+            let points = match &glyph {
+                Some(Glyph::Simple(simple)) => Some(simple.num_points()),
+                Some(Glyph::Composite(_)) => None,
+                // Empty glyphs have no glyf data at all
+                None => outlines.is_some().then_some(0),
+            };
+            let outline = match &outlines {
+                Some((glyf, loca)) => Some(content_hash(
+                    extract::normalized_outline(glyf, loca, id)?.as_bytes(),
+                )),
+                None => None,
+            };
+            let metrics = GlyphMetrics {
+                advance: hmtx.as_ref().and_then(|hmtx| hmtx.advance(gid)),
+                bbox,
+                points,
+                outline,
+            };
+            Ok((codepoint, metrics))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::font_builder::{build_font_bytes, FontOptions};
+    use crate::svg_parser::parse_svg_str;
+
+    #[test]
+    fn test_manifest() {
+        let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 24 24"><path d="M4 4h16v16H4z"/></svg>"#;
+        let mut icon = parse_svg_str(svg, "box", 0xE000).unwrap();
+        icon.filename = r#"say "hi""#.to_string();
+        icon.source = Some(Path::new("icons/nested/box.svg").to_path_buf());
+        icon.categories = vec!["shapes".to_string()];
+        icon.tags = vec!["square".to_string()];
+        let icons = [icon];
+        let data = build_font_bytes(&icons, "Test", &FontOptions::default(), None, false).unwrap();
+
+        let manifest = build_manifest(
+            &icons,
+            "Test",
+            None,
+            Some("2026-10-16T00:00:00Z"),
+            &data,
+            Path::new("icons"),
+        )
+        .unwrap();
+        let data = embed_manifest(&data, &manifest).unwrap();
+        // Read back from the font, as `inspect --manifest` does
+        let font = FontRef::new(&data).unwrap();
+        let parsed = embedded_manifest(&font).unwrap().unwrap();
+        assert!(font.glyf().is_ok());
+        let entry = &parsed["icons"][0];
+        assert_eq!(entry["filename"], r#"say "hi""#);
+        assert_eq!(entry["codepoint"], "E000");
+        assert_eq!(entry["source"], "nested/box.svg");
+        assert_eq!(entry["categories"][0], "shapes");
+        assert_eq!(entry["tags"][0], "square");
+        assert_eq!(entry["points"], 4);
+        assert_eq!(entry["outline"].as_str().unwrap().len(), 64);
+        assert_eq!(entry["advance"], 1000);
+        assert!(entry["bbox"][2].as_i64().unwrap() > entry["bbox"][0].as_i64().unwrap());
+        assert!(entry.get("animated").is_none());
+        assert_eq!(parsed["generated"], "2026-10-16T00:00:00Z");
+        assert!(parsed.get("provenance").is_none());
+    }
 }
@@ -0,0 +1,276 @@
+use crate::font_builder;
+use crate::subset::{codepoint_mappings, remap_glyph};
+use crate::unicode_ranges;
+use anyhow::{Context, Result};
+use read_fonts::{types::GlyphId16, FontRef, TableProvider};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use write_fonts::{
+    from_obj::ToOwnedTable,
+    tables::{cmap::Cmap, glyf::GlyfLocaBuilder, hmtx::Hmtx, post::Post, vmtx::LongMetric},
+    types::{GlyphId, Tag, UfWord, Version16Dot16},
+    FontBuilder,
+};
+
+/// Tables taken from the first font unchanged, ignored in the others
+const COPIED_TABLES: &[&[u8; 4]] = &[b"name", b"gasp"];
+
+/// Tables rebuilt for the merged font
+const REBUILT_TABLES: &[&[u8; 4]] = &[
+    b"head", b"hhea", b"maxp", b"OS/2", b"hmtx", b"cmap", b"post", b"loca", b"glyf",
+];
+
+/// Combine TrueType icon fonts into one.
+///
+/// `fonts` pairs a label used in messages (usually the file name) with the
+/// font data. The glyphs of every font are appended after the first font's
+/// .notdef and all cmaps are unioned. A codepoint mapped by several fonts to
+/// different outlines, or a `post` glyph name used by several fonts, is a
+/// collision; all collisions are reported together as one error. Head, hhea,
+/// OS/2 and name come from the first font, with the extents of all fonts.
+pub fn merge_fonts(fonts: &[(String, Vec<u8>)]) -> Result<Vec<u8>> {
+    let parsed = fonts
+        .iter()
+        .map(|(label, data)| {
+            FontRef::new(data).map_err(|e| anyhow::anyhow!("{} does not parse: {}", label, e))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let first = parsed.first().context("No fonts to merge")?;
+    let units_per_em = first.head()?.units_per_em();
+
+    let mut glyf_builder = GlyfLocaBuilder::new();
+    let mut metrics = Vec::new();
+    let mut glyph_names: Vec<Option<String>> = Vec::new();
+    let mut glyphs = Vec::new();
+    let mut mappings: BTreeMap<u32, (usize, u16)> = BTreeMap::new();
+    let mut name_owners: HashMap<String, usize> = HashMap::new();
+    let mut collisions = Vec::new();
+    let mut maxp: write_fonts::tables::maxp::Maxp = first.maxp()?.to_owned_table();
+    let mut head: write_fonts::tables::head::Head = first.head()?.to_owned_table();
+    let mut hhea: write_fonts::tables::hhea::Hhea = first.hhea()?.to_owned_table();
+    let mut advance_width_max = 0;
+    let mut dropped = BTreeSet::new();
+
+    for (i, font) in parsed.iter().enumerate() {
+        let label = &fonts[i].0;
+        if font.head()?.units_per_em() != units_per_em {
+            anyhow::bail!(
+                "{} has {} units per em, but {} has {}",
+                label,
+                font.head()?.units_per_em(),
+                fonts[0].0,
+                units_per_em
+            );
+        }
+        let glyf = font
+            .glyf()
+            .with_context(|| format!("{} has no TrueType outlines", label))?;
+        let loca = font.loca(None)?;
+        let hmtx = font.hmtx()?;
+        let post = font.post().ok();
+
+        // Every font but the first leaves out its .notdef
+        let skip = usize::from(i > 0) as u16;
+        let offset = glyphs.len() as u32;
+        let num_glyphs = font.maxp()?.num_glyphs();
+        if offset + num_glyphs.saturating_sub(skip) as u32 > u16::MAX as u32 {
+            anyhow::bail!("The merged font would have more than {} glyphs", u16::MAX);
+        }
+        // Components may reference the left out .notdef, which becomes the first font's
+        let new_id = |gid: u16| {
+            gid.checked_sub(skip)
+                .map_or(0, |gid| (offset + gid as u32) as u16)
+        };
+
+        for gid in skip..num_glyphs {
+            let glyph = remap_glyph(loca.get_glyf(GlyphId::from(gid), &glyf)?, new_id)
+                .with_context(|| format!("Glyph {} of {} is broken", gid, label))?;
+            let advance = hmtx.advance(GlyphId::from(gid)).unwrap_or_default();
+            advance_width_max = advance_width_max.max(advance);
+            metrics.push(LongMetric {
+                advance,
+                side_bearing: hmtx.side_bearing(GlyphId::from(gid)).unwrap_or_default(),
+            });
+
+            let name = post
+                .as_ref()
+                .and_then(|post| post.glyph_name(GlyphId16::new(gid)))
+                .map(str::to_string);
+            if let Some(name) = name.as_ref().filter(|_| gid > 0) {
+                match name_owners.get(name) {
+                    Some(&owner) if owner != i => collisions.push(format!(
+                        "glyph name `{}` is used in both {} and {}",
+                        name, fonts[owner].0, label
+                    )),
+                    _ => {
+                        name_owners.insert(name.clone(), i);
+                    }
+                }
+            }
+            glyph_names.push(name);
+            glyphs.push((glyph, advance));
+        }
+
+        for (codepoint, gid) in codepoint_mappings(font)? {
+            let gid = new_id(gid);
+            match mappings.get(&codepoint) {
+                // The same icon in several fonts is fine
+                Some(&(_, existing)) if glyphs[existing as usize] == glyphs[gid as usize] => {}
+                Some(&(owner, _)) => collisions.push(format!(
+                    "U+{:04X} is mapped in both {} and {}",
+                    codepoint, fonts[owner].0, label
+                )),
+                None => {
+                    mappings.insert(codepoint, (i, gid));
+                }
+            }
+        }
+
+        // Every font's extents are already computed from its outlines
+        let source_head = font.head()?;
+        head.x_min = head.x_min.min(source_head.x_min());
+        head.y_min = head.y_min.min(source_head.y_min());
+        head.x_max = head.x_max.max(source_head.x_max());
+        head.y_max = head.y_max.max(source_head.y_max());
+        let source_hhea = font.hhea()?;
+        hhea.min_left_side_bearing = hhea
+            .min_left_side_bearing
+            .min(source_hhea.min_left_side_bearing());
+        hhea.min_right_side_bearing = hhea
+            .min_right_side_bearing
+            .min(source_hhea.min_right_side_bearing());
+        hhea.x_max_extent = hhea.x_max_extent.max(source_hhea.x_max_extent());
+
+        let source_maxp: write_fonts::tables::maxp::Maxp = font.maxp()?.to_owned_table();
+        maxp.max_points = maxp.max_points.max(source_maxp.max_points);
+        maxp.max_contours = maxp.max_contours.max(source_maxp.max_contours);
+        maxp.max_composite_points = maxp
+            .max_composite_points
+            .max(source_maxp.max_composite_points);
+        maxp.max_composite_contours = maxp
+            .max_composite_contours
+            .max(source_maxp.max_composite_contours);
+        maxp.max_component_elements = maxp
+            .max_component_elements
+            .max(source_maxp.max_component_elements);
+        maxp.max_component_depth = maxp
+            .max_component_depth
+            .max(source_maxp.max_component_depth);
+
+        for record in font.table_directory.table_records() {
+            let tag = record.tag();
+            let kept = REBUILT_TABLES
+                .iter()
+                .chain(COPIED_TABLES)
+                .any(|t| Tag::new(t) == tag);
+            if !kept {
+                dropped.insert(tag);
+            }
+        }
+    }
+
+    if !collisions.is_empty() {
+        anyhow::bail!("Fonts collide:\n  {}", collisions.join("\n  "));
+    }
+    for tag in dropped {
+        eprintln!("Warning: Dropped the {} table, which cannot be merged", tag);
+    }
+
+    for (glyph, _) in &glyphs {
+        glyf_builder.add_glyph(glyph)?;
+    }
+    let (glyf, loca, loca_format) = glyf_builder.build();
+    let num_glyphs = glyphs.len() as u16;
+
+    let cmap = Cmap::from_mappings(mappings.iter().filter_map(|(codepoint, (_, gid))| {
+        Some((char::from_u32(*codepoint)?, GlyphId::new(*gid as u32)))
+    }))
+    .map_err(|e| anyhow::anyhow!("Failed to create cmap: {:?}", e))?;
+
+    head.index_to_loc_format = loca_format as i16;
+
+    hhea.number_of_h_metrics = num_glyphs;
+    hhea.advance_width_max = UfWord::new(advance_width_max);
+
+    maxp.num_glyphs = num_glyphs;
+
+    let post = if glyph_names.iter().skip(1).any(Option::is_some) {
+        Post::new_v2(
+            glyph_names
+                .iter()
+                .map(|name| name.as_deref().unwrap_or(".notdef")),
+        )
+    } else {
+        Post {
+            version: Version16Dot16::VERSION_3_0,
+            ..Default::default()
+        }
+    };
+
+    let mut builder = FontBuilder::new();
+    builder
+        .add_table(&head)?
+        .add_table(&hhea)?
+        .add_table(&maxp)?
+        .add_table(&Hmtx::new(metrics, vec![]))?
+        .add_table(&cmap)?
+        .add_table(&post)?
+        .add_table(&loca)?
+        .add_table(&glyf)?;
+
+    if let Ok(os2) = first.os2() {
+        let mut os2: write_fonts::tables::os2::Os2 = os2.to_owned_table();
+        let codepoints = mappings.keys().copied();
+        os2.us_first_char_index = codepoints.clone().min().unwrap_or(0).min(0xFFFF) as u16;
+        os2.us_last_char_index = codepoints.max().unwrap_or(0).min(0xFFFF) as u16;
+        unicode_ranges::set_ranges(&mut os2, &mappings.keys().copied().collect::<Vec<_>>());
+        builder.add_table(&os2)?;
+    }
+
+    for tag in COPIED_TABLES {
+        if let Some(table) = first.table_data(Tag::new(tag)) {
+            builder.add_raw(Tag::new(tag), table.as_bytes().to_vec());
+        }
+    }
+
+    font_builder::build_checked(&mut builder)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::font_builder::{build_font_bytes, FontOptions};
+    use crate::svg_parser::parse_svg_str;
+
+    #[test]
+    fn test_merge_fonts() {
+        let square = r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 24 24"><rect x="4" y="4" width="16" height="16"/></svg>"#;
+        let dot = r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 24 24"><circle cx="12" cy="12" r="4"/></svg>"#;
+        let font = |icons: Vec<(&str, u32)>| {
+            let icons: Vec<_> = icons
+                .into_iter()
+                .map(|(svg, codepoint)| parse_svg_str(svg, "icon", codepoint).unwrap())
+                .collect();
+            build_font_bytes(&icons, "Test", &FontOptions::default(), None, false).unwrap()
+        };
+
+        // A shared identical icon is not a collision
+        let a = ("a.ttf".to_string(), font(vec![(square, 0xE000)]));
+        let b = (
+            "b.ttf".to_string(),
+            font(vec![(square, 0xE000), (dot, 0xE001)]),
+        );
+        let merged = merge_fonts(&[a.clone(), b]).unwrap();
+        let merged = FontRef::new(&merged).unwrap();
+        assert_eq!(merged.maxp().unwrap().num_glyphs(), 6);
+        assert_eq!(
+            codepoint_mappings(&merged).unwrap(),
+            BTreeMap::from([(0x20, 2), (0xE000, 1), (0xE001, 4)])
+        );
+
+        let c = ("c.ttf".to_string(), font(vec![(dot, 0xE000)]));
+        let err = merge_fonts(&[a, c]).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("U+E000 is mapped in both a.ttf and c.ttf"));
+    }
+}
@@ -0,0 +1,156 @@
+//! Rendering icons to PNG images, for raster fallbacks and asset catalogs.
+
+use crate::raster::Mask;
+use crate::svg_parser::{Icon, ShapeFill};
+use anyhow::{Context, Result};
+use flate2::{write::ZlibEncoder, Compression, Crc};
+use kurbo::{Affine, BezPath};
+use rayon::prelude::*;
+use std::io::Write;
+use std::path::Path;
+use usvg::FillRule;
+
+const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1A, b'\n'];
+
+/// An RGBA image with straight (not premultiplied) alpha, row by row
+#[derive(Debug, Clone, PartialEq)]
+pub struct Image {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<[u8; 4]>,
+}
+
+/// Render an icon into a `size` pixel square, with its viewBox scaled to fit
+/// and centered. Shapes are black unless `color` is set, in which case each
+/// keeps its fill color, as in color fonts.
+pub fn render_icon(icon: &Icon, size: u32, color: bool) -> Image {
+    let side = size as f64;
+    let scale = side / icon.width.max(icon.height).max(f64::EPSILON);
+    let transform = Affine::translate((
+        (side - icon.width * scale) / 2.0,
+        (side - icon.height * scale) / 2.0,
+    )) * Affine::scale(scale);
+
+    let shapes = if color && !icon.fills.is_empty() {
+        icon.fills.clone()
+    } else {
+        vec![ShapeFill {
+            elements: 0..icon.path.elements().len(),
+            rgba: ShapeFill::BLACK,
+            secondary: false,
+        }]
+    };
+
+    // Premultiplied color, painted shape by shape
+    let mut canvas = vec![[0.0f32; 4]; (size * size) as usize];
+    for shape in shapes {
+        let Some(elements) = icon.path.elements().get(shape.elements) else {
+            continue;
+        };
+        let mut mask = Mask::new(size as usize, size as usize);
+        // Outlines are wound for the non-zero rule, as in the glyphs
+        mask.fill(
+            &(transform * BezPath::from_vec(elements.to_vec())),
+            FillRule::NonZero,
+        );
+        let [r, g, b, a] = shape.rgba.map(|c| c as f32 / 255.0);
+        for (pixel, &coverage) in canvas.iter_mut().zip(&mask.data) {
+            let alpha = coverage * a;
+            *pixel = [
+                pixel[0] * (1.0 - alpha) + r * alpha,
+                pixel[1] * (1.0 - alpha) + g * alpha,
+                pixel[2] * (1.0 - alpha) + b * alpha,
+                pixel[3] * (1.0 - alpha) + alpha,
+            ];
+        }
+    }
+
+    let pixels = canvas
+        .into_iter()
+        .map(|[r, g, b, a]| {
+            if a <= 0.0 {
+                return [0; 4];
+            }
+            let channel = |c: f32| (c / a * 255.0).round().clamp(0.0, 255.0) as u8;
+            [
+                channel(r),
+                channel(g),
+                channel(b),
+                (a * 255.0).round() as u8,
+            ]
+        })
+        .collect();
+    Image {
+        width: size,
+        height: size,
+        pixels,
+    }
+}
+
+/// Encode an image as an 8-bit RGBA PNG
+pub fn encode_png(image: &Image) -> Result<Vec<u8>> {
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&image.width.to_be_bytes());
+    header.extend_from_slice(&image.height.to_be_bytes());
+    // Bit depth 8, RGBA, deflate, adaptive filtering, no interlace
+    header.extend_from_slice(&[8, 6, 0, 0, 0]);
+
+    // Every row starts with its filter type, 0 for none
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::best());
+    for row in image.pixels.chunks(image.width.max(1) as usize) {
+        encoder.write_all(&[0])?;
+        encoder.write_all(row.as_flattened())?;
+    }
+    let data = encoder.finish()?;
+
+    let mut png = SIGNATURE.to_vec();
+    for (kind, body) in [
+        (b"IHDR", header.as_slice()),
+        (b"IDAT", &data),
+        (b"IEND", &[]),
+    ] {
+        png.extend_from_slice(&(body.len() as u32).to_be_bytes());
+        png.extend_from_slice(kind);
+        png.extend_from_slice(body);
+        let mut crc = Crc::new();
+        crc.update(kind);
+        crc.update(body);
+        png.extend_from_slice(&crc.sum().to_be_bytes());
+    }
+    Ok(png)
+}
+
+/// Write every icon to `<dir>/<name>.png`, `size` pixels square
+pub fn export_pngs(icons: &[Icon], size: u32, color: bool, dir: &Path) -> Result<()> {
+    std::fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    icons.par_iter().try_for_each(|icon| {
+        let path = dir.join(format!("{}.png", icon.name));
+        let png = encode_png(&render_icon(icon, size, color))?;
+        std::fs::write(&path, png).with_context(|| format!("Failed to write {}", path.display()))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::svg_parser::parse_svg_str;
+
+    #[test]
+    fn test_render_icon() {
+        let svg = r##"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 24 12"><rect width="12" height="12" fill="#f00"/></svg>"##;
+        let icon = parse_svg_str(svg, "half", 0xE000).unwrap();
+        // The wide viewBox is centered vertically, with the square on the left
+        let image = render_icon(&icon, 8, true);
+        assert_eq!(image.pixels[2 * 8], [255, 0, 0, 255]);
+        assert_eq!(image.pixels[2 * 8 + 7], [0; 4]);
+        assert_eq!(image.pixels[0], [0; 4]);
+        assert_eq!(render_icon(&icon, 8, false).pixels[2 * 8], [0, 0, 0, 255]);
+
+        let png = encode_png(&image).unwrap();
+        assert_eq!(png[..8], SIGNATURE);
+        assert_eq!(&png[12..16], b"IHDR");
+        assert_eq!(&png[png.len() - 8..png.len() - 4], b"IEND");
+        // CRC of an empty IEND chunk
+        assert_eq!(png[png.len() - 4..], [0xAE, 0x42, 0x60, 0x82]);
+    }
+}
@@ -1,75 +1,87 @@
-use crate::svg_parser::Icon;
+use crate::codegen;
+use crate::family::{FamilyGlyphs, FamilyMember};
+use crate::svg_parser::{self, Icon};
 use anyhow::{Context, Result};
-use std::io::Write;
+use base64::engine::general_purpose::STANDARD;
+use base64::write::EncoderWriter;
+use base64::Engine as _;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::io::{BufWriter, Write};
 use std::path::Path;
 
-/// Generate an HTML preview page for the icon font
+/// Generate an HTML preview page embedding the font at `font_path`, a TTF or
+/// a much smaller WOFF2. With `ligatures`, its text playground starts out
+/// spelling icon names instead of codepoints. A `template` replaces the
+/// built-in page (see [`PreviewContext`]).
 pub fn generate_preview(
     icons: &[Icon],
     font_name: &str,
-    ttf_path: &Path,
+    font_path: &Path,
+    ligatures: bool,
+    template: Option<&Path>,
     output_path: &Path,
 ) -> Result<()> {
-    // Read TTF and encode as base64
-    let ttf_data = std::fs::read(ttf_path)
-        .with_context(|| format!("Failed to read {}", ttf_path.display()))?;
-    let ttf_base64 = base64_encode(&ttf_data);
-
-    let html = generate_html(icons, font_name, &ttf_base64);
+    if let Some(template) = template {
+        let source = std::fs::read_to_string(template)
+            .with_context(|| format!("Failed to read {}", template.display()))?;
+        let font = std::fs::read(font_path)
+            .with_context(|| format!("Failed to read {}", font_path.display()))?;
+        let format = font_format(font_path);
+        let html = render_template(&source, icons, font_name, &font, format, ligatures)
+            .with_context(|| format!("Failed to render {}", template.display()))?;
+        return std::fs::write(output_path, html)
+            .with_context(|| format!("Failed to write {}", output_path.display()));
+    }
 
-    let mut file = std::fs::File::create(output_path)
+    let file = std::fs::File::create(output_path)
         .with_context(|| format!("Failed to create {}", output_path.display()))?;
+    let mut out = BufWriter::new(file);
 
-    file.write_all(html.as_bytes())
+    write_preview(&mut out, icons, font_name, font_path, ligatures)
+        .with_context(|| format!("Failed to write {}", output_path.display()))?;
+    out.flush()
         .with_context(|| format!("Failed to write {}", output_path.display()))?;
 
     Ok(())
 }
 
-fn base64_encode(data: &[u8]) -> String {
-    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
-    let mut result = String::with_capacity(data.len().div_ceil(3) * 4);
-
-    for chunk in data.chunks(3) {
-        let b0 = chunk[0] as usize;
-        let b1 = chunk.get(1).copied().unwrap_or(0) as usize;
-        let b2 = chunk.get(2).copied().unwrap_or(0) as usize;
+/// Write the preview page, streaming the font into it as base64
+fn write_preview(
+    out: &mut impl Write,
+    icons: &[Icon],
+    font_name: &str,
+    font_path: &Path,
+    ligatures: bool,
+) -> Result<()> {
+    let mut font = std::fs::File::open(font_path)
+        .with_context(|| format!("Failed to read {}", font_path.display()))?;
+    let format = font_format(font_path);
 
-        result.push(ALPHABET[b0 >> 2] as char);
-        result.push(ALPHABET[((b0 & 0x03) << 4) | (b1 >> 4)] as char);
+    out.write_all(html_head(font_name, format).as_bytes())?;
 
-        if chunk.len() > 1 {
-            result.push(ALPHABET[((b1 & 0x0f) << 2) | (b2 >> 6)] as char);
-        } else {
-            result.push('=');
-        }
+    let mut encoder = EncoderWriter::new(out, &STANDARD);
+    std::io::copy(&mut font, &mut encoder)
+        .with_context(|| format!("Failed to read {}", font_path.display()))?;
+    let out = encoder.finish()?;
 
-        if chunk.len() > 2 {
-            result.push(ALPHABET[b2 & 0x3f] as char);
-        } else {
-            result.push('=');
-        }
-    }
+    out.write_all(html_body(icons, font_name, format, ligatures).as_bytes())?;
 
-    result
+    Ok(())
 }
 
-fn generate_html(icons: &[Icon], font_name: &str, ttf_base64: &str) -> String {
-    let mut icons_html = String::new();
-
-    for icon in icons {
-        icons_html.push_str(&format!(
-            r#"
-        <div class="icon-card" data-name="{name}" data-codepoint="{codepoint:04X}">
-            <div class="icon-glyph">&#x{codepoint:04X};</div>
-            <div class="icon-name">{name}</div>
-            <div class="icon-code">U+{codepoint:04X}</div>
-        </div>"#,
-            name = icon.filename,
-            codepoint = icon.codepoint
-        ));
+/// CSS format name of a font file, which is also its MIME subtype
+fn font_format(path: &Path) -> &'static str {
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("woff2") => "woff2",
+        Some("woff") => "woff",
+        _ => "truetype",
     }
+}
 
+/// Everything up to the start of the embedded font data
+fn html_head(font_name: &str, format: &str) -> String {
     format!(
         r##"<!DOCTYPE html>
 <html lang="en">
@@ -80,7 +92,152 @@ fn generate_html(icons: &[Icon], font_name: &str, ttf_base64: &str) -> String {
     <style>
         @font-face {{
             font-family: '{font_name}';
-            src: url('data:font/truetype;base64,{ttf_base64}') format('truetype');
+            src: url('data:font/{format};base64,"##,
+        font_name = font_name
+    )
+}
+
+/// The icon's source SVG as a data URI, for comparing it with the glyph.
+///
+/// Shown through `<img>`, so scripts in the SVG never run and its IDs cannot
+/// clash with the page's.
+fn source_image(icon: &Icon) -> Option<String> {
+    let svg = svg_parser::read_svg(icon.source.as_deref()?).ok()?;
+    Some(format!(
+        "data:image/svg+xml;base64,{}",
+        STANDARD.encode(svg)
+    ))
+}
+
+/// Pixel sizes of the row showing each glyph at common UI sizes
+const ROW_SIZES: [u32; 4] = [16, 24, 32, 48];
+
+/// Section of the preview for icons without a category
+const UNCATEGORIZED: &str = "Uncategorized";
+
+/// The grid of icon cards, split into a collapsible section per category
+/// when any icon has one. Icons in several categories show in each.
+fn icons_html<'a>(icons: &'a [Icon], font_name: &str) -> (String, Vec<&'a str>) {
+    let cards: Vec<String> = icons
+        .iter()
+        .map(|icon| icon_card(icon, font_name))
+        .collect();
+    if icons.iter().all(|icon| icon.categories.is_empty()) {
+        return (
+            format!("<div class=\"grid\">{}\n        </div>", cards.concat()),
+            Vec::new(),
+        );
+    }
+
+    let mut sections: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+    let mut uncategorized = Vec::new();
+    for (icon, card) in icons.iter().zip(&cards) {
+        if icon.categories.is_empty() {
+            uncategorized.push(card.as_str());
+        }
+        for category in &icon.categories {
+            sections.entry(category).or_default().push(card);
+        }
+    }
+    let uncategorized = (!uncategorized.is_empty()).then_some((UNCATEGORIZED, uncategorized));
+    let mut html = String::new();
+    let mut categories = Vec::new();
+    for (category, cards) in sections.into_iter().chain(uncategorized) {
+        categories.push(category);
+        let _ = write!(
+            html,
+            r#"
+        <details class="category" data-category="{category}" open>
+            <summary><span class="category-name">{category}</span> <span class="category-count">{count}</span></summary>
+            <div class="grid">{cards}
+            </div>
+        </details>"#,
+            category = escape_html(category),
+            count = cards.len(),
+            cards = cards.concat()
+        );
+    }
+    (html, categories)
+}
+
+/// One icon's card, with its glyph, source SVG, name, codepoint and snippets
+fn icon_card(icon: &Icon, font_name: &str) -> String {
+    let source = source_image(icon)
+        .map(|uri| format!(r#"<img class="icon-source" src="{}" alt="">"#, uri))
+        .unwrap_or_default();
+    let snippets = codegen::icon_snippets(icon, font_name);
+    let mut buttons = String::new();
+    for (label, title, text) in [
+        ("CSS", "CSS class", &snippets.css_class),
+        ("HTML", "HTML entity", &snippets.html_entity),
+        ("Dart", "Flutter IconData", &snippets.dart),
+        ("\\u", "Unicode escape", &snippets.escape),
+    ] {
+        let _ = write!(
+            buttons,
+            r#"<button class="snippet" data-copy="{}" title="Copy {}">{}</button>"#,
+            escape_html(text),
+            title,
+            label
+        );
+    }
+
+    let sizes: String = ROW_SIZES
+        .iter()
+        .map(|size| {
+            format!(
+                r#"<span style="font-size: {size}px; width: {size}px; height: {size}px" title="{size}px">&#x{codepoint:04X};</span>"#,
+                codepoint = icon.codepoint
+            )
+        })
+        .collect();
+
+    // Show the accessibility text as a tooltip
+    let title = match (&icon.label, &icon.description) {
+        (Some(label), Some(description)) => format!("{}: {}", label, description),
+        (Some(text), None) | (None, Some(text)) => text.clone(),
+        (None, None) => String::new(),
+    };
+
+    format!(
+        r#"
+    <div class="icon-card" data-name="{name}" data-codepoint="{codepoint:04X}" data-tags="{tags}" title="{title}">
+        <div class="icon-glyph"><span>&#x{codepoint:04X};</span>{source}</div>
+        <div class="icon-sizes">{sizes}</div>
+        <div class="icon-name">{name}</div>
+        <div class="icon-code">U+{codepoint:04X}</div>
+        <div class="icon-snippets">{buttons}</div>
+    </div>"#,
+        name = icon.filename,
+        codepoint = icon.codepoint,
+        tags = escape_html(&icon.tags.join(" ")),
+        title = escape_html(&title),
+        source = source,
+        sizes = sizes,
+        buttons = buttons
+    )
+}
+
+/// Everything after the embedded font data
+fn html_body(icons: &[Icon], font_name: &str, format: &str, ligatures: bool) -> String {
+    let (icons_html, categories) = icons_html(icons, font_name);
+    // Only sectioned previews get a category filter
+    let category_filter = if categories.is_empty() {
+        String::new()
+    } else {
+        let options: String = categories
+            .iter()
+            .map(|c| format!("<option>{}</option>", escape_html(c)))
+            .collect();
+        format!(
+            r#"<select class="category-filter" id="category"><option value="">All categories</option>{}</select>"#,
+            options
+        )
+    };
+    let playground_text = escape_html(&playground_text(icons, ligatures));
+
+    format!(
+        r##"') format('{format}');
             font-weight: normal;
             font-style: normal;
         }}
@@ -254,11 +411,41 @@ fn generate_html(icons: &[Icon], font_name: &str, ttf_base64: &str) -> String {
             transform: translateY(-2px);
         }}
 
-        .icon-card.hidden {{
+        .icon-card.hidden, .category.hidden {{
             display: none;
         }}
 
+        .category {{
+            margin-bottom: 2rem;
+        }}
+
+        .category summary {{
+            cursor: pointer;
+            font-size: 1rem;
+            font-weight: 600;
+            margin-bottom: 1rem;
+        }}
+
+        .category-count {{
+            color: var(--text-muted);
+            font-size: 0.875rem;
+            font-weight: normal;
+        }}
+
+        .category-filter {{
+            background: var(--input-bg);
+            border: 1px solid var(--border-color);
+            border-radius: 8px;
+            padding: 0.5rem;
+            color: var(--text-primary);
+            font-size: 0.875rem;
+        }}
+
         .icon-glyph {{
+            display: flex;
+            justify-content: center;
+            align-items: center;
+            gap: 0.5rem;
             font-family: '{font_name}';
             font-size: var(--icon-size, 32px);
             line-height: 1;
@@ -266,6 +453,57 @@ fn generate_html(icons: &[Icon], font_name: &str, ttf_base64: &str) -> String {
             color: var(--icon-color);
         }}
 
+        /* The source SVG, drawn in its own colors on white */
+        .icon-source {{
+            display: none;
+            width: var(--icon-size, 32px);
+            height: var(--icon-size, 32px);
+            background: #fff;
+            border-radius: 4px;
+        }}
+
+        .compare .icon-source {{
+            display: block;
+        }}
+
+        .compare-toggle {{
+            font-size: 0.875rem;
+            color: var(--text-secondary);
+            display: flex;
+            align-items: center;
+            gap: 0.25rem;
+            cursor: pointer;
+        }}
+
+        /* The glyph at each of ROW_SIZES, shown with "All sizes" */
+        .icon-sizes {{
+            display: none;
+            justify-content: center;
+            align-items: flex-end;
+            gap: 0.375rem;
+            margin-bottom: 0.75rem;
+            font-family: '{font_name}';
+            color: var(--icon-color);
+        }}
+
+        .icon-sizes span {{
+            display: block;
+            line-height: 1;
+        }}
+
+        .sizes .grid {{
+            grid-template-columns: repeat(auto-fill, minmax(200px, 1fr));
+        }}
+
+        .sizes .icon-sizes {{
+            display: flex;
+        }}
+
+        /* A checkerboard with one square per CSS pixel */
+        .pixel-grid .icon-sizes span {{
+            background: repeating-conic-gradient(rgba(128, 128, 128, 0.2) 0 25%, transparent 0 50%) 0 0 / 2px 2px;
+        }}
+
         .icon-name {{
             font-size: 0.75rem;
             color: var(--text-secondary);
@@ -279,6 +517,61 @@ fn generate_html(icons: &[Icon], font_name: &str, ttf_base64: &str) -> String {
             font-family: monospace;
         }}
 
+        .icon-snippets {{
+            display: flex;
+            justify-content: center;
+            flex-wrap: wrap;
+            gap: 0.25rem;
+            margin-top: 0.5rem;
+        }}
+
+        .snippet {{
+            background: var(--input-bg);
+            border: 1px solid var(--border-color);
+            border-radius: 4px;
+            color: var(--text-secondary);
+            font-family: monospace;
+            font-size: 0.625rem;
+            padding: 0.125rem 0.375rem;
+            cursor: pointer;
+        }}
+
+        .snippet:hover {{
+            border-color: var(--border-hover);
+            color: var(--text-primary);
+        }}
+
+        .playground {{
+            display: grid;
+            grid-template-columns: repeat(auto-fit, minmax(280px, 1fr));
+            gap: 1rem;
+            margin-bottom: 2rem;
+        }}
+
+        .playground textarea {{
+            background: var(--input-bg);
+            border: 1px solid var(--border-color);
+            border-radius: 8px;
+            padding: 0.75rem 1rem;
+            color: var(--text-primary);
+            font-family: monospace;
+            font-size: 0.875rem;
+            min-height: 5rem;
+            resize: vertical;
+        }}
+
+        .playground-output {{
+            background: var(--bg-card);
+            border: 1px solid var(--border-color);
+            border-radius: 8px;
+            padding: 0.75rem 1rem;
+            font-family: '{font_name}', sans-serif;
+            font-size: var(--icon-size, 32px);
+            color: var(--icon-color);
+            white-space: pre-wrap;
+            word-break: break-word;
+        }}
+
         .toast {{
             position: fixed;
             bottom: 2rem;
@@ -316,11 +609,21 @@ fn generate_html(icons: &[Icon], font_name: &str, ttf_base64: &str) -> String {
             </div>
             <div class="controls">
                 <input type="text" class="search-box" placeholder="Search icons..." id="search">
+                {category_filter}
                 <div class="size-control">
                     <label>Size:</label>
                     <input type="range" class="size-slider" id="size" min="16" max="64" value="32">
                     <span id="size-value">32px</span>
                 </div>
+                <label class="compare-toggle" title="Show each icon's source SVG next to its glyph">
+                    <input type="checkbox" id="compare"> Compare SVG
+                </label>
+                <label class="compare-toggle" title="Show each icon at 16, 24, 32 and 48px">
+                    <input type="checkbox" id="sizes"> All sizes
+                </label>
+                <label class="compare-toggle" title="Draw the all-sizes row on a pixel grid">
+                    <input type="checkbox" id="pixel-grid"> Pixel grid
+                </label>
                 <button class="theme-toggle" id="theme-toggle" title="Toggle theme">
                     <span class="theme-icon">🌙</span>
                 </button>
@@ -329,7 +632,11 @@ fn generate_html(icons: &[Icon], font_name: &str, ttf_base64: &str) -> String {
     </header>
 
     <main class="container">
-        <div class="grid" id="grid">
+        <section class="playground">
+            <textarea id="playground" spellcheck="false" title="Icon names (with ligatures), U+E001, \u{{E001}}, &amp;#xE001; or 0xE001" aria-label="Text to render">{playground_text}</textarea>
+            <div class="playground-output" id="playground-output"></div>
+        </section>
+        <div id="grid">
             {icons_html}
         </div>
     </main>
@@ -373,24 +680,52 @@ fn generate_html(icons: &[Icon], font_name: &str, ttf_base64: &str) -> String {
             setTheme(newTheme);
         }});
 
-        // Search functionality
-        search.addEventListener('input', (e) => {{
-            const query = e.target.value.toLowerCase();
+        // Search and category filters
+        const categoryFilter = document.getElementById('category');
+        function applyFilters() {{
+            const query = search.value.toLowerCase();
             document.querySelectorAll('.icon-card').forEach(card => {{
                 const name = card.dataset.name.toLowerCase();
                 const code = card.dataset.codepoint.toLowerCase();
-                const matches = name.includes(query) || code.includes(query);
+                const tags = card.dataset.tags.toLowerCase();
+                const matches = name.includes(query) || code.includes(query) || tags.includes(query);
                 card.classList.toggle('hidden', !matches);
             }});
 
-            const visible = document.querySelectorAll('.icon-card:not(.hidden)').length;
+            const category = categoryFilter ? categoryFilter.value : '';
+            document.querySelectorAll('.category').forEach(section => {{
+                const count = section.querySelectorAll('.icon-card:not(.hidden)').length;
+                section.querySelector('.category-count').textContent = count;
+                const shown = count > 0 && (!category || section.dataset.category === category);
+                section.classList.toggle('hidden', !shown);
+            }});
+
+            const visible = document.querySelectorAll('#grid > .grid .icon-card:not(.hidden), .category:not(.hidden) .icon-card:not(.hidden)').length;
             const noResults = document.querySelector('.no-results');
             if (visible === 0 && !noResults) {{
                 grid.insertAdjacentHTML('beforeend', '<div class="no-results">No icons found</div>');
             }} else if (visible > 0 && noResults) {{
                 noResults.remove();
             }}
-        }});
+        }}
+        search.addEventListener('input', applyFilters);
+        if (categoryFilter) categoryFilter.addEventListener('change', applyFilters);
+
+        // Text playground: codepoints written as U+E001, \u{{E001}}, &#xE001; or
+        // 0xE001 become their characters, the rest shows as typed so
+        // ligatures can form
+        const playground = document.getElementById('playground');
+        const playgroundOutput = document.getElementById('playground-output');
+        function renderPlayground() {{
+            playgroundOutput.textContent = playground.value.replace(
+                /U\+([0-9A-Fa-f]{{4,6}})|\\u\{{([0-9A-Fa-f]{{1,6}})\}}|&#x([0-9A-Fa-f]{{1,6}});|0x([0-9A-Fa-f]{{4,6}})/g,
+                (match, ...hex) => {{
+                    const code = parseInt(hex.slice(0, 4).find(h => h !== undefined), 16);
+                    return code <= 0x10FFFF ? String.fromCodePoint(code) : match;
+                }});
+        }}
+        playground.addEventListener('input', renderPlayground);
+        renderPlayground();
 
         // Size control
         sizeSlider.addEventListener('input', (e) => {{
@@ -399,13 +734,27 @@ fn generate_html(icons: &[Icon], font_name: &str, ttf_base64: &str) -> String {
             sizeValue.textContent = size + 'px';
         }});
 
+        // Toggles for the source SVGs next to the glyphs, the all-sizes row
+        // and its pixel grid, each a class on the body remembered across visits
+        ['compare', 'sizes', 'pixel-grid'].forEach(id => {{
+            const toggle = document.getElementById(id);
+            function set(on) {{
+                document.body.classList.toggle(id, on);
+                toggle.checked = on;
+                localStorage.setItem(id, on ? '1' : '');
+            }}
+            set(localStorage.getItem(id) === '1');
+            toggle.addEventListener('change', () => set(toggle.checked));
+        }});
+
         // Copy on click
         grid.addEventListener('click', (e) => {{
             const card = e.target.closest('.icon-card');
             if (!card) return;
 
-            const codepoint = card.dataset.codepoint;
-            const text = `U+${{codepoint}}`;
+            // A snippet button copies its snippet, the rest of the card the codepoint
+            const snippet = e.target.closest('.snippet');
+            const text = snippet ? snippet.dataset.copy : `U+${{card.dataset.codepoint}}`;
 
             navigator.clipboard.writeText(text).then(() => {{
                 toast.textContent = `Copied ${{text}}`;
@@ -417,8 +766,229 @@ fn generate_html(icons: &[Icon], font_name: &str, ttf_base64: &str) -> String {
 </body>
 </html>"##,
         font_name = font_name,
-        ttf_base64 = ttf_base64,
         icon_count = icons.len(),
         icons_html = icons_html
     )
 }
+
+/// Variables of a custom preview template, rendered with Tera
+#[derive(Debug, Serialize)]
+pub struct PreviewContext<'a> {
+    pub font_name: &'a str,
+    /// The font as base64, for an `@font-face` data URI
+    pub font_base64: String,
+    /// CSS format of the font, `truetype` or `woff2`, also its MIME subtype
+    pub font_format: &'a str,
+    /// Prefix of the CSS classes, as in the generated stylesheets
+    pub css_prefix: String,
+    pub icon_count: usize,
+    pub ligatures: bool,
+    pub icons: Vec<PreviewIcon<'a>>,
+    /// Every category in use, sorted
+    pub categories: Vec<&'a str>,
+    /// The built-in grid of icon cards, for templates that only restyle the page
+    pub grid_html: String,
+}
+
+/// One icon as a template sees it
+#[derive(Debug, Serialize)]
+pub struct PreviewIcon<'a> {
+    pub name: &'a str,
+    pub filename: &'a str,
+    /// Codepoint in hex, e.g. `E001`
+    pub codepoint: String,
+    /// The icon's character, to print in the font
+    pub char: String,
+    pub css_class: String,
+    pub html_entity: String,
+    pub dart: String,
+    pub escape: String,
+    pub label: Option<&'a str>,
+    pub description: Option<&'a str>,
+    pub categories: &'a [String],
+    pub tags: &'a [String],
+    /// The source SVG as a data URI
+    pub source_svg: Option<String>,
+}
+
+/// Render a custom preview template embedding `font` of the CSS format
+/// `font_format`, with HTML autoescaping
+fn render_template(
+    template: &str,
+    icons: &[Icon],
+    font_name: &str,
+    font: &[u8],
+    font_format: &str,
+    ligatures: bool,
+) -> Result<String> {
+    let preview_icons = icons
+        .iter()
+        .map(|icon| {
+            let snippets = codegen::icon_snippets(icon, font_name);
+            PreviewIcon {
+                name: &icon.name,
+                filename: &icon.filename,
+                codepoint: format!("{:04X}", icon.codepoint),
+                char: char::from_u32(icon.codepoint)
+                    .map(String::from)
+                    .unwrap_or_default(),
+                css_class: snippets.css_class,
+                html_entity: snippets.html_entity,
+                dart: snippets.dart,
+                escape: snippets.escape,
+                label: icon.label.as_deref(),
+                description: icon.description.as_deref(),
+                categories: &icon.categories,
+                tags: &icon.tags,
+                source_svg: source_image(icon),
+            }
+        })
+        .collect();
+    let mut categories: Vec<&str> = icons
+        .iter()
+        .flat_map(|icon| icon.categories.iter().map(String::as_str))
+        .collect();
+    categories.sort_unstable();
+    categories.dedup();
+
+    let context = PreviewContext {
+        font_name,
+        font_base64: STANDARD.encode(font),
+        font_format,
+        css_prefix: codegen::css_prefix(font_name),
+        icon_count: icons.len(),
+        ligatures,
+        icons: preview_icons,
+        categories,
+        grid_html: icons_html(icons, font_name).0,
+    };
+    let context = tera::Context::from_serialize(&context)?;
+    Ok(tera::Tera::one_off(template, &context, true)?)
+}
+
+/// The playground's starting text: a few icon names when they form ligatures,
+/// otherwise their codepoints
+fn playground_text(icons: &[Icon], ligatures: bool) -> String {
+    let sample = &icons[..icons.len().min(5)];
+    if ligatures {
+        // Single-character names never become ligatures
+        let names: Vec<&str> = sample
+            .iter()
+            .map(|icon| icon.name.as_str())
+            .filter(|name| name.is_ascii() && name.len() > 1)
+            .collect();
+        if !names.is_empty() {
+            return names.join(" ");
+        }
+    }
+    sample
+        .iter()
+        .map(|icon| format!("U+{:04X}", icon.codepoint))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Generate an HTML page showing every icon of a family side by side in each style
+pub fn generate_family_preview(
+    family: &str,
+    members: &[FamilyMember],
+    glyphs: &FamilyGlyphs,
+    output_path: &Path,
+) -> Result<()> {
+    let mut faces = String::new();
+    let mut header = String::new();
+    for (i, member) in members.iter().enumerate() {
+        // Writing to a String cannot fail
+        let _ = write!(
+            faces,
+            r#"
+        @font-face {{
+            font-family: 'style-{i}';
+            src: url('data:font/truetype;base64,{data}') format('truetype');
+        }}
+        .style-{i} {{ font-family: 'style-{i}'; }}"#,
+            data = STANDARD.encode(&member.data)
+        );
+        let _ = write!(header, "<th>{}</th>", escape_html(&member.subfamily));
+    }
+
+    let mut rows = String::new();
+    for (codepoint, name) in glyphs {
+        let _ = write!(
+            rows,
+            "\n            <tr><td class=\"icon-name\">{}</td><td class=\"icon-code\">U+{:04X}</td>",
+            escape_html(name.as_deref().unwrap_or("")),
+            codepoint
+        );
+        for i in 0..members.len() {
+            let _ = write!(
+                rows,
+                "<td class=\"icon-glyph style-{}\">&#x{:04X};</td>",
+                i, codepoint
+            );
+        }
+        rows.push_str("</tr>");
+    }
+
+    let html = format!(
+        r##"<!DOCTYPE html>
+<html lang="en">
+<head>
+    <meta charset="UTF-8">
+    <meta name="viewport" content="width=device-width, initial-scale=1.0">
+    <title>{family} - Font Family Preview</title>
+    <style>{faces}
+        body {{ font-family: -apple-system, BlinkMacSystemFont, 'Segoe UI', Roboto, sans-serif; margin: 2rem; color: #1a1a1a; }}
+        table {{ border-collapse: collapse; }}
+        th, td {{ padding: 0.5rem 1rem; border-bottom: 1px solid #e0e0e0; text-align: left; }}
+        .icon-glyph {{ font-size: 32px; text-align: center; }}
+        .icon-name {{ font-family: monospace; }}
+        .icon-code {{ font-family: monospace; color: #666; }}
+    </style>
+</head>
+<body>
+    <h1>{family}</h1>
+    <table>
+        <thead>
+            <tr><th>Name</th><th>Codepoint</th>{header}</tr>
+        </thead>
+        <tbody>{rows}
+        </tbody>
+    </table>
+</body>
+</html>"##,
+        family = escape_html(family),
+    );
+
+    std::fs::write(output_path, html)
+        .with_context(|| format!("Failed to write {}", output_path.display()))
+}
+
+/// Escape text for use in HTML content and attribute values
+pub(crate) fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::svg_parser::parse_svg_str;
+
+    #[test]
+    fn test_render_template() {
+        let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 24 24"><rect width="24" height="24"/></svg>"#;
+        let mut icon = parse_svg_str(svg, "home", 0xE001).unwrap();
+        icon.label = Some("Home <main>".to_string());
+        let template = "{{ font_name }}: {% for icon in icons %}{{ icon.css_class }} U+{{ icon.codepoint }} {{ icon.label }}{% endfor %} {{ font_base64 }}";
+        let html =
+            render_template(template, &[icon], "My Icons", b"ttf", "truetype", false).unwrap();
+        assert_eq!(
+            html,
+            "My Icons: my-icons-home U+E001 Home &lt;main&gt; dHRm"
+        );
+        assert!(render_template("{{ missing }}", &[], "My Icons", b"", "truetype", false).is_err());
+    }
+}
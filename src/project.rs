@@ -0,0 +1,1119 @@
+//! The whole `generate` pipeline behind one call, for build scripts and
+//! other tools that embed svg2font.
+
+use crate::{
+    archive, cache, codegen, codepoints, config, docs, duotone, flutter, font_builder, iconset,
+    identifier, license, lockfile, manifest, png, preview, provenance, remote, rust_crate,
+    specimen, sprite, styles, svg_parser, svg_table, variants, woff,
+};
+use anyhow::{Context, Result};
+use clap::Parser;
+use rayon::prelude::*;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, clap::Args)]
+pub struct GenerateOptions {
+    /// Input directory, zip archive, zip URL or Iconify/IcoMoon JSON with the icons, and the config and lockfile when files are given
+    #[arg(short, long, default_value = "./icons")]
+    pub input: PathBuf,
+
+    /// SVG files to build instead of every SVG in the input directory
+    #[arg(value_name = "FILE")]
+    pub files: Vec<PathBuf>,
+
+    /// Read more SVG file paths from this file, one per line, or `-` for stdin
+    #[arg(long, value_name = "LIST")]
+    pub files_from: Option<PathBuf>,
+
+    /// Output directory for generated files
+    #[arg(short, long, default_value = "./output")]
+    pub output: PathBuf,
+
+    /// Font family name
+    #[arg(short, long, default_value = "Icons")]
+    pub name: String,
+
+    /// Generate HTML preview page
+    #[arg(short, long)]
+    pub preview: bool,
+
+    /// Tera template to render the preview page with instead of the built-in one
+    #[arg(long, value_name = "FILE", requires = "preview")]
+    pub preview_template: Option<PathBuf>,
+
+    /// Generate a TypeScript module mapping icon names to characters, with a union type of the names
+    #[arg(long)]
+    pub typescript: bool,
+
+    /// Generate a React component showing icons by name (includes the TypeScript module)
+    #[arg(long)]
+    pub react: bool,
+
+    /// Generate a Swift enum of the icons' characters
+    #[arg(long)]
+    pub swift: bool,
+
+    /// Generate a Kotlin object of the icons' characters
+    #[arg(long)]
+    pub kotlin: bool,
+
+    /// Package of the generated Kotlin object, e.g. com.example.icons
+    #[arg(long, requires = "kotlin")]
+    pub kotlin_package: Option<String>,
+
+    /// Generate a Flutter package with the font, its pubspec.yaml and a Dart class of IconData constants
+    #[arg(long)]
+    pub flutter_package: bool,
+
+    /// Generate a Rust crate embedding the font, with an `Icon` enum of its codepoints
+    #[arg(long)]
+    pub rust_crate: bool,
+
+    /// Generate an IcoMoon selection.json of the font, for IcoMoon and tools that read it
+    #[arg(long)]
+    pub icomoon: bool,
+
+    /// Write a `.codepoints` file of `name hex` lines, as Material Symbols ships
+    #[arg(long)]
+    pub codepoints_list: bool,
+
+    /// Render each icon to `png/<SIZE>/<name>.png` at every `--png-size`
+    #[arg(long)]
+    pub export_png: bool,
+
+    /// Pixel sizes of the exported PNGs
+    #[arg(long, value_delimiter = ',', default_values_t = [24, 48], value_parser = clap::value_parser!(u32).range(1..=4096))]
+    pub png_size: Vec<u32>,
+
+    /// Write a PNG sprite sheet of all icons with a stylesheet of classes showing each one
+    #[arg(long)]
+    pub sprite: bool,
+
+    /// Pixel size of each icon in the sprite sheet
+    #[arg(long, default_value_t = 24, value_parser = clap::value_parser!(u32).range(1..=1024))]
+    pub sprite_size: u32,
+
+    /// Write a printable PDF of every glyph with its name and codepoint
+    #[arg(long)]
+    pub specimen_pdf: bool,
+
+    /// Write a static documentation site with a page per category and icon to `docs/`
+    #[arg(long)]
+    pub docs: bool,
+
+    /// Store the manifest in the font itself, in a private `SVGM` table
+    #[arg(long)]
+    pub embed_manifest: bool,
+
+    /// License to embed: MIT, OFL-1.1, proprietary or file:PATH
+    #[arg(long)]
+    pub license: Option<license::License>,
+
+    /// URL of the license terms, in place of the preset license's URL
+    #[arg(long, value_name = "URL")]
+    pub license_url: Option<String>,
+
+    /// Copyright holder, e.g. "2026 Example Corp" [default: the <NAME> authors]
+    #[arg(long)]
+    pub copyright: Option<String>,
+
+    /// Font version as MAJOR.MINOR, e.g. 2.1 [default: 1.0]
+    #[arg(long, value_parser = font_builder::parse_font_version)]
+    pub version: Option<String>,
+
+    /// Designer name to record in the font
+    #[arg(long)]
+    pub designer: Option<String>,
+
+    /// Description to record in the font
+    #[arg(long)]
+    pub description: Option<String>,
+
+    /// Four-character vendor ID registered with Microsoft, e.g. ACME
+    #[arg(long, value_parser = font_builder::parse_vendor_id)]
+    pub vendor_id: Option<write_fonts::types::Tag>,
+
+    /// Config file [default: svg2font.toml in the input directory, if present]
+    #[arg(short, long)]
+    pub config: Option<PathBuf>,
+
+    /// Add mirrored `_rtl` variants of direction-sensitive icons
+    #[arg(long)]
+    pub rtl: bool,
+
+    /// Languages that icon identifiers must be valid in
+    #[arg(long, value_delimiter = ',', default_values = ["dart", "typescript", "swift", "kotlin", "rust"])]
+    pub languages: Vec<identifier::Language>,
+
+    /// Fail on invalid identifiers instead of renaming them
+    #[arg(long)]
+    pub strict_identifiers: bool,
+
+    /// Also map icons to U+F021.. in a Windows Symbol cmap for legacy applications
+    #[arg(long)]
+    pub symbol_cmap: bool,
+
+    /// Weight class, from 100 (Thin) to 900 (Black) [default: 400]
+    #[arg(long, value_parser = clap::value_parser!(u16).range(100..=900))]
+    pub weight: Option<u16>,
+
+    /// Width class, from 1 (Ultra-condensed) to 9 (Ultra-expanded) [default: 5]
+    #[arg(long, value_parser = clap::value_parser!(u16).range(1..=9))]
+    pub width: Option<u16>,
+
+    /// Mark the font italic, optionally with the glyphs' slant in degrees (negative leans right)
+    #[arg(long, value_name = "ANGLE", num_args = 0..=1, default_missing_value = "0", allow_negative_numbers = true)]
+    pub italic: Option<f64>,
+
+    /// Embedding permissions: installable, restricted, preview or editable
+    #[arg(long, value_enum, default_value_t = font_builder::Embedding::Installable)]
+    pub embedding: font_builder::Embedding,
+
+    /// Adjust tables for applications with special requirements
+    #[arg(long, value_enum)]
+    pub compat: Option<font_builder::Compatibility>,
+
+    /// Split translucent shapes into a second glyph per icon and emit CSS to stack them
+    #[arg(long)]
+    pub duotone: bool,
+
+    /// Add COLR/CPAL color layers so multi-color icons keep their colors
+    #[arg(long, conflicts_with = "duotone")]
+    pub color: bool,
+
+    /// Add a `liga` feature so typing an icon's name shows the icon
+    #[arg(long)]
+    pub ligatures: bool,
+
+    /// SVG to draw for characters the font has no glyph for, instead of nothing
+    #[arg(long, value_name = "SVG")]
+    pub notdef: Option<PathBuf>,
+
+    /// Embed each icon's sanitized SVG in an `SVG ` table, keeping the outlines as a fallback
+    #[arg(long)]
+    pub svg_table: bool,
+
+    /// Build glyphs one icon at a time, keeping memory flat for huge sets
+    #[arg(long, conflicts_with = "duotone")]
+    pub low_memory: bool,
+
+    /// Deduplicate glyphs and compact tables, reporting per-table savings
+    #[arg(long)]
+    pub optimize_size: bool,
+
+    /// Record the svg2font version, git commit and build time in the font and manifest
+    #[arg(long)]
+    pub provenance: bool,
+
+    /// Leave the build time out of the provenance and manifest so rebuilds are byte-identical
+    #[arg(long)]
+    pub deterministic: bool,
+
+    /// Build separate Filled and Outlined fonts from `-filled`/`-outline` SVG pairs
+    #[arg(long, conflicts_with_all = ["duotone", "low_memory"])]
+    pub styles: bool,
+
+    /// Build one variable font with a FILL axis from `-filled`/`-outline` SVG pairs
+    #[arg(long, conflicts_with_all = ["styles", "duotone", "low_memory", "color", "otf"])]
+    pub variable: bool,
+
+    /// Also write an OpenType font with CFF outlines, keeping the SVGs' cubic curves
+    // The CFF build has no ligature, color layer or SVG tables, so it would
+    // behave differently from the TTF with those options
+    #[arg(long, conflicts_with_all = ["low_memory", "ligatures", "color", "svg_table"])]
+    pub otf: bool,
+
+    /// Web font formats to write next to each TTF
+    #[arg(long, value_delimiter = ',')]
+    pub formats: Vec<woff::WebFormat>,
+
+    /// Brotli quality for WOFF2 output, from 0 (fastest) to 11 (smallest)
+    #[arg(long, default_value_t = woff::MAX_BROTLI_QUALITY, value_parser = clap::value_parser!(u32).range(0..=11))]
+    pub brotli_quality: u32,
+
+    /// Maximum distance in font units between a cubic curve and its quadratic approximation
+    #[arg(long, default_value_t = font_builder::DEFAULT_CURVE_TOLERANCE, value_parser = font_builder::parse_curve_tolerance)]
+    pub curve_tolerance: f64,
+
+    /// How the SVG viewBox is placed between the ascender and descender
+    #[arg(long, value_enum, default_value = "none")]
+    pub fit: font_builder::VerticalFit,
+
+    /// Font units to move every glyph up by, or down if negative
+    #[arg(long, default_value_t = 0, allow_negative_numbers = true)]
+    pub baseline_offset: i16,
+
+    /// Fit each glyph's advance to its outline instead of making every icon one em wide
+    #[arg(long, conflicts_with_all = ["duotone", "svg_table"])]
+    pub proportional: bool,
+
+    /// Space in font units on either side of each glyph with `--proportional`
+    #[arg(long, default_value_t = 50, requires = "proportional")]
+    pub side_bearing: u16,
+
+    /// Crop each icon to its outline, centered in a square, instead of its viewBox
+    #[arg(long)]
+    pub trim: bool,
+
+    /// Margin around each icon, in percent of its larger side
+    #[arg(long, default_value_t = 0.0, value_parser = font_builder::parse_padding)]
+    pub padding: f64,
+
+    /// Center each icon horizontally by its bounding box or its visual weight
+    #[arg(long, value_enum, default_value = "none")]
+    pub center_x: font_builder::Centering,
+
+    /// Center each icon vertically by its bounding box or its visual weight
+    #[arg(long, value_enum, default_value = "none")]
+    pub center_y: font_builder::Centering,
+
+    /// Units per em of the font, which icons are scaled to [default: 1000, or `font.units_per_em` in the config]
+    #[arg(long)]
+    pub units_per_em: Option<u16>,
+
+    /// Ascender in font units [default: 80% of the em, or `font.ascender` in the config]
+    #[arg(long, allow_negative_numbers = true)]
+    pub ascender: Option<i16>,
+
+    /// Descender in font units, usually negative [default: -20% of the em, or `font.descender` in the config]
+    #[arg(long, allow_negative_numbers = true)]
+    pub descender: Option<i16>,
+
+    /// Lockfile keeping codepoints stable [default: svg2font.lock in the input directory]
+    #[arg(long)]
+    pub lockfile: Option<PathBuf>,
+
+    /// Fail if a locked icon would get a different codepoint or name, or disappeared without `remove`
+    #[arg(long)]
+    pub frozen: bool,
+
+    /// JSON file mapping icon names to codepoints (e.g. {"home": "U+E900"}), overriding the lockfile
+    #[arg(long)]
+    pub codepoints: Option<PathBuf>,
+
+    /// Cache converted outlines here so unchanged SVGs are not re-parsed
+    #[arg(long)]
+    pub cache_dir: Option<PathBuf>,
+
+    /// Keep shapes that draw nothing, such as unfilled guides and hidden or transparent paths
+    #[arg(long)]
+    pub keep_hidden: bool,
+
+    /// Enable verbose output
+    #[arg(short, long)]
+    pub verbose: bool,
+
+    /// Report progress on stderr, keeping stdout for the pipeline report
+    #[arg(skip)]
+    pub pipeline: bool,
+
+    /// Report no progress at all
+    #[arg(skip)]
+    pub quiet: bool,
+}
+
+/// Parses an empty command line, so defaults match the CLI's
+#[derive(Parser)]
+struct DefaultOptions {
+    #[command(flatten)]
+    options: GenerateOptions,
+}
+
+impl Default for GenerateOptions {
+    fn default() -> Self {
+        DefaultOptions::parse_from(["svg2font"]).options
+    }
+}
+
+impl GenerateOptions {
+    /// Whether icons get symbol cmap codepoints, which the Windows symbol profile needs
+    fn symbol_cmap(&self) -> bool {
+        self.symbol_cmap || self.compat == Some(font_builder::Compatibility::WindowsSymbol)
+    }
+
+    /// The SVG files given on the command line and in `--files-from`, or
+    /// every SVG in `icon_dir` if there are none
+    fn svg_files(&self, icon_dir: &Path) -> Result<Vec<PathBuf>> {
+        let mut files = self.files.clone();
+        if let Some(list) = &self.files_from {
+            let content = if list.as_os_str() == "-" {
+                std::io::read_to_string(std::io::stdin())
+                    .context("Failed to read file list from stdin")?
+            } else {
+                std::fs::read_to_string(list)
+                    .with_context(|| format!("Failed to read {}", list.display()))?
+            };
+            files.extend(
+                content
+                    .lines()
+                    .map(str::trim)
+                    .filter(|line| !line.is_empty())
+                    .map(PathBuf::from),
+            );
+        } else if files.is_empty() {
+            return Ok(svg_parser::list_svg_files(icon_dir));
+        }
+
+        if let Some(missing) = files.iter().find(|file| !file.is_file()) {
+            anyhow::bail!("No such SVG file: {}", missing.display());
+        }
+        // Sorted like a directory listing, so new codepoints don't depend on the order given
+        files.sort_by(|a, b| a.file_name().cmp(&b.file_name()).then_with(|| a.cmp(b)));
+        files.dedup();
+        Ok(files)
+    }
+}
+
+/// An icon directory and the options to build it with.
+///
+/// ```no_run
+/// use svg2font::project::{FontProject, GenerateOptions};
+///
+/// let project = FontProject::new(GenerateOptions {
+///     input: "icons".into(),
+///     output: "dist".into(),
+///     name: "AppIcons".into(),
+///     quiet: true,
+///     ..Default::default()
+/// });
+/// for (kind, path) in project.generate()? {
+///     println!("{}: {}", kind, path.display());
+/// }
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct FontProject {
+    pub options: GenerateOptions,
+}
+
+impl FontProject {
+    pub fn new(options: GenerateOptions) -> Self {
+        Self { options }
+    }
+
+    /// Build the font and its artifacts, returning the kind and path of
+    /// every file written, fonts first
+    pub fn generate(&self) -> Result<Vec<(&'static str, PathBuf)>> {
+        generate_font(&self.options)
+    }
+}
+
+/// The kind of an output file, where it goes and the job that writes it
+type Artifact<'a> = (
+    &'static str,
+    PathBuf,
+    Box<dyn FnOnce(&Path) -> Result<()> + Send + 'a>,
+);
+
+/// Print a progress line; pipeline builds send it to stderr to keep stdout machine-readable
+macro_rules! status {
+    ($args:expr, $($arg:tt)*) => {
+        if $args.quiet {
+        } else if $args.pipeline {
+            eprintln!($($arg)*);
+        } else {
+            println!($($arg)*);
+        }
+    };
+}
+
+/// Build the font and its artifacts, returning the kind and path of every file written
+fn generate_font(args: &GenerateOptions) -> Result<Vec<(&'static str, PathBuf)>> {
+    // Icons in a zip archive or JSON icon set are unpacked for the build, and
+    // the config and lockfile sit next to the archive, or in the current
+    // directory for a downloaded one
+    let archive = if remote::is_url(&args.input) {
+        status!(args, "Downloading: {}", args.input.display());
+        Some(archive::UnpackedArchive::download(
+            &args.input.to_string_lossy(),
+        )?)
+    } else if archive::is_zip(&args.input) {
+        Some(archive::UnpackedArchive::unpack(&args.input)?)
+    } else if iconset::is_icon_set(&args.input) {
+        let files = iconset::read(&args.input)?;
+        Some(archive::UnpackedArchive::from_files(
+            files,
+            &args.input.display().to_string(),
+        )?)
+    } else {
+        None
+    };
+    let input = match &archive {
+        Some(_) if remote::is_url(&args.input) => Path::new("."),
+        Some(_) => args
+            .input
+            .parent()
+            .filter(|dir| !dir.as_os_str().is_empty())
+            .unwrap_or(Path::new(".")),
+        None => args.input.as_path(),
+    };
+    let icon_dir = archive.as_ref().map_or(input, |archive| archive.dir());
+    let output = &args.output;
+    let font_name = &args.name;
+    let verbose = args.verbose;
+
+    // Create output directory if it doesn't exist
+    std::fs::create_dir_all(output)?;
+
+    if verbose {
+        status!(args, "Scanning SVG files in: {}", args.input.display());
+    }
+    let files = args.svg_files(icon_dir)?;
+    let no_icons = || {
+        if args.files.is_empty() && args.files_from.is_none() {
+            anyhow::anyhow!("No SVG files found in {}", args.input.display())
+        } else {
+            anyhow::anyhow!("No SVG files given")
+        }
+    };
+
+    let config = config::Config::load(args.config.as_deref(), input)?;
+    let lock_path = args
+        .lockfile
+        .clone()
+        .unwrap_or_else(|| input.join(lockfile::LOCKFILE_NAME));
+    let mut lock = lockfile::Lockfile::load(&lock_path)?;
+    let explicit = args
+        .codepoints
+        .as_deref()
+        .map(codepoints::CodepointMap::load)
+        .transpose()?
+        .unwrap_or_default();
+
+    let base_name = font_name.to_lowercase().replace(' ', "_");
+    let font_file = format!("{}.ttf", base_name);
+    let ttf_path = output.join(&font_file);
+
+    // Outlines with hidden shapes kept are cached apart from the default ones
+    let cache = args
+        .cache_dir
+        .as_ref()
+        .map(|dir| {
+            if args.keep_hidden {
+                cache::PathCache::open(&dir.join("keep-hidden"))
+            } else {
+                cache::PathCache::open(dir)
+            }
+        })
+        .transpose()?;
+    let cache = cache.as_ref();
+
+    let holder = args
+        .copyright
+        .clone()
+        .unwrap_or_else(|| format!("the {} authors", font_name));
+    let license = args
+        .license
+        .as_ref()
+        .map(|l| l.resolve(&holder))
+        .transpose()?;
+
+    let provenance = args
+        .provenance
+        .then(|| provenance::Provenance::collect(input, args.deterministic));
+    let generated = match &provenance {
+        Some(provenance) => provenance.timestamp.clone(),
+        None => provenance::build_time(args.deterministic),
+    };
+
+    let options = font_builder::FontOptions {
+        optimize_size: args.optimize_size,
+        copyright: args.copyright.clone(),
+        license: license.clone(),
+        license_url: args.license_url.clone(),
+        version: args.version.clone(),
+        designer: args.designer.clone(),
+        description: args.description.clone(),
+        vendor_id: args.vendor_id,
+        embedding: args.embedding,
+        notdef: args.notdef.as_deref().map(load_notdef).transpose()?,
+        provenance: provenance.clone(),
+        subfamily: None,
+        weight: args.weight,
+        width: args.width,
+        italic: args.italic,
+        compat: args.compat,
+        color: args.color,
+        ligatures: args.ligatures,
+        fill_axis: args.variable,
+        curve_tolerance: Some(args.curve_tolerance),
+        proportional: args.proportional.then_some(args.side_bearing),
+        units_per_em: args.units_per_em.or(config.font.units_per_em),
+        ascender: args.ascender.or(config.font.ascender),
+        descender: args.descender.or(config.font.descender),
+        fit: args.fit,
+        baseline_offset: args.baseline_offset,
+        trim: args.trim,
+        padding: args.padding,
+        center_x: args.center_x,
+        center_y: args.center_y,
+    };
+    options.validate()?;
+
+    let (icons, fonts) = if args.low_memory {
+        // Build each glyph as soon as its SVG is parsed, then drop the outline
+        let mut assembler = font_builder::FontAssembler::new(options.clone())?;
+        let mut icons = Vec::new();
+        // Variants are only built from a few icons, so keep their outlines until the end
+        let mut derived = Vec::new();
+        let mut components = Vec::new();
+        svg_parser::for_each_svg(&files, cache, args.keep_hidden, verbose, |mut icon| {
+            if config.skips(&icon.name) {
+                return Ok(());
+            }
+            config.apply_icon_metadata(std::slice::from_mut(&mut icon))?;
+            assembler.add_icon(&icon, cache, verbose)?;
+            derived.extend(variants::variants_of(&icon, &config, args.rtl));
+            if variants::is_component(&icon, &config) {
+                components.push(icon.clone());
+            }
+            icon.path = Default::default();
+            icons.push(icon);
+            Ok(())
+        })?;
+
+        if icons.is_empty() {
+            return Err(no_icons());
+        }
+
+        variants::check_derived_bases(&icons, &config)?;
+        derived.extend(variants::composed_icons(&components, &config)?);
+        let first_derived = icons.len();
+        variants::append(&mut icons, derived)?;
+        config.apply_icon_metadata(&mut icons[first_derived..])?;
+        for icon in &mut icons[first_derived..] {
+            assembler.add_icon(icon, cache, verbose)?;
+            icon.path = Default::default();
+        }
+
+        identifier::check_icon_names(&mut icons, &args.languages, args.strict_identifiers)?;
+        assign_codepoints(&mut icons, &lock, &explicit, args)?;
+        config.apply_aliases(&mut icons)?;
+        status!(args, "Found {} icons", icons.len());
+
+        let font_data = assembler.finish(font_name, &icons)?;
+        let font_data = with_svg_table(font_data, &files, &icons, &options, args)?;
+        font_builder::write_font(&font_data, &ttf_path)?;
+        status!(args, "Generated: {}", ttf_path.display());
+        (icons, vec![ttf_path])
+    } else {
+        // Parse all SVG files
+        let mut icons = svg_parser::parse_svg_files(&files, cache, args.keep_hidden, verbose)?;
+        icons.retain(|icon| !config.skips(&icon.name));
+        config.apply_icon_metadata(&mut icons)?;
+
+        if icons.is_empty() {
+            return Err(no_icons());
+        }
+
+        if args.variable {
+            // One font morphing from the outlined style, the default, to the filled one
+            let [(_, mut filled), (_, mut outlined)] = styles::split_styles(icons)?;
+            prepare_icons(&mut outlined, &config, &lock, &explicit, args)?;
+            prepare_icons(&mut filled, &config, &lock, &explicit, args)?;
+            status!(args, "Found {} icons", outlined.len());
+            let font_data = font_builder::build_fill_font_bytes(
+                &outlined, &filled, font_name, &options, cache, verbose,
+            )?;
+            let font_data = with_svg_table(font_data, &files, &outlined, &options, args)?;
+            font_builder::write_font(&font_data, &ttf_path)?;
+            status!(args, "Generated: {}", ttf_path.display());
+            (outlined, vec![ttf_path.clone()])
+        } else {
+            // Each style becomes its own font, with the same icon names and codepoints
+            let fonts = if args.styles {
+                styles::split_styles(icons)?
+                    .into_iter()
+                    .map(|(style, icons)| {
+                        let subfamily = style.subfamily();
+                        let path =
+                            output.join(format!("{}_{}.ttf", base_name, subfamily.to_lowercase()));
+                        let options = font_builder::FontOptions {
+                            subfamily: Some(subfamily.to_string()),
+                            ..options.clone()
+                        };
+                        (icons, path, options)
+                    })
+                    .collect()
+            } else {
+                vec![(icons, ttf_path.clone(), options)]
+            };
+
+            let mut built = Vec::new();
+            let mut paths = Vec::new();
+            for (mut icons, path, options) in fonts {
+                prepare_icons(&mut icons, &config, &lock, &explicit, args)?;
+                status!(args, "Found {} icons", icons.len());
+                // Secondary layers only become glyphs; they are dropped again once the font is built
+                let icon_count = icons.len();
+                if args.duotone {
+                    let mut codepoints = lock.secondary_codepoints(&icons);
+                    let layers = duotone::split_layers(&mut icons, &mut codepoints)?;
+                    icons.extend(layers);
+                }
+                build_icons(&icons, font_name, &path, &options, &files, args, cache)?;
+                icons.truncate(icon_count);
+                status!(args, "Generated: {}", path.display());
+                built.push(icons);
+                paths.push(path);
+            }
+
+            // The styles only differ in outlines, so the other artifacts describe the first one
+            (built.swap_remove(0), paths)
+        }
+    };
+    let ttf_path = &fonts[0];
+
+    // The manifest travels with each font; the glyphs it describes are already final
+    if args.embed_manifest {
+        for path in &fonts {
+            let data = std::fs::read(path)
+                .with_context(|| format!("Failed to read {}", path.display()))?;
+            let manifest = manifest::build_manifest(
+                &icons,
+                font_name,
+                provenance.as_ref(),
+                generated.as_deref(),
+                &data,
+                icon_dir,
+            )?;
+            font_builder::write_font(&manifest::embed_manifest(&data, &manifest)?, path)?;
+        }
+    }
+
+    // Record the build so the next one keeps these codepoints
+    let font_data = fonts
+        .iter()
+        .map(|path| {
+            std::fs::read(path).with_context(|| format!("Failed to read {}", path.display()))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let changes = lock.update(&icons, &lockfile::glyph_hashes(&font_data)?);
+    drop(font_data);
+    lock.save(&lock_path)?;
+    for (label, names) in [
+        ("New", &changes.added),
+        ("Changed", &changes.changed),
+        ("Removed", &changes.removed),
+        ("Renamed", &changes.renamed),
+    ] {
+        if !names.is_empty() {
+            status!(args, "{} icons: {}", label, names.join(", "));
+        }
+    }
+    status!(args, "Generated: {}", lock_path.display());
+
+    // Everything else only reads the finished font, so write the artifacts in
+    // parallel: web fonts first, since the preview embeds a WOFF2 when there is one
+    let write_all = |artifacts: Vec<Artifact>| {
+        artifacts
+            .into_par_iter()
+            .map(|(kind, path, write)| write(&path).map(|()| (kind, path)))
+            .collect::<Result<Vec<_>>>()
+    };
+
+    // Web fonts wrap each TTF
+    let mut web_fonts: Vec<Artifact> = Vec::new();
+    for font in &fonts {
+        for &format in &args.formats {
+            web_fonts.push((
+                "font",
+                font.with_extension(format.extension()),
+                Box::new(move |path| {
+                    let ttf = std::fs::read(font)
+                        .with_context(|| format!("Failed to read {}", font.display()))?;
+                    font_builder::write_font(&format.encode(&ttf, args.brotli_quality)?, path)
+                }),
+            ));
+        }
+    }
+
+    let mut written = write_all(web_fonts)?;
+
+    let mut artifacts: Vec<Artifact> = Vec::new();
+
+    // Generate manifest (always)
+    artifacts.push((
+        "manifest",
+        output.join(format!("{}.json", base_name)),
+        Box::new(|path| {
+            manifest::generate_manifest(
+                &icons,
+                font_name,
+                provenance.as_ref(),
+                generated.as_deref(),
+                ttf_path,
+                icon_dir,
+                path,
+            )
+        }),
+    ));
+
+    if args.icomoon {
+        artifacts.push((
+            "manifest",
+            output.join(format!("{}_selection.json", base_name)),
+            Box::new(|path| iconset::generate_selection(&icons, font_name, ttf_path, path)),
+        ));
+    }
+    if args.codepoints_list {
+        artifacts.push((
+            "manifest",
+            output.join(format!("{}.codepoints", base_name)),
+            Box::new(|path| codepoints::generate_codepoints_list(&icons, path)),
+        ));
+    }
+
+    if args.export_png {
+        let icons = &icons;
+        for &size in &args.png_size {
+            artifacts.push((
+                "image",
+                output.join("png").join(size.to_string()),
+                Box::new(move |path| png::export_pngs(icons, size, args.color, path)),
+            ));
+        }
+    }
+
+    if args.sprite {
+        let icons = &icons;
+        let image_file = format!("{}_sprite.png", base_name);
+        artifacts.push((
+            "image",
+            output.join(&image_file),
+            Box::new(|path| sprite::generate_sheet(icons, args.sprite_size, args.color, path)),
+        ));
+        artifacts.push((
+            "stylesheet",
+            output.join(format!("{}_sprite.css", base_name)),
+            Box::new(move |path| {
+                sprite::generate_css(icons, font_name, args.sprite_size, &image_file, path)
+            }),
+        ));
+    }
+
+    // Ship the license next to the font
+    if let Some(license) = &license {
+        artifacts.push((
+            "license",
+            output.join("LICENSE"),
+            Box::new(|path| {
+                std::fs::write(path, &license.text)
+                    .with_context(|| format!("Failed to write {}", path.display()))
+            }),
+        ));
+    }
+
+    // Generate duotone stylesheet
+    if args.duotone {
+        artifacts.push((
+            "stylesheet",
+            output.join(format!("{}.css", base_name)),
+            Box::new(|path| duotone::generate_css(&icons, font_name, &font_file, path)),
+        ));
+    }
+
+    // Generate preview if requested
+    if args.preview {
+        let icons = &icons;
+        let font_path = if args.formats.contains(&woff::WebFormat::Woff2) {
+            ttf_path.with_extension("woff2")
+        } else {
+            ttf_path.clone()
+        };
+        artifacts.push((
+            "preview",
+            output.join(format!("{}_preview.html", base_name)),
+            Box::new(move |path| {
+                preview::generate_preview(
+                    icons,
+                    font_name,
+                    &font_path,
+                    args.ligatures,
+                    args.preview_template.as_deref(),
+                    path,
+                )
+            }),
+        ));
+    }
+
+    if args.specimen_pdf {
+        artifacts.push((
+            "preview",
+            output.join(format!("{}_specimen.pdf", base_name)),
+            Box::new(|path| specimen::generate_specimen(&icons, font_name, ttf_path, path)),
+        ));
+    }
+
+    // The React component imports the TypeScript module
+    if args.typescript || args.react {
+        artifacts.push((
+            "code",
+            output.join(format!("{}.ts", base_name)),
+            Box::new(|path| codegen::generate_typescript(&icons, font_name, path)),
+        ));
+    }
+    if args.react {
+        artifacts.push((
+            "code",
+            output.join(format!("{}.tsx", codegen::component_name(font_name))),
+            Box::new(|path| codegen::generate_react(font_name, &base_name, path)),
+        ));
+    }
+    if args.swift {
+        artifacts.push((
+            "code",
+            output.join(format!("{}.swift", codegen::file_name(font_name))),
+            Box::new(|path| codegen::generate_swift(&icons, font_name, path)),
+        ));
+    }
+    if args.kotlin {
+        artifacts.push((
+            "code",
+            output.join(format!("{}.kt", codegen::file_name(font_name))),
+            Box::new(|path| {
+                let package = args.kotlin_package.as_deref();
+                codegen::generate_kotlin(&icons, font_name, package, path)
+            }),
+        ));
+    }
+    if args.flutter_package {
+        artifacts.push((
+            "package",
+            output.join(codegen::package_name(font_name)),
+            Box::new(|path| {
+                let info = codegen::PackageInfo {
+                    version: args.version.as_deref(),
+                    description: args.description.as_deref(),
+                    license: license.as_ref().map(|license| license.text.as_str()),
+                };
+                flutter::generate_package(&icons, font_name, ttf_path, &info, path)
+            }),
+        ));
+    }
+    if args.rust_crate {
+        artifacts.push((
+            "package",
+            output.join(format!("{}-rs", rust_crate::crate_name(font_name))),
+            Box::new(|path| {
+                let info = codegen::PackageInfo {
+                    version: args.version.as_deref(),
+                    description: args.description.as_deref(),
+                    license: license.as_ref().map(|license| license.text.as_str()),
+                };
+                rust_crate::generate_crate(&icons, font_name, ttf_path, &info, path)
+            }),
+        ));
+    }
+
+    written.extend(write_all(artifacts)?);
+    for (_, path) in &written {
+        status!(args, "Generated: {}", path.display());
+    }
+    // Directories, like exported images and packages, are reported file by file
+    let mut written = written
+        .into_iter()
+        .map(|(kind, path)| {
+            walkdir::WalkDir::new(path)
+                .sort_by_file_name()
+                .into_iter()
+                .filter(|entry| entry.as_ref().map_or(true, |e| e.file_type().is_file()))
+                .map(|entry| Ok((kind, entry?.into_path())))
+                .collect::<Result<Vec<_>>>()
+        })
+        .collect::<Result<Vec<_>>>()?
+        .concat();
+
+    // The site's downloads page lists everything else, so it comes last
+    if args.docs {
+        let dir = output.join("docs");
+        let otf_fonts = fonts.iter().filter(|_| args.otf);
+        let downloads: Vec<_> = fonts
+            .iter()
+            .cloned()
+            .chain(otf_fonts.map(|path| path.with_extension("otf")))
+            .map(|path| ("font", path))
+            .chain(written.iter().cloned())
+            .collect();
+        docs::generate_docs(&icons, font_name, ttf_path, &downloads, output, &dir)?;
+        let index = dir.join("index.html");
+        status!(args, "Generated: {}", index.display());
+        written.push(("preview", index));
+    }
+
+    status!(args, "\nDone! {} icons processed.", icons.len());
+
+    let otf_fonts = fonts.iter().filter(|_| args.otf);
+    let otf_fonts = otf_fonts.map(|path| path.with_extension("otf"));
+    let fonts = fonts
+        .iter()
+        .cloned()
+        .chain(otf_fonts)
+        .map(|path| ("font", path));
+    let lock = std::iter::once(("lockfile", lock_path));
+    Ok(fonts.chain(lock).chain(written).collect())
+}
+
+/// Add the configured variants to `icons`, apply their metadata, check names and assign codepoints
+fn prepare_icons(
+    icons: &mut Vec<svg_parser::Icon>,
+    config: &config::Config,
+    lock: &lockfile::Lockfile,
+    explicit: &codepoints::CodepointMap,
+    args: &GenerateOptions,
+) -> Result<()> {
+    variants::check_derived_bases(icons, config)?;
+    let mut derived: Vec<_> = icons
+        .iter()
+        .flat_map(|icon| variants::variants_of(icon, config, args.rtl))
+        .collect();
+    derived.extend(variants::composed_icons(icons, config)?);
+    let first_derived = icons.len();
+    variants::append(icons, derived)?;
+    config.apply_icon_metadata(&mut icons[first_derived..])?;
+
+    // Make sure generated code using these names will compile
+    identifier::check_icon_names(icons, &args.languages, args.strict_identifiers)?;
+    assign_codepoints(icons, lock, explicit, args)?;
+    config.apply_aliases(icons)
+}
+
+/// Parse the SVG given for the .notdef glyph
+fn load_notdef(path: &Path) -> Result<svg_parser::Icon> {
+    let svg = svg_parser::read_svg(path)?;
+    svg_parser::parse_svg_str(&svg, "notdef", 0)
+        .with_context(|| format!("Failed to parse the .notdef SVG {}", path.display()))
+}
+
+/// Give icons their explicit or locked codepoints, and symbol codepoints when requested
+fn assign_codepoints(
+    icons: &mut [svg_parser::Icon],
+    lock: &lockfile::Lockfile,
+    explicit: &codepoints::CodepointMap,
+    args: &GenerateOptions,
+) -> Result<()> {
+    let unknown = explicit.unknown_names(icons);
+    if !unknown.is_empty() {
+        eprintln!(
+            "Warning: No icons named {} for the codepoints file",
+            unknown.join(", ")
+        );
+    }
+    let explicit = explicit.with_icon_codepoints(icons)?;
+    lock.assign_codepoints(icons, &explicit)?;
+    if args.frozen {
+        lock.check_frozen(icons)?;
+    }
+    if args.symbol_cmap() {
+        for icon in icons.iter_mut() {
+            icon.symbol_codepoint = Some(font_builder::symbol_codepoint(icon.codepoint)?);
+        }
+    }
+    Ok(())
+}
+
+/// Build the font for `icons` and write it to `path`
+fn build_icons(
+    icons: &[svg_parser::Icon],
+    font_name: &str,
+    path: &Path,
+    options: &font_builder::FontOptions,
+    files: &[PathBuf],
+    args: &GenerateOptions,
+    cache: Option<&cache::PathCache>,
+) -> Result<()> {
+    if args.optimize_size {
+        // Assemble an unoptimized font as well so the savings can be reported
+        let glyphs = font_builder::convert_glyphs(icons, options, cache, args.verbose)?;
+        let unoptimized = font_builder::FontOptions {
+            optimize_size: false,
+            ..options.clone()
+        };
+        let baseline = font_builder::assemble_font(icons, &glyphs, font_name, &unoptimized)?;
+        let font_data = font_builder::assemble_font(icons, &glyphs, font_name, options)?;
+        status!(args, "{}", size_report(&baseline, &font_data)?);
+        let font_data = with_svg_table(font_data, files, icons, options, args)?;
+        font_builder::write_font(&font_data, path)?;
+    } else {
+        let font_data =
+            font_builder::build_font_bytes(icons, font_name, options, cache, args.verbose)?;
+        let font_data = with_svg_table(font_data, files, icons, options, args)?;
+        font_builder::write_font(&font_data, path)?;
+    }
+    if args.otf {
+        let otf_path = path.with_extension("otf");
+        let font_data = font_builder::build_otf_bytes(icons, font_name, options)?;
+        font_builder::write_font(&font_data, &otf_path)?;
+        status!(args, "Generated: {}", otf_path.display());
+    }
+    Ok(())
+}
+
+/// Add the `SVG ` table to a built TTF when `--svg-table` is set
+fn with_svg_table(
+    font_data: Vec<u8>,
+    files: &[PathBuf],
+    icons: &[svg_parser::Icon],
+    options: &font_builder::FontOptions,
+    args: &GenerateOptions,
+) -> Result<Vec<u8>> {
+    if !args.svg_table {
+        return Ok(font_data);
+    }
+    svg_table::add_svg_table(&font_data, files, icons, options)
+}
+
+/// Per-table sizes of the optimized font against an unoptimized build
+fn size_report(baseline: &[u8], optimized: &[u8]) -> Result<String> {
+    let before = font_builder::table_sizes(baseline)?;
+    let after = font_builder::table_sizes(optimized)?;
+
+    let size = |sizes: &[(String, u32)], tag: &str| {
+        sizes
+            .iter()
+            .find(|(t, _)| t == tag)
+            .map_or(0, |(_, size)| *size)
+    };
+    // Tables only one of the fonts has are listed with a size of 0 in the other
+    let tags: std::collections::BTreeSet<&str> = before
+        .iter()
+        .chain(&after)
+        .map(|(tag, _)| tag.as_str())
+        .collect();
+
+    let mut report = String::from("Table sizes (bytes):\n");
+    for tag in tags {
+        let (old_size, new_size) = (size(&before, tag), size(&after, tag));
+        report.push_str(&format!(
+            "  {}  {:>8} -> {:>8}  ({:+})\n",
+            tag,
+            old_size,
+            new_size,
+            new_size as i64 - old_size as i64
+        ));
+    }
+    report.push_str(&format!(
+        "  total {:>8} -> {:>8}  ({:+})",
+        baseline.len(),
+        optimized.len(),
+        optimized.len() as i64 - baseline.len() as i64
+    ));
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_otf_conflicts() {
+        let parse = |args: &[&str]| {
+            DefaultOptions::try_parse_from(std::iter::once("svg2font").chain(args.iter().copied()))
+        };
+        assert!(parse(&["--otf"]).is_ok());
+        for option in ["--ligatures", "--color", "--svg-table"] {
+            assert!(parse(&["--otf", option]).is_err(), "--otf {}", option);
+        }
+    }
+}
@@ -0,0 +1,120 @@
+use std::path::Path;
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Where a font came from, so a copy found in the wild can be traced back to its source
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Provenance {
+    /// svg2font version that built the font
+    pub version: &'static str,
+    /// Git commit of the icon sources, with a `-dirty` suffix for uncommitted changes
+    pub commit: Option<String>,
+    /// Build time as an RFC 3339 UTC timestamp, omitted in deterministic builds
+    pub timestamp: Option<String>,
+}
+
+impl Provenance {
+    /// Collect provenance for icons in `input_dir`.
+    ///
+    /// The build time honors `SOURCE_DATE_EPOCH` and is left out entirely
+    /// when `deterministic` is set.
+    pub fn collect(input_dir: &Path, deterministic: bool) -> Self {
+        Self {
+            version: env!("CARGO_PKG_VERSION"),
+            commit: git_commit(input_dir),
+            timestamp: build_time(deterministic),
+        }
+    }
+
+    /// Provenance as `key value` pairs separated by `; `, for the name table
+    pub fn summary(&self) -> String {
+        let mut parts = vec![format!("svg2font {}", self.version)];
+        if let Some(commit) = &self.commit {
+            parts.push(format!("git {}", commit));
+        }
+        if let Some(timestamp) = &self.timestamp {
+            parts.push(format!("built {}", timestamp));
+        }
+        parts.join("; ")
+    }
+}
+
+/// The current time as an RFC 3339 UTC timestamp, or `SOURCE_DATE_EPOCH`
+/// when set, and `None` in deterministic builds
+pub fn build_time(deterministic: bool) -> Option<String> {
+    if deterministic {
+        return None;
+    }
+    let seconds = std::env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs())
+        });
+    Some(format_timestamp(seconds))
+}
+
+/// Commit checked out in the repository containing `dir`, if any
+fn git_commit(dir: &Path) -> Option<String> {
+    let git = |args: &[&str]| {
+        let output = Command::new("git")
+            .arg("-C")
+            .arg(dir)
+            .args(args)
+            .output()
+            .ok()?;
+        output
+            .status
+            .success()
+            .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+    };
+
+    let commit = git(&["rev-parse", "HEAD"])?;
+    let dirty = git(&["status", "--porcelain", "--", "."]).is_some_and(|s| !s.is_empty());
+    Some(if dirty {
+        format!("{}-dirty", commit)
+    } else {
+        commit
+    })
+}
+
+/// Format seconds since the Unix epoch as `YYYY-MM-DDTHH:MM:SSZ`
+fn format_timestamp(seconds: u64) -> String {
+    let days = (seconds / 86400) as i64;
+    let time = seconds % 86400;
+
+    // Civil date from days since 1970-01-01 (Howard Hinnant's algorithm)
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        time / 3600,
+        time % 3600 / 60,
+        time % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_timestamp() {
+        assert_eq!(format_timestamp(0), "1970-01-01T00:00:00Z");
+        assert_eq!(format_timestamp(951_782_400), "2000-02-29T00:00:00Z");
+        assert_eq!(format_timestamp(1_792_150_245), "2026-10-16T11:30:45Z");
+    }
+}
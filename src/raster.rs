@@ -0,0 +1,172 @@
+//! A small anti-aliased scanline rasterizer for rendering outlines to coverage masks.
+
+use kurbo::{BezPath, PathEl, Point};
+use usvg::FillRule;
+
+/// Vertical samples per pixel row
+const SAMPLES: usize = 5;
+
+/// Maximum distance in pixels between a curve and the lines it is flattened to
+const TOLERANCE: f64 = 0.05;
+
+/// Coverage of each pixel, from 0 (empty) to 1 (covered), row by row
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mask {
+    pub width: usize,
+    pub height: usize,
+    pub data: Vec<f32>,
+}
+
+impl Mask {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            data: vec![0.0; width * height],
+        }
+    }
+
+    /// Paint a path given in pixel coordinates, fully opaque
+    pub fn fill(&mut self, path: &BezPath, rule: FillRule) {
+        self.paint(path, rule, 1.0, 1.0);
+    }
+
+    /// Paint a path given in pixel coordinates over the mask with a
+    /// coverage `level` at `opacity`, as source-over compositing would
+    pub fn paint(&mut self, path: &BezPath, rule: FillRule, level: f32, opacity: f32) {
+        let edges = edges(path);
+        if edges.is_empty() {
+            return;
+        }
+        let mut row = vec![0.0f32; self.width];
+        let mut crossings = Vec::new();
+        for y in 0..self.height {
+            row.iter_mut().for_each(|c| *c = 0.0);
+            for sample in 0..SAMPLES {
+                let sy = y as f64 + (sample as f64 + 0.5) / SAMPLES as f64;
+                crossings.clear();
+                for &(a, b) in &edges {
+                    let (top, bottom, winding) = if a.y < b.y { (a, b, 1) } else { (b, a, -1) };
+                    if top.y <= sy && sy < bottom.y {
+                        let x = top.x + (sy - top.y) / (bottom.y - top.y) * (bottom.x - top.x);
+                        crossings.push((x, winding));
+                    }
+                }
+                crossings.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+                let mut winding = 0;
+                for pair in crossings.windows(2) {
+                    winding += pair[0].1;
+                    let inside = match rule {
+                        FillRule::NonZero => winding != 0,
+                        FillRule::EvenOdd => winding % 2 != 0,
+                    };
+                    if inside {
+                        add_span(&mut row, pair[0].0, pair[1].0, 1.0 / SAMPLES as f32);
+                    }
+                }
+            }
+
+            let pixels = &mut self.data[y * self.width..(y + 1) * self.width];
+            for (pixel, &coverage) in pixels.iter_mut().zip(&row) {
+                let alpha = coverage.min(1.0) * opacity;
+                *pixel = *pixel * (1.0 - alpha) + level * alpha;
+            }
+        }
+    }
+
+    /// Multiply the mask by another of the same size, as a clip
+    pub fn intersect(&mut self, other: &Mask) {
+        for (pixel, &clip) in self.data.iter_mut().zip(&other.data) {
+            *pixel *= clip;
+        }
+    }
+
+    /// Paint another mask of the same size over this one
+    pub fn composite(&mut self, other: &Mask) {
+        for (pixel, &alpha) in self.data.iter_mut().zip(&other.data) {
+            *pixel = *pixel * (1.0 - alpha) + alpha;
+        }
+    }
+}
+
+/// The line segments of a path flattened, with every subpath closed
+fn edges(path: &BezPath) -> Vec<(Point, Point)> {
+    let mut edges = Vec::new();
+    let (mut start, mut last) = (Point::ZERO, Point::ZERO);
+    let close = |edges: &mut Vec<_>, last: Point, start: Point| {
+        if last != start {
+            edges.push((last, start));
+        }
+    };
+    kurbo::flatten(path, TOLERANCE, |el| match el {
+        PathEl::MoveTo(p) => {
+            close(&mut edges, last, start);
+            (start, last) = (p, p);
+        }
+        PathEl::LineTo(p) => {
+            edges.push((last, p));
+            last = p;
+        }
+        PathEl::ClosePath => {
+            close(&mut edges, last, start);
+            last = start;
+        }
+        _ => {}
+    });
+    close(&mut edges, last, start);
+    // Horizontal edges never cross a sample line
+    edges.retain(|(a, b)| a.y != b.y && a.is_finite() && b.is_finite());
+    edges
+}
+
+/// Add the horizontal span from `x0` to `x1` to a row of coverage,
+/// counting partly covered pixels by the fraction covered
+fn add_span(row: &mut [f32], x0: f64, x1: f64, weight: f32) {
+    let width = row.len() as f64;
+    let (x0, x1) = (x0.clamp(0.0, width), x1.clamp(0.0, width));
+    if x0 >= x1 {
+        return;
+    }
+    let (first, last) = (x0.floor() as usize, x1.floor() as usize);
+    if first == last {
+        row[first] += (x1 - x0) as f32 * weight;
+        return;
+    }
+    row[first] += (first as f64 + 1.0 - x0) as f32 * weight;
+    for pixel in &mut row[first + 1..last] {
+        *pixel += weight;
+    }
+    if last < row.len() {
+        row[last] += (x1 - last as f64) as f32 * weight;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kurbo::{Rect, Shape};
+
+    #[test]
+    fn test_fill() {
+        // Half a pixel of the square's edges falls on the border pixels
+        let mut mask = Mask::new(4, 4);
+        mask.fill(
+            &Rect::new(0.5, 0.0, 3.5, 4.0).to_path(0.1),
+            FillRule::NonZero,
+        );
+        let row: Vec<f32> = mask.data[..4].iter().map(|c| (c * 100.0).round()).collect();
+        assert_eq!(row, [50.0, 100.0, 100.0, 50.0]);
+
+        // A hole wound the same way shows only under the even-odd rule
+        let mut path = Rect::new(0.0, 0.0, 4.0, 4.0).to_path(0.1);
+        path.extend(Rect::new(1.0, 1.0, 3.0, 3.0).to_path(0.1));
+        let mut nonzero = Mask::new(4, 4);
+        nonzero.fill(&path, FillRule::NonZero);
+        let mut evenodd = Mask::new(4, 4);
+        evenodd.fill(&path, FillRule::EvenOdd);
+        assert_eq!(nonzero.data[5], 1.0);
+        assert_eq!(evenodd.data[5], 0.0);
+        assert_eq!(evenodd.data[0], 1.0);
+    }
+}
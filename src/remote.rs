@@ -0,0 +1,47 @@
+//! Downloads over HTTP, through the system's `curl` so svg2font needs no TLS
+//! stack of its own.
+
+use anyhow::{Context, Result};
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+/// Whether an input names a URL rather than a local path
+pub fn is_url(input: &Path) -> bool {
+    input
+        .to_str()
+        .is_some_and(|s| s.starts_with("https://") || s.starts_with("http://"))
+}
+
+/// Download `url`, following redirects and sending `headers` (`Name: value`).
+///
+/// Only HTTP and HTTPS are allowed, for redirects too, so a redirect cannot
+/// make curl read local files or talk to other services.
+pub fn fetch(url: &str, headers: &[String]) -> Result<Vec<u8>> {
+    let mut child = Command::new("curl")
+        .args(["--silent", "--show-error", "--fail", "--location"])
+        .args(["--proto", "=https,http", "--proto-redir", "=https,http"])
+        .args(["--header", "@-"])
+        .arg(url)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("Failed to run curl, which downloads need (version 7.55 or later)")?;
+
+    // Headers go through stdin so tokens don't show up in the process list
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin
+            .write_all(headers.join("\n").as_bytes())
+            .context("Failed to pass headers to curl")?;
+    }
+    let output = child.wait_with_output().context("Failed to run curl")?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "Failed to download {}: {}",
+            url,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(output.stdout)
+}
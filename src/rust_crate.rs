@@ -0,0 +1,272 @@
+//! A Rust crate embedding the font, with an `Icon` enum of its codepoints for
+//! egui, iced and other toolkits that load fonts from bytes.
+
+use crate::codegen;
+use crate::identifier::pascal_case;
+use crate::svg_parser::Icon;
+use anyhow::{Context, Result};
+use std::collections::HashSet;
+use std::fmt::Write as _;
+use std::path::Path;
+
+/// Name of the crate, e.g. `my-app-icons` for "My App Icons"
+pub fn crate_name(font_name: &str) -> String {
+    codegen::package_name(font_name).replace('_', "-")
+}
+
+/// Write the crate for the font at `ttf_path` into `dir`: `Cargo.toml`, the
+/// font under `fonts/` and `src/lib.rs` embedding it with `include_bytes!`
+pub fn generate_crate(
+    icons: &[Icon],
+    font_name: &str,
+    ttf_path: &Path,
+    info: &codegen::PackageInfo,
+    dir: &Path,
+) -> Result<()> {
+    let name = crate_name(font_name);
+    let font_file = ttf_path
+        .file_name()
+        .context("Font path has no file name")?
+        .to_string_lossy()
+        .into_owned();
+    for sub in ["fonts", "src"] {
+        let path = dir.join(sub);
+        std::fs::create_dir_all(&path)
+            .with_context(|| format!("Failed to create {}", path.display()))?;
+    }
+
+    let font_path = dir.join("fonts").join(&font_file);
+    std::fs::copy(ttf_path, &font_path)
+        .with_context(|| format!("Failed to copy the font to {}", font_path.display()))?;
+
+    let mut files = vec![
+        ("Cargo.toml", manifest(&name, font_name, info)),
+        ("src/lib.rs", lib_source(icons, font_name, &font_file)),
+        ("README.md", readme(icons, font_name, &name)),
+    ];
+    if let Some(license) = info.license {
+        files.push(("LICENSE", license.to_string()));
+    }
+    for (file, content) in files {
+        let path = dir.join(file);
+        std::fs::write(&path, content)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+    }
+    Ok(())
+}
+
+fn manifest(name: &str, font_name: &str, info: &codegen::PackageInfo) -> String {
+    let description = info.description.map_or_else(
+        || {
+            format!(
+                "The {} icon font, with an Icon enum of its codepoints.",
+                font_name
+            )
+        },
+        str::to_string,
+    );
+    let mut toml = format!(
+        "[package]\n\
+         name = \"{name}\"\n\
+         version = \"{version}.0\"\n\
+         edition = \"2021\"\n\
+         description = {description}\n",
+        version = info.version.unwrap_or("1.0"),
+        description = toml_string(&description),
+    );
+    if info.license.is_some() {
+        toml.push_str("license-file = \"LICENSE\"\n");
+    }
+    toml
+}
+
+/// A basic TOML string
+fn toml_string(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Enum variant of each icon: its PascalCase name, numbered when two names
+/// only differ in case or underscores
+fn variant_names(icons: &[Icon]) -> Vec<String> {
+    let mut seen = HashSet::new();
+    icons
+        .iter()
+        .map(|icon| {
+            let base = pascal_case(&icon.name);
+            let mut name = base.clone();
+            let mut suffix = 2;
+            while !seen.insert(name.clone()) {
+                name = format!("{}{}", base, suffix);
+                suffix += 1;
+            }
+            name
+        })
+        .collect()
+}
+
+fn lib_source(icons: &[Icon], font_name: &str, font_file: &str) -> String {
+    let variants = variant_names(icons);
+    let mut rust = format!(
+        "//! The {font_name} icon font. Generated by svg2font; do not edit.\n\n\
+         #![no_std]\n\n\
+         /// Font family to render the icons with\n\
+         pub const FONT_FAMILY: &str = {family:?};\n\n\
+         /// The font file, to register with the UI toolkit\n\
+         pub static FONT: &[u8] = include_bytes!(\"../fonts/{font_file}\");\n\n\
+         /// Icons of the {font_name} font\n\
+         #[non_exhaustive]\n\
+         #[allow(non_camel_case_types)]\n\
+         #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]\n\
+         pub enum Icon {{\n",
+        family = font_name,
+    );
+    for (icon, variant) in icons.iter().zip(&variants) {
+        if let Some(label) = &icon.label {
+            let _ = writeln!(rust, "    /// {}", label);
+        }
+        let _ = writeln!(rust, "    {},", variant);
+    }
+    rust.push_str(
+        "}\n\n\
+         impl Icon {\n\
+         \x20   /// Every icon, in font order\n\
+         \x20   pub const ALL: &'static [Icon] = &[\n",
+    );
+    for variant in &variants {
+        let _ = writeln!(rust, "        Icon::{},", variant);
+    }
+    rust.push_str(
+        "    ];\n\n\
+         \x20   /// The icon's codepoint in the font\n\
+         \x20   pub const fn codepoint(self) -> u32 {\n\
+         \x20       match self {\n",
+    );
+    for (icon, variant) in icons.iter().zip(&variants) {
+        let _ = writeln!(
+            rust,
+            "            Icon::{} => 0x{:04X},",
+            variant, icon.codepoint
+        );
+    }
+    rust.push_str(
+        "        }\n\
+         \x20   }\n\n\
+         \x20   /// The character showing the icon\n\
+         \x20   pub const fn char(self) -> char {\n\
+         \x20       match self {\n",
+    );
+    for (icon, variant) in icons.iter().zip(&variants) {
+        let _ = writeln!(
+            rust,
+            "            Icon::{} => '\\u{{{:04X}}}',",
+            variant, icon.codepoint
+        );
+    }
+    rust.push_str(
+        "        }\n\
+         \x20   }\n\n\
+         \x20   /// The icon's name in the font, e.g. for the ligature that shows it\n\
+         \x20   pub const fn name(self) -> &'static str {\n\
+         \x20       match self {\n",
+    );
+    for (icon, variant) in icons.iter().zip(&variants) {
+        let _ = writeln!(rust, "            Icon::{} => {:?},", variant, icon.name);
+    }
+    rust.push_str(
+        "        }\n\
+         \x20   }\n\n\
+         \x20   /// The icon named `name`\n\
+         \x20   pub fn from_name(name: &str) -> Option<Icon> {\n\
+         \x20       Icon::ALL.iter().copied().find(|icon| icon.name() == name)\n\
+         \x20   }\n\
+         }\n\n\
+         impl From<Icon> for char {\n\
+         \x20   fn from(icon: Icon) -> char {\n\
+         \x20       icon.char()\n\
+         \x20   }\n\
+         }\n\n\
+         impl TryFrom<char> for Icon {\n\
+         \x20   /// The character, which no icon shows\n\
+         \x20   type Error = char;\n\n\
+         \x20   fn try_from(c: char) -> Result<Icon, char> {\n\
+         \x20       match c {\n",
+    );
+    for (icon, variant) in icons.iter().zip(&variants) {
+        let _ = writeln!(
+            rust,
+            "            '\\u{{{:04X}}}' => Ok(Icon::{}),",
+            icon.codepoint, variant
+        );
+    }
+    rust.push_str(
+        "            _ => Err(c),\n\
+         \x20       }\n\
+         \x20   }\n\
+         }\n\n\
+         impl core::fmt::Display for Icon {\n\
+         \x20   /// Writes the icon's character, to show it in text set in the font\n\
+         \x20   fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {\n\
+         \x20       core::fmt::Write::write_char(f, self.char())\n\
+         \x20   }\n\
+         }\n",
+    );
+    rust
+}
+
+fn readme(icons: &[Icon], font_name: &str, name: &str) -> String {
+    let example = icons
+        .first()
+        .map_or("ArrowLeft".to_string(), |icon| pascal_case(&icon.name));
+    format!(
+        "# {name}\n\n\
+         The {font_name} icon font, {count} icons, generated by svg2font.\n\n\
+         ```rust\n\
+         use {module}::{{Icon, FONT, FONT_FAMILY}};\n\n\
+         // Register FONT with the toolkit under FONT_FAMILY, then set text in it\n\
+         let text = Icon::{example}.to_string();\n\
+         ```\n",
+        module = name.replace('-', "_"),
+        count = icons.len(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::svg_parser::parse_svg_str;
+
+    #[test]
+    fn test_lib_source() {
+        let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 24 24"><title>Go back</title><rect width="24" height="24"/></svg>"#;
+        let icons: Vec<Icon> = ["arrow_left", "arrowLeft"]
+            .iter()
+            .enumerate()
+            .map(|(i, name)| {
+                let mut icon = parse_svg_str(svg, "icon", 0xE001 + i as u32).unwrap();
+                icon.name = name.to_string();
+                icon
+            })
+            .collect();
+
+        let rust = lib_source(&icons, "My \"Icons\"", "my_icons.ttf");
+        assert!(rust.contains("pub const FONT_FAMILY: &str = \"My \\\"Icons\\\"\";"));
+        assert!(rust.contains("include_bytes!(\"../fonts/my_icons.ttf\")"));
+        assert!(rust.contains("#[non_exhaustive]"));
+        assert!(
+            rust.contains("    /// Go back\n    ArrowLeft,\n    /// Go back\n    ArrowLeft2,\n")
+        );
+        assert!(rust.contains("            Icon::ArrowLeft2 => 0xE002,\n"));
+        assert!(rust.contains("            Icon::ArrowLeft => '\\u{E001}',\n"));
+        assert!(rust.contains("            Icon::ArrowLeft2 => \"arrowLeft\",\n"));
+        assert!(rust.contains("            '\\u{E002}' => Ok(Icon::ArrowLeft2),\n"));
+
+        let info = codegen::PackageInfo {
+            version: Some("2.1"),
+            license: Some("MIT"),
+            ..Default::default()
+        };
+        let toml = manifest(&crate_name("3D Icons"), "3D Icons", &info);
+        assert!(toml.starts_with("[package]\nname = \"icons-3d-icons\"\nversion = \"2.1.0\"\n"));
+        assert!(toml.ends_with("license-file = \"LICENSE\"\n"));
+    }
+}
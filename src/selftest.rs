@@ -0,0 +1,207 @@
+use crate::{Cli, Commands};
+use anyhow::{Context, Result};
+use clap::Parser;
+use read_fonts::{tables::glyf::Glyph, FontRef, TableProvider};
+use std::ffi::OsString;
+use std::path::Path;
+use svg2font::check::{self, Severity};
+use svg2font::project::FontProject;
+use svg2font::verify;
+
+/// Sample icons covering the tricky parts of the pipeline, in codepoint order
+const SAMPLES: &[(&str, &str)] = &[
+    ("arc", include_str!("../assets/selftest/arc.svg")),
+    ("evenodd", include_str!("../assets/selftest/evenodd.svg")),
+    ("stroke", include_str!("../assets/selftest/stroke.svg")),
+    (
+        "transform",
+        include_str!("../assets/selftest/transform.svg"),
+    ),
+];
+
+const FONT_NAME: &str = "Selftest";
+
+/// Pixel size the glyphs are rendered at next to their samples
+const VERIFY_SIZE: usize = 64;
+
+/// Percentage of differing pixels above which a glyph fails, as in `svg2font verify`
+const VERIFY_THRESHOLD: f64 = 5.0;
+
+/// Generate a font from the bundled samples and validate every artifact
+pub fn run(keep: bool, verbose: bool) -> Result<()> {
+    let work_dir = std::env::temp_dir().join(format!("svg2font-selftest-{}", std::process::id()));
+    let result = run_in(&work_dir, verbose);
+
+    if keep {
+        println!("Selftest files kept in {}", work_dir.display());
+    } else {
+        let _ = std::fs::remove_dir_all(&work_dir);
+    }
+
+    result?;
+    println!("\nSelftest passed.");
+    Ok(())
+}
+
+fn run_in(work_dir: &Path, verbose: bool) -> Result<()> {
+    let input = work_dir.join("icons");
+    let output = work_dir.join("output");
+    std::fs::create_dir_all(&input)
+        .with_context(|| format!("Failed to create {}", input.display()))?;
+
+    for (name, svg) in SAMPLES {
+        let path = input.join(format!("{}.svg", name));
+        std::fs::write(&path, svg)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+    }
+
+    // Go through the real command line so flag defaults are exercised too
+    let mut argv: Vec<OsString> = vec![
+        "svg2font".into(),
+        "generate".into(),
+        "--input".into(),
+        input.clone().into_os_string(),
+        "--output".into(),
+        output.clone().into_os_string(),
+        "--name".into(),
+        FONT_NAME.into(),
+        "--preview".into(),
+        "--strict-identifiers".into(),
+    ];
+    if verbose {
+        argv.push("--verbose".into());
+    }
+    let Commands::Generate(args) = Cli::try_parse_from(argv)?.command else {
+        unreachable!("argv starts with the generate subcommand");
+    };
+    FontProject::new(*args).generate()?;
+
+    let base_name = FONT_NAME.to_lowercase();
+    check("font", || {
+        check_font(&output.join(format!("{}.ttf", base_name)))
+    })?;
+    check("sanity checks", || {
+        check_sanity(&output.join(format!("{}.ttf", base_name)))
+    })?;
+    check("manifest", || {
+        check_manifest(&output.join(format!("{}.json", base_name)))
+    })?;
+    check("rendering", || {
+        check_rendering(
+            &output.join(format!("{}.ttf", base_name)),
+            &output.join(format!("{}.json", base_name)),
+            &input,
+        )
+    })?;
+    check("preview", || {
+        check_non_empty(&output.join(format!("{}_preview.html", base_name)))
+    })?;
+
+    Ok(())
+}
+
+fn check(label: &str, f: impl FnOnce() -> Result<()>) -> Result<()> {
+    f().with_context(|| format!("Selftest check failed: {}", label))?;
+    println!("  ok: {}", label);
+    Ok(())
+}
+
+/// Re-read the generated font and verify its structure
+fn check_font(path: &Path) -> Result<()> {
+    let data = std::fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let font = FontRef::new(&data).map_err(|e| anyhow::anyhow!("Font does not parse: {}", e))?;
+
+    // The samples, .notdef and the space
+    let num_glyphs = font.maxp()?.num_glyphs() as usize;
+    if num_glyphs != SAMPLES.len() + 2 {
+        anyhow::bail!(
+            "Expected {} glyphs, found {}",
+            SAMPLES.len() + 2,
+            num_glyphs
+        );
+    }
+
+    let cmap = font.cmap()?;
+    let glyf = font.glyf()?;
+    let loca = font.loca(None)?;
+
+    for (i, (name, _)) in SAMPLES.iter().enumerate() {
+        let codepoint = 0xE000 + i as u32;
+        let gid = cmap
+            .map_codepoint(codepoint)
+            .with_context(|| format!("U+{:04X} ({}) is not mapped", codepoint, name))?;
+        if gid.to_u32() != i as u32 + 1 {
+            anyhow::bail!("U+{:04X} ({}) maps to glyph {}", codepoint, name, gid);
+        }
+
+        match loca.get_glyf(gid, &glyf)? {
+            Some(Glyph::Simple(glyph)) if glyph.num_points() > 0 => {}
+            _ => anyhow::bail!("Glyph for {} has no outline", name),
+        }
+    }
+
+    Ok(())
+}
+
+/// Run the checks of `svg2font check` on the font, failing on any error
+fn check_sanity(path: &Path) -> Result<()> {
+    let data = std::fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let errors: Vec<String> = check::check_font(&data)
+        .into_iter()
+        .filter(|finding| finding.severity == Severity::Error)
+        .map(|finding| finding.message)
+        .collect();
+    if !errors.is_empty() {
+        anyhow::bail!("{}", errors.join("\n"));
+    }
+    Ok(())
+}
+
+fn check_manifest(path: &Path) -> Result<()> {
+    let json = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+
+    for (name, _) in SAMPLES {
+        if !json.contains(&format!("\"name\": \"{}\"", name)) {
+            anyhow::bail!("Manifest is missing {}", name);
+        }
+    }
+
+    Ok(())
+}
+
+/// Render every glyph next to its sample, which catches shapes lost or
+/// inverted in conversion and strokes left as bare centerlines
+fn check_rendering(font_path: &Path, manifest_path: &Path, input: &Path) -> Result<()> {
+    let data = std::fs::read(font_path)
+        .with_context(|| format!("Failed to read {}", font_path.display()))?;
+    let json = std::fs::read_to_string(manifest_path)
+        .with_context(|| format!("Failed to read {}", manifest_path.display()))?;
+    let manifest = serde_json::from_str(&json)
+        .with_context(|| format!("Invalid manifest {}", manifest_path.display()))?;
+
+    let scores = verify::verify_font(&data, &manifest, input, VERIFY_SIZE)?;
+    if scores.len() != SAMPLES.len() {
+        anyhow::bail!("Verified {} of {} samples", scores.len(), SAMPLES.len());
+    }
+    for score in scores {
+        if score.score > VERIFY_THRESHOLD {
+            anyhow::bail!(
+                "{} differs from its sample in {:.1}% of pixels",
+                score.name,
+                score.score
+            );
+        }
+    }
+    Ok(())
+}
+
+fn check_non_empty(path: &Path) -> Result<()> {
+    let len = std::fs::metadata(path)
+        .with_context(|| format!("Missing {}", path.display()))?
+        .len();
+    if len == 0 {
+        anyhow::bail!("{} is empty", path.display());
+    }
+    Ok(())
+}
@@ -0,0 +1,310 @@
+//! `svg2font serve`: build the preview, serve it over HTTP and rebuild it
+//! whenever the icon directory changes, reloading open pages.
+
+use anyhow::{Context, Result};
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+use svg2font::lockfile;
+use svg2font::project::{FontProject, GenerateOptions};
+
+/// Path pages poll for the build number
+const VERSION_PATH: &str = "/__svg2font/version";
+
+/// How often the icon directory is checked for changes
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// What the server shows: the latest build's preview, or why it failed
+#[derive(Debug, Default)]
+struct Build {
+    /// Counts builds, so pages can tell when to reload
+    version: u64,
+    preview: Option<PathBuf>,
+    error: Option<String>,
+}
+
+/// Serve the preview of `options` on `address` until interrupted
+pub fn run(mut options: GenerateOptions, address: &str) -> Result<()> {
+    if !options.input.is_dir() {
+        anyhow::bail!(
+            "{} is not a directory; serve watches an icon directory",
+            options.input.display()
+        );
+    }
+    options.preview = true;
+
+    let build = Arc::new(Mutex::new(Build::default()));
+    rebuild(&options, &build);
+
+    let listener =
+        TcpListener::bind(address).with_context(|| format!("Failed to listen on {}", address))?;
+    println!("\nServing the preview on http://{}/", address);
+    println!("Watching {} for changes", options.input.display());
+
+    {
+        let build = Arc::clone(&build);
+        let output = options.output.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let build = Arc::clone(&build);
+                let output = output.clone();
+                std::thread::spawn(move || {
+                    if let Err(e) = respond(stream, &build, &output) {
+                        eprintln!("Request failed: {:#}", e);
+                    }
+                });
+            }
+        });
+    }
+
+    // The lockfile and the output are written by the build itself
+    let lock_path = options
+        .lockfile
+        .clone()
+        .unwrap_or_else(|| options.input.join(lockfile::LOCKFILE_NAME));
+    let skip = [lock_path, options.output.clone()];
+    let mut last = snapshot(&options.input, &skip);
+    loop {
+        std::thread::sleep(POLL_INTERVAL);
+        let current = snapshot(&options.input, &skip);
+        if current != last {
+            println!("\nChange detected, rebuilding...");
+            rebuild(&options, &build);
+            // Pick up the lockfile and anything changed during the build
+            last = snapshot(&options.input, &skip);
+        }
+    }
+}
+
+/// Build the font and its preview, keeping the error for the page on failure
+fn rebuild(options: &GenerateOptions, build: &Mutex<Build>) {
+    let result = FontProject::new(options.clone()).generate();
+    let mut build = build.lock().unwrap_or_else(|e| e.into_inner());
+    build.version += 1;
+    match result {
+        Ok(artifacts) => {
+            build.preview = artifacts
+                .into_iter()
+                .find(|(kind, path)| {
+                    *kind == "preview" && path.to_string_lossy().ends_with("_preview.html")
+                })
+                .map(|(_, path)| path);
+            build.error = None;
+        }
+        Err(e) => {
+            eprintln!("Error: {:#}", e);
+            build.error = Some(format!("{:#}", e));
+        }
+    }
+}
+
+/// Modification time and size of every file under `dir`, except `skip`
+fn snapshot(dir: &Path, skip: &[PathBuf]) -> Vec<(PathBuf, Option<SystemTime>, u64)> {
+    let skip: Vec<PathBuf> = skip
+        .iter()
+        .map(|path| path.canonicalize().unwrap_or_else(|_| path.clone()))
+        .collect();
+    let root = dir.canonicalize().unwrap_or_else(|_| dir.to_path_buf());
+    walkdir::WalkDir::new(root)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|entry| !skip.iter().any(|skip| entry.path() == skip))
+        .flatten()
+        .filter(|entry| entry.file_type().is_file())
+        .map(|entry| {
+            let metadata = entry.metadata().ok();
+            (
+                entry.path().to_path_buf(),
+                metadata.as_ref().and_then(|m| m.modified().ok()),
+                metadata.map_or(0, |m| m.len()),
+            )
+        })
+        .collect()
+}
+
+/// Answer one request: the preview at `/`, the build number for live reload,
+/// or a file from the output directory
+fn respond(stream: TcpStream, build: &Mutex<Build>, output: &Path) -> Result<()> {
+    let mut reader = BufReader::new(stream);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // Headers are not needed, but the client expects them to be read
+    let mut line = String::new();
+    while reader.read_line(&mut line)? > 2 {
+        line.clear();
+    }
+    let mut stream = reader.into_inner();
+
+    let mut parts = request_line.split_whitespace();
+    let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+        return send(&mut stream, "400 Bad Request", "text/plain", b"Bad request");
+    };
+    if method != "GET" {
+        return send(
+            &mut stream,
+            "405 Method Not Allowed",
+            "text/plain",
+            b"Only GET is supported",
+        );
+    }
+    let path = percent_decode(target.split(['?', '#']).next().unwrap_or("/"));
+
+    let (version, preview, error) = {
+        let build = build.lock().unwrap_or_else(|e| e.into_inner());
+        (build.version, build.preview.clone(), build.error.clone())
+    };
+    if path == VERSION_PATH {
+        return send(
+            &mut stream,
+            "200 OK",
+            "text/plain",
+            version.to_string().as_bytes(),
+        );
+    }
+    if path == "/" {
+        let page = match (error, preview) {
+            (Some(error), _) => error_page(&error),
+            (None, Some(preview)) => std::fs::read_to_string(&preview)
+                .with_context(|| format!("Failed to read {}", preview.display()))?,
+            (None, None) => error_page("The build wrote no preview"),
+        };
+        let page = with_live_reload(&page, version);
+        return send(
+            &mut stream,
+            "200 OK",
+            "text/html; charset=utf-8",
+            page.as_bytes(),
+        );
+    }
+
+    match resolve(output, &path).and_then(|file| std::fs::read(&file).ok().map(|data| (file, data)))
+    {
+        Some((file, data)) => send(&mut stream, "200 OK", content_type(&file), &data),
+        None => send(&mut stream, "404 Not Found", "text/plain", b"Not found"),
+    }
+}
+
+fn send(stream: &mut TcpStream, status: &str, content_type: &str, body: &[u8]) -> Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nCache-Control: no-store\r\nConnection: close\r\n\r\n",
+        status,
+        content_type,
+        body.len()
+    )?;
+    stream.write_all(body)?;
+    stream.flush()?;
+    Ok(())
+}
+
+/// The file under `root` a request path names, refusing paths that leave it
+fn resolve(root: &Path, path: &str) -> Option<PathBuf> {
+    let relative = Path::new(path.trim_start_matches('/'));
+    if !relative
+        .components()
+        .all(|c| matches!(c, Component::Normal(_)))
+    {
+        return None;
+    }
+    let file = root.join(relative);
+    if file.is_dir() {
+        Some(file.join("index.html"))
+    } else {
+        Some(file)
+    }
+}
+
+/// Decode `%XX` escapes in a request path
+fn percent_decode(path: &str) -> String {
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = (bytes[i] == b'%')
+            .then(|| std::str::from_utf8(bytes.get(i + 1..i + 3)?).ok())
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+fn content_type(path: &Path) -> &'static str {
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("html") => "text/html; charset=utf-8",
+        Some("css") => "text/css",
+        Some("js") => "text/javascript",
+        Some("json") => "application/json",
+        Some("svg") => "image/svg+xml",
+        Some("png") => "image/png",
+        Some("pdf") => "application/pdf",
+        Some("ttf") => "font/ttf",
+        Some("otf") => "font/otf",
+        Some("woff") => "font/woff",
+        Some("woff2") => "font/woff2",
+        _ => "application/octet-stream",
+    }
+}
+
+fn error_page(error: &str) -> String {
+    let escaped = error
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;");
+    format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head><meta charset=\"UTF-8\"><title>Build failed</title></head>\n\
+         <body style=\"font-family: sans-serif; padding: 2rem\">\n<h1>Build failed</h1>\n\
+         <pre style=\"white-space: pre-wrap\">{}</pre>\n<p>Fix the icons and the page reloads.</p>\n</body>\n</html>",
+        escaped
+    )
+}
+
+/// Add a script reloading the page once a build newer than `version` is done
+fn with_live_reload(page: &str, version: u64) -> String {
+    let script = format!(
+        r#"<script>
+        setInterval(() => {{
+            fetch('{VERSION_PATH}')
+                .then(response => response.text())
+                .then(latest => {{ if (latest !== '{version}') location.reload(); }})
+                .catch(() => {{}});
+        }}, 1000);
+    </script>
+"#
+    );
+    match page.rfind("</body>") {
+        Some(end) => format!("{}{}{}", &page[..end], script, &page[end..]),
+        None => format!("{}{}", page, script),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_requests() {
+        assert_eq!(percent_decode("/docs/my%20icon.html"), "/docs/my icon.html");
+        assert_eq!(percent_decode("/100%"), "/100%");
+        assert_eq!(
+            resolve(Path::new("out"), "/icons.ttf"),
+            Some(PathBuf::from("out/icons.ttf"))
+        );
+        assert_eq!(resolve(Path::new("out"), "/../secret"), None);
+
+        let page = with_live_reload("<html><body>icons</body></html>", 3);
+        assert!(page.contains("latest !== '3'"));
+        assert!(page.ends_with("</script>\n</body></html>"));
+    }
+}
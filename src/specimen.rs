@@ -0,0 +1,309 @@
+//! A printable PDF specimen of an icon font: every glyph in a grid with its
+//! name and codepoint.
+
+use crate::extract;
+use crate::subset::codepoint_mappings;
+use crate::svg_parser::Icon;
+use anyhow::{Context, Result};
+use flate2::{write::ZlibEncoder, Compression};
+use kurbo::{Affine, BezPath, PathEl};
+use read_fonts::{FontRef, TableProvider};
+use std::fmt::Write as _;
+use std::io::Write as _;
+use std::path::Path;
+
+/// A4 in points
+const PAGE_WIDTH: f64 = 595.0;
+const PAGE_HEIGHT: f64 = 842.0;
+const MARGIN: f64 = 36.0;
+/// Space above the grid for the family name
+const HEADER: f64 = 32.0;
+const COLUMNS: usize = 6;
+const CELL_HEIGHT: f64 = 92.0;
+/// Height of one em in points
+const GLYPH_SIZE: f64 = 40.0;
+const LABEL_SIZE: f64 = 7.0;
+
+/// Write a PDF specimen of the glyphs of `icons` in the font at `font_path`
+pub fn generate_specimen(
+    icons: &[Icon],
+    font_name: &str,
+    font_path: &Path,
+    output_path: &Path,
+) -> Result<()> {
+    let data = std::fs::read(font_path)
+        .with_context(|| format!("Failed to read {}", font_path.display()))?;
+    std::fs::write(output_path, specimen_pdf(icons, font_name, &data)?)
+        .with_context(|| format!("Failed to write {}", output_path.display()))
+}
+
+/// The specimen as PDF bytes, one A4 page per screenful of the grid
+fn specimen_pdf(icons: &[Icon], font_name: &str, font_data: &[u8]) -> Result<Vec<u8>> {
+    let font =
+        FontRef::new(font_data).map_err(|e| anyhow::anyhow!("Font does not parse: {}", e))?;
+    let glyf = font.glyf()?;
+    let loca = font.loca(None)?;
+    let hmtx = font.hmtx()?;
+    let units_per_em = font.head()?.units_per_em() as f64;
+    let descender = font.hhea()?.descender().to_i16() as f64;
+    let mappings = codepoint_mappings(&font)?;
+
+    let cell_width = (PAGE_WIDTH - 2.0 * MARGIN) / COLUMNS as f64;
+    let rows = ((PAGE_HEIGHT - 2.0 * MARGIN - HEADER) / CELL_HEIGHT).floor() as usize;
+    let per_page = rows * COLUMNS;
+    let page_count = icons.len().div_ceil(per_page).max(1);
+    let scale = GLYPH_SIZE / units_per_em;
+
+    let mut pages = Vec::with_capacity(page_count);
+    for page in 0..page_count {
+        let mut content = String::new();
+        let top = PAGE_HEIGHT - MARGIN;
+        text(&mut content, MARGIN, top - 16.0, 14.0, font_name);
+        text(
+            &mut content,
+            MARGIN,
+            MARGIN - 16.0,
+            LABEL_SIZE,
+            &format!(
+                "{} icons - page {} of {}",
+                icons.len(),
+                page + 1,
+                page_count
+            ),
+        );
+
+        let first = page * per_page;
+        for (i, icon) in icons.iter().enumerate().skip(first).take(per_page) {
+            let (column, row) = ((i - first) % COLUMNS, (i - first) / COLUMNS);
+            let x = MARGIN + column as f64 * cell_width;
+            let y = top - HEADER - (row + 1) as f64 * CELL_HEIGHT;
+            // Cell border in light gray
+            let _ = writeln!(
+                content,
+                "0.85 G 0.5 w {} {} {} {} re S",
+                num(x),
+                num(y),
+                num(cell_width),
+                num(CELL_HEIGHT)
+            );
+
+            if let Some(&gid) = mappings.get(&icon.codepoint) {
+                let advance = hmtx
+                    .advance(read_fonts::types::GlyphId::new(gid as u32))
+                    .map_or(units_per_em, |a| a as f64);
+                // The em box sits centered in the upper part of the cell
+                let origin = (
+                    x + (cell_width - advance * scale) / 2.0,
+                    y + CELL_HEIGHT - 12.0 - GLYPH_SIZE - descender * scale,
+                );
+                let outline = extract::glyph_outline(&glyf, &loca, gid)?;
+                let path = Affine::translate(origin) * Affine::scale(scale) * outline;
+                path_operators(&mut content, &path);
+            }
+
+            text(
+                &mut content,
+                x + 4.0,
+                y + 16.0,
+                LABEL_SIZE,
+                &truncate(&icon.name, cell_width - 8.0),
+            );
+            text(
+                &mut content,
+                x + 4.0,
+                y + 6.0,
+                LABEL_SIZE,
+                &format!("U+{:04X}", icon.codepoint),
+            );
+        }
+        pages.push(content);
+    }
+
+    write_pdf(&pages)
+}
+
+/// Append a line of Helvetica text in black
+fn text(content: &mut String, x: f64, y: f64, size: f64, text: &str) {
+    let escaped: String = text
+        .chars()
+        .map(|c| match c {
+            '(' | ')' | '\\' => format!("\\{}", c),
+            ' '..='~' => c.to_string(),
+            _ => "?".to_string(),
+        })
+        .collect();
+    let _ = writeln!(
+        content,
+        "0 g BT /F1 {} Tf {} {} Td ({}) Tj ET",
+        num(size),
+        num(x),
+        num(y),
+        escaped
+    );
+}
+
+/// Shorten a label to fit `width` points, estimating half an em per character
+fn truncate(label: &str, width: f64) -> String {
+    let max = (width / (LABEL_SIZE * 0.55)) as usize;
+    if label.chars().count() <= max {
+        return label.to_string();
+    }
+    let kept: String = label.chars().take(max.saturating_sub(3)).collect();
+    kept + "..."
+}
+
+/// Append a path as PDF operators, filled in black with the non-zero rule
+fn path_operators(content: &mut String, path: &BezPath) {
+    if path.elements().is_empty() {
+        return;
+    }
+    content.push_str("0 g\n");
+    let (mut start, mut last) = (kurbo::Point::ZERO, kurbo::Point::ZERO);
+    for el in path.elements() {
+        let _ = match *el {
+            PathEl::MoveTo(p) => writeln!(content, "{} {} m", num(p.x), num(p.y)),
+            PathEl::LineTo(p) => writeln!(content, "{} {} l", num(p.x), num(p.y)),
+            // PDF has only cubic curves, which hold any quadratic exactly
+            PathEl::QuadTo(p1, p2) => {
+                let c1 = last + (p1 - last) * (2.0 / 3.0);
+                let c2 = p2 + (p1 - p2) * (2.0 / 3.0);
+                writeln!(
+                    content,
+                    "{} {} {} {} {} {} c",
+                    num(c1.x),
+                    num(c1.y),
+                    num(c2.x),
+                    num(c2.y),
+                    num(p2.x),
+                    num(p2.y)
+                )
+            }
+            PathEl::CurveTo(p1, p2, p3) => writeln!(
+                content,
+                "{} {} {} {} {} {} c",
+                num(p1.x),
+                num(p1.y),
+                num(p2.x),
+                num(p2.y),
+                num(p3.x),
+                num(p3.y)
+            ),
+            PathEl::ClosePath => writeln!(content, "h"),
+        };
+        last = match *el {
+            PathEl::MoveTo(p) => {
+                start = p;
+                p
+            }
+            PathEl::LineTo(p) | PathEl::QuadTo(_, p) | PathEl::CurveTo(_, _, p) => p,
+            PathEl::ClosePath => start,
+        };
+    }
+    content.push_str("f\n");
+}
+
+/// A number with at most two decimals and no trailing zeros
+fn num(value: f64) -> String {
+    let text = format!("{:.2}", value);
+    let text = text.trim_end_matches('0').trim_end_matches('.');
+    match text {
+        "-0" | "" => "0".to_string(),
+        text => text.to_string(),
+    }
+}
+
+/// Assemble a PDF from the content streams of its pages, with Helvetica as `/F1`
+fn write_pdf(pages: &[String]) -> Result<Vec<u8>> {
+    // Catalog, page tree and font come first, then each page and its content
+    let mut objects: Vec<Vec<u8>> = vec![
+        b"<< /Type /Catalog /Pages 2 0 R >>".to_vec(),
+        format!(
+            "<< /Type /Pages /Kids [{}] /Count {} >>",
+            (0..pages.len())
+                .map(|i| format!("{} 0 R", 4 + 2 * i))
+                .collect::<Vec<_>>()
+                .join(" "),
+            pages.len()
+        )
+        .into_bytes(),
+        b"<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>"
+            .to_vec(),
+    ];
+    for (i, content) in pages.iter().enumerate() {
+        objects.push(
+            format!(
+                "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] /Resources << /Font << /F1 3 0 R >> >> /Contents {} 0 R >>",
+                num(PAGE_WIDTH),
+                num(PAGE_HEIGHT),
+                5 + 2 * i
+            )
+            .into_bytes(),
+        );
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::best());
+        encoder.write_all(content.as_bytes())?;
+        let stream = encoder.finish()?;
+        let mut object = format!(
+            "<< /Length {} /Filter /FlateDecode >>\nstream\n",
+            stream.len()
+        )
+        .into_bytes();
+        object.extend_from_slice(&stream);
+        object.extend_from_slice(b"\nendstream");
+        objects.push(object);
+    }
+
+    let mut pdf = b"%PDF-1.4\n%\xE2\xE3\xCF\xD3\n".to_vec();
+    let mut offsets = Vec::with_capacity(objects.len());
+    for (i, object) in objects.iter().enumerate() {
+        offsets.push(pdf.len());
+        pdf.extend_from_slice(format!("{} 0 obj\n", i + 1).as_bytes());
+        pdf.extend_from_slice(object);
+        pdf.extend_from_slice(b"\nendobj\n");
+    }
+
+    let xref = pdf.len();
+    let mut table = format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1);
+    for offset in offsets {
+        let _ = writeln!(table, "{:010} 00000 n ", offset);
+    }
+    let _ = write!(
+        table,
+        "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+        objects.len() + 1,
+        xref
+    );
+    pdf.extend_from_slice(table.as_bytes());
+    Ok(pdf)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::font_builder::{build_font_bytes, FontOptions};
+    use crate::svg_parser::parse_svg_str;
+
+    #[test]
+    fn test_specimen_pdf() {
+        let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 24 24"><circle cx="12" cy="12" r="8"/></svg>"#;
+        // More icons than fit on one page
+        let icons: Vec<Icon> = (0..60)
+            .map(|i| parse_svg_str(svg, &format!("dot{}", i), 0xE000 + i).unwrap())
+            .collect();
+        let data = build_font_bytes(&icons, "Test", &FontOptions::default(), None, false).unwrap();
+        let pdf = specimen_pdf(&icons, "Test (Icons)", &data).unwrap();
+        assert!(pdf.starts_with(b"%PDF-1.4"));
+        assert!(pdf.windows(8).any(|w| w == b"/Count 2"));
+
+        // Every cross-reference points at its object
+        let trailer = std::str::from_utf8(&pdf[pdf.len() - 30..]).unwrap();
+        let start: usize = trailer.lines().rev().nth(1).unwrap().parse().unwrap();
+        let xref = std::str::from_utf8(&pdf[start..]).unwrap();
+        assert!(xref.starts_with("xref"));
+        for (i, line) in xref.lines().skip(3).take(7).enumerate() {
+            let offset: usize = line[..10].parse().unwrap();
+            assert!(pdf[offset..].starts_with(format!("{} 0 obj", i + 1).as_bytes()));
+        }
+        assert_eq!(num(-0.001), "0");
+        assert_eq!(num(12.5), "12.5");
+    }
+}
@@ -0,0 +1,119 @@
+//! A PNG sprite sheet of all icons with CSS classes showing each one, for
+//! environments that cannot load custom fonts.
+
+use crate::codegen;
+use crate::png::{encode_png, render_icon, Image};
+use crate::svg_parser::Icon;
+use anyhow::{Context, Result};
+use rayon::prelude::*;
+use std::fmt::Write as _;
+use std::path::Path;
+
+/// Columns of the roughly square grid holding `count` icons
+fn columns(count: usize) -> usize {
+    (count as f64).sqrt().ceil().max(1.0) as usize
+}
+
+/// Render every icon `size` pixels square into one grid, in icon order
+pub fn render_sheet(icons: &[Icon], size: u32, color: bool) -> Image {
+    let columns = columns(icons.len());
+    let rows = icons.len().div_ceil(columns);
+    let width = columns as u32 * size;
+    let mut sheet = Image {
+        width,
+        height: rows as u32 * size,
+        pixels: vec![[0; 4]; width as usize * rows * size as usize],
+    };
+
+    let tiles: Vec<Image> = icons
+        .par_iter()
+        .map(|icon| render_icon(icon, size, color))
+        .collect();
+    for (i, tile) in tiles.iter().enumerate() {
+        let (x, y) = ((i % columns) * size as usize, (i / columns) * size as usize);
+        for (row, pixels) in tile.pixels.chunks(size as usize).enumerate() {
+            let start = (y + row) * width as usize + x;
+            sheet.pixels[start..start + pixels.len()].copy_from_slice(pixels);
+        }
+    }
+    sheet
+}
+
+/// Write the sprite sheet of `icons` as a PNG
+pub fn generate_sheet(icons: &[Icon], size: u32, color: bool, output_path: &Path) -> Result<()> {
+    std::fs::write(output_path, encode_png(&render_sheet(icons, size, color))?)
+        .with_context(|| format!("Failed to write {}", output_path.display()))
+}
+
+/// Write a stylesheet with a `<prefix>-sprite` class sized like one icon and a
+/// `<prefix>-sprite-<name>` class per icon moving its tile of `image_file` into view
+pub fn generate_css(
+    icons: &[Icon],
+    font_name: &str,
+    size: u32,
+    image_file: &str,
+    output_path: &Path,
+) -> Result<()> {
+    std::fs::write(output_path, stylesheet(icons, font_name, size, image_file))
+        .with_context(|| format!("Failed to write {}", output_path.display()))
+}
+
+fn stylesheet(icons: &[Icon], font_name: &str, size: u32, image_file: &str) -> String {
+    let prefix = codegen::css_prefix(font_name);
+    let mut css = format!(
+        r#".{prefix}-sprite {{
+  display: inline-block;
+  width: {size}px;
+  height: {size}px;
+  background-image: url("{image_file}");
+  background-repeat: no-repeat;
+  vertical-align: middle;
+}}
+"#
+    );
+
+    let columns = columns(icons.len());
+    for (i, icon) in icons.iter().enumerate() {
+        let (x, y) = ((i % columns) as u32 * size, (i / columns) as u32 * size);
+        let offset = |v: u32| match v {
+            0 => "0".to_string(),
+            v => format!("-{}px", v),
+        };
+        let _ = writeln!(
+            css,
+            ".{}-sprite-{} {{ background-position: {} {}; }}",
+            prefix,
+            icon.name,
+            offset(x),
+            offset(y)
+        );
+    }
+    css
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::svg_parser::parse_svg_str;
+
+    #[test]
+    fn test_sprite() {
+        let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 24 24"><rect width="24" height="24"/></svg>"#;
+        let icons: Vec<Icon> = ["a", "b", "c"]
+            .iter()
+            .enumerate()
+            .map(|(i, name)| parse_svg_str(svg, name, 0xE000 + i as u32).unwrap())
+            .collect();
+
+        // Three icons fill two columns and two rows
+        let sheet = render_sheet(&icons, 4, false);
+        assert_eq!((sheet.width, sheet.height), (8, 8));
+        assert_eq!(sheet.pixels[4 * 8 + 3], [0, 0, 0, 255]);
+        assert_eq!(sheet.pixels[4 * 8 + 4], [0; 4]);
+
+        let css = stylesheet(&icons, "My Icons", 4, "my-icons_sprite.png");
+        assert!(css.contains("url(\"my-icons_sprite.png\")"));
+        assert!(css.contains(".my-icons-sprite-b { background-position: -4px 0; }"));
+        assert!(css.contains(".my-icons-sprite-c { background-position: 0 -4px; }"));
+    }
+}
@@ -0,0 +1,107 @@
+use crate::codepoints;
+use crate::svg_parser::{filename_to_identifier, Icon};
+use anyhow::Result;
+use std::collections::BTreeMap;
+
+/// A style of an icon set, recognised by the SVG file name suffix
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Style {
+    Filled,
+    Outlined,
+}
+
+impl Style {
+    const ALL: [Style; 2] = [Style::Filled, Style::Outlined];
+
+    /// File name suffix marking an SVG as this style
+    fn suffix(self) -> &'static str {
+        match self {
+            Style::Filled => "-filled",
+            Style::Outlined => "-outline",
+        }
+    }
+
+    /// Subfamily name of this style's font
+    pub fn subfamily(self) -> &'static str {
+        match self {
+            Style::Filled => "Filled",
+            Style::Outlined => "Outlined",
+        }
+    }
+}
+
+/// Split `-filled`/`-outline` pairs into one icon set per style.
+///
+/// The suffix is dropped from the icon names and both icons of a pair get
+/// the same codepoint, so apps switch style by picking the font rather than
+/// a different glyph. Every icon must have a counterpart in the other style.
+pub fn split_styles(icons: Vec<Icon>) -> Result<[(Style, Vec<Icon>); 2]> {
+    let mut pairs: BTreeMap<String, [Option<Icon>; 2]> = BTreeMap::new();
+    let mut unstyled = Vec::new();
+    for icon in icons {
+        let styled = Style::ALL.iter().enumerate().find_map(|(i, style)| {
+            let base = icon.filename.strip_suffix(style.suffix())?;
+            Some((i, base.to_string()))
+        });
+        match styled {
+            Some((i, base)) => pairs.entry(base).or_default()[i] = Some(icon),
+            None => unstyled.push(icon.filename),
+        }
+    }
+    if !unstyled.is_empty() {
+        anyhow::bail!(
+            "Icons without a -filled or -outline suffix: {}",
+            unstyled.join(", ")
+        );
+    }
+
+    let mut sets = Style::ALL.map(|style| (style, Vec::with_capacity(pairs.len())));
+    let mut codepoint = codepoints::FIRST_PRIVATE_USE - 1;
+    for (base, pair) in pairs {
+        codepoint = codepoints::next_private_use(codepoint)?;
+        for (i, icon) in pair.into_iter().enumerate() {
+            let mut icon = icon.ok_or_else(|| {
+                anyhow::anyhow!(
+                    "{} has no {}{} counterpart",
+                    base,
+                    base,
+                    Style::ALL[i].suffix()
+                )
+            })?;
+            icon.name = filename_to_identifier(&base);
+            icon.codepoint = codepoint;
+            sets[i].1.push(icon);
+        }
+    }
+
+    Ok(sets)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::svg_parser::parse_svg_str;
+
+    #[test]
+    fn test_split_styles_share_codepoints() {
+        let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 24 24"><rect x="4" y="4" width="16" height="16"/></svg>"#;
+        let icons = ["home-filled", "home-outline", "bell-outline", "bell-filled"]
+            .iter()
+            .zip(0xE000..)
+            .map(|(name, codepoint)| parse_svg_str(svg, name, codepoint).unwrap())
+            .collect();
+
+        let sets = split_styles(icons).unwrap();
+        for (_, icons) in &sets {
+            let names: Vec<_> = icons
+                .iter()
+                .map(|i| (i.name.as_str(), i.codepoint))
+                .collect();
+            assert_eq!(names, [("bell", 0xE000), ("home", 0xE001)]);
+        }
+        assert_eq!(sets[1].1[0].filename, "bell-outline");
+
+        let unpaired = vec![parse_svg_str(svg, "home-filled", 0xE000).unwrap()];
+        assert!(split_styles(unpaired).is_err());
+    }
+}
@@ -0,0 +1,293 @@
+use crate::font_builder;
+use crate::unicode_ranges;
+use anyhow::{Context, Result};
+use read_fonts::{
+    tables::{cmap::CmapSubtable, glyf::Glyph as ReadGlyph},
+    FontRef, TableProvider,
+};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+use write_fonts::{
+    from_obj::ToOwnedTable,
+    tables::{
+        cmap::Cmap,
+        glyf::{CompositeGlyph, GlyfLocaBuilder, Glyph},
+        hmtx::Hmtx,
+        post::Post,
+        vmtx::LongMetric,
+    },
+    types::{GlyphId, GlyphId16, Tag, Version16Dot16},
+    FontBuilder,
+};
+
+/// The space character, kept in every subset
+const SPACE: u32 = 0x20;
+
+/// Tables that do not refer to glyph ids and can be copied unchanged
+const COPIED_TABLES: &[&[u8; 4]] = &[b"name", b"cvt ", b"fpgm", b"prep", b"gasp"];
+
+/// Tables rebuilt for the subset
+const REBUILT_TABLES: &[&[u8; 4]] = &[
+    b"head", b"hhea", b"maxp", b"OS/2", b"hmtx", b"cmap", b"post", b"loca", b"glyf",
+];
+
+/// Glyphs to keep when subsetting a font
+#[derive(Debug, Clone, Default)]
+pub struct SubsetRequest {
+    /// Keep the glyphs these codepoints map to
+    pub codepoints: BTreeSet<u32>,
+    /// Keep glyphs with these `post` table names
+    pub glyph_names: Vec<String>,
+}
+
+impl SubsetRequest {
+    /// Add every codepoint listed in an svg2font JSON manifest
+    pub fn add_manifest(&mut self, path: &Path) -> Result<()> {
+        let json = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let manifest: serde_json::Value = serde_json::from_str(&json)
+            .with_context(|| format!("Invalid manifest {}", path.display()))?;
+
+        let icons = manifest["icons"]
+            .as_array()
+            .with_context(|| format!("Manifest {} has no icons array", path.display()))?;
+        for icon in icons {
+            let codepoint = icon["codepoint"]
+                .as_str()
+                .and_then(|c| u32::from_str_radix(c, 16).ok())
+                .with_context(|| format!("Invalid codepoint in manifest entry {}", icon))?;
+            self.codepoints.insert(codepoint);
+        }
+
+        Ok(())
+    }
+}
+
+/// Trim a TrueType font down to the requested glyphs.
+///
+/// The kept glyphs are renumbered in their original order after .notdef,
+/// together with any glyphs their composites reference. cmap, hmtx, loca,
+/// glyf and post are regenerated; tables that refer to glyph ids but cannot
+/// be remapped here (GSUB, GPOS, kern, ...) are dropped with a warning.
+pub fn subset_font(data: &[u8], request: &SubsetRequest) -> Result<Vec<u8>> {
+    let font = FontRef::new(data).map_err(|e| anyhow::anyhow!("Font does not parse: {}", e))?;
+    let glyf = font
+        .glyf()
+        .context("Only fonts with TrueType outlines can be subset")?;
+    let loca = font.loca(None)?;
+    let hmtx = font.hmtx()?;
+    let post = font.post().ok();
+    let num_glyphs = font.maxp()?.num_glyphs();
+
+    let mappings = codepoint_mappings(&font)?;
+    let name_of = |gid: u16| post.as_ref()?.glyph_name(GlyphId16::new(gid));
+
+    // Resolve the request to original glyph ids. The space stays, like
+    // .notdef, for text stacks that will not use a font without one
+    let mut keep = BTreeSet::from([0u16]);
+    keep.extend(mappings.get(&SPACE));
+    for codepoint in &request.codepoints {
+        let gid = mappings
+            .get(codepoint)
+            .with_context(|| format!("U+{:04X} is not mapped in the font", codepoint))?;
+        keep.insert(*gid);
+    }
+    let mut named = BTreeSet::new();
+    for name in &request.glyph_names {
+        let gid = (0..num_glyphs)
+            .find(|gid| name_of(*gid) == Some(name.as_str()))
+            .with_context(|| format!("No glyph named `{}` in the font", name))?;
+        named.insert(gid);
+    }
+    keep.extend(&named);
+
+    // Only requested codepoints stay mapped, not those of glyphs pulled in as components
+    let mappings: BTreeMap<u32, u16> = mappings
+        .into_iter()
+        .filter(|(codepoint, gid)| {
+            request.codepoints.contains(codepoint) || named.contains(gid) || *codepoint == SPACE
+        })
+        .collect();
+
+    // Composites need the glyphs they are built from
+    let mut pending: Vec<u16> = keep.iter().copied().collect();
+    while let Some(gid) = pending.pop() {
+        if let Some(ReadGlyph::Composite(composite)) = loca.get_glyf(GlyphId::from(gid), &glyf)? {
+            for component in composite.components() {
+                if keep.insert(component.glyph.to_u16()) {
+                    pending.push(component.glyph.to_u16());
+                }
+            }
+        }
+    }
+
+    let new_ids: BTreeMap<u16, u16> = keep
+        .iter()
+        .enumerate()
+        .map(|(new, old)| (*old, new as u16))
+        .collect();
+
+    // glyf, loca and hmtx
+    let mut glyf_builder = GlyfLocaBuilder::new();
+    let mut metrics = Vec::with_capacity(keep.len());
+    for &old in &keep {
+        let gid = GlyphId::from(old);
+        let glyph = remap_glyph(loca.get_glyf(gid, &glyf)?, |id| new_ids[&id])
+            .with_context(|| format!("Glyph {} is broken", old))?;
+        glyf_builder.add_glyph(&glyph)?;
+        metrics.push(LongMetric {
+            advance: hmtx.advance(gid).unwrap_or_default(),
+            side_bearing: hmtx.side_bearing(gid).unwrap_or_default(),
+        });
+    }
+    let (new_glyf, new_loca, loca_format) = glyf_builder.build();
+    let num_kept = keep.len() as u16;
+
+    let cmap_mappings = mappings.iter().filter_map(|(codepoint, gid)| {
+        Some((
+            char::from_u32(*codepoint)?,
+            GlyphId::new(new_ids[gid] as u32),
+        ))
+    });
+    let cmap = Cmap::from_mappings(cmap_mappings)
+        .map_err(|e| anyhow::anyhow!("Failed to create cmap: {:?}", e))?;
+
+    let mut head: write_fonts::tables::head::Head = font.head()?.to_owned_table();
+    head.index_to_loc_format = loca_format as i16;
+
+    let mut hhea: write_fonts::tables::hhea::Hhea = font.hhea()?.to_owned_table();
+    hhea.number_of_h_metrics = num_kept;
+
+    let mut maxp: write_fonts::tables::maxp::Maxp = font.maxp()?.to_owned_table();
+    maxp.num_glyphs = num_kept;
+
+    let post = match &post {
+        Some(source) => {
+            let mut new_post = if source.num_names() > 0 {
+                Post::new_v2(keep.iter().map(|gid| name_of(*gid).unwrap_or(".notdef")))
+            } else {
+                Post {
+                    version: Version16Dot16::VERSION_3_0,
+                    ..Default::default()
+                }
+            };
+            new_post.italic_angle = source.italic_angle();
+            new_post.underline_position = source.underline_position();
+            new_post.underline_thickness = source.underline_thickness();
+            new_post.is_fixed_pitch = source.is_fixed_pitch();
+            new_post
+        }
+        None => Post::new_v2(std::iter::empty::<&str>()),
+    };
+
+    let mut builder = FontBuilder::new();
+    builder
+        .add_table(&head)?
+        .add_table(&hhea)?
+        .add_table(&maxp)?
+        .add_table(&Hmtx::new(metrics, vec![]))?
+        .add_table(&cmap)?
+        .add_table(&post)?
+        .add_table(&new_loca)?
+        .add_table(&new_glyf)?;
+
+    if let Ok(os2) = font.os2() {
+        let mut os2: write_fonts::tables::os2::Os2 = os2.to_owned_table();
+        let kept_codepoints = mappings.keys().copied();
+        os2.us_first_char_index = kept_codepoints.clone().min().unwrap_or(0).min(0xFFFF) as u16;
+        os2.us_last_char_index = kept_codepoints.max().unwrap_or(0).min(0xFFFF) as u16;
+        unicode_ranges::set_ranges(&mut os2, &mappings.keys().copied().collect::<Vec<_>>());
+        builder.add_table(&os2)?;
+    }
+
+    for record in font.table_directory.table_records() {
+        let tag = record.tag();
+        if REBUILT_TABLES.iter().any(|t| Tag::new(t) == tag) {
+            continue;
+        }
+        match font.table_data(tag) {
+            Some(table) if COPIED_TABLES.iter().any(|t| Tag::new(t) == tag) => {
+                builder.add_raw(tag, table.as_bytes().to_vec());
+            }
+            _ => eprintln!("Warning: Dropped the {} table, which cannot be subset", tag),
+        }
+    }
+
+    font_builder::build_checked(&mut builder)
+}
+
+/// Copy a glyph, pointing the components of a composite at their new glyph ids
+pub(crate) fn remap_glyph(glyph: Option<ReadGlyph>, new_id: impl Fn(u16) -> u16) -> Result<Glyph> {
+    Ok(match glyph {
+        None => Glyph::Empty,
+        Some(ReadGlyph::Simple(simple)) => Glyph::Simple(simple.to_owned_table()),
+        Some(ReadGlyph::Composite(composite)) => {
+            let composite: CompositeGlyph = composite.to_owned_table();
+            let components = composite.components().iter().map(|component| {
+                let mut component = component.clone();
+                component.glyph = GlyphId16::new(new_id(component.glyph.to_u16()));
+                (component, composite.bbox)
+            });
+            Glyph::Composite(
+                CompositeGlyph::try_from_iter(components)
+                    .map_err(|_| anyhow::anyhow!("Composite glyph has no components"))?,
+            )
+        }
+    })
+}
+
+/// Every codepoint the font maps, from its format 4 and format 12 cmap subtables
+pub(crate) fn codepoint_mappings(font: &FontRef) -> Result<BTreeMap<u32, u16>> {
+    let cmap = font.cmap()?;
+    let mut mappings = BTreeMap::new();
+
+    for record in cmap.encoding_records() {
+        let pairs: Vec<(u32, GlyphId)> = match record.subtable(cmap.offset_data())? {
+            CmapSubtable::Format4(subtable) => subtable.iter().collect(),
+            CmapSubtable::Format12(subtable) => subtable.iter().collect(),
+            _ => continue,
+        };
+        // Mappings to .notdef (like the 0xFFFF sentinel segment) mean "unmapped"
+        for (codepoint, gid) in pairs.into_iter().filter(|(_, gid)| gid.to_u32() != 0) {
+            mappings.entry(codepoint).or_insert(gid.to_u32() as u16);
+        }
+    }
+
+    Ok(mappings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::font_builder::{build_font_bytes, FontOptions};
+    use crate::svg_parser::parse_svg_str;
+
+    #[test]
+    fn test_subset_keeps_composite_components() {
+        let square = r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 24 24"><rect x="4" y="4" width="16" height="16"/></svg>"#;
+        let dot = r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 24 24"><circle cx="12" cy="12" r="4"/></svg>"#;
+        let icons = vec![
+            parse_svg_str(dot, "dot", 0xE000).unwrap(),
+            parse_svg_str(square, "square", 0xE001).unwrap(),
+            parse_svg_str(square, "box", 0xE002).unwrap(),
+        ];
+        let options = FontOptions {
+            optimize_size: true,
+            ..Default::default()
+        };
+        let data = build_font_bytes(&icons, "Test", &options, None, false).unwrap();
+
+        // U+E002 is a composite of the glyph for U+E001, which must come along,
+        // and the space is always kept
+        let request = SubsetRequest {
+            codepoints: BTreeSet::from([0xE002]),
+            ..Default::default()
+        };
+        let subset = subset_font(&data, &request).unwrap();
+
+        let font = FontRef::new(&subset).unwrap();
+        assert_eq!(font.maxp().unwrap().num_glyphs(), 4);
+        let mappings = codepoint_mappings(&font).unwrap();
+        assert_eq!(mappings, BTreeMap::from([(0x20, 3), (0xE002, 2)]));
+    }
+}
@@ -1,9 +1,30 @@
+use crate::boolean;
+use crate::cache::{self, PathCache};
+use crate::codepoints;
+use crate::config::IconMetadata;
 use anyhow::{Context, Result};
-use kurbo::{BezPath, PathEl, Point, Shape};
-use std::path::Path;
-use usvg::{Options, Tree};
+use kurbo::{Affine, BezPath, ParamCurve, PathEl, Point, Shape};
+use rayon::prelude::*;
+use std::io::Read;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use usvg::{FillRule, Options, Tree};
 use walkdir::WalkDir;
 
+/// File in the input directory listing SVG file name patterns to skip, one per line
+pub const IGNORE_FILE_NAME: &str = ".svgfontignore";
+
+/// Largest decompressed `.svgz` file or archive entry, in bytes; anything
+/// bigger is a decompression bomb rather than an icon
+pub const MAX_DECOMPRESSED_SIZE: u64 = 64 << 20;
+
+/// Resolution scale strokes are outlined at, so the curves of caps and joins
+/// on a small canvas stay smooth at the size of a 1000-unit em
+const STROKE_RESOLUTION: f32 = 64.0;
+
+/// Injected into every SVG so classed secondary duotone shapes count as translucent
+const SECONDARY_LAYER_STYLE: &str = ".secondary, .fa-secondary { opacity: 0.4 }";
+
 /// Represents a parsed icon with its name and path data
 #[derive(Debug, Clone)]
 pub struct Icon {
@@ -13,19 +34,199 @@ pub struct Icon {
     pub filename: String,
     /// Bezier path representing the icon shape
     pub path: BezPath,
-    /// Original viewBox width
+    /// Codepoint of the glyph holding the secondary layer, in duotone builds
+    pub secondary_codepoint: Option<u32>,
+    /// Codepoint in the legacy symbol cmap, when one is emitted
+    pub symbol_codepoint: Option<u32>,
+    /// Fill color of each shape, in paint order
+    pub fills: Vec<ShapeFill>,
+    /// Width of the SVG canvas, whose origin is moved to 0,0 whatever the
+    /// viewBox's min-x and min-y
     pub width: f64,
-    /// Original viewBox height
+    /// Height of the SVG canvas
     pub height: f64,
     /// Unicode codepoint assigned to this icon (set later)
     pub codepoint: u32,
+    /// SHA-256 of the SVG source, used as the cache key
+    pub source_hash: String,
+    /// SVG file the icon was parsed from
+    pub source: Option<PathBuf>,
+    /// Whether the source SVG contains SMIL or CSS animations (dropped from the glyph)
+    pub animated: bool,
+    /// Human-readable label for screen readers
+    pub label: Option<String>,
+    /// Longer accessibility description
+    pub description: Option<String>,
+    /// Categories the icon is filed under, from its metadata
+    pub categories: Vec<String>,
+    /// Search keywords, from its metadata
+    pub tags: Vec<String>,
+    /// Former names that still draw this icon, from the `[aliases]` config
+    pub aliases: Vec<IconAlias>,
+    /// Adjustments from the icon's config entry or sidecar file
+    pub overrides: IconOverrides,
+}
+
+/// Per-icon exceptions to how the icon set is built
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct IconOverrides {
+    /// Codepoint taking precedence over the lockfile
+    pub codepoint: Option<u32>,
+    /// Factor the outline is scaled by, around the middle of the glyph
+    pub scale: Option<f64>,
+    /// Font units to move the glyph up by, on top of `--baseline-offset`
+    pub baseline_offset: Option<i16>,
+    /// Advance width in font units, with the outline kept centered
+    pub advance: Option<u16>,
+}
+
+impl IconOverrides {
+    /// The overrides that change the outline, for glyph cache keys
+    pub fn outline_settings(&self) -> String {
+        match (self.scale, self.baseline_offset) {
+            (None, None) => String::new(),
+            (scale, offset) => format!("-scale{:?}-offset{:?}", scale, offset),
+        }
+    }
+}
+
+/// Another name for an icon, spelled as a ligature, and optionally another
+/// codepoint mapped to its glyph
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IconAlias {
+    pub name: String,
+    pub codepoint: Option<u32>,
+}
+
+/// The fill of one SVG shape, for color fonts
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShapeFill {
+    /// Elements of the icon's path making up the shape
+    pub elements: Range<usize>,
+    /// sRGB color and alpha, including group and fill opacity
+    pub rgba: [u8; 4],
+    /// Whether the shape is translucent, part of the secondary layer of a
+    /// duotone icon
+    pub secondary: bool,
+}
+
+impl ShapeFill {
+    /// Opaque black, the color of shapes in monochrome icons
+    pub const BLACK: [u8; 4] = [0, 0, 0, 255];
 }
 
 /// Parse all SVG files in a directory
-pub fn parse_svg_directory(dir: &Path, verbose: bool) -> Result<Vec<Icon>> {
-    let mut icons = Vec::new();
-    let mut codepoint = 0xE000u32; // Start at Private Use Area
+pub fn parse_svg_directory(
+    dir: &Path,
+    cache: Option<&PathCache>,
+    keep_hidden: bool,
+    verbose: bool,
+) -> Result<Vec<Icon>> {
+    parse_svg_files(&list_svg_files(dir), cache, keep_hidden, verbose)
+}
+
+/// Parse the given SVG files in parallel, returning the icons in file order
+pub fn parse_svg_files(
+    files: &[PathBuf],
+    cache: Option<&PathCache>,
+    keep_hidden: bool,
+    verbose: bool,
+) -> Result<Vec<Icon>> {
+    let parsed: Vec<_> = files
+        .par_iter()
+        .map(|path| parse_svg_file(path, 0, cache, keep_hidden))
+        .collect();
+
+    // Codepoints and messages follow file order, as if parsed one by one
+    let mut codepoint = codepoints::FIRST_PRIVATE_USE - 1;
+    files
+        .iter()
+        .zip(parsed)
+        .filter_map(|(path, result)| parsed_icon(path, result, &mut codepoint, verbose).transpose())
+        .collect()
+}
+
+/// Parse SVG files one at a time, handing each icon to `f`.
+///
+/// Only one parsed icon is alive at a time, which keeps memory flat for
+/// very large icon sets.
+pub fn for_each_svg(
+    files: &[PathBuf],
+    cache: Option<&PathCache>,
+    keep_hidden: bool,
+    verbose: bool,
+    mut f: impl FnMut(Icon) -> Result<()>,
+) -> Result<()> {
+    let mut codepoint = codepoints::FIRST_PRIVATE_USE - 1;
+
+    for path in files {
+        let result = parse_svg_file(path, 0, cache, keep_hidden);
+        if let Some(icon) = parsed_icon(path, result, &mut codepoint, verbose)? {
+            f(icon)?;
+        }
+    }
+
+    Ok(())
+}
 
+/// Report how parsing `path` went, numbering a parsed icon with the codepoint
+/// after `codepoint`. Files that fail to parse are warned about and left out.
+fn parsed_icon(
+    path: &Path,
+    result: Result<Option<Icon>>,
+    codepoint: &mut u32,
+    verbose: bool,
+) -> Result<Option<Icon>> {
+    Ok(match result {
+        Ok(Some(mut icon)) => {
+            *codepoint = codepoints::next_private_use(*codepoint)?;
+            icon.codepoint = *codepoint;
+            if verbose {
+                println!("  Parsed: {}", icon.filename);
+            }
+            Some(icon)
+        }
+        Ok(None) => {
+            if verbose {
+                println!("  Skipped: {}", path.display());
+            }
+            None
+        }
+        Err(e) => {
+            eprintln!("Warning: Failed to parse {}: {}", path.display(), e);
+            None
+        }
+    })
+}
+
+/// Read an SVG file, decompressing gzipped `.svgz` files
+pub fn read_svg(path: &Path) -> Result<String> {
+    let data = std::fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    if !data.starts_with(&[0x1f, 0x8b]) {
+        return String::from_utf8(data)
+            .with_context(|| format!("Failed to read {}", path.display()));
+    }
+    let svg = read_limited(
+        flate2::read::GzDecoder::new(data.as_slice()),
+        MAX_DECOMPRESSED_SIZE,
+    )
+    .with_context(|| format!("Failed to decompress {}", path.display()))?;
+    String::from_utf8(svg).with_context(|| format!("Failed to read {}", path.display()))
+}
+
+/// Read everything `reader` decompresses, failing once it exceeds `limit` bytes
+pub fn read_limited(reader: impl Read, limit: u64) -> Result<Vec<u8>> {
+    let mut data = Vec::new();
+    reader.take(limit + 1).read_to_end(&mut data)?;
+    if data.len() as u64 > limit {
+        anyhow::bail!("Decompresses to more than {} bytes", limit);
+    }
+    Ok(data)
+}
+
+/// List the SVG files directly inside a directory, minus those matched by its ignore file
+pub fn list_svg_files(dir: &Path) -> Vec<PathBuf> {
+    let ignored = ignore_patterns(dir);
     let mut entries: Vec<_> = WalkDir::new(dir)
         .max_depth(1)
         .into_iter()
@@ -33,37 +234,67 @@ pub fn parse_svg_directory(dir: &Path, verbose: bool) -> Result<Vec<Icon>> {
         .filter(|e| {
             e.path()
                 .extension()
-                .map(|ext| ext == "svg")
+                .map(|ext| ext == "svg" || ext == "svgz")
                 .unwrap_or(false)
         })
+        .filter(|e| {
+            let name = e.file_name().to_string_lossy();
+            !ignored.iter().any(|p| p.matches(&name))
+        })
         .collect();
 
     // Sort for deterministic codepoint assignment
     entries.sort_by(|a, b| a.file_name().cmp(b.file_name()));
 
-    for entry in entries {
-        let path = entry.path();
-        match parse_svg_file(path, codepoint) {
-            Ok(icon) => {
-                if verbose {
-                    println!("  Parsed: {} -> U+{:04X}", icon.filename, icon.codepoint);
-                }
-                icons.push(icon);
-                codepoint += 1;
-            }
+    entries.into_iter().map(|e| e.into_path()).collect()
+}
+
+/// Glob patterns from the directory's ignore file; blank lines and `#` comments are skipped
+fn ignore_patterns(dir: &Path) -> Vec<glob::Pattern> {
+    let path = dir.join(IGNORE_FILE_NAME);
+    let Ok(content) = std::fs::read_to_string(&path) else {
+        return Vec::new();
+    };
+
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| match glob::Pattern::new(line) {
+            Ok(pattern) => Some(pattern),
             Err(e) => {
-                eprintln!("Warning: Failed to parse {}: {}", path.display(), e);
+                eprintln!(
+                    "Warning: Invalid pattern `{}` in {}: {}",
+                    line,
+                    path.display(),
+                    e
+                );
+                None
             }
-        }
-    }
-
-    Ok(icons)
+        })
+        .collect()
 }
 
-/// Parse a single SVG file
-fn parse_svg_file(path: &Path, codepoint: u32) -> Result<Icon> {
-    let svg_content = std::fs::read_to_string(path)
-        .with_context(|| format!("Failed to read {}", path.display()))?;
+/// Parse a single SVG file, reusing a cached outline when the content is
+/// unchanged. Returns `None` if its sidecar file says to skip it.
+fn parse_svg_file(
+    path: &Path,
+    codepoint: u32,
+    cache: Option<&PathCache>,
+    keep_hidden: bool,
+) -> Result<Option<Icon>> {
+    // A sidecar `<name>.toml` next to the SVG overrides metadata from the file itself
+    let sidecar = path.with_extension("toml");
+    let metadata = if sidecar.is_file() {
+        Some(IconMetadata::load(&sidecar)?)
+    } else {
+        None
+    };
+    if metadata.as_ref().is_some_and(|metadata| metadata.skip) {
+        return Ok(None);
+    }
+
+    let svg_content = read_svg(path)?;
 
     let filename = path
         .file_stem()
@@ -71,59 +302,484 @@ fn parse_svg_file(path: &Path, codepoint: u32) -> Result<Icon> {
         .unwrap_or("unknown")
         .to_string();
 
+    let source_hash = cache::content_hash(svg_content.as_bytes());
+    let key = cache::parsed_key(&source_hash);
+
+    let mut icon = match cache.and_then(|c| c.load(&key)) {
+        Some(cached) => {
+            let info = read_source_info(&svg_content);
+            Icon {
+                name: filename_to_identifier(&filename),
+                filename,
+                path: cached.path,
+                secondary_codepoint: None,
+                symbol_codepoint: None,
+                fills: cached.fills,
+                width: cached.width,
+                height: cached.height,
+                codepoint,
+                source_hash,
+                source: None,
+                animated: info.animated,
+                label: info.label,
+                description: info.description,
+                categories: Vec::new(),
+                tags: Vec::new(),
+                aliases: Vec::new(),
+                overrides: IconOverrides::default(),
+            }
+        }
+        None => {
+            let icon = parse_svg(&svg_content, &filename, codepoint, keep_hidden)
+                .with_context(|| format!("Failed to parse SVG: {}", path.display()))?;
+            if let Some(cache) = cache {
+                cache.store(&key, icon.width, icon.height, &icon.fills, &icon.path)?;
+            }
+            icon
+        }
+    };
+
+    icon.source = Some(path.to_path_buf());
+    if let Some(metadata) = metadata {
+        metadata
+            .apply(&mut icon)
+            .with_context(|| format!("Invalid metadata {}", sidecar.display()))?;
+    }
+
+    Ok(Some(icon))
+}
+
+/// Parse SVG source text into an icon named after `filename`
+pub fn parse_svg_str(svg_content: &str, filename: &str, codepoint: u32) -> Result<Icon> {
+    parse_svg(svg_content, filename, codepoint, false)
+}
+
+/// Parse SVG source text, keeping invisible shapes such as guides when
+/// `keep_hidden` is set
+pub fn parse_svg(
+    svg_content: &str,
+    filename: &str,
+    codepoint: u32,
+    keep_hidden: bool,
+) -> Result<Icon> {
     // Convert filename to valid Dart identifier
-    let name = filename_to_identifier(&filename);
+    let name = filename_to_identifier(filename);
 
-    let opt = Options::default();
-    let tree = Tree::from_str(&svg_content, &opt)
-        .with_context(|| format!("Failed to parse SVG: {}", path.display()))?;
+    let opt = Options {
+        // Duotone sets mark their secondary layer with these classes
+        style_sheet: Some(SECONDARY_LAYER_STYLE.to_string()),
+        ..Options::default()
+    };
+    let tree = Tree::from_str(svg_content, &opt)?;
 
     let size = tree.size();
-    let width = size.width() as f64;
-    let height = size.height() as f64;
+    let (width, height) =
+        square_canvas(svg_content, &opt).unwrap_or((size.width() as f64, size.height() as f64));
 
     // Extract all paths from the SVG
-    let bez_path = extract_paths(&tree);
+    let (bez_path, fills) = extract_paths(&tree, keep_hidden);
+    let info = read_source_info(svg_content);
 
     Ok(Icon {
         name,
-        filename,
+        filename: filename.to_string(),
         path: bez_path,
+        secondary_codepoint: None,
+        symbol_codepoint: None,
+        fills,
         width,
         height,
         codepoint,
+        source_hash: cache::content_hash(svg_content.as_bytes()),
+        source: None,
+        animated: info.animated,
+        label: info.label,
+        description: info.description,
+        categories: Vec::new(),
+        tags: Vec::new(),
+        aliases: Vec::new(),
+        overrides: IconOverrides::default(),
     })
 }
 
-/// Extract all paths from an SVG tree into a single BezPath
-fn extract_paths(tree: &Tree) -> BezPath {
-    let mut combined = BezPath::new();
-    collect_paths_recursive(tree.root(), &mut combined);
-    combined
+/// Canvas size of an SVG without a viewBox that only gives one absolute
+/// dimension, which usvg would replace with the outline's bounds.
+///
+/// Icons are square, so the missing side is assumed equal to the given one.
+fn square_canvas(svg_content: &str, opt: &Options) -> Option<(f64, f64)> {
+    let doc = usvg::roxmltree::Document::parse(svg_content).ok()?;
+    let root = doc.root_element();
+    if root.has_attribute("viewBox") {
+        return None;
+    }
+    let length = |name| {
+        root.attribute(name)
+            .and_then(|value| parse_length(value, opt))
+    };
+    match (length("width"), length("height")) {
+        (Some(side), None) | (None, Some(side)) => Some((side, side)),
+        _ => None,
+    }
+}
+
+/// An absolute SVG length in pixels, converting physical units at the DPI.
+///
+/// Percentages have nothing to refer to on the root element, so they are
+/// treated as missing.
+fn parse_length(value: &str, opt: &Options) -> Option<f64> {
+    let value = value.trim();
+    let split = value
+        .find(|c: char| c.is_ascii_alphabetic() || c == '%')
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let number: f64 = number.trim().parse().ok()?;
+    let dpi = opt.dpi as f64;
+    let factor = match unit.trim() {
+        "" | "px" => 1.0,
+        "in" => dpi,
+        "cm" => dpi / 2.54,
+        "mm" => dpi / 25.4,
+        "pt" => dpi / 72.0,
+        "pc" => dpi / 6.0,
+        "em" => opt.font_size as f64,
+        "ex" => opt.font_size as f64 / 2.0,
+        _ => return None,
+    };
+    Some(number * factor).filter(|length| length.is_finite() && *length > 0.0)
+}
+
+/// SMIL elements that animate an SVG
+const SMIL_ELEMENTS: &[&str] = &["animate", "animateMotion", "animateTransform", "set"];
+
+/// Metadata read from the SVG markup itself
+#[derive(Debug, Default)]
+struct SourceInfo {
+    /// Uses SMIL animation elements or CSS animations
+    animated: bool,
+    /// Text of the root `<title>`
+    label: Option<String>,
+    /// Text of the root `<desc>`
+    description: Option<String>,
+}
+
+fn read_source_info(svg_content: &str) -> SourceInfo {
+    let Ok(doc) = usvg::roxmltree::Document::parse(svg_content) else {
+        return SourceInfo::default();
+    };
+
+    let animated = doc.descendants().any(|node| {
+        let name = node.tag_name().name();
+        if SMIL_ELEMENTS.contains(&name) {
+            return true;
+        }
+
+        let css = match name {
+            "style" => node.text().unwrap_or_default(),
+            _ => node.attribute("style").unwrap_or_default(),
+        };
+        css.contains("@keyframes") || css.contains("animation")
+    });
+
+    let child_text = |tag: &str| {
+        doc.root_element()
+            .children()
+            .find(|n| n.has_tag_name(tag))
+            .and_then(|n| n.text())
+            .map(|t| t.split_whitespace().collect::<Vec<_>>().join(" "))
+            .filter(|t| !t.is_empty())
+    };
+
+    SourceInfo {
+        animated,
+        label: child_text("title"),
+        description: child_text("desc"),
+    }
+}
+
+/// Extract all paths from an SVG tree into a single BezPath, in document
+/// order, with the fill of every shape
+fn extract_paths(tree: &Tree, keep_hidden: bool) -> (BezPath, Vec<ShapeFill>) {
+    let mut path = BezPath::new();
+    let mut fills = Vec::new();
+    collect_paths_recursive(tree.root(), 1.0, keep_hidden, &mut path, &mut fills);
+    (path, fills)
 }
 
-/// Recursively collect paths from a group and its children
-fn collect_paths_recursive(group: &usvg::Group, combined: &mut BezPath) {
+/// Recursively collect paths from a group and its children, noting each
+/// shape's fill
+fn collect_paths_recursive(
+    group: &usvg::Group,
+    opacity: f32,
+    keep_hidden: bool,
+    layer: &mut BezPath,
+    fills: &mut Vec<ShapeFill>,
+) {
+    let opacity = opacity * group.opacity().get();
     for node in group.children() {
         match node {
             usvg::Node::Path(ref path) => {
-                let bez = usvg_path_to_kurbo(path);
-                for el in bez.elements() {
-                    combined.push(*el);
+                if !keep_hidden && is_hidden(path, opacity) {
+                    continue;
+                }
+                // The fill and the outlined stroke are painted as two shapes;
+                // kept guides without either keep their path as drawn
+                let mut shapes = Vec::new();
+                if let Some(fill) = path.fill() {
+                    let paint = paint_color(fill.paint());
+                    shapes.push((usvg_path_to_kurbo(path), paint, fill.opacity().get()));
+                }
+                if let Some(stroke) = path.stroke() {
+                    let paint = paint_color(stroke.paint());
+                    shapes.push((stroke_outline(path, stroke), paint, stroke.opacity().get()));
+                }
+                if shapes.is_empty() {
+                    let black = (usvg::Color::black(), 1.0);
+                    shapes.push((usvg_path_to_kurbo(path), black, 1.0));
+                }
+
+                for (shape, (color, color_opacity), paint_opacity) in shapes {
+                    let start = layer.elements().len();
+                    layer.extend(shape);
+                    let shape_opacity = opacity * paint_opacity;
+                    let alpha = (shape_opacity * color_opacity * 255.0).round() as u8;
+                    fills.push(ShapeFill {
+                        elements: start..layer.elements().len(),
+                        rgba: [color.red, color.green, color.blue, alpha],
+                        secondary: shape_opacity < 1.0,
+                    });
                 }
             }
-            usvg::Node::Group(ref g) => {
-                collect_paths_recursive(g, combined);
+            usvg::Node::Group(ref g) => match visible_region(g) {
+                None => collect_paths_recursive(g, opacity, keep_hidden, layer, fills),
+                Some(region) => {
+                    // Shapes under a clip path or mask are cut down to what shows
+                    let mut clipped = BezPath::new();
+                    let mut clipped_fills = Vec::new();
+                    collect_paths_recursive(
+                        g,
+                        opacity,
+                        keep_hidden,
+                        &mut clipped,
+                        &mut clipped_fills,
+                    );
+                    for fill in clipped_fills {
+                        let shape = BezPath::from_vec(clipped.elements()[fill.elements].to_vec());
+                        let start = layer.elements().len();
+                        layer.extend(boolean::intersect(
+                            &shape,
+                            FillRule::NonZero,
+                            &region,
+                            FillRule::NonZero,
+                        ));
+                        fills.push(ShapeFill {
+                            elements: start..layer.elements().len(),
+                            ..fill
+                        });
+                    }
+                }
+            },
+            _ => {}
+        }
+    }
+}
+
+/// Whether a path draws nothing: hidden with `visibility`, without fill
+/// and stroke, or fully transparent. Design tools export guides like this.
+fn is_hidden(path: &usvg::Path, opacity: f32) -> bool {
+    let paint_opacity = path
+        .fill()
+        .map(|f| f.opacity().get())
+        .into_iter()
+        .chain(path.stroke().map(|s| s.opacity().get()))
+        .reduce(f32::max);
+    !path.is_visible() || paint_opacity.is_none_or(|paint| opacity * paint <= 0.0)
+}
+
+/// The area a group's clip path and mask leave visible, if it has either
+fn visible_region(group: &usvg::Group) -> Option<BezPath> {
+    let transform = to_affine(group.abs_transform());
+    let clip = group
+        .clip_path()
+        .map(|clip| clip_path_region(clip, transform));
+    let mask = group.mask().map(|mask| mask_region(mask, transform));
+    match (clip, mask) {
+        (Some(clip), Some(mask)) => Some(boolean::intersect(
+            &clip,
+            FillRule::NonZero,
+            &mask,
+            FillRule::NonZero,
+        )),
+        (clip, mask) => clip.or(mask),
+    }
+}
+
+/// Union of a clip path's shapes, each under its `clip-rule`, placed by
+/// `transform` (the clipped element's user space)
+fn clip_path_region(clip: &usvg::ClipPath, transform: Affine) -> BezPath {
+    let clip_transform = transform * to_affine(clip.transform());
+    let mut region = BezPath::new();
+    for_each_path(clip.root(), &mut |path| {
+        let mut shape = path_data(path);
+        shape.apply_affine(clip_transform * to_affine(path.abs_transform()));
+        let rule = path.fill().map_or(FillRule::NonZero, |f| f.rule());
+        region = boolean::union(&region, FillRule::NonZero, &shape, rule);
+    });
+
+    match clip.clip_path() {
+        Some(inner) => boolean::intersect(
+            &region,
+            FillRule::NonZero,
+            &clip_path_region(inner, transform),
+            FillRule::NonZero,
+        ),
+        None => region,
+    }
+}
+
+/// Area a mask shows, treating it as opaque where its shapes are painted
+/// light (or mostly opaque, for alpha masks) and transparent elsewhere.
+/// Partial transparency cannot be kept in a glyph.
+fn mask_region(mask: &usvg::Mask, transform: Affine) -> BezPath {
+    let mut region = BezPath::new();
+    for_each_path(mask.root(), &mut |path| {
+        let Some(fill) = path.fill() else {
+            return;
+        };
+        let (color, paint_opacity) = paint_color(fill.paint());
+        let alpha = fill.opacity().get() * paint_opacity;
+        let coverage = match mask.kind() {
+            usvg::MaskType::Alpha => alpha,
+            usvg::MaskType::Luminance => {
+                let luminance = 0.2126 * color.red as f32
+                    + 0.7152 * color.green as f32
+                    + 0.0722 * color.blue as f32;
+                alpha * luminance / 255.0
             }
+        };
+
+        let mut shape = path_data(path);
+        shape.apply_affine(transform * to_affine(path.abs_transform()));
+        region = if coverage >= 0.5 {
+            boolean::union(&region, FillRule::NonZero, &shape, fill.rule())
+        } else {
+            boolean::difference(&region, FillRule::NonZero, &shape, fill.rule())
+        };
+    });
+
+    let rect = mask.rect();
+    let mut bounds = kurbo::Rect::new(
+        rect.left() as f64,
+        rect.top() as f64,
+        rect.right() as f64,
+        rect.bottom() as f64,
+    )
+    .to_path(0.1);
+    bounds.apply_affine(transform);
+    let region = boolean::intersect(&region, FillRule::NonZero, &bounds, FillRule::NonZero);
+
+    match mask.mask() {
+        Some(inner) => boolean::intersect(
+            &region,
+            FillRule::NonZero,
+            &mask_region(inner, transform),
+            FillRule::NonZero,
+        ),
+        None => region,
+    }
+}
+
+/// Call `f` for every path in a group and its descendants
+fn for_each_path(group: &usvg::Group, f: &mut impl FnMut(&usvg::Path)) {
+    for node in group.children() {
+        match node {
+            usvg::Node::Path(ref path) => f(path),
+            usvg::Node::Group(ref g) => for_each_path(g, f),
             _ => {}
         }
     }
 }
 
+pub(crate) fn to_affine(t: usvg::Transform) -> Affine {
+    Affine::new([
+        t.sx as f64,
+        t.ky as f64,
+        t.kx as f64,
+        t.sy as f64,
+        t.tx as f64,
+        t.ty as f64,
+    ])
+}
+
+/// Color and opacity of a fill or stroke paint
+fn paint_color(paint: &usvg::Paint) -> (usvg::Color, f32) {
+    match paint {
+        usvg::Paint::Color(color) => (*color, 1.0),
+        // Gradients are approximated by their first stop
+        usvg::Paint::LinearGradient(g) => first_stop(g.stops()),
+        usvg::Paint::RadialGradient(g) => first_stop(g.stops()),
+        usvg::Paint::Pattern(_) => (usvg::Color::black(), 1.0),
+    }
+}
+
+fn first_stop(stops: &[usvg::Stop]) -> (usvg::Color, f32) {
+    stops.first().map_or((usvg::Color::black(), 1.0), |stop| {
+        (stop.color(), stop.opacity().get())
+    })
+}
+
 /// Convert a usvg path to a kurbo BezPath, handling fill rules
 fn usvg_path_to_kurbo(path: &usvg::Path) -> BezPath {
+    let mut bez = path_data(path);
+
+    // Path data is local to the path; place it with its own and every
+    // ancestor group's transform, including the viewBox mapping
+    let transform = path.abs_transform();
+    if !transform.is_identity() {
+        bez.apply_affine(to_affine(transform));
+    }
+
+    // Check if this path uses evenodd fill rule
+    let fill_rule = path.fill().map(|f| f.rule()).unwrap_or(FillRule::NonZero);
+
+    if fill_rule == FillRule::EvenOdd {
+        // For evenodd fill rule, we need to fix winding directions
+        // TrueType uses non-zero winding, so inner contours must wind opposite to outer
+        fix_evenodd_winding(&mut bez);
+    }
+
+    bez
+}
+
+/// The area a path's stroke paints, as a shape filled with the non-zero rule
+fn stroke_outline(path: &usvg::Path, stroke: &usvg::Stroke) -> BezPath {
+    let Some(outline) = path
+        .data()
+        .stroke(&stroke.to_tiny_skia(), STROKE_RESOLUTION)
+    else {
+        return BezPath::new();
+    };
+    let mut bez = skia_to_kurbo(&outline);
+    bez.apply_affine(to_affine(path.abs_transform()));
+    bez
+}
+
+/// A usvg path's data in its own coordinates
+fn path_data(path: &usvg::Path) -> BezPath {
+    skia_to_kurbo(path.data())
+}
+
+/// Convert tiny-skia path data, such as usvg's or an outlined stroke, to a BezPath
+pub(crate) fn skia_to_kurbo(data: &usvg::tiny_skia_path::Path) -> BezPath {
     let mut bez = BezPath::new();
-    let data = path.data();
+
+    // Non-finite coordinates poison every later geometry step
+    if data
+        .points()
+        .iter()
+        .any(|pt| !pt.x.is_finite() || !pt.y.is_finite())
+    {
+        return bez;
+    }
 
     for segment in data.segments() {
         match segment {
@@ -149,18 +805,6 @@ fn usvg_path_to_kurbo(path: &usvg::Path) -> BezPath {
         }
     }
 
-    // Check if this path uses evenodd fill rule
-    let fill_rule = path
-        .fill()
-        .map(|f| f.rule())
-        .unwrap_or(usvg::FillRule::NonZero);
-
-    if fill_rule == usvg::FillRule::EvenOdd {
-        // For evenodd fill rule, we need to fix winding directions
-        // TrueType uses non-zero winding, so inner contours must wind opposite to outer
-        fix_evenodd_winding(&mut bez);
-    }
-
     bez
 }
 
@@ -189,45 +833,10 @@ fn split_into_contours(path: &BezPath) -> Vec<BezPath> {
         contours.push(current);
     }
 
-    contours
-}
-
-/// Calculate the signed area of a contour
-/// Positive = counter-clockwise, Negative = clockwise
-fn signed_area(contour: &BezPath) -> f64 {
-    let mut area = 0.0;
-    let mut first_point: Option<Point> = None;
-    let mut prev_point: Option<Point> = None;
-
-    for el in contour.elements() {
-        match el {
-            PathEl::MoveTo(p) => {
-                first_point = Some(*p);
-                prev_point = Some(*p);
-            }
-            PathEl::LineTo(p) => {
-                if let Some(prev) = prev_point {
-                    // Shoelace formula
-                    area += (prev.x * p.y) - (p.x * prev.y);
-                }
-                prev_point = Some(*p);
-            }
-            PathEl::QuadTo(_, p) | PathEl::CurveTo(_, _, p) => {
-                // Approximate - just use end points for area calculation
-                if let Some(prev) = prev_point {
-                    area += (prev.x * p.y) - (p.x * prev.y);
-                }
-                prev_point = Some(*p);
-            }
-            PathEl::ClosePath => {
-                if let (Some(prev), Some(first)) = (prev_point, first_point) {
-                    area += (prev.x * first.y) - (first.x * prev.y);
-                }
-            }
-        }
-    }
+    // A lone MoveTo has no area and only confuses winding detection
+    contours.retain(|c| c.elements().len() > 1);
 
-    area / 2.0
+    contours
 }
 
 /// Reverse the winding direction of a contour
@@ -294,116 +903,49 @@ fn reverse_contour(contour: &BezPath) -> BezPath {
     reversed
 }
 
-/// Check if a point is inside a contour using ray casting
-fn point_in_contour(point: Point, contour: &BezPath) -> bool {
-    let mut inside = false;
-    let mut prev_point: Option<Point> = None;
-    let mut first_point: Option<Point> = None;
-
-    for el in contour.elements() {
-        match el {
-            PathEl::MoveTo(p) => {
-                first_point = Some(*p);
-                prev_point = Some(*p);
-            }
-            PathEl::LineTo(p) | PathEl::QuadTo(_, p) | PathEl::CurveTo(_, _, p) => {
-                if let Some(prev) = prev_point {
-                    // Ray casting algorithm
-                    if (prev.y > point.y) != (p.y > point.y) {
-                        let x_intersect =
-                            prev.x + (point.y - prev.y) / (p.y - prev.y) * (p.x - prev.x);
-                        if point.x < x_intersect {
-                            inside = !inside;
-                        }
-                    }
-                }
-                prev_point = Some(*p);
-            }
-            PathEl::ClosePath => {
-                if let (Some(prev), Some(first)) = (prev_point, first_point) {
-                    if (prev.y > point.y) != (first.y > point.y) {
-                        let x_intersect =
-                            prev.x + (point.y - prev.y) / (first.y - prev.y) * (first.x - prev.x);
-                        if point.x < x_intersect {
-                            inside = !inside;
-                        }
-                    }
-                }
-            }
-        }
+/// Make an even-odd path fill the same area under TrueType's non-zero rule.
+///
+/// Contours that stay clear of each other keep their curves and are only
+/// reoriented: a contour inside an even number of others winds clockwise
+/// and one inside an odd number counter-clockwise. Contours that cross or
+/// touch cannot be fixed that way, so the path is rebuilt from the area it
+/// covers instead.
+fn fix_evenodd_winding(path: &mut BezPath) {
+    if boolean::has_crossings(path) {
+        *path = boolean::simplify(path, FillRule::EvenOdd);
+        return;
     }
 
-    inside
-}
-
-/// Fix winding directions for evenodd fill rule to work with non-zero winding
-fn fix_evenodd_winding(path: &mut BezPath) {
     let contours = split_into_contours(path);
-
     if contours.len() <= 1 {
-        return; // Nothing to fix for single contours
+        return;
     }
 
-    // Calculate signed areas and bounding boxes for all contours
-    let mut contour_info: Vec<(BezPath, f64, kurbo::Rect)> = contours
-        .into_iter()
-        .map(|c| {
-            let area = signed_area(&c);
-            let bbox = c.bounding_box();
-            (c, area, bbox)
-        })
-        .collect();
-
-    // Sort by bounding box area (descending) - larger contours are likely outer
-    contour_info.sort_by(|a, b| {
-        let area_a = a.2.width() * a.2.height();
-        let area_b = b.2.width() * b.2.height();
-        area_b.partial_cmp(&area_a).unwrap_or(std::cmp::Ordering::Equal)
-    });
-
-    // Determine nesting level for each contour
-    let mut fixed_contours: Vec<BezPath> = Vec::new();
-
-    for i in 0..contour_info.len() {
-        let (contour, area, bbox) = &contour_info[i];
-
-        // Count how many contours this one is inside of
-        let mut nesting_level = 0;
-        let center = Point::new(bbox.x0 + bbox.width() / 2.0, bbox.y0 + bbox.height() / 2.0);
-
-        for (other_contour, _, other_bbox) in contour_info.iter().take(i) {
-            // Quick check: if bounding box doesn't contain our center, skip
-            if other_bbox.contains(center) && point_in_contour(center, other_contour) {
-                nesting_level += 1;
-            }
-        }
+    *path = BezPath::new();
+    for (i, contour) in contours.iter().enumerate() {
+        // Any point on a contour is clear of all the others
+        let Some(sample) = contour.segments().next().map(|segment| segment.eval(0.5)) else {
+            continue;
+        };
+        let nesting_level = contours
+            .iter()
+            .enumerate()
+            .filter(|(j, other)| *j != i && other.winding(sample) != 0)
+            .count();
 
-        // For TrueType non-zero winding:
-        // - Outer contours (even nesting level) should be clockwise (negative area)
-        // - Inner contours (odd nesting level) should be counter-clockwise (positive area)
+        // Non-zero winding counts each enclosing contour, so alternate directions
         let should_be_clockwise = nesting_level % 2 == 0;
-        let is_clockwise = *area < 0.0;
-
-        let fixed_contour = if should_be_clockwise != is_clockwise {
-            reverse_contour(contour)
+        let is_clockwise = contour.area() < 0.0;
+        if should_be_clockwise != is_clockwise {
+            path.extend(reverse_contour(contour));
         } else {
-            contour.clone()
-        };
-
-        fixed_contours.push(fixed_contour);
-    }
-
-    // Rebuild the path
-    *path = BezPath::new();
-    for contour in fixed_contours {
-        for el in contour.elements() {
-            path.push(*el);
+            path.extend(contour.iter());
         }
     }
 }
 
 /// Convert a filename to a valid Dart identifier
-fn filename_to_identifier(filename: &str) -> String {
+pub(crate) fn filename_to_identifier(filename: &str) -> String {
     // Remove common suffixes
     let name = filename
         .replace("-filled", "Filled")
@@ -458,4 +1000,131 @@ mod tests {
         assert_eq!(filename_to_identifier("Bank-filled"), "bank_filled");
         assert_eq!(filename_to_identifier("123icon"), "icon_123icon");
     }
+
+    #[test]
+    fn test_read_source_info() {
+        let static_svg = r#"<svg xmlns="http://www.w3.org/2000/svg"><path d="M0 0h4v4z"/></svg>"#;
+        let smil = r#"<svg xmlns="http://www.w3.org/2000/svg"><path d="M0 0h4v4z"><animateTransform attributeName="transform" type="rotate" from="0" to="360" dur="1s"/></path></svg>"#;
+        let titled = r#"<svg xmlns="http://www.w3.org/2000/svg"><title>
+            Next page
+        </title><path d="M0 0h4v4z"/></svg>"#;
+        let css = r#"<svg xmlns="http://www.w3.org/2000/svg"><style>@keyframes spin { to { transform: rotate(1turn) } }</style><path d="M0 0h4v4z"/></svg>"#;
+
+        assert!(!read_source_info(static_svg).animated);
+        assert!(read_source_info(smil).animated);
+        assert!(read_source_info(css).animated);
+        assert_eq!(read_source_info(titled).label.as_deref(), Some("Next page"));
+        assert_eq!(read_source_info(titled).description, None);
+    }
+
+    #[test]
+    fn test_group_and_path_transforms() {
+        let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" width="48" height="48" viewBox="0 0 24 24"><g transform="translate(4 2)"><g transform="scale(2)"><rect width="4" height="4" transform="translate(1 1)"/></g></g></svg>"#;
+        let icon = parse_svg_str(svg, "nested", 0xE000).unwrap();
+
+        // (1..5) * 2 + (4, 2) in viewBox units, doubled into the 48px canvas
+        let bbox = icon.path.bounding_box();
+        assert_eq!((icon.width, icon.height), (48.0, 48.0));
+        assert_eq!(
+            (bbox.x0, bbox.y0, bbox.x1, bbox.y1),
+            (12.0, 8.0, 28.0, 24.0)
+        );
+
+        // The viewBox origin becomes 0,0
+        let offset = r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="-2 -2 28 28"><rect x="-2" y="-2" width="4" height="4"/></svg>"#;
+        let icon = parse_svg_str(offset, "offset", 0xE000).unwrap();
+        assert_eq!((icon.width, icon.height), (28.0, 28.0));
+        assert_eq!(
+            icon.path.bounding_box(),
+            kurbo::Rect::new(0.0, 0.0, 4.0, 4.0)
+        );
+    }
+
+    #[test]
+    fn test_read_svgz() {
+        use std::io::Write;
+        let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 24 24"/>"#;
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), Default::default());
+        encoder.write_all(svg.as_bytes()).unwrap();
+        let path = std::env::temp_dir().join(format!("svg2font-{}.svgz", std::process::id()));
+        std::fs::write(&path, encoder.finish().unwrap()).unwrap();
+
+        let read = read_svg(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(read.unwrap(), svg);
+
+        let bomb = [0u8; 4096];
+        assert_eq!(read_limited(&bomb[..], 4096).unwrap().len(), 4096);
+        let err = read_limited(&bomb[..], 4095).unwrap_err();
+        assert!(err.to_string().contains("more than 4095 bytes"));
+    }
+
+    #[test]
+    fn test_size_without_viewbox() {
+        let size = |attributes: &str| {
+            let svg = format!(
+                r#"<svg xmlns="http://www.w3.org/2000/svg" {}><rect x="2" y="2" width="4" height="4"/></svg>"#,
+                attributes
+            );
+            let icon = parse_svg_str(&svg, "icon", 0xE000).unwrap();
+            (icon.width, icon.height)
+        };
+
+        assert_eq!(size(r#"width="24" height="12""#), (24.0, 12.0));
+        assert_eq!(size(r#"width="0.25in" height="18pt""#), (24.0, 24.0));
+        // A single dimension makes a square rather than the outline's bounds
+        assert_eq!(size(r#"width="2em""#), (24.0, 24.0));
+        assert_eq!(size(r#"width="100%" height="24px""#), (24.0, 24.0));
+    }
+
+    #[test]
+    fn test_clip_paths_and_masks() {
+        let clipped = r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 24 24"><clipPath id="c"><rect x="4" y="4" width="8" height="16"/></clipPath><rect width="24" height="24" clip-path="url(#c)"/></svg>"#;
+        let icon = parse_svg_str(clipped, "clipped", 0xE000).unwrap();
+        assert_eq!(
+            icon.path.bounding_box(),
+            kurbo::Rect::new(4.0, 4.0, 12.0, 20.0)
+        );
+        assert_eq!(icon.fills[0].elements, 0..icon.path.elements().len());
+
+        // White reveals and black hides
+        let masked = r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 24 24"><mask id="m"><rect width="24" height="12" fill="white"/><rect width="6" height="6" fill="black"/></mask><rect x="2" y="2" width="20" height="20" mask="url(#m)"/></svg>"#;
+        let icon = parse_svg_str(masked, "masked", 0xE000).unwrap();
+        assert_eq!(
+            icon.path.bounding_box(),
+            kurbo::Rect::new(2.0, 2.0, 22.0, 12.0)
+        );
+        assert_eq!(icon.path.area(), -(20.0 * 10.0 - 4.0 * 4.0));
+    }
+
+    #[test]
+    fn test_hidden_shapes() {
+        let svg = r##"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 24 24"><rect width="24" height="24" fill="none"/><rect width="24" height="24" visibility="hidden"/><rect width="24" height="24" fill-opacity="0"/><g opacity="0"><rect width="24" height="24"/></g><path d="M2 12H22" fill="none" stroke="#000"/><rect x="4" y="4" width="4" height="4"/></svg>"##;
+        let icon = parse_svg_str(svg, "guides", 0xE000).unwrap();
+        assert_eq!(icon.fills.len(), 2);
+        // The stroke is outlined, half its width to either side of the line
+        assert_eq!(
+            icon.path.bounding_box(),
+            kurbo::Rect::new(2.0, 4.0, 22.0, 12.5)
+        );
+        let stroke =
+            BezPath::from_vec(icon.path.elements()[icon.fills[0].elements.clone()].to_vec());
+        assert!((stroke.area().abs() - 20.0).abs() < 1e-6);
+
+        let icon = parse_svg(svg, "guides", 0xE000, true).unwrap();
+        assert_eq!(icon.fills.len(), 6);
+    }
+
+    #[test]
+    fn test_evenodd_to_nonzero() {
+        // A C-shaped hole in a C shape: the hole's bbox center is outside both
+        let crescent = r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 24 24"><path fill-rule="evenodd" d="M2 2H22V8H8V16H22V22H2Z M3 3H21V7H7V17H21V21H3Z"/></svg>"#;
+        let icon = parse_svg_str(crescent, "crescent", 0xE000).unwrap();
+        assert_eq!(icon.path.area(), -(288.0 - 184.0));
+
+        // Overlapping squares leave their overlap empty
+        let overlap = r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 24 24"><path fill-rule="evenodd" d="M0 0H10V10H0Z M5 5H15V15H5Z"/></svg>"#;
+        let icon = parse_svg_str(overlap, "overlap", 0xE000).unwrap();
+        assert_eq!(icon.path.area(), -150.0);
+    }
 }
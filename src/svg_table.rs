@@ -0,0 +1,165 @@
+use crate::cache;
+use crate::font_builder::{self, glyph_transform, FontOptions};
+use crate::subset::codepoint_mappings;
+use crate::svg_parser::Icon;
+use anyhow::{Context, Result};
+use kurbo::Affine;
+use read_fonts::{types::Tag, FontRef};
+use std::collections::{BTreeMap, HashMap};
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+use usvg::{ImageHrefResolver, Indent, Options, Tree, WriteOptions};
+use write_fonts::FontBuilder;
+
+/// Add an `SVG ` table holding each icon's sanitized source document.
+///
+/// Icons are matched to their glyph through the cmap and to their SVG file
+/// among `files` through the source hash, so derived and composed icons
+/// (which have no file of their own) keep only their glyf outline.
+pub fn add_svg_table(
+    font_data: &[u8],
+    files: &[PathBuf],
+    icons: &[Icon],
+    options: &FontOptions,
+) -> Result<Vec<u8>> {
+    let font =
+        FontRef::new(font_data).map_err(|e| anyhow::anyhow!("Font does not parse: {}", e))?;
+    let mapping = codepoint_mappings(&font)?;
+    let sources: HashMap<&OsStr, &Path> = files
+        .iter()
+        .filter_map(|path| Some((path.file_stem()?, path.as_path())))
+        .collect();
+
+    let mut documents = BTreeMap::new();
+    for icon in icons {
+        let gid = [Some(icon.codepoint), icon.symbol_codepoint]
+            .into_iter()
+            .flatten()
+            .find_map(|codepoint| mapping.get(&codepoint));
+        let Some(&gid) = gid else { continue };
+
+        let Some(path) = sources.get(OsStr::new(&icon.filename)) else {
+            continue;
+        };
+        let Ok(source) = crate::svg_parser::read_svg(path) else {
+            continue;
+        };
+        if cache::content_hash(source.as_bytes()) != icon.source_hash {
+            continue;
+        }
+        let transform = glyph_transform(icon, options)?;
+        let document = glyph_document(&source, gid, transform)
+            .with_context(|| format!("Failed to embed {}", path.display()))?;
+        documents.insert(gid, document);
+    }
+
+    let mut builder = FontBuilder::new();
+    builder.add_raw(Tag::new(b"SVG "), build_svg_table(&documents));
+    builder.copy_missing_tables(font);
+    font_builder::build_checked(&mut builder)
+}
+
+/// Rewrite an SVG as the document for glyph `gid`.
+///
+/// usvg resolves styles and drops scripts, event handlers and external
+/// references; the result is scaled into font units and placed by
+/// `transform`, the same transform as the glyf outline.
+fn glyph_document(source: &str, gid: u16, transform: Affine) -> Result<String> {
+    let opt = Options {
+        // Only embedded data: images may not pull in local files
+        image_href_resolver: ImageHrefResolver {
+            resolve_data: ImageHrefResolver::default_data_resolver(),
+            resolve_string: Box::new(|_, _| None),
+        },
+        ..Options::default()
+    };
+    let tree = Tree::from_str(source, &opt)?;
+    let svg = tree.to_string(&WriteOptions {
+        indent: Indent::None,
+        ..WriteOptions::default()
+    });
+
+    // Keep the content of the root element, which is rewritten below
+    let content = match (svg.find('>'), svg.rfind("</svg>")) {
+        (Some(start), Some(end)) if start < end => &svg[start + 1..end],
+        _ => "",
+    };
+
+    // The glyph transform without its y flip, as SVG glyphs are y-down
+    let [scale, _, _, _, x, y] = transform.as_coeffs();
+    Ok(format!(
+        concat!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" xmlns:xlink="http://www.w3.org/1999/xlink">"#,
+            r#"<g id="glyph{}" transform="matrix({} 0 0 {} {} {})">{}</g></svg>"#
+        ),
+        gid, scale, scale, x, -y, content
+    ))
+}
+
+/// Serialize an `SVG ` table with one document per glyph
+fn build_svg_table(documents: &BTreeMap<u16, String>) -> Vec<u8> {
+    const HEADER_SIZE: u32 = 10;
+    const RECORD_SIZE: u32 = 12;
+
+    let mut out = Vec::new();
+    out.extend_from_slice(&0u16.to_be_bytes()); // version
+    out.extend_from_slice(&HEADER_SIZE.to_be_bytes()); // svgDocumentListOffset
+    out.extend_from_slice(&0u32.to_be_bytes()); // reserved
+
+    // Document offsets are relative to the start of the document list
+    out.extend_from_slice(&(documents.len() as u16).to_be_bytes());
+    let mut offset = 2 + RECORD_SIZE * documents.len() as u32;
+    for (&gid, document) in documents {
+        out.extend_from_slice(&gid.to_be_bytes());
+        out.extend_from_slice(&gid.to_be_bytes());
+        out.extend_from_slice(&offset.to_be_bytes());
+        out.extend_from_slice(&(document.len() as u32).to_be_bytes());
+        offset += document.len() as u32;
+    }
+    for document in documents.values() {
+        out.extend_from_slice(document.as_bytes());
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::font_builder::build_font_bytes;
+    use crate::svg_parser::parse_svg_str;
+    use read_fonts::TableProvider;
+
+    #[test]
+    fn test_svg_table_embeds_sanitized_documents() {
+        let dir = std::env::temp_dir().join(format!("svg2font-svg-table-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let source = concat!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 24 24" onload="alert(1)">"#,
+            r#"<script>alert(1)</script>"#,
+            r#"<linearGradient id="g"><stop offset="0" stop-color="red"/>"#,
+            r#"<stop offset="1" stop-color="blue"/></linearGradient>"#,
+            r#"<rect width="24" height="24" fill="url(#g)"/></svg>"#
+        );
+        let file = dir.join("square.svg");
+        std::fs::write(&file, source).unwrap();
+
+        let icon = parse_svg_str(source, "square", 0xE000).unwrap();
+        let icons = [icon];
+        let ttf = build_font_bytes(&icons, "Test", &FontOptions::default(), None, false).unwrap();
+        let data = add_svg_table(&ttf, &[file], &icons, &FontOptions::default()).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let font = FontRef::new(&data).unwrap();
+        assert!(font.glyf().is_ok(), "glyf fallback is kept");
+        let table = font.table_data(Tag::new(b"SVG ")).unwrap();
+        let table = table.as_bytes();
+        assert_eq!(&table[10..12], &1u16.to_be_bytes());
+        // One record for glyph 1
+        assert_eq!(&table[12..16], &[0, 1, 0, 1]);
+
+        let document = std::str::from_utf8(&table[24..]).unwrap();
+        assert!(document.contains(r#"id="glyph1""#));
+        assert!(document.contains("linearGradient"));
+        assert!(!document.contains("script") && !document.contains("onload"));
+    }
+}
@@ -0,0 +1,239 @@
+use crate::identifier::camel_case;
+use anyhow::{Context, Result};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+/// Private Use Area ranges; codepoints outside them are not treated as icon references
+const PRIVATE_USE: [std::ops::RangeInclusive<u32>; 2] = [0xE000..=0xF8FF, 0xF0000..=0x10FFFF];
+
+/// An icon listed in a manifest
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ManifestIcon {
+    pub name: String,
+    pub codepoint: u32,
+}
+
+/// A reference in the scanned code that matches no icon
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reference {
+    pub path: PathBuf,
+    /// 1-based line number
+    pub line: usize,
+    pub text: String,
+}
+
+/// Result of scanning a codebase for icon references
+#[derive(Debug, Clone, Default)]
+pub struct UsageReport {
+    /// Icons never referenced by name or codepoint
+    pub unused: Vec<ManifestIcon>,
+    /// Qualified names and Private Use Area codepoints that match no icon
+    pub undefined: Vec<Reference>,
+    /// Number of files scanned
+    pub files: usize,
+}
+
+/// Read the font family and icons of an svg2font JSON manifest
+pub fn read_manifest(path: &Path) -> Result<(String, Vec<ManifestIcon>)> {
+    let json = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let manifest: serde_json::Value = serde_json::from_str(&json)
+        .with_context(|| format!("Invalid manifest {}", path.display()))?;
+
+    let family = manifest["fontFamily"]
+        .as_str()
+        .unwrap_or_default()
+        .to_string();
+    let icons = manifest["icons"]
+        .as_array()
+        .with_context(|| format!("Manifest {} has no icons array", path.display()))?
+        .iter()
+        .map(|icon| {
+            Some(ManifestIcon {
+                name: icon["name"].as_str()?.to_string(),
+                codepoint: u32::from_str_radix(icon["codepoint"].as_str()?, 16).ok()?,
+            })
+        })
+        .collect::<Option<Vec<_>>>()
+        .with_context(|| format!("Invalid icon entry in manifest {}", path.display()))?;
+
+    Ok((family, icons))
+}
+
+/// Find the files under `root` matching any `include` pattern and no `exclude` pattern
+pub fn find_sources(
+    root: &Path,
+    include: &[glob::Pattern],
+    exclude: &[glob::Pattern],
+) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = WalkDir::new(root)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+        .filter(|e| {
+            let relative = e.path().strip_prefix(root).unwrap_or(e.path());
+            include.iter().any(|p| p.matches_path(relative))
+                && !exclude.iter().any(|p| p.matches_path(relative))
+        })
+        .map(|e| e.into_path())
+        .collect();
+    files.sort();
+    files
+}
+
+/// Scan `files` for references to `icons`.
+///
+/// An icon counts as used when its snake_case or camelCase name appears as
+/// a word, or its codepoint appears as a hex escape (`\uE000`, `0xE000`,
+/// `&#xE000;`, ...). Bare words may match unrelated code, which keeps an
+/// icon rather than risk pruning one in use. Undefined references are only
+/// reported where intent is clear: names qualified with one of `qualifiers`
+/// (e.g. `AppIcons.foo`) and Private Use Area codepoints.
+pub fn scan(icons: &[ManifestIcon], qualifiers: &[String], files: &[PathBuf]) -> UsageReport {
+    let mut by_word: HashMap<String, usize> = HashMap::new();
+    let mut by_codepoint: HashMap<u32, usize> = HashMap::new();
+    for (i, icon) in icons.iter().enumerate() {
+        by_word.insert(icon.name.clone(), i);
+        by_word.insert(camel_case(&icon.name), i);
+        by_codepoint.insert(icon.codepoint, i);
+    }
+
+    let mut used = BTreeSet::new();
+    let mut undefined = Vec::new();
+    let mut scanned = 0;
+    for path in files {
+        // Binary and unreadable files cannot reference icons
+        let Ok(content) = std::fs::read_to_string(path) else {
+            continue;
+        };
+        scanned += 1;
+
+        for (line_index, line) in content.lines().enumerate() {
+            let mut undefined_here = BTreeMap::new();
+            let words = words(line);
+            for (i, &(start, word)) in words.iter().enumerate() {
+                if let Some(&icon) = by_word.get(word) {
+                    used.insert(icon);
+                    continue;
+                }
+                if let Some(codepoint) = hex_codepoint(word) {
+                    match by_codepoint.get(&codepoint) {
+                        Some(&icon) => {
+                            used.insert(icon);
+                        }
+                        None if PRIVATE_USE.iter().any(|r| r.contains(&codepoint)) => {
+                            undefined_here.insert(start, format!("U+{:04X}", codepoint));
+                        }
+                        None => {}
+                    }
+                    continue;
+                }
+
+                // `Qualifier.word` with nothing but a dot in between
+                let qualified = i > 0 && {
+                    let (prev_start, prev) = words[i - 1];
+                    line[prev_start + prev.len()..start] == *"."
+                        && qualifiers.iter().any(|q| q == prev)
+                };
+                if qualified {
+                    let (prev_start, _) = words[i - 1];
+                    undefined_here.insert(start, line[prev_start..start + word.len()].to_string());
+                }
+            }
+
+            undefined.extend(undefined_here.into_values().map(|text| Reference {
+                path: path.clone(),
+                line: line_index + 1,
+                text,
+            }));
+        }
+    }
+
+    UsageReport {
+        unused: icons
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| !used.contains(i))
+            .map(|(_, icon)| icon.clone())
+            .collect(),
+        undefined,
+        files: scanned,
+    }
+}
+
+/// Identifier-like words in `line`, with their byte offsets
+fn words(line: &str) -> Vec<(usize, &str)> {
+    let mut words = Vec::new();
+    let mut start = None;
+    for (i, c) in line
+        .char_indices()
+        .chain(std::iter::once((line.len(), ' ')))
+    {
+        let word_char = c.is_alphanumeric() || c == '_' || c == '$';
+        match (start, word_char) {
+            (None, true) => start = Some(i),
+            (Some(s), false) => {
+                words.push((s, &line[s..i]));
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    words
+}
+
+/// Parse a word like `uE000`, `0xE000`, `xE000` or `E000` as a codepoint.
+///
+/// Bare hex needs at least one digit, so words like `face` are not codepoints.
+fn hex_codepoint(word: &str) -> Option<u32> {
+    let lower = word.to_ascii_lowercase();
+    let (hex, prefixed) = match ["0x", "u", "x"].iter().find_map(|p| lower.strip_prefix(p)) {
+        Some(hex) => (hex, true),
+        None => (lower.as_str(), false),
+    };
+    if !(4..=6).contains(&hex.len())
+        || !hex.chars().all(|c| c.is_ascii_hexdigit())
+        || !(prefixed || hex.chars().any(|c| c.is_ascii_digit()))
+    {
+        return None;
+    }
+    u32::from_str_radix(hex, 16).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scan_reports_unused_and_undefined() {
+        let dir = std::env::temp_dir().join(format!("svg2font-usage-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let source = dir.join("app.ts");
+        std::fs::write(
+            &source,
+            "const a = AppIcons.arrowDown;\nconst b = AppIcons.missing;\nconst c = '\\uE002 \\uE0FF face';\n",
+        )
+        .unwrap();
+
+        let icon = |name: &str, codepoint| ManifestIcon {
+            name: name.to_string(),
+            codepoint,
+        };
+        let icons = [
+            icon("arrow_down", 0xE000),
+            icon("circle", 0xE001),
+            icon("star", 0xE002),
+        ];
+        let report = scan(&icons, &["AppIcons".to_string()], &[source]);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(report.unused, [icon("circle", 0xE001)]);
+        let undefined: Vec<_> = report
+            .undefined
+            .iter()
+            .map(|r| (r.line, r.text.as_str()))
+            .collect();
+        assert_eq!(undefined, [(2, "AppIcons.missing"), (3, "U+E0FF")]);
+    }
+}
@@ -0,0 +1,282 @@
+//! Variable fonts with a FILL axis that morphs each icon from its outlined
+//! to its filled style, as Material Symbols do.
+
+use crate::font_builder::create_name_record;
+use anyhow::Result;
+use read_fonts::tables::glyf::CurvePoint;
+use write_fonts::{
+    tables::{
+        fvar::{AxisInstanceArrays, Fvar, InstanceRecord, VariationAxisRecord},
+        glyf::{Bbox, Contour, SimpleGlyph},
+        gvar::{GlyphDelta, GlyphDeltas, GlyphVariations, Gvar, Tent},
+        name::Name,
+        stat::{AxisRecord, AxisValue, AxisValueTableFlags, Stat},
+    },
+    types::{F2Dot14, Fixed, GlyphId, NameId, Tag},
+    OffsetMarker,
+};
+
+/// Tag of the fill axis, running from 0 (outlined) to 1 (filled)
+pub const FILL_AXIS: Tag = Tag::new(b"FILL");
+
+const AXIS_NAME_ID: NameId = NameId::new(256);
+const OUTLINED_NAME_ID: NameId = NameId::new(257);
+const FILLED_NAME_ID: NameId = NameId::new(258);
+
+/// Deltas moving a glyph's points from the outlined to the filled master
+pub type FillDeltas = Vec<GlyphDelta>;
+
+/// Make the two styles of an icon interpolatable, returning the glyph to
+/// store and the same glyph with its points where FILL=1 puts them.
+///
+/// Styles drawn with the same contours and points interpolate directly.
+/// Other pairs keep both sets of contours: the filled ones are collapsed
+/// to their centers in the stored glyph and grow out at FILL=1 while the
+/// outlined ones shrink away.
+pub fn fill_masters(outlined: &SimpleGlyph, filled: &SimpleGlyph) -> (SimpleGlyph, SimpleGlyph) {
+    if compatible(outlined, filled) {
+        return (outlined.clone(), filled.clone());
+    }
+    let default = outlined
+        .contours
+        .iter()
+        .cloned()
+        .chain(filled.contours.iter().map(collapsed))
+        .collect();
+    let full = outlined
+        .contours
+        .iter()
+        .map(collapsed)
+        .chain(filled.contours.iter().cloned())
+        .collect();
+    (glyph(default), glyph(full))
+}
+
+/// Point deltas from `default` to `filled`, or `None` when nothing moves
+pub fn fill_deltas(default: &SimpleGlyph, filled: &SimpleGlyph) -> Option<FillDeltas> {
+    let points = |glyph: &SimpleGlyph| -> Vec<CurvePoint> {
+        glyph
+            .contours
+            .iter()
+            .flat_map(|contour| contour.iter().copied())
+            .collect()
+    };
+    let mut deltas: Vec<GlyphDelta> = points(default)
+        .iter()
+        .zip(points(filled))
+        .map(|(from, to)| GlyphDelta::required(to.x - from.x, to.y - from.y))
+        .collect();
+    if deltas.iter().all(|delta| delta.x == 0 && delta.y == 0) {
+        return None;
+    }
+    // The advance does not change, so the four phantom points stay put
+    deltas.extend([GlyphDelta::required(0, 0); 4]);
+    Some(deltas)
+}
+
+/// Build fvar, gvar and STAT for `num_glyphs` glyphs, of which those in
+/// `variations` move along the fill axis, and name the axis in `name`
+pub fn fill_axis_tables(
+    num_glyphs: u16,
+    variations: &[(u16, FillDeltas)],
+    name: &mut Name,
+) -> Result<(Fvar, Gvar, Stat)> {
+    for (name_id, value) in [
+        (AXIS_NAME_ID, "Fill"),
+        (OUTLINED_NAME_ID, "Outlined"),
+        (FILLED_NAME_ID, "Filled"),
+    ] {
+        name.name_record.push(create_name_record(name_id, value));
+    }
+
+    let axis = VariationAxisRecord::new(
+        FILL_AXIS,
+        Fixed::ZERO,
+        Fixed::ZERO,
+        Fixed::ONE,
+        0,
+        AXIS_NAME_ID,
+    );
+    let instance = |name_id: NameId, value: Fixed| InstanceRecord {
+        subfamily_name_id: name_id,
+        coordinates: vec![value],
+        ..Default::default()
+    };
+    let fvar = Fvar::new(AxisInstanceArrays::new(
+        vec![axis],
+        vec![
+            instance(OUTLINED_NAME_ID, Fixed::ZERO),
+            instance(FILLED_NAME_ID, Fixed::ONE),
+        ],
+    ));
+
+    let mut deltas = variations.iter().peekable();
+    let glyphs = (0..num_glyphs)
+        .map(|gid| {
+            let variation = match deltas.next_if(|(varied, _)| *varied == gid) {
+                Some((_, deltas)) => vec![GlyphDeltas::new(
+                    vec![Tent::new(F2Dot14::ONE, None)],
+                    deltas.clone(),
+                )],
+                None => Vec::new(),
+            };
+            GlyphVariations::new(GlyphId::new(gid as u32), variation)
+        })
+        .collect();
+    let gvar = Gvar::new(glyphs, 1)
+        .map_err(|e| anyhow::anyhow!("Failed to build the fill axis variations: {}", e))?;
+
+    let value = |name_id: NameId, value: Fixed, flags: AxisValueTableFlags| {
+        OffsetMarker::new(AxisValue::format_1(0, flags, name_id, value))
+    };
+    let stat = Stat {
+        design_axes: OffsetMarker::new(vec![AxisRecord::new(FILL_AXIS, AXIS_NAME_ID, 0)]),
+        offset_to_axis_values: Some(vec![
+            value(
+                OUTLINED_NAME_ID,
+                Fixed::ZERO,
+                AxisValueTableFlags::ELIDABLE_AXIS_VALUE_NAME,
+            ),
+            value(FILLED_NAME_ID, Fixed::ONE, AxisValueTableFlags::empty()),
+        ])
+        .into(),
+        elided_fallback_name_id: Some(NameId::SUBFAMILY_NAME),
+    };
+
+    Ok((fvar, gvar, stat))
+}
+
+/// Whether two glyphs have the same contours with the same kinds of points
+fn compatible(a: &SimpleGlyph, b: &SimpleGlyph) -> bool {
+    a.contours.len() == b.contours.len()
+        && a.contours.iter().zip(&b.contours).all(|(a, b)| {
+            a.len() == b.len()
+                && a.iter()
+                    .zip(b.iter())
+                    .all(|(p, q)| p.on_curve == q.on_curve)
+        })
+}
+
+/// A contour with every point moved to the center of its bounds
+fn collapsed(contour: &Contour) -> Contour {
+    let bounds = bounds(contour.iter());
+    let (x, y) = match bounds {
+        Some(b) => (
+            ((b.x_min as i32 + b.x_max as i32) / 2) as i16,
+            ((b.y_min as i32 + b.y_max as i32) / 2) as i16,
+        ),
+        None => (0, 0),
+    };
+    contour
+        .iter()
+        .map(|point| CurvePoint { x, y, ..*point })
+        .collect::<Vec<_>>()
+        .into()
+}
+
+fn glyph(contours: Vec<Contour>) -> SimpleGlyph {
+    SimpleGlyph {
+        bbox: bounds(contours.iter().flat_map(|contour| contour.iter())).unwrap_or_default(),
+        contours,
+        instructions: Vec::new(),
+    }
+}
+
+fn bounds<'a>(points: impl Iterator<Item = &'a CurvePoint>) -> Option<Bbox> {
+    points.fold(None, |bbox, p| {
+        Some(match bbox {
+            None => Bbox {
+                x_min: p.x,
+                y_min: p.y,
+                x_max: p.x,
+                y_max: p.y,
+            },
+            Some(b) => Bbox {
+                x_min: b.x_min.min(p.x),
+                y_min: b.y_min.min(p.y),
+                x_max: b.x_max.max(p.x),
+                y_max: b.y_max.max(p.y),
+            },
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kurbo::{BezPath, Rect, Shape};
+
+    fn rect_glyph(rects: &[Rect]) -> SimpleGlyph {
+        let mut path = BezPath::new();
+        for rect in rects {
+            path.extend(rect.path_elements(0.1));
+        }
+        SimpleGlyph::from_bezpath(&path).unwrap()
+    }
+
+    #[test]
+    fn test_fill_masters() {
+        // Same structure: points move directly
+        let outlined = rect_glyph(&[Rect::new(100.0, 100.0, 900.0, 900.0)]);
+        let filled = rect_glyph(&[Rect::new(50.0, 50.0, 950.0, 950.0)]);
+        let (default, full) = fill_masters(&outlined, &filled);
+        assert_eq!(default, outlined);
+        let deltas = fill_deltas(&default, &full).unwrap();
+        assert_eq!(deltas.len(), 4 + 4);
+        assert_eq!((deltas[0].x, deltas[0].y), (-50, -50));
+        assert!(fill_deltas(&outlined, &outlined).is_none());
+
+        // Different structure: both sets of contours, one collapsed at a time
+        let ring = rect_glyph(&[
+            Rect::new(100.0, 100.0, 900.0, 900.0),
+            Rect::new(200.0, 200.0, 800.0, 800.0),
+        ]);
+        let (default, full) = fill_masters(&ring, &filled);
+        assert_eq!(default.contours.len(), 3);
+        assert_eq!(full.contours.len(), 3);
+        assert!(default.contours[2].iter().all(|p| (p.x, p.y) == (500, 500)));
+        assert!(full.contours[0].iter().all(|p| (p.x, p.y) == (500, 500)));
+        assert_eq!(default.bbox, ring.bbox);
+        assert_eq!(fill_deltas(&default, &full).unwrap().len(), 12 + 4);
+    }
+
+    #[test]
+    fn test_fill_font() {
+        use crate::font_builder::{build_fill_font_bytes, FontOptions};
+        use crate::svg_parser::parse_svg_str;
+        use read_fonts::{FontRef, TableProvider};
+
+        let svg = |d: &str| {
+            format!(
+                r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 24 24"><path d="{}"/></svg>"#,
+                d
+            )
+        };
+        let outlined = [parse_svg_str(&svg("M8 8h8v8H8z"), "dot", 0xE000).unwrap()];
+        let filled = [parse_svg_str(&svg("M4 4h16v16H4z"), "dot", 0xE000).unwrap()];
+        let data = build_fill_font_bytes(
+            &outlined,
+            &filled,
+            "Test",
+            &FontOptions::default(),
+            None,
+            false,
+        )
+        .unwrap();
+
+        let font = FontRef::new(&data).unwrap();
+        let axes = font.fvar().unwrap().axes().unwrap();
+        assert_eq!(axes[0].axis_tag(), FILL_AXIS);
+        let variation = font
+            .gvar()
+            .unwrap()
+            .glyph_variation_data(GlyphId::new(1))
+            .unwrap()
+            .unwrap();
+        let tuple = variation.tuples().next().unwrap();
+        let delta = tuple.deltas().next().unwrap();
+        // The square grows by 4 of 24 units on each side, and its first point is the top left
+        assert_eq!((delta.x_delta, delta.y_delta), (-166, 166));
+        assert!(font.stat().is_ok());
+    }
+}
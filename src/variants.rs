@@ -0,0 +1,293 @@
+use crate::cache;
+use crate::codepoints;
+use crate::config::Config;
+use crate::svg_parser::{Icon, IconOverrides};
+use anyhow::Result;
+use kurbo::{Affine, BezPath, Shape, Vec2};
+use serde::Deserialize;
+
+/// Name suffix that marks an icon as direction sensitive
+const LTR_SUFFIX: &str = "_ltr";
+
+/// Name suffix given to mirrored right-to-left variants
+const RTL_SUFFIX: &str = "_rtl";
+
+/// A config entry deriving an icon from another, e.g. `rotate(arrow_right, 90)`
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct Derivation {
+    /// Name of the source icon
+    pub base: String,
+    pub operation: Operation,
+}
+
+/// Transform applied to a base icon
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Operation {
+    /// Rotate by degrees around the icon center; positive turns counterclockwise
+    Rotate(f64),
+    /// Mirror left to right
+    FlipX,
+    /// Mirror top to bottom
+    FlipY,
+}
+
+impl TryFrom<String> for Derivation {
+    type Error = String;
+
+    fn try_from(expr: String) -> Result<Self, Self::Error> {
+        let invalid = || {
+            format!(
+                "invalid derivation `{}`, expected rotate(name, degrees), flip_x(name) or flip_y(name)",
+                expr
+            )
+        };
+
+        let (op, args) = expr.trim().split_once('(').ok_or_else(invalid)?;
+        let args = args.strip_suffix(')').ok_or_else(invalid)?;
+        let mut args = args.split(',').map(str::trim);
+        let base = args.next().filter(|b| !b.is_empty()).ok_or_else(invalid)?;
+
+        let operation = match (op.trim(), args.next(), args.next()) {
+            ("rotate", Some(angle), None) => {
+                let angle = angle.trim_end_matches('°').trim_end_matches("deg").trim();
+                Operation::Rotate(angle.parse().map_err(|_| invalid())?)
+            }
+            ("flip_x", None, None) => Operation::FlipX,
+            ("flip_y", None, None) => Operation::FlipY,
+            _ => return Err(invalid()),
+        };
+
+        Ok(Self {
+            base: base.to_string(),
+            operation,
+        })
+    }
+}
+
+impl Operation {
+    /// Transform in SVG coordinates for an icon of the given size
+    fn transform(self, width: f64, height: f64) -> Affine {
+        let center = Vec2::new(width / 2.0, height / 2.0);
+        let around_center = |a: Affine| Affine::translate(center) * a * Affine::translate(-center);
+        match self {
+            // SVG is Y-down, so a visually counterclockwise turn is a negative angle
+            Operation::Rotate(degrees) => around_center(Affine::rotate(-degrees.to_radians())),
+            Operation::FlipX => around_center(Affine::FLIP_X),
+            Operation::FlipY => around_center(Affine::FLIP_Y),
+        }
+    }
+}
+
+/// Build every variant of `icon`: its configured derived icons, then its
+/// mirrored right-to-left counterpart when `rtl` is set
+pub fn variants_of(icon: &Icon, config: &Config, rtl: bool) -> Vec<Icon> {
+    let mut variants: Vec<Icon> = config
+        .derived
+        .iter()
+        .filter(|(_, derivation)| derivation.base == icon.name)
+        .map(|(name, derivation)| {
+            let transform = derivation.operation.transform(icon.width, icon.height);
+            derived_icon(icon, name.clone(), "derive", transform)
+        })
+        .collect();
+
+    if rtl {
+        variants.extend(mirrored_variant(icon, config));
+    }
+    variants
+}
+
+/// Fail if a derived icon refers to an icon that does not exist
+pub fn check_derived_bases(icons: &[Icon], config: &Config) -> Result<()> {
+    let missing: Vec<String> = config
+        .derived
+        .iter()
+        .filter(|(_, derivation)| !icons.iter().any(|i| i.name == derivation.base))
+        .map(|(name, derivation)| format!("{} (from `{}`)", name, derivation.base))
+        .collect();
+
+    if !missing.is_empty() {
+        anyhow::bail!(
+            "Derived icons refer to unknown icons: {}",
+            missing.join(", ")
+        );
+    }
+    Ok(())
+}
+
+/// Whether `icon` is used as a layer of a composed icon
+pub fn is_component(icon: &Icon, config: &Config) -> bool {
+    config
+        .composed
+        .values()
+        .flatten()
+        .any(|name| *name == icon.name)
+}
+
+/// Build the icons declared in `[composed]` by merging the outlines of their components.
+///
+/// Components are scaled to the first component's size. Each component's
+/// contours are oriented the same way first, so overlapping shapes add up
+/// under the nonzero fill rule instead of cancelling out.
+pub fn composed_icons(components: &[Icon], config: &Config) -> Result<Vec<Icon>> {
+    config
+        .composed
+        .iter()
+        .map(|(name, parts)| {
+            let find = |part: &String| {
+                components.iter().find(|i| i.name == *part).ok_or_else(|| {
+                    anyhow::anyhow!("Composed icon {} refers to unknown icon `{}`", name, part)
+                })
+            };
+
+            let first = parts
+                .first()
+                .ok_or_else(|| anyhow::anyhow!("Composed icon {} has no components", name))?;
+            let base = find(first)?;
+            let mut path = BezPath::new();
+            let mut hashes = Vec::new();
+            for part in parts {
+                let icon = find(part)?;
+                let scale =
+                    Affine::scale_non_uniform(base.width / icon.width, base.height / icon.height);
+                let mut outline = scale * &icon.path;
+                if outline.area() < 0.0 {
+                    outline = outline.reverse_subpaths();
+                }
+                path.extend(outline);
+                hashes.push(icon.source_hash.as_str());
+            }
+
+            Ok(Icon {
+                name: name.clone(),
+                filename: base.filename.clone(),
+                path,
+                secondary_codepoint: None,
+                symbol_codepoint: None,
+                fills: Vec::new(),
+                width: base.width,
+                height: base.height,
+                codepoint: 0,
+                source_hash: cache::content_hash(
+                    format!("compose:{}", hashes.join(":")).as_bytes(),
+                ),
+                source: None,
+                animated: false,
+                label: None,
+                description: None,
+                categories: base.categories.clone(),
+                tags: Vec::new(),
+                aliases: Vec::new(),
+                overrides: IconOverrides::default(),
+            })
+        })
+        .collect()
+}
+
+/// Build the horizontally mirrored right-to-left counterpart of `icon`.
+///
+/// Returns `None` unless the icon name ends in `_ltr` or matches one of the
+/// configured `rtl.mirror` patterns. The variant's codepoint is assigned by
+/// [`append`].
+fn mirrored_variant(icon: &Icon, config: &Config) -> Option<Icon> {
+    let name = match icon.name.strip_suffix(LTR_SUFFIX) {
+        Some(base) => format!("{}{}", base, RTL_SUFFIX),
+        None if config.rtl.mirror.iter().any(|p| p.matches(&icon.name)) => {
+            format!("{}{}", icon.name, RTL_SUFFIX)
+        }
+        None => return None,
+    };
+
+    // A mirrored icon means the same thing, so it keeps the accessibility text
+    let mirror = Operation::FlipX.transform(icon.width, icon.height);
+    let mut variant = derived_icon(icon, name, "mirror", mirror);
+    variant.label = icon.label.clone();
+    variant.description = icon.description.clone();
+    variant.tags = icon.tags.clone();
+    Some(variant)
+}
+
+/// Copy `icon` under a new name with its outline transformed.
+///
+/// `operation` identifies the transform in the cache key, so derived glyphs
+/// never collide with their source in the outline cache.
+fn derived_icon(icon: &Icon, name: String, operation: &str, transform: Affine) -> Icon {
+    Icon {
+        name,
+        filename: icon.filename.clone(),
+        path: transform * &icon.path,
+        secondary_codepoint: None,
+        symbol_codepoint: None,
+        fills: icon.fills.clone(),
+        width: icon.width,
+        height: icon.height,
+        codepoint: 0,
+        source_hash: cache::content_hash(
+            format!(
+                "{}:{}:{:?}",
+                icon.source_hash,
+                operation,
+                transform.as_coeffs()
+            )
+            .as_bytes(),
+        ),
+        source: icon.source.clone(),
+        animated: icon.animated,
+        label: None,
+        description: None,
+        categories: icon.categories.clone(),
+        tags: Vec::new(),
+        aliases: Vec::new(),
+        // The same artwork needs the same adjustments, but not the codepoint
+        overrides: IconOverrides {
+            codepoint: None,
+            ..icon.overrides.clone()
+        },
+    }
+}
+
+/// Append derived icons after the source icons, giving them the next free codepoints
+pub fn append(icons: &mut Vec<Icon>, variants: Vec<Icon>) -> Result<()> {
+    let mut codepoint = icons
+        .iter()
+        .map(|i| i.codepoint)
+        .max()
+        .unwrap_or(codepoints::FIRST_PRIVATE_USE - 1);
+    for mut variant in variants {
+        codepoint = codepoints::next_private_use(codepoint)?;
+        variant.codepoint = codepoint;
+        icons.push(variant);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_derivation() {
+        let parse = |s: &str| Derivation::try_from(s.to_string());
+        assert_eq!(
+            parse("rotate(arrow_right, 90°)"),
+            Ok(Derivation {
+                base: "arrow_right".to_string(),
+                operation: Operation::Rotate(90.0),
+            })
+        );
+        assert_eq!(
+            parse("flip_y( chevron )").unwrap().operation,
+            Operation::FlipY
+        );
+        assert!(parse("rotate(arrow_right)").is_err());
+        assert!(parse("flip_x(arrow, 90)").is_err());
+    }
+
+    #[test]
+    fn test_rotate_counterclockwise() {
+        // A point right of center ends up above it (smaller y in SVG space)
+        let p = Operation::Rotate(90.0).transform(24.0, 24.0) * kurbo::Point::new(20.0, 12.0);
+        assert!((p - kurbo::Point::new(12.0, 4.0)).hypot() < 1e-9);
+    }
+}
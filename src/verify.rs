@@ -0,0 +1,344 @@
+//! Rendering the glyphs of a font next to their source SVGs to catch
+//! conversions that visibly diverged.
+
+use crate::extract;
+use crate::raster::Mask;
+use crate::subset::codepoint_mappings;
+use crate::svg_parser::{read_svg, skia_to_kurbo, to_affine};
+use anyhow::{Context, Result};
+use kurbo::{Affine, BezPath, Rect, Shape};
+use read_fonts::{FontRef, TableProvider};
+use serde_json::Value;
+use std::path::Path;
+use usvg::{FillRule, Options, Tree};
+
+/// Pixels kept free around the outline in each rendering
+const MARGIN: f64 = 2.0;
+
+/// How far one glyph strays from its source
+#[derive(Debug, Clone, PartialEq)]
+pub struct IconScore {
+    pub name: String,
+    pub codepoint: u32,
+    /// Percentage of the inked pixels that differ, from 0 (identical) to 100
+    pub score: f64,
+}
+
+/// Render every icon of a manifest from its glyph in `font_data` and from
+/// its source SVG under `source_dir`, both `size` pixels square, and score
+/// how much they differ.
+///
+/// Each rendering is fitted to its outline's bounds, so padding, fitting and
+/// centering options do not count as differences; lost or inverted shapes,
+/// clipping and loose curve approximation do. A duotone icon's glyph is
+/// rendered together with its secondary layer.
+pub fn verify_font(
+    font_data: &[u8],
+    manifest: &Value,
+    source_dir: &Path,
+    size: usize,
+) -> Result<Vec<IconScore>> {
+    let font =
+        FontRef::new(font_data).map_err(|e| anyhow::anyhow!("Font does not parse: {}", e))?;
+    let glyf = font
+        .glyf()
+        .context("Only fonts with TrueType outlines can be verified")?;
+    let loca = font.loca(None)?;
+    let mappings = codepoint_mappings(&font)?;
+
+    let icons = manifest["icons"]
+        .as_array()
+        .context("Manifest has no icons array")?;
+    icons
+        .iter()
+        .map(|icon| {
+            let hex = |value: &Value| u32::from_str_radix(value.as_str()?, 16).ok();
+            let name = icon["name"]
+                .as_str()
+                .with_context(|| format!("Missing name in manifest entry {}", icon))?;
+            let codepoint = hex(&icon["codepoint"])
+                .with_context(|| format!("Invalid codepoint in manifest entry {}", icon))?;
+            let source = match icon["source"].as_str() {
+                Some(source) => source_dir.join(source),
+                None => {
+                    source_dir.join(format!("{}.svg", icon["filename"].as_str().unwrap_or(name)))
+                }
+            };
+
+            let mut outline = BezPath::new();
+            for codepoint in std::iter::once(codepoint).chain(hex(&icon["secondaryCodepoint"])) {
+                if let Some(&gid) = mappings.get(&codepoint) {
+                    outline.extend(extract::glyph_outline(&glyf, &loca, gid)?);
+                }
+            }
+            // Font units point up
+            outline.apply_affine(Affine::FLIP_Y);
+            let glyph = render_glyph(&outline, size);
+
+            let svg = read_svg(&source)?;
+            let reference = render_svg(&svg, size)
+                .with_context(|| format!("Failed to render {}", source.display()))?;
+            Ok(IconScore {
+                name: name.to_string(),
+                codepoint,
+                score: difference(&glyph, &reference),
+            })
+        })
+        .collect()
+}
+
+/// Transform fitting `bounds` into the middle of a `size` pixel square
+fn fit(bounds: Rect, size: usize) -> Affine {
+    let room = size as f64 - 2.0 * MARGIN;
+    let scale = room / bounds.width().max(bounds.height()).max(f64::EPSILON);
+    let center = size as f64 / 2.0;
+    Affine::translate((center, center))
+        * Affine::scale(scale)
+        * Affine::translate(-bounds.center().to_vec2())
+}
+
+fn render_glyph(outline: &BezPath, size: usize) -> Mask {
+    let mut mask = Mask::new(size, size);
+    if !outline.elements().is_empty() {
+        // Glyphs are always filled with the non-zero rule
+        mask.fill(
+            &(fit(outline.bounding_box(), size) * outline.clone()),
+            FillRule::NonZero,
+        );
+    }
+    mask
+}
+
+/// Render an SVG the way a browser would, with its strokes, clip paths and
+/// masks, cut to its canvas. Translucent shapes are drawn opaque, like glyphs.
+fn render_svg(svg: &str, size: usize) -> Result<Mask> {
+    let tree = Tree::from_str(svg, &Options::default())?;
+    let canvas = Rect::from_origin_size(
+        (0.0, 0.0),
+        (tree.size().width() as f64, tree.size().height() as f64),
+    );
+    let mut mask = Mask::new(size, size);
+    let Some(bounds) = group_bounds(tree.root()).map(|bounds| bounds.intersect(canvas)) else {
+        return Ok(mask);
+    };
+    if bounds.area() <= 0.0 {
+        return Ok(mask);
+    }
+
+    let transform = fit(bounds, size);
+    paint_group(tree.root(), transform, &mut mask);
+    let mut clip = Mask::new(size, size);
+    clip.fill(&(transform * canvas.to_path(0.1)), FillRule::NonZero);
+    mask.intersect(&clip);
+    Ok(mask)
+}
+
+/// The filled and outlined stroke areas a path paints, in canvas units
+fn painted_shapes(path: &usvg::Path) -> Vec<(BezPath, FillRule)> {
+    if !path.is_visible() {
+        return Vec::new();
+    }
+    let transform = path.abs_transform();
+    let mut shapes = Vec::new();
+    if let Some(fill) = path.fill() {
+        if let Some(data) = path.data().clone().transform(transform) {
+            shapes.push((skia_to_kurbo(&data), fill.rule()));
+        }
+    }
+    if let Some(stroke) = path.stroke() {
+        let outline = path
+            .data()
+            .stroke(&stroke.to_tiny_skia(), 1.0)
+            .and_then(|outline| outline.transform(transform));
+        if let Some(outline) = outline {
+            shapes.push((skia_to_kurbo(&outline), FillRule::NonZero));
+        }
+    }
+    shapes
+}
+
+/// Bounds of what a group paints, in canvas units
+fn group_bounds(group: &usvg::Group) -> Option<Rect> {
+    if group.opacity().get() <= 0.0 {
+        return None;
+    }
+    let mut bounds: Option<Rect> = None;
+    for node in group.children() {
+        let node_bounds = match node {
+            usvg::Node::Path(ref path) => painted_shapes(path)
+                .iter()
+                .map(|(shape, _)| shape.bounding_box())
+                .reduce(|a, b| a.union(b)),
+            usvg::Node::Group(ref g) => group_bounds(g),
+            _ => None,
+        };
+        if let Some(node_bounds) = node_bounds {
+            bounds = Some(bounds.map_or(node_bounds, |b| b.union(node_bounds)));
+        }
+    }
+
+    let bounds = bounds?;
+    let clip = group.clip_path().and_then(|clip| {
+        let mut clip_bounds: Option<Rect> = None;
+        for_each_clip_shape(clip, group, &mut |shape, _| {
+            let shape_bounds = shape.bounding_box();
+            clip_bounds = Some(clip_bounds.map_or(shape_bounds, |b| b.union(shape_bounds)));
+        });
+        clip_bounds
+    });
+    let bounds = clip.map_or(bounds, |clip| bounds.intersect(clip));
+    let bounds = match group.mask() {
+        Some(mask) => bounds.intersect(mask_rect(mask, group).bounding_box()),
+        None => bounds,
+    };
+    (bounds.area() > 0.0).then_some(bounds)
+}
+
+/// Paint a group's shapes through `transform` (canvas units to pixels)
+fn paint_group(group: &usvg::Group, transform: Affine, mask: &mut Mask) {
+    if group.opacity().get() <= 0.0 {
+        return;
+    }
+    let mut layer = Mask::new(mask.width, mask.height);
+    for node in group.children() {
+        match node {
+            usvg::Node::Path(ref path) => {
+                for (shape, rule) in painted_shapes(path) {
+                    layer.fill(&(transform * shape), rule);
+                }
+            }
+            usvg::Node::Group(ref g) => paint_group(g, transform, &mut layer),
+            _ => {}
+        }
+    }
+
+    if let Some(clip) = group.clip_path() {
+        let mut region = Mask::new(mask.width, mask.height);
+        for_each_clip_shape(clip, group, &mut |shape, rule| {
+            region.fill(&(transform * shape), rule);
+        });
+        layer.intersect(&region);
+    }
+    if let Some(group_mask) = group.mask() {
+        layer.intersect(&mask_coverage(group_mask, group, transform, mask.width));
+    }
+    mask.composite(&layer);
+}
+
+/// Call `f` with every shape of a clip path applied to `group`, in canvas units
+fn for_each_clip_shape(
+    clip: &usvg::ClipPath,
+    group: &usvg::Group,
+    f: &mut impl FnMut(BezPath, FillRule),
+) {
+    let transform = group.abs_transform().pre_concat(clip.transform());
+    for_each_path(clip.root(), &mut |path| {
+        let Some(data) = path
+            .data()
+            .clone()
+            .transform(transform.pre_concat(path.abs_transform()))
+        else {
+            return;
+        };
+        let rule = path.fill().map_or(FillRule::NonZero, |fill| fill.rule());
+        f(skia_to_kurbo(&data), rule);
+    });
+}
+
+/// The area a mask may show, in canvas units
+fn mask_rect(mask: &usvg::Mask, group: &usvg::Group) -> BezPath {
+    let rect = mask.rect();
+    let mut bounds = Rect::new(
+        rect.left() as f64,
+        rect.top() as f64,
+        rect.right() as f64,
+        rect.bottom() as f64,
+    )
+    .to_path(0.1);
+    bounds.apply_affine(to_affine(group.abs_transform()));
+    bounds
+}
+
+/// How much of a group a mask lets through at each pixel
+fn mask_coverage(mask: &usvg::Mask, group: &usvg::Group, transform: Affine, size: usize) -> Mask {
+    let mut coverage = Mask::new(size, size);
+    let group_transform = group.abs_transform();
+    for_each_path(mask.root(), &mut |path| {
+        let Some(fill) = path.fill() else {
+            return;
+        };
+        let Some(data) = path
+            .data()
+            .clone()
+            .transform(group_transform.pre_concat(path.abs_transform()))
+        else {
+            return;
+        };
+        let level = match (mask.kind(), fill.paint()) {
+            (usvg::MaskType::Luminance, usvg::Paint::Color(color)) => {
+                (0.2126 * color.red as f32
+                    + 0.7152 * color.green as f32
+                    + 0.0722 * color.blue as f32)
+                    / 255.0
+            }
+            _ => 1.0,
+        };
+        coverage.paint(
+            &(transform * skia_to_kurbo(&data)),
+            fill.rule(),
+            level,
+            fill.opacity().get(),
+        );
+    });
+
+    let mut rect = Mask::new(size, size);
+    rect.fill(&(transform * mask_rect(mask, group)), FillRule::NonZero);
+    coverage.intersect(&rect);
+    if let Some(inner) = mask.mask() {
+        coverage.intersect(&mask_coverage(inner, group, transform, size));
+    }
+    coverage
+}
+
+/// Call `f` for every path in a group and its descendants
+fn for_each_path(group: &usvg::Group, f: &mut impl FnMut(&usvg::Path)) {
+    for node in group.children() {
+        match node {
+            usvg::Node::Path(ref path) => f(path),
+            usvg::Node::Group(ref g) => for_each_path(g, f),
+            _ => {}
+        }
+    }
+}
+
+/// Percentage of the pixels inked in either mask that differ
+fn difference(a: &Mask, b: &Mask) -> f64 {
+    let (mut differing, mut inked) = (0.0, 0.0);
+    for (&a, &b) in a.data.iter().zip(&b.data) {
+        differing += (a - b).abs() as f64;
+        inked += a.max(b) as f64;
+    }
+    if inked == 0.0 {
+        return 0.0;
+    }
+    differing / inked * 100.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_svg() {
+        let square = r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 24 24"><rect x="4" y="4" width="16" height="16"/></svg>"#;
+        let reference = render_svg(square, 32).unwrap();
+        let mut outline = Rect::new(0.0, 0.0, 500.0, 500.0).to_path(0.1);
+        outline.apply_affine(Affine::FLIP_Y);
+        assert!(difference(&render_glyph(&outline, 32), &reference) < 1.0);
+
+        // A stroke-only circle draws a ring, not a disc
+        let ring = r##"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 24 24"><circle cx="12" cy="12" r="8" fill="none" stroke="#000" stroke-width="2"/></svg>"##;
+        let disc = kurbo::Circle::new((12.0, 12.0), 9.0).to_path(0.1);
+        assert!(difference(&render_glyph(&disc, 32), &render_svg(ring, 32).unwrap()) > 30.0);
+    }
+}
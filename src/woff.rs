@@ -0,0 +1,619 @@
+use anyhow::{Context, Result};
+use brotli::enc::{backward_references::BrotliEncoderMode, BrotliEncoderParams};
+use flate2::{write::ZlibEncoder, Compression};
+use read_fonts::{
+    tables::glyf::{CompositeGlyph, Glyph, SimpleGlyph},
+    types::{GlyphId, Tag},
+    FontRef, TableProvider,
+};
+use std::io::Write;
+
+/// Web font formats written next to the TTF
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum WebFormat {
+    /// WOFF 1.0: every table zlib-compressed
+    Woff,
+    /// WOFF 2.0: transformed glyf and loca, Brotli-compressed
+    Woff2,
+}
+
+impl WebFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            WebFormat::Woff => "woff",
+            WebFormat::Woff2 => "woff2",
+        }
+    }
+
+    /// Wrap a TrueType font in this format
+    pub fn encode(self, ttf: &[u8], brotli_quality: u32) -> Result<Vec<u8>> {
+        match self {
+            WebFormat::Woff => woff(ttf),
+            WebFormat::Woff2 => woff2(ttf, brotli_quality),
+        }
+    }
+}
+
+/// Highest Brotli quality, giving the smallest WOFF2 files
+pub const MAX_BROTLI_QUALITY: u32 = 11;
+
+const WOFF_HEADER_SIZE: usize = 44;
+const WOFF_ENTRY_SIZE: usize = 20;
+
+/// Wrap a TrueType font in WOFF 1.0.
+///
+/// Tables are stored zlib-compressed unless that does not make them
+/// smaller, in which case they are stored as-is.
+pub fn woff(ttf: &[u8]) -> Result<Vec<u8>> {
+    let font = FontRef::new(ttf).map_err(|e| anyhow::anyhow!("Font does not parse: {}", e))?;
+    let mut records: Vec<_> = font.table_directory.table_records().to_vec();
+    records.sort_by_key(|record| record.tag());
+
+    let mut tables = Vec::with_capacity(records.len());
+    for record in &records {
+        let data = font
+            .table_data(record.tag())
+            .with_context(|| format!("The {} table is out of bounds", record.tag()))?;
+        let data = data.as_bytes();
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::best());
+        encoder.write_all(data)?;
+        let compressed = encoder.finish()?;
+        let stored = if compressed.len() < data.len() {
+            compressed
+        } else {
+            data.to_vec()
+        };
+        tables.push((record, data.len(), stored));
+    }
+
+    let sfnt_size =
+        12 + 16 * tables.len() + tables.iter().map(|(_, len, _)| padded(*len)).sum::<usize>();
+
+    let mut offset = WOFF_HEADER_SIZE + WOFF_ENTRY_SIZE * tables.len();
+    let mut directory = Vec::with_capacity(WOFF_ENTRY_SIZE * tables.len());
+    for (record, orig_len, stored) in &tables {
+        directory.extend_from_slice(&record.tag().to_be_bytes());
+        directory.extend_from_slice(&(offset as u32).to_be_bytes());
+        directory.extend_from_slice(&(stored.len() as u32).to_be_bytes());
+        directory.extend_from_slice(&(*orig_len as u32).to_be_bytes());
+        directory.extend_from_slice(&record.checksum().to_be_bytes());
+        offset += padded(stored.len());
+    }
+    let length = offset;
+
+    let mut out = Vec::with_capacity(length);
+    out.extend_from_slice(b"wOFF");
+    out.extend_from_slice(&font.table_directory.sfnt_version().to_be_bytes());
+    out.extend_from_slice(&(length as u32).to_be_bytes());
+    out.extend_from_slice(&(tables.len() as u16).to_be_bytes());
+    out.extend_from_slice(&0u16.to_be_bytes());
+    out.extend_from_slice(&(sfnt_size as u32).to_be_bytes());
+    // Font version 1.0, then no metadata or private data blocks
+    out.extend_from_slice(&1u16.to_be_bytes());
+    out.extend_from_slice(&0u16.to_be_bytes());
+    out.extend_from_slice(&[0; 20]);
+    out.extend_from_slice(&directory);
+    for (_, _, stored) in &tables {
+        out.extend_from_slice(stored);
+        out.resize(padded(out.len()), 0);
+    }
+
+    Ok(out)
+}
+
+const WOFF2_HEADER_SIZE: usize = 48;
+
+/// Tables the WOFF2 directory refers to by index instead of by tag
+const WOFF2_KNOWN_TAGS: [&[u8; 4]; 63] = [
+    b"cmap", b"head", b"hhea", b"hmtx", b"maxp", b"name", b"OS/2", b"post", b"cvt ", b"fpgm",
+    b"glyf", b"loca", b"prep", b"CFF ", b"VORG", b"EBDT", b"EBLC", b"gasp", b"hdmx", b"kern",
+    b"LTSH", b"PCLT", b"VDMX", b"vhea", b"vmtx", b"BASE", b"GDEF", b"GPOS", b"GSUB", b"EBSC",
+    b"JSTF", b"MATH", b"CBDT", b"CBLC", b"COLR", b"CPAL", b"SVG ", b"sbix", b"acnt", b"avar",
+    b"bdat", b"bloc", b"bsln", b"cvar", b"fdsc", b"feat", b"fmtx", b"fvar", b"gvar", b"hsty",
+    b"just", b"lcar", b"mort", b"morx", b"opbd", b"prop", b"trak", b"Zapf", b"Silf", b"Glat",
+    b"Gloc", b"Feat", b"Sill",
+];
+const WOFF2_CUSTOM_TAG: u8 = 63;
+
+const GLYF: Tag = Tag::new(b"glyf");
+const LOCA: Tag = Tag::new(b"loca");
+
+/// Wrap a TrueType font in WOFF 2.0.
+///
+/// glyf and loca get the WOFF2 glyf transform; every other table is stored
+/// unchanged. All tables are then compressed as one Brotli stream with the
+/// given quality (0-11).
+pub fn woff2(ttf: &[u8], brotli_quality: u32) -> Result<Vec<u8>> {
+    let font = FontRef::new(ttf).map_err(|e| anyhow::anyhow!("Font does not parse: {}", e))?;
+    let mut records: Vec<_> = font.table_directory.table_records().to_vec();
+    records.sort_by_key(|record| record.tag());
+    // The transformed loca must directly follow glyf
+    if let Some(loca) = records.iter().position(|record| record.tag() == LOCA) {
+        let loca = records.remove(loca);
+        let glyf = records
+            .iter()
+            .position(|record| record.tag() == GLYF)
+            .context("The font has a loca table but no glyf table")?;
+        records.insert(glyf + 1, loca);
+    }
+
+    let mut directory = Vec::new();
+    let mut stream = Vec::new();
+    let mut sfnt_size = 12 + 16 * records.len();
+    for record in &records {
+        let tag = record.tag();
+        match WOFF2_KNOWN_TAGS
+            .iter()
+            .position(|known| Tag::new(known) == tag)
+        {
+            Some(index) => directory.push(index as u8),
+            None => {
+                directory.push(WOFF2_CUSTOM_TAG);
+                directory.extend_from_slice(&tag.to_be_bytes());
+            }
+        }
+
+        let data = font
+            .table_data(tag)
+            .with_context(|| format!("The {} table is out of bounds", tag))?;
+        let data = data.as_bytes();
+        let orig_len = if tag == GLYF {
+            let (transformed, decoded_len) = transform_glyf(&font)?;
+            write_base128(&mut directory, decoded_len as u32);
+            write_base128(&mut directory, transformed.len() as u32);
+            stream.extend_from_slice(&transformed);
+            decoded_len
+        } else if tag == LOCA {
+            // Decoders rebuild loca from the transformed glyf
+            write_base128(&mut directory, data.len() as u32);
+            write_base128(&mut directory, 0);
+            data.len()
+        } else {
+            write_base128(&mut directory, data.len() as u32);
+            stream.extend_from_slice(data);
+            data.len()
+        };
+        sfnt_size += padded(orig_len);
+    }
+
+    let params = BrotliEncoderParams {
+        quality: brotli_quality.min(MAX_BROTLI_QUALITY) as i32,
+        mode: BrotliEncoderMode::BROTLI_MODE_FONT,
+        size_hint: stream.len(),
+        ..Default::default()
+    };
+    let mut compressed = Vec::new();
+    brotli::BrotliCompress(&mut stream.as_slice(), &mut compressed, &params)
+        .context("Brotli compression failed")?;
+
+    let length = WOFF2_HEADER_SIZE + directory.len() + compressed.len();
+    let mut out = Vec::with_capacity(length);
+    out.extend_from_slice(b"wOF2");
+    out.extend_from_slice(&font.table_directory.sfnt_version().to_be_bytes());
+    out.extend_from_slice(&(length as u32).to_be_bytes());
+    out.extend_from_slice(&(records.len() as u16).to_be_bytes());
+    out.extend_from_slice(&0u16.to_be_bytes());
+    out.extend_from_slice(&(sfnt_size as u32).to_be_bytes());
+    out.extend_from_slice(&(compressed.len() as u32).to_be_bytes());
+    // Font version 1.0, then no metadata or private data blocks
+    out.extend_from_slice(&1u16.to_be_bytes());
+    out.extend_from_slice(&0u16.to_be_bytes());
+    out.extend_from_slice(&[0; 20]);
+    out.extend_from_slice(&directory);
+    out.extend_from_slice(&compressed);
+
+    Ok(out)
+}
+
+/// The streams of the WOFF2 transformed glyf table
+#[derive(Default)]
+struct GlyfStreams {
+    n_contours: Vec<u8>,
+    n_points: Vec<u8>,
+    flags: Vec<u8>,
+    glyphs: Vec<u8>,
+    composites: Vec<u8>,
+    bbox_bitmap: Vec<u8>,
+    bboxes: Vec<u8>,
+    instructions: Vec<u8>,
+}
+
+/// Apply the WOFF2 glyf transform, returning the transformed table and the
+/// size of the glyf table decoders will reconstruct from it
+fn transform_glyf(font: &FontRef) -> Result<(Vec<u8>, usize)> {
+    let glyf = font.glyf()?;
+    let loca = font.loca(None)?;
+    let num_glyphs = font.maxp()?.num_glyphs();
+
+    let mut streams = GlyfStreams {
+        bbox_bitmap: vec![0; 4 * (num_glyphs as usize).div_ceil(32)],
+        ..Default::default()
+    };
+    let mut decoded_len = 0;
+    for gid in 0..num_glyphs {
+        let glyph = loca
+            .get_glyf(GlyphId::from(gid), &glyf)
+            .with_context(|| format!("Glyph {} does not parse", gid))?;
+        let len = match glyph {
+            None => {
+                streams.n_contours.extend_from_slice(&0i16.to_be_bytes());
+                0
+            }
+            Some(Glyph::Simple(simple)) => streams.add_simple(gid, &simple),
+            Some(Glyph::Composite(composite)) => streams.add_composite(gid, &composite),
+        };
+        decoded_len += padded(len);
+    }
+
+    let GlyfStreams {
+        n_contours,
+        n_points,
+        flags,
+        glyphs,
+        composites,
+        bbox_bitmap,
+        bboxes,
+        instructions,
+    } = streams;
+    let mut out = Vec::new();
+    out.extend_from_slice(&0u16.to_be_bytes());
+    // No overlapSimpleBitmap
+    out.extend_from_slice(&0u16.to_be_bytes());
+    out.extend_from_slice(&num_glyphs.to_be_bytes());
+    out.extend_from_slice(&font.head()?.index_to_loc_format().to_be_bytes());
+    for len in [
+        n_contours.len(),
+        n_points.len(),
+        flags.len(),
+        glyphs.len(),
+        composites.len(),
+        bbox_bitmap.len() + bboxes.len(),
+        instructions.len(),
+    ] {
+        out.extend_from_slice(&(len as u32).to_be_bytes());
+    }
+    for stream in [
+        n_contours,
+        n_points,
+        flags,
+        glyphs,
+        composites,
+        bbox_bitmap,
+        bboxes,
+        instructions,
+    ] {
+        out.extend_from_slice(&stream);
+    }
+
+    Ok((out, decoded_len))
+}
+
+impl GlyfStreams {
+    /// Add a simple glyph, returning its size once decoded
+    fn add_simple(&mut self, gid: u16, glyph: &SimpleGlyph) -> usize {
+        let end_points = glyph.end_pts_of_contours();
+        self.n_contours
+            .extend_from_slice(&(end_points.len() as i16).to_be_bytes());
+        let mut start = 0;
+        for end in end_points {
+            write_255_u16(&mut self.n_points, end.get() + 1 - start);
+            start = end.get() + 1;
+        }
+
+        let (mut x, mut y) = (0i32, 0i32);
+        let mut bounds: Option<[i16; 4]> = None;
+        let mut coord_len = 0;
+        let mut flag_runs = Vec::<(u8, usize)>::new();
+        for point in glyph.points() {
+            let (dx, dy) = (point.x as i32 - x, point.y as i32 - y);
+            (x, y) = (point.x as i32, point.y as i32);
+            self.push_triplet(point.on_curve, dx, dy);
+
+            let [x_min, y_min, x_max, y_max] =
+                bounds.get_or_insert([point.x, point.y, point.x, point.y]);
+            *x_min = (*x_min).min(point.x);
+            *y_min = (*y_min).min(point.y);
+            *x_max = (*x_max).max(point.x);
+            *y_max = (*y_max).max(point.y);
+
+            // Decoders write the glyf flags and coordinates in their shortest form
+            let flag =
+                point.on_curve as u8 | coord_flag(dx, 0x02, 0x10) | coord_flag(dy, 0x04, 0x20);
+            coord_len += coord_size(dx) + coord_size(dy);
+            match flag_runs.last_mut() {
+                Some((last, count)) if *last == flag && *count < 256 => *count += 1,
+                _ => flag_runs.push((flag, 1)),
+            }
+        }
+        let flag_len: usize = flag_runs
+            .iter()
+            .map(|(_, count)| if *count > 1 { 2 } else { 1 })
+            .sum();
+
+        let instructions = glyph.instructions();
+        write_255_u16(&mut self.glyphs, instructions.len() as u16);
+        self.instructions.extend_from_slice(instructions);
+
+        let bbox = [glyph.x_min(), glyph.y_min(), glyph.x_max(), glyph.y_max()];
+        if bounds != Some(bbox) {
+            self.add_bbox(gid, bbox);
+        }
+
+        10 + 2 * end_points.len() + 2 + instructions.len() + flag_len + coord_len
+    }
+
+    /// Add a composite glyph, returning its size once decoded
+    fn add_composite(&mut self, gid: u16, glyph: &CompositeGlyph) -> usize {
+        self.n_contours.extend_from_slice(&(-1i16).to_be_bytes());
+        let data = glyph.component_data();
+        let instructions = glyph.instructions();
+        let components = match instructions {
+            Some(instructions) => &data[..data.len() - 2 - instructions.len()],
+            None => data,
+        };
+        self.composites.extend_from_slice(components);
+        if let Some(instructions) = instructions {
+            write_255_u16(&mut self.glyphs, instructions.len() as u16);
+            self.instructions.extend_from_slice(instructions);
+        }
+        // Composites always store their bounding box
+        self.add_bbox(
+            gid,
+            [glyph.x_min(), glyph.y_min(), glyph.x_max(), glyph.y_max()],
+        );
+
+        10 + data.len()
+    }
+
+    fn add_bbox(&mut self, gid: u16, bbox: [i16; 4]) {
+        self.bbox_bitmap[gid as usize / 8] |= 0x80 >> (gid % 8);
+        for value in bbox {
+            self.bboxes.extend_from_slice(&value.to_be_bytes());
+        }
+    }
+
+    /// Encode a point as a flag byte and 1-4 bytes of coordinate deltas
+    fn push_triplet(&mut self, on_curve: bool, dx: i32, dy: i32) {
+        let on_curve_bit = if on_curve { 0 } else { 0x80 };
+        let (abs_x, abs_y) = (dx.unsigned_abs(), dy.unsigned_abs());
+        let x_sign = (dx >= 0) as u8;
+        let y_sign = (dy >= 0) as u8;
+        let xy_signs = x_sign | (y_sign << 1);
+
+        let (flag, bytes): (u8, Vec<u8>) = if dx == 0 && abs_y < 1280 {
+            (((abs_y >> 7) & 0x0E) as u8 | y_sign, vec![abs_y as u8])
+        } else if dy == 0 && abs_x < 1280 {
+            (
+                (10 + ((abs_x >> 7) & 0x0E) as u8) | x_sign,
+                vec![abs_x as u8],
+            )
+        } else if abs_x < 65 && abs_y < 65 {
+            let (x, y) = (abs_x - 1, abs_y - 1);
+            (
+                20 + (x & 0x30) as u8 + ((y & 0x30) >> 2) as u8 + xy_signs,
+                vec![(((x & 0x0F) << 4) | (y & 0x0F)) as u8],
+            )
+        } else if abs_x < 769 && abs_y < 769 {
+            let (x, y) = (abs_x - 1, abs_y - 1);
+            (
+                84 + 12 * (x >> 8) as u8 + ((y >> 8) << 2) as u8 + xy_signs,
+                vec![x as u8, y as u8],
+            )
+        } else if abs_x < 4096 && abs_y < 4096 {
+            (
+                120 + xy_signs,
+                vec![
+                    (abs_x >> 4) as u8,
+                    (((abs_x & 0x0F) << 4) | (abs_y >> 8)) as u8,
+                    abs_y as u8,
+                ],
+            )
+        } else {
+            (
+                124 + xy_signs,
+                vec![
+                    (abs_x >> 8) as u8,
+                    abs_x as u8,
+                    (abs_y >> 8) as u8,
+                    abs_y as u8,
+                ],
+            )
+        };
+        self.flags.push(on_curve_bit | flag);
+        self.glyphs.extend_from_slice(&bytes);
+    }
+}
+
+/// glyf flag bits for a coordinate delta: `same` when zero, `short` (with
+/// `same` as the positive sign) when it fits a byte
+fn coord_flag(delta: i32, short: u8, same: u8) -> u8 {
+    match delta {
+        0 => same,
+        1..=255 => short | same,
+        -255..=-1 => short,
+        _ => 0,
+    }
+}
+
+fn coord_size(delta: i32) -> usize {
+    match delta {
+        0 => 0,
+        -255..=255 => 1,
+        _ => 2,
+    }
+}
+
+/// Write a WOFF2 UIntBase128: 7 bits per byte, most significant first
+fn write_base128(out: &mut Vec<u8>, value: u32) {
+    let len = (1..5).find(|n| value >> (7 * n) == 0).unwrap_or(5);
+    for i in (0..len).rev() {
+        let byte = ((value >> (7 * i)) & 0x7F) as u8;
+        out.push(if i > 0 { byte | 0x80 } else { byte });
+    }
+}
+
+/// Write a WOFF2 255UInt16
+fn write_255_u16(out: &mut Vec<u8>, value: u16) {
+    match value {
+        0..=252 => out.push(value as u8),
+        253..=505 => out.extend_from_slice(&[255, (value - 253) as u8]),
+        506..=761 => out.extend_from_slice(&[254, (value - 506) as u8]),
+        _ => {
+            out.push(253);
+            out.extend_from_slice(&value.to_be_bytes());
+        }
+    }
+}
+
+fn padded(len: usize) -> usize {
+    (len + 3) & !3
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::font_builder::{build_font_bytes, FontOptions};
+    use crate::svg_parser::parse_svg_str;
+    use flate2::read::ZlibDecoder;
+    use std::io::Read;
+
+    #[test]
+    fn test_woff_tables_round_trip() {
+        let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 24 24"><circle cx="12" cy="12" r="8"/></svg>"#;
+        let icons = vec![parse_svg_str(svg, "dot", 0xE000).unwrap()];
+        let ttf = build_font_bytes(&icons, "Test", &FontOptions::default(), None, false).unwrap();
+        let woff = woff(&ttf).unwrap();
+
+        let be32 = |at: usize| u32::from_be_bytes(woff[at..at + 4].try_into().unwrap());
+        assert_eq!(&woff[..4], b"wOFF");
+        assert_eq!(be32(8) as usize, woff.len());
+
+        let font = FontRef::new(&ttf).unwrap();
+        let num_tables = u16::from_be_bytes([woff[12], woff[13]]) as usize;
+        assert_eq!(num_tables, font.table_directory.table_records().len());
+        for entry in 0..num_tables {
+            let at = WOFF_HEADER_SIZE + entry * WOFF_ENTRY_SIZE;
+            let tag = Tag::from_be_bytes(woff[at..at + 4].try_into().unwrap());
+            let (offset, comp_len, orig_len) = (be32(at + 4), be32(at + 8), be32(at + 12));
+            let stored = &woff[offset as usize..(offset + comp_len) as usize];
+
+            let mut table = Vec::new();
+            if comp_len < orig_len {
+                ZlibDecoder::new(stored).read_to_end(&mut table).unwrap();
+            } else {
+                table = stored.to_vec();
+            }
+            assert_eq!(table, font.table_data(tag).unwrap().as_bytes(), "{}", tag);
+        }
+    }
+
+    /// Decode the triplet-encoded points of the first simple glyph in a transformed glyf
+    fn first_simple_glyph_points(glyf: &[u8]) -> Vec<(i16, i16, bool)> {
+        let be16 = |at: usize| i16::from_be_bytes([glyf[at], glyf[at + 1]]);
+        let be32 = |at: usize| u32::from_be_bytes(glyf[at..at + 4].try_into().unwrap()) as usize;
+        let sizes: Vec<usize> = (0..7).map(|i| be32(8 + 4 * i)).collect();
+        let mut at = 36;
+        let mut streams = Vec::new();
+        for size in &sizes {
+            streams.push(&glyf[at..at + size]);
+            at += size;
+        }
+        let (n_contours, n_points, flags, glyphs) =
+            (streams[0], streams[1], streams[2], streams[3]);
+
+        // Skip empty glyphs like .notdef; no glyph here needs a two-byte point count
+        let gid = (0..).find(|gid| be16(36 + 2 * gid) > 0).unwrap();
+        assert!(n_contours[..2 * gid].iter().all(|b| *b == 0));
+        let num_points: usize = n_points[..be16(36 + 2 * gid) as usize]
+            .iter()
+            .map(|n| *n as usize)
+            .sum();
+
+        let (mut x, mut y, mut data) = (0i32, 0i32, glyphs);
+        let mut points = Vec::new();
+        for &flag in &flags[..num_points] {
+            let on_curve = flag & 0x80 == 0;
+            let flag = (flag & 0x7F) as i32;
+            let sign = |bit: i32, value: i32| if bit & 1 == 1 { value } else { -value };
+            let b = |i: usize| data[i] as i32;
+            let (dx, dy, len) = match flag {
+                0..=9 => (0, sign(flag, ((flag & 14) << 7) + b(0)), 1),
+                10..=19 => (sign(flag, (((flag - 10) & 14) << 7) + b(0)), 0, 1),
+                20..=83 => {
+                    let b0 = flag - 20;
+                    (
+                        sign(flag, 1 + (b0 & 0x30) + (b(0) >> 4)),
+                        sign(flag >> 1, 1 + ((b0 & 0x0C) << 2) + (b(0) & 0x0F)),
+                        1,
+                    )
+                }
+                84..=119 => {
+                    let b0 = flag - 84;
+                    (
+                        sign(flag, 1 + ((b0 / 12) << 8) + b(0)),
+                        sign(flag >> 1, 1 + (((b0 % 12) >> 2) << 8) + b(1)),
+                        2,
+                    )
+                }
+                120..=123 => (
+                    sign(flag, (b(0) << 4) + (b(1) >> 4)),
+                    sign(flag >> 1, ((b(1) & 0x0F) << 8) + b(2)),
+                    3,
+                ),
+                _ => (
+                    sign(flag, (b(0) << 8) + b(1)),
+                    sign(flag >> 1, (b(2) << 8) + b(3)),
+                    4,
+                ),
+            };
+            data = &data[len..];
+            (x, y) = (x + dx, y + dy);
+            points.push((x as i16, y as i16, on_curve));
+        }
+        points
+    }
+
+    #[test]
+    fn test_woff2_glyf_transform_round_trips_points() {
+        let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 24 24"><circle cx="12" cy="12" r="8"/><rect x="1" y="1" width="22" height="3"/></svg>"#;
+        let icons = vec![parse_svg_str(svg, "shapes", 0xE000).unwrap()];
+        let ttf = build_font_bytes(&icons, "Test", &FontOptions::default(), None, false).unwrap();
+        let woff2 = woff2(&ttf, MAX_BROTLI_QUALITY).unwrap();
+
+        let be32 = |at: usize| u32::from_be_bytes(woff2[at..at + 4].try_into().unwrap());
+        assert_eq!(&woff2[..4], b"wOF2");
+        assert_eq!(be32(8) as usize, woff2.len());
+        let compressed_len = be32(20) as usize;
+        let mut stream = Vec::new();
+        brotli::Decompressor::new(&woff2[woff2.len() - compressed_len..], 4096)
+            .read_to_end(&mut stream)
+            .unwrap();
+
+        // Tables before glyf in tag order are stored untransformed
+        let font = FontRef::new(&ttf).unwrap();
+        let mut tags: Vec<Tag> = font
+            .table_directory
+            .table_records()
+            .iter()
+            .map(|record| record.tag())
+            .collect();
+        tags.sort();
+        let mut glyf = stream.as_slice();
+        for tag in tags.iter().take_while(|tag| **tag != GLYF) {
+            let table = font.table_data(*tag).unwrap();
+            assert!(glyf.starts_with(table.as_bytes()), "{}", tag);
+            glyf = &glyf[table.len()..];
+        }
+
+        let Some(Glyph::Simple(simple)) = font
+            .loca(None)
+            .unwrap()
+            .get_glyf(GlyphId::new(1), &font.glyf().unwrap())
+            .unwrap()
+        else {
+            panic!("glyph 1 is not a simple glyph");
+        };
+        let expected: Vec<_> = simple.points().map(|p| (p.x, p.y, p.on_curve)).collect();
+        assert_eq!(first_simple_glyph_points(glyf), expected);
+    }
+}